@@ -5,9 +5,10 @@ use omega_bevy::{
 };
 use omega_content::bootstrap_game_state_with_mode;
 use omega_core::{
-    Alignment, CharacterCreation, GameMode, GameState, LegacyQuestionnaireAnswers,
-    LegacyQuestionnaireProfile, apply_character_creation, apply_legacy_questionnaire_profile,
-    default_character_archetypes, derive_legacy_questionnaire_creation,
+    Alignment, CharacterCreation, DeterministicRng, GameMode, GameState,
+    LegacyQuestionnaireAnswers, LegacyQuestionnaireProfile, apply_character_creation,
+    apply_legacy_questionnaire_profile, default_character_archetypes,
+    derive_legacy_questionnaire_creation, random_player_name,
 };
 use omega_save::decode_state_json_for_mode;
 use std::fs;
@@ -280,7 +281,7 @@ fn questionnaire_character_creation(name: String) -> Result<CreationSelection> {
 fn prompt_character_creation() -> Result<CreationSelection> {
     println!();
     println!("=== Character Creation ===");
-    let name = read_line("Name (blank for Adventurer): ")?;
+    let name = read_line("Name (blank for a random name): ")?;
     let mode = read_line(CREATION_MODE_PROMPT)?;
     if mode.trim() == "2" {
         return questionnaire_character_creation(name);
@@ -489,7 +490,11 @@ fn main() -> Result<()> {
         match choice.as_str() {
             "1" => {
                 let mut bootstrap = load_bootstrap_state(mode)?;
-                let selection = prompt_character_creation()?;
+                let mut selection = prompt_character_creation()?;
+                if selection.creation.name.trim().is_empty() {
+                    selection.creation.name =
+                        random_player_name(&mut DeterministicRng::seeded(seed));
+                }
                 apply_character_creation(&mut bootstrap, &selection.creation);
                 if let Some(profile) = selection.legacy_profile {
                     apply_legacy_questionnaire_profile(&mut bootstrap, profile);
@@ -12,9 +12,12 @@ use omega_core::{
     Command, DeterministicRng, Direction, GameMode, GameState, ModalInputProfile,
     ObjectiveSnapshot, Outcome, Position, SessionStatus, SiteInteractionKind, TILE_FLAG_BLOCK_MOVE,
     TILE_FLAG_BURNING, TILE_FLAG_BURNT, active_activation_interaction_help_hint,
-    active_activation_interaction_prompt, active_inventory_interaction_help_hint,
+    active_activation_interaction_prompt, active_call_label_help_hint, active_call_label_prompt,
+    active_dialogue_help_hint, active_dialogue_prompt, active_guild_action_help_hint,
+    active_guild_action_prompt, active_inventory_interaction_help_hint,
     active_inventory_interaction_prompt, active_item_prompt, active_item_prompt_help_hint,
-    active_objective_snapshot, active_quit_interaction_help_hint, active_quit_interaction_prompt,
+    active_look_interaction_help_hint, active_look_interaction_prompt, active_objective_snapshot,
+    active_quit_interaction_help_hint, active_quit_interaction_prompt,
     active_site_interaction_help_hint, active_site_interaction_prompt,
     active_spell_interaction_help_hint, active_spell_interaction_prompt,
     active_talk_direction_help_hint, active_talk_direction_prompt,
@@ -603,13 +606,17 @@ pub fn map_shared_gameplay_key(key: BevyKey) -> InputAction {
             'A' => InputAction::Dispatch(Command::Attack(Direction::West)),
             'D' => InputAction::Dispatch(Command::Attack(Direction::East)),
             'g' => InputAction::Dispatch(Command::Pickup),
+            'Y' => InputAction::Dispatch(Command::OfferSurrender),
+            ';' => InputAction::Dispatch(Command::Look),
+            'B' => InputAction::Dispatch(Command::Butcher),
+            'K' => InputAction::Dispatch(Command::RecallCompanions),
             ',' | '@' | '<' | '>' | '?' | '/' => {
                 InputAction::Dispatch(Command::Legacy { token: ch.to_string() })
             }
             'a' | 'e' | 'f' | 'm' | 'o' | 'p' | 'r' | 't' | 'v' | 'x' | 'z' | 'c' => {
                 InputAction::Dispatch(Command::Legacy { token: ch.to_string() })
             }
-            'C' | 'E' | 'F' | 'G' | 'H' | 'I' | 'M' | 'O' | 'T' | 'V' | 'Z' => {
+            'C' | 'E' | 'F' | 'G' | 'H' | 'I' | 'J' | 'M' | 'O' | 'T' | 'U' | 'V' | 'Z' => {
                 InputAction::Dispatch(Command::Legacy { token: ch.to_string() })
             }
             'u' | 'y' | 'b' | 'n' => {
@@ -733,7 +740,7 @@ pub fn project_to_frame(
         }
     }
 
-    for monster in &state.monsters {
+    for monster in state.monsters.iter().chain(&state.companions) {
         tiles.push(TileRender {
             position: monster.position,
             kind: TileKind::Monster,
@@ -828,19 +835,27 @@ pub fn project_to_frame(
         .or_else(|| active_spell_interaction_prompt(state))
         .or_else(|| active_quit_interaction_prompt(state))
         .or_else(|| active_talk_direction_prompt(state))
+        .or_else(|| active_dialogue_prompt(state))
         .or_else(|| active_activation_interaction_prompt(state))
         .or_else(|| active_targeting_interaction_prompt(state))
+        .or_else(|| active_look_interaction_prompt(state))
+        .or_else(|| active_guild_action_prompt(state))
         .or_else(|| active_inventory_interaction_prompt(state))
         .or_else(|| active_item_prompt(state))
+        .or_else(|| active_call_label_prompt(state))
         .or_else(|| active_site_interaction_prompt(state));
     let active_hint = active_wizard_interaction_help_hint(state)
         .or_else(|| active_spell_interaction_help_hint(state))
         .or_else(|| active_quit_interaction_help_hint(state))
         .or_else(|| active_talk_direction_help_hint(state))
+        .or_else(|| active_dialogue_help_hint(state))
         .or_else(|| active_activation_interaction_help_hint(state))
         .or_else(|| active_targeting_interaction_help_hint(state))
+        .or_else(|| active_look_interaction_help_hint(state))
+        .or_else(|| active_guild_action_help_hint(state))
         .or_else(|| active_inventory_interaction_help_hint(state))
         .or_else(|| active_item_prompt_help_hint(state))
+        .or_else(|| active_call_label_help_hint(state))
         .or_else(|| active_site_interaction_help_hint(state));
     if let Some(prompt) = active_prompt.as_ref() {
         interaction_lines.push(format!("ACTIVE: {prompt}"));
@@ -914,6 +929,9 @@ fn describe_pending_interaction(state: &GameState) -> String {
     if state.pending_talk_direction.is_some() {
         return "directional talk/tunnel prompt".to_string();
     }
+    if state.pending_dialogue.is_some() {
+        return "conversation".to_string();
+    }
     if state.pending_targeting_interaction.is_some() {
         return "targeting prompt".to_string();
     }
@@ -923,6 +941,12 @@ fn describe_pending_interaction(state: &GameState) -> String {
     if state.pending_item_prompt.is_some() {
         return "item selection prompt".to_string();
     }
+    if state.pending_call_label.is_some() {
+        return "item naming prompt".to_string();
+    }
+    if state.pending_guild_action.is_some() {
+        return "guild action prompt".to_string();
+    }
     let Some(kind) = state.pending_site_interaction.as_ref() else {
         return "none".to_string();
     };
@@ -974,9 +998,12 @@ pub fn interaction_focus_state(state: &GameState) -> InteractionFocusState {
         || state.pending_spell_interaction.is_some()
         || state.pending_quit_interaction.is_some()
         || state.pending_talk_direction.is_some()
+        || state.pending_dialogue.is_some()
         || state.pending_activation_interaction.is_some()
         || state.pending_inventory_interaction.is_some()
         || state.pending_item_prompt.is_some()
+        || state.pending_call_label.is_some()
+        || state.pending_guild_action.is_some()
         || state.pending_site_interaction.is_some()
     {
         return if modal_input_profile(state) == ModalInputProfile::TextEntry {
@@ -1824,6 +1851,26 @@ mod tests {
             map_shared_gameplay_key(BevyKey::Char('g')),
             InputAction::Dispatch(Command::Pickup)
         );
+        assert_eq!(
+            map_shared_gameplay_key(BevyKey::Char(';')),
+            InputAction::Dispatch(Command::Look)
+        );
+        assert_eq!(
+            map_shared_gameplay_key(BevyKey::Char('B')),
+            InputAction::Dispatch(Command::Butcher)
+        );
+        assert_eq!(
+            map_shared_gameplay_key(BevyKey::Char('K')),
+            InputAction::Dispatch(Command::RecallCompanions)
+        );
+        assert_eq!(
+            map_shared_gameplay_key(BevyKey::Char('J')),
+            InputAction::Dispatch(Command::Legacy { token: "J".to_string() })
+        );
+        assert_eq!(
+            map_shared_gameplay_key(BevyKey::Char('U')),
+            InputAction::Dispatch(Command::Legacy { token: "U".to_string() })
+        );
         assert_eq!(
             map_shared_gameplay_key(BevyKey::Char('3')),
             InputAction::Dispatch(Command::Drop { slot: 2 })
@@ -2139,6 +2186,7 @@ mod tests {
             minutes: 6,
             status: SessionStatus::InProgress,
             events: vec![omega_core::Event::Waited],
+            should_autosave: false,
         };
         let frame = project_to_frame(&state, Some(&outcome), &SpriteAtlas::default());
         assert_eq!(
@@ -2158,6 +2206,7 @@ mod tests {
                 from: Position { x: 2, y: 2 },
                 to: Position { x: 3, y: 2 },
             }],
+            should_autosave: false,
         };
         let frame = project_to_frame(&state, Some(&outcome), &SpriteAtlas::default());
         assert!(frame.event_lines.iter().any(|line| line.contains("moved:")));
@@ -1,5 +1,6 @@
 use bevy_ecs::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
 use std::sync::OnceLock;
 
 pub mod color;
@@ -18,6 +19,49 @@ impl MapBounds {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum MapError {
+    #[error("map has no rows")]
+    Empty,
+    #[error("map row 0 has zero width")]
+    ZeroWidth,
+    #[error("map row {row} has width {found}, expected {expected}")]
+    RaggedRow { row: usize, expected: usize, found: usize },
+    #[error("spawn {spawn:?} lies outside map bounds {bounds:?}")]
+    SpawnOutOfBounds { spawn: Position, bounds: MapBounds },
+}
+
+/// Bounds of a rectangular map, or why `rows` do not form one.
+fn map_rows_bounds(rows: &[String]) -> Result<MapBounds, MapError> {
+    let Some(first) = rows.first() else {
+        return Err(MapError::Empty);
+    };
+    let expected = first.chars().count();
+    if expected == 0 {
+        return Err(MapError::ZeroWidth);
+    }
+    if let Some((row, found)) =
+        rows.iter().map(|row| row.chars().count()).enumerate().find(|(_, width)| *width != expected)
+    {
+        return Err(MapError::RaggedRow { row, expected, found });
+    }
+    Ok(MapBounds { width: expected as i32, height: rows.len() as i32 })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum EquipError {
+    #[error("item is not in the pack")]
+    ItemNotFound,
+    #[error("item does not fit that slot")]
+    WrongFamily,
+    #[error("a two-handed weapon leaves no hand for a shield")]
+    TwoHandedConflict,
+    #[error("item is cursed and cannot be moved")]
+    Cursed,
+    #[error("the item in that slot is cursed and cannot be removed")]
+    SlotOccupiedByCursed,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Component)]
 pub struct Position {
     pub x: i32,
@@ -51,12 +95,24 @@ pub struct Stats {
 }
 
 impl Stats {
+    /// Removes up to `raw_damage` hp, never below zero, and returns how much
+    /// was actually taken. Negative damage is ignored rather than healing.
     fn apply_damage(&mut self, raw_damage: i32) -> i32 {
-        let applied = raw_damage.max(0).min(self.hp.max(0));
-        self.hp -= applied;
+        let applied = raw_damage.clamp(0, self.hp.max(0));
+        self.set_hp(self.hp - applied);
         applied
     }
 
+    /// Restores hp up to `max_hp`; negative amounts are ignored.
+    fn heal(&mut self, amount: i32) {
+        self.set_hp(self.hp.saturating_add(amount.max(0)));
+    }
+
+    /// Sets hp, keeping `0 <= hp <= max_hp`.
+    fn set_hp(&mut self, hp: i32) {
+        self.hp = hp.clamp(0, self.max_hp.max(0));
+    }
+
     fn is_alive(self) -> bool {
         self.hp > 0
     }
@@ -130,6 +186,10 @@ pub struct Item {
     pub truename: String,
     #[serde(default)]
     pub cursestr: String,
+    #[serde(default)]
+    pub poison_doses: i32,
+    #[serde(default)]
+    pub poison_potency: i32,
 }
 
 impl Item {
@@ -208,6 +268,8 @@ pub const TILE_FLAG_BLOCK_MOVE: u16 = 0x0008;
 pub const TILE_FLAG_OPENED_DOOR: u16 = 0x0010;
 pub const TILE_FLAG_BURNING: u16 = 0x0020;
 pub const TILE_FLAG_BURNT: u16 = 0x0040;
+pub const TILE_FLAG_LOCKED_DOOR: u16 = 0x0080;
+pub const TILE_FLAG_LIT: u16 = 0x0100;
 
 pub const SITE_AUX_NONE: i32 = 0;
 pub const SITE_AUX_EXIT_COUNTRYSIDE: i32 = 1;
@@ -337,6 +399,62 @@ impl Default for TileSiteCell {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GlyphRule {
+    pub glyph: char,
+    #[serde(default)]
+    pub world_mode: Option<WorldMode>,
+    pub walkable: bool,
+    #[serde(default)]
+    pub flags: u16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GlyphLegend {
+    pub rules: Vec<GlyphRule>,
+}
+
+impl Default for GlyphLegend {
+    fn default() -> Self {
+        let blocked = |glyph: char, world_mode: Option<WorldMode>| GlyphRule {
+            glyph,
+            world_mode,
+            walkable: false,
+            flags: 0,
+        };
+        Self {
+            rules: vec![
+                blocked('#', None),
+                blocked('=', Some(WorldMode::DungeonCity)),
+                blocked('-', Some(WorldMode::DungeonCity)),
+                blocked('D', Some(WorldMode::DungeonCity)),
+                blocked('J', Some(WorldMode::DungeonCity)),
+            ],
+        }
+    }
+}
+
+impl GlyphLegend {
+    // Later rules win, so content can override a default by appending.
+    pub fn rule_for(&self, glyph: char, world_mode: WorldMode) -> Option<&GlyphRule> {
+        self.rules.iter().rev().find(|rule| {
+            rule.glyph == glyph && rule.world_mode.is_none_or(|mode| mode == world_mode)
+        })
+    }
+
+    pub fn is_walkable(&self, glyph: char, world_mode: WorldMode) -> bool {
+        self.rule_for(glyph, world_mode).is_none_or(|rule| rule.walkable)
+    }
+
+    pub fn flags(&self, glyph: char, world_mode: WorldMode) -> u16 {
+        self.rule_for(glyph, world_mode).map_or(0, |rule| rule.flags)
+    }
+
+    pub fn set_rule(&mut self, rule: GlyphRule) {
+        self.rules.push(rule);
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct SiteMapDefinition {
     pub map_id: u16,
@@ -465,6 +583,43 @@ pub enum CombatManeuver {
     Lunge,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TimeOfDay {
+    Dawn,
+    Day,
+    Dusk,
+    Night,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum EncumbranceTier {
+    #[default]
+    Unencumbered,
+    Burdened,
+    Strained,
+    Overloaded,
+}
+
+impl EncumbranceTier {
+    pub fn defense_penalty(self) -> i32 {
+        match self {
+            Self::Unencumbered => 0,
+            Self::Burdened => 1,
+            Self::Strained => 2,
+            Self::Overloaded => 4,
+        }
+    }
+
+    pub fn agility_penalty(self) -> i32 {
+        match self {
+            Self::Unencumbered => 0,
+            Self::Burdened => 1,
+            Self::Strained => 3,
+            Self::Overloaded => 6,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct CombatStep {
     pub maneuver: CombatManeuver,
@@ -477,6 +632,16 @@ impl Default for CombatStep {
     }
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GuildContract {
+    /// Map the target lies on; the same coordinates elsewhere don't count.
+    #[serde(default)]
+    pub map_id: u16,
+    pub target: Position,
+    pub deadline_turn: u64,
+    pub reward_gold: i32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
 pub struct GuildTrackState {
     #[serde(default)]
@@ -485,12 +650,16 @@ pub struct GuildTrackState {
     pub xp: i64,
     #[serde(default)]
     pub dues_paid: i64,
+    /// What the guild owes the player: salary accrues upward and weekly dues
+    /// downward, so a negative balance is dues in arrears.
     #[serde(default)]
     pub salary_due: i64,
     #[serde(default)]
     pub promotion_flags: u64,
     #[serde(default)]
     pub quest_flags: u64,
+    #[serde(default)]
+    pub active_contract: Option<GuildContract>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
@@ -603,6 +772,11 @@ pub struct PlayerProgression {
     pub arena_opponent: u8,
     #[serde(default)]
     pub arena_match_active: bool,
+    /// Gold staked on the player's next arena bout; paid out at `arena_wager_odds`.
+    #[serde(default)]
+    pub arena_wager: i32,
+    #[serde(default)]
+    pub arena_wager_odds: i32,
     pub quest_state: LegacyQuestState,
     pub total_winner_unlocked: bool,
     pub quest_steps_completed: u8,
@@ -634,6 +808,8 @@ impl Default for PlayerProgression {
             arena_rank: 0,
             arena_opponent: 0,
             arena_match_active: false,
+            arena_wager: 0,
+            arena_wager_odds: 0,
             quest_state: LegacyQuestState::NotStarted,
             total_winner_unlocked: false,
             quest_steps_completed: 0,
@@ -700,6 +876,115 @@ pub struct StatusEffect {
     pub magnitude: i32,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum StatusCategory {
+    Buff,
+    Debuff,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StatusSummary {
+    pub id: String,
+    pub name: String,
+    pub remaining_turns: u32,
+    pub magnitude: i32,
+    pub category: StatusCategory,
+}
+
+const AURA_SIGHT_STATUS: &str = "aura_sight";
+const AURA_SIGHT_RADIUS: i32 = 6;
+const AURA_SIGHT_TRAIT_RADIUS: i32 = 2;
+
+fn session_status_display_name(status: SessionStatus) -> &'static str {
+    match status {
+        SessionStatus::InProgress => "in progress",
+        SessionStatus::Won => "won",
+        SessionStatus::Lost => "lost",
+    }
+}
+
+fn quest_state_display_name(state: LegacyQuestState) -> &'static str {
+    match state {
+        LegacyQuestState::NotStarted => "not started",
+        LegacyQuestState::Active => "active",
+        LegacyQuestState::ArtifactRecovered => "artifact recovered",
+        LegacyQuestState::ReturnToPatron => "return to patron",
+        LegacyQuestState::Completed => "completed",
+        LegacyQuestState::Failed => "failed",
+    }
+}
+
+fn alignment_display_name(alignment: Alignment) -> &'static str {
+    match alignment {
+        Alignment::Lawful => "lawful",
+        Alignment::Neutral => "neutral",
+        Alignment::Chaotic => "chaotic",
+    }
+}
+
+fn environment_display_name(environment: LegacyEnvironment) -> &'static str {
+    match environment {
+        LegacyEnvironment::City => "Rampart",
+        LegacyEnvironment::Countryside => "the countryside",
+        LegacyEnvironment::Village => "the village",
+        LegacyEnvironment::TacticalMap => "the tactical map",
+        LegacyEnvironment::Sewers => "the sewers",
+        LegacyEnvironment::Castle => "the castle",
+        LegacyEnvironment::Palace => "the palace",
+        LegacyEnvironment::Caves => "the caves",
+        LegacyEnvironment::Volcano => "the volcano",
+        LegacyEnvironment::Astral => "the astral plane",
+        LegacyEnvironment::Arena => "the arena",
+        LegacyEnvironment::Hovel => "the hovel",
+        LegacyEnvironment::Mansion => "the mansion",
+        LegacyEnvironment::House => "the house",
+        LegacyEnvironment::DragonLair => "the dragon's lair",
+        LegacyEnvironment::Abyss => "the abyss",
+        LegacyEnvironment::StarPeak => "Star Peak",
+        LegacyEnvironment::MagicIsle => "the Magic Isle",
+        LegacyEnvironment::Temple => "the temple",
+        LegacyEnvironment::Circle => "the Circle of Sorcerors",
+        LegacyEnvironment::HedgeMaze => "the hedge maze",
+        LegacyEnvironment::Unknown => "somewhere unknown",
+    }
+}
+
+fn status_display_name(id: &str) -> String {
+    let name = match id {
+        "poison" => "Poisoned",
+        "haste" => "Hasted",
+        "slow" => "Slowed",
+        "immobile" => "Paralyzed",
+        "fear" => "Afraid",
+        "invisible" => "Invisible",
+        "levitate" => "Levitating",
+        "regen" => "Regenerating",
+        "truesight" => "True sight",
+        AURA_SIGHT_STATUS => "Aura sight",
+        "block_bonus" => "Guarding",
+        "riposte_ready" => "Riposte ready",
+        _ => {
+            let spaced = id.replace('_', " ");
+            let mut chars = spaced.chars();
+            return match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            };
+        }
+    };
+    name.to_string()
+}
+
+fn status_category(id: &str, magnitude: i32) -> StatusCategory {
+    match id {
+        "poison" | "slow" | "immobile" | "fear" | "blind" | "confused" | "disease" | "burning" => {
+            StatusCategory::Debuff
+        }
+        _ if magnitude < 0 => StatusCategory::Debuff,
+        _ => StatusCategory::Buff,
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct RuntimeOptions {
     pub topinv: bool,
@@ -715,6 +1000,22 @@ pub struct RuntimeOptions {
     pub searchnum: u8,
     #[serde(default)]
     pub interactive_sites: bool,
+    #[serde(default = "default_autosave_gold_threshold")]
+    pub autosave_gold_threshold: i32,
+    #[serde(default)]
+    pub combat_variance: CombatVariance,
+}
+
+/// Shape of the damage roll between a combatant's `attack_min` and `attack_max`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum CombatVariance {
+    /// Every value in the range is equally likely (the legacy behaviour).
+    #[default]
+    Uniform,
+    /// Two rolls averaged together, clustering hits around the midpoint.
+    Average,
+    /// Two rolls keeping whichever lands further from the midpoint.
+    Extreme,
 }
 
 impl Default for RuntimeOptions {
@@ -732,6 +1033,8 @@ impl Default for RuntimeOptions {
             verbosity: LegacyVerbosity::Medium,
             searchnum: 1,
             interactive_sites: false,
+            autosave_gold_threshold: default_autosave_gold_threshold(),
+            combat_variance: CombatVariance::Uniform,
         }
     }
 }
@@ -766,6 +1069,14 @@ pub enum SiteInteractionKind {
     Altar { deity_id: u8 },
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum GuildAction {
+    Join,
+    TakeContract,
+    Promote,
+    Train,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum WizardInteraction {
     EnterWizardConfirm { via_backdoor: bool },
@@ -777,6 +1088,8 @@ pub enum WizardInteraction {
     StatEditorSelect { slot: u8 },
     StatEditorValueEntry { slot: u8 },
     BashDirectionSelect,
+    TeleportEntry,
+    SpawnMonsterEntry,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -797,6 +1110,8 @@ pub enum ItemPromptContext {
     ActivateArtifact,
     CallItem,
     Give,
+    Recharge,
+    Enchant,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -831,6 +1146,44 @@ pub enum QuitInteraction {
 pub enum TalkDirectionInteraction {
     Talk,
     Tunnel,
+    OpenDoor,
+    CloseDoor,
+    Engage,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum DialogueAction {
+    Rumor,
+    QuestOffer,
+    Trade,
+    AskAboutLaw,
+    PayFine,
+    Farewell,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DialogueNode {
+    pub text: String,
+    pub options: Vec<(String, DialogueAction)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CallLabelInteraction {
+    pub item_id: u32,
+    pub item_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DialogueInteraction {
+    pub speaker: String,
+    pub monster_id: u64,
+    pub node: DialogueNode,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TunnelProgress {
+    pub target: Position,
+    pub effort: i32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -840,6 +1193,26 @@ pub struct TargetingInteraction {
     pub mode: ProjectileKind,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LookInteraction {
+    pub cursor: Position,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DuelState {
+    pub monster_id: u64,
+    pub round: u32,
+}
+
+/// Noise from the player's last attack, waiting for the monster phase to
+/// alert the struck monster's allies within `radius` of `origin`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CombatAlarm {
+    pub origin: Position,
+    pub faction: Faction,
+    pub radius: i32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct PendingProjectileAction {
     pub source_token: String,
@@ -880,6 +1253,7 @@ pub enum ProjectileKind {
     MagicMissile,
     FireBolt,
     LightningBolt,
+    Healing,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -986,6 +1360,10 @@ pub struct WorldTopology {
     pub last_city_position: Option<Position>,
     pub last_country_position: Option<Position>,
     pub country_rampart_position: Option<Position>,
+    #[serde(default)]
+    pub country_region_positions: BTreeMap<u8, Position>,
+    #[serde(default)]
+    pub country_region_exits: BTreeMap<u16, u8>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
@@ -1013,6 +1391,18 @@ pub struct SpellbookState {
     pub next_spell_index: u8,
 }
 
+impl SpellbookState {
+    /// Restores mana up to `max_mana`; negative amounts are ignored.
+    fn restore_mana(&mut self, amount: i32) {
+        self.set_mana(self.mana.saturating_add(amount.max(0)));
+    }
+
+    /// Sets mana, keeping `0 <= mana <= max_mana`.
+    fn set_mana(&mut self, mana: i32) {
+        self.mana = mana.clamp(0, self.max_mana.max(0));
+    }
+}
+
 impl Default for SpellbookState {
     fn default() -> Self {
         Self { mana: 120, max_mana: 120, spells: default_spellbook_spells(), next_spell_index: 0 }
@@ -1035,6 +1425,18 @@ pub struct CharacterCreation {
     pub alignment: Alignment,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct QuestionnaireTraits {
+    #[serde(default)]
+    pub has_esp: bool,
+    #[serde(default)]
+    pub is_irish: bool,
+    #[serde(default)]
+    pub animals_react_oddly: bool,
+    #[serde(default)]
+    pub can_see_auras: bool,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct LegacyQuestionnaireProfile {
     pub strength: i32,
@@ -1044,6 +1446,7 @@ pub struct LegacyQuestionnaireProfile {
     pub constitution: i32,
     pub power: i32,
     pub preference: char,
+    pub traits: QuestionnaireTraits,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -1303,34 +1706,54 @@ pub fn derive_legacy_questionnaire_profile(
         _ => 'n',
     };
 
-    LegacyQuestionnaireProfile { strength, iq, agility, dexterity, constitution, power, preference }
+    let traits = QuestionnaireTraits {
+        has_esp: answers.has_esp,
+        is_irish: answers.is_irish,
+        animals_react_oddly: answers.animals_react_oddly,
+        can_see_auras: answers.can_see_auras,
+    };
+
+    LegacyQuestionnaireProfile {
+        strength,
+        iq,
+        agility,
+        dexterity,
+        constitution,
+        power,
+        preference,
+        traits,
+    }
 }
 
-pub fn derive_legacy_questionnaire_creation(
-    name: String,
-    answers: &LegacyQuestionnaireAnswers,
-) -> LegacyQuestionnaireCreation {
-    let profile = derive_legacy_questionnaire_profile(answers);
+pub fn questionnaire_archetype_scores(profile: &LegacyQuestionnaireProfile) -> Vec<(String, i32)> {
     let fighter_score = profile.strength * 3 + profile.constitution * 2 + profile.agility;
     let rogue_score = profile.dexterity * 3 + profile.agility * 2 + profile.iq;
     let mage_score = profile.iq * 3 + profile.power * 3 + profile.dexterity;
     let priest_score = profile.power * 3 + profile.iq * 2 + profile.constitution;
 
-    let mut best_id = "fighter";
-    let mut best_score = fighter_score;
-    for (id, score) in [("rogue", rogue_score), ("mage", mage_score), ("priest", priest_score)] {
-        if score > best_score {
-            best_id = id;
-            best_score = score;
-        }
-    }
-    let _ = best_score;
+    let mut scores = vec![
+        ("fighter".to_string(), fighter_score),
+        ("rogue".to_string(), rogue_score),
+        ("mage".to_string(), mage_score),
+        ("priest".to_string(), priest_score),
+    ];
+    // Stable sort keeps the legacy tie-break order (fighter, rogue, mage, priest).
+    scores.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+    scores
+}
 
-    let creation = CharacterCreation {
-        name,
-        archetype_id: best_id.to_string(),
-        alignment: Alignment::Neutral,
-    };
+pub fn derive_legacy_questionnaire_creation(
+    name: String,
+    answers: &LegacyQuestionnaireAnswers,
+) -> LegacyQuestionnaireCreation {
+    let profile = derive_legacy_questionnaire_profile(answers);
+    let best_id = questionnaire_archetype_scores(&profile)
+        .into_iter()
+        .next()
+        .map(|(id, _)| id)
+        .unwrap_or_else(|| "fighter".to_string());
+
+    let creation = CharacterCreation { name, archetype_id: best_id, alignment: Alignment::Neutral };
     LegacyQuestionnaireCreation { creation, profile }
 }
 
@@ -1344,6 +1767,7 @@ pub fn apply_legacy_questionnaire_profile(
     state.attributes.dexterity = profile.dexterity.clamp(1, 32);
     state.attributes.constitution = profile.constitution.clamp(1, 32);
     state.attributes.power = profile.power.clamp(1, 32);
+    state.questionnaire_traits = profile.traits;
 
     let base_stats = state.player.stats;
     let strength_delta = profile.strength - 12;
@@ -1523,12 +1947,12 @@ fn recompute_derived_combat_and_mana_from_attributes(state: &mut GameState) {
     let max_mana = (100 + power_delta * 6 + iq_delta * 3).clamp(40, 320);
 
     state.player.stats.max_hp = max_hp;
-    state.player.stats.hp = state.player.stats.hp.clamp(0, max_hp);
+    state.player.stats.set_hp(state.player.stats.hp);
     state.player.stats.attack_min = attack_min;
     state.player.stats.attack_max = attack_max;
     state.player.stats.defense = defense;
     state.spellbook.max_mana = max_mana;
-    state.spellbook.mana = state.spellbook.mana.clamp(0, max_mana);
+    state.spellbook.set_mana(state.spellbook.mana);
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -1560,6 +1984,14 @@ pub enum MonsterBehavior {
     Social,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum Awareness {
+    Asleep,
+    #[default]
+    Aware,
+    Searching,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
 pub enum Faction {
     Law,
@@ -1569,6 +2001,20 @@ pub enum Faction {
     Wild,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum BreathElement {
+    Fire,
+    Cold,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SpecialAttack {
+    Drain,
+    Breath { element: BreathElement, damage: i32 },
+    ParalyzingGaze { turns: u32 },
+    Steal,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Monster {
     pub id: u64,
@@ -1583,6 +2029,81 @@ pub struct Monster {
     pub display_glyph: Option<char>,
     #[serde(default)]
     pub on_death_drops: Vec<Item>,
+    #[serde(default)]
+    pub special_attacks: Vec<SpecialAttack>,
+    #[serde(default)]
+    pub allied: bool,
+    #[serde(default)]
+    pub pack_id: Option<u64>,
+    #[serde(default)]
+    pub status_effects: Vec<StatusEffect>,
+    #[serde(default)]
+    pub awareness: Awareness,
+    /// Story bosses that accept a formal duel via `Command::Engage`.
+    #[serde(default)]
+    pub duelist: bool,
+}
+
+impl Monster {
+    /// City guards are the social monsters whose name says so.
+    fn is_guard(&self) -> bool {
+        self.behavior == MonsterBehavior::Social && self.name.to_ascii_lowercase().contains("guard")
+    }
+
+    /// Allies never turn on the player. A belligerent player picks fights
+    /// with everyone else, so every other monster answers in kind; otherwise
+    /// guards watch legal heat and the rest follow faction and alignment.
+    pub fn is_hostile_to_player(&self, state: &GameState) -> bool {
+        if self.allied {
+            return false;
+        }
+        if state.options.belligerent {
+            return true;
+        }
+        if self.is_guard() {
+            return state.legal_heat >= GUARD_HOSTILE_HEAT;
+        }
+        !matches!(
+            (self.faction, state.progression.alignment, self.behavior),
+            (Faction::Law, Alignment::Lawful, _)
+                | (Faction::Chaos, Alignment::Chaotic, _)
+                | (Faction::Neutral | Faction::Wild, _, MonsterBehavior::Social)
+        )
+    }
+
+    /// A comparable danger score for frontends to sort or highlight by.
+    /// Raw power is scaled by behavior, padded for special attacks, and
+    /// weighted toward monsters close enough to act this turn. Peaceful or
+    /// dead monsters score zero.
+    pub fn threat_level(&self, state: &GameState) -> u32 {
+        if !self.is_hostile_to_player(state) || !self.stats.is_alive() {
+            return 0;
+        }
+        let power =
+            self.stats.hp.max(0) + self.stats.attack_max.max(0) * 3 + self.stats.defense.max(0) * 2;
+        let behavior_percent = match self.behavior {
+            MonsterBehavior::Caster => 150,
+            MonsterBehavior::Skirmisher => 120,
+            MonsterBehavior::Brute => 100,
+            MonsterBehavior::Social => 50,
+        };
+        let specials: i32 = self
+            .special_attacks
+            .iter()
+            .map(|special| match special {
+                SpecialAttack::Breath { damage, .. } => (*damage).max(0) + 10,
+                SpecialAttack::ParalyzingGaze { .. } => 20,
+                SpecialAttack::Drain => 15,
+                SpecialAttack::Steal => 5,
+            })
+            .sum();
+        let distance = projectile_distance(self.position, state.player.position).max(1);
+        let proximity_percent = (120 - distance * 10).max(20);
+        let awake_percent = if self.awareness == Awareness::Asleep { 50 } else { 100 };
+        let base = power * behavior_percent / 100 + specials;
+        let score = base * proximity_percent / 100 * awake_percent / 100;
+        score.max(1) as u32
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -1592,6 +2113,72 @@ pub struct Trap {
     pub damage: i32,
     pub effect_id: String,
     pub armed: bool,
+    #[serde(default)]
+    pub discovered: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileDescription {
+    pub position: Position,
+    pub glyph: char,
+    pub items: Vec<String>,
+    pub traps: Vec<String>,
+    pub site: Option<String>,
+    pub engraving: Option<String>,
+}
+
+impl TileDescription {
+    pub fn is_notable(&self) -> bool {
+        !self.items.is_empty()
+            || !self.traps.is_empty()
+            || self.site.is_some()
+            || self.engraving.is_some()
+    }
+
+    pub fn summary(&self) -> String {
+        let mut parts = self.items.clone();
+        parts.extend(self.traps.iter().map(|effect| format!("a {effect} trap")));
+        parts.extend(self.site.clone());
+        let engraving =
+            self.engraving.as_ref().map(|text| format!("Something is engraved here: \"{text}\"."));
+        match (parts.is_empty(), engraving) {
+            (true, Some(engraving)) => engraving,
+            (false, Some(engraving)) => {
+                format!("You see here: {}. {engraving}", parts.join(", "))
+            }
+            _ => format!("You see here: {}.", parts.join(", ")),
+        }
+    }
+}
+
+// Units on hand for the shop's wares, indexed by menu choice: ration, potion, identify scroll.
+// A haggled price sticks to the shop until its next restock.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct ShopStock {
+    pub day: u64,
+    pub wares: [u8; 3],
+    #[serde(default)]
+    pub haggle_percent: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Engraving {
+    #[serde(default)]
+    pub map_id: u16,
+    pub position: Position,
+    pub text: String,
+    pub turn_engraved: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Container {
+    pub position: Position,
+    #[serde(default)]
+    pub locked: bool,
+    #[serde(default)]
+    pub trapped: bool,
+    #[serde(default)]
+    pub contents: Vec<Item>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -1601,6 +2188,12 @@ pub struct GameClock {
     pub minutes_per_turn: u64,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EnvironmentTurnMinutes {
+    pub environment: LegacyEnvironment,
+    pub minutes_per_turn: u64,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
 pub enum SessionStatus {
     #[default]
@@ -1629,6 +2222,13 @@ pub struct GameState {
     #[serde(default)]
     pub country_grid: CountryGrid,
     #[serde(default)]
+    pub country_regions: BTreeMap<u8, CountryGrid>,
+    /// Map rows of each stored region, kept alongside `country_regions`.
+    #[serde(default)]
+    pub country_region_rows: BTreeMap<u8, Vec<String>>,
+    #[serde(default)]
+    pub glyph_legend: GlyphLegend,
+    #[serde(default)]
     pub city_map_id: u16,
     #[serde(default)]
     pub city_level_index: u16,
@@ -1667,12 +2267,27 @@ pub struct GameState {
     #[serde(default)]
     pub pending_talk_direction: Option<TalkDirectionInteraction>,
     #[serde(default)]
+    pub pending_dialogue: Option<DialogueInteraction>,
+    #[serde(default)]
+    pub tunnel_progress: Option<TunnelProgress>,
+    #[serde(default)]
     pub pending_inventory_interaction: Option<InventoryInteraction>,
     #[serde(default)]
     pub pending_item_prompt: Option<ItemPromptInteraction>,
     #[serde(default)]
+    pub pending_call_label: Option<CallLabelInteraction>,
+    #[serde(default)]
     pub pending_targeting_interaction: Option<TargetingInteraction>,
     #[serde(default)]
+    pub pending_look_interaction: Option<LookInteraction>,
+    /// Guild whose action prompt (`U`) is open, waiting for a letter.
+    #[serde(default)]
+    pub pending_guild_action: Option<SiteInteractionKind>,
+    #[serde(default)]
+    pub active_duel: Option<DuelState>,
+    #[serde(default)]
+    pub pending_combat_alarm: Option<CombatAlarm>,
+    #[serde(default)]
     pub pending_projectile_action: Option<PendingProjectileAction>,
     #[serde(default)]
     pub transient_projectile_path: Vec<Position>,
@@ -1704,8 +2319,12 @@ pub struct GameState {
     pub resistances: ResistanceProfile,
     #[serde(default)]
     pub immunities: ImmunityFlags,
+    #[serde(default)]
+    pub questionnaire_traits: QuestionnaireTraits,
     #[serde(default = "default_encounter_monsters")]
     pub encounter_monsters: Vec<String>,
+    #[serde(default = "default_environment_turn_minutes")]
+    pub environment_turn_minutes: Vec<EnvironmentTurnMinutes>,
     #[serde(default)]
     pub topology: WorldTopology,
     #[serde(default)]
@@ -1720,10 +2339,22 @@ pub struct GameState {
     pub bank_gold: i32,
     #[serde(default = "default_food")]
     pub food: i32,
+    /// Set once the too-hungry-to-recover warning has been logged, so it
+    /// appears when starvation begins rather than on every blocked tick.
+    #[serde(default)]
+    pub hunger_recovery_warned: bool,
     #[serde(default)]
     pub legal_heat: i32,
     #[serde(default)]
+    pub shop_stock: BTreeMap<u64, ShopStock>,
+    #[serde(default)]
+    pub pawn_stock: Vec<Item>,
+    #[serde(default)]
     pub known_sites: Vec<Position>,
+    /// Tiles auto-explore has already seen on the active map. Cleared whenever
+    /// the map rows change; unrelated to the site list in `known_sites`.
+    #[serde(default)]
+    pub explored_tiles: Vec<Position>,
     #[serde(default)]
     pub pending_confirmation: Option<String>,
     #[serde(default)]
@@ -1740,7 +2371,16 @@ pub struct GameState {
     pub carry_burden: i32,
     #[serde(default)]
     pub traps: Vec<Trap>,
+    #[serde(default)]
+    pub containers: Vec<Container>,
+    #[serde(default)]
+    pub engravings: Vec<Engraving>,
     pub monsters: Vec<Monster>,
+    #[serde(default)]
+    pub companions: Vec<Monster>,
+    /// Wages owed to hired companions that the purse could not cover.
+    #[serde(default)]
+    pub companion_wages_due: i64,
     pub ground_items: Vec<GroundItem>,
     pub log: Vec<String>,
     #[serde(default)]
@@ -1759,8 +2399,37 @@ fn default_player_name() -> String {
     "Adventurer".to_string()
 }
 
-fn default_gold() -> i32 {
-    250
+const NAME_ONSETS: [&str; 16] = [
+    "al", "bel", "cor", "dar", "el", "fen", "gal", "hal", "is", "jor", "kal", "lor", "mor", "nim",
+    "or", "thal",
+];
+const NAME_MIDDLES: [&str; 8] = ["a", "e", "i", "o", "an", "en", "ar", "il"];
+const NAME_CODAS: [&str; 12] =
+    ["dor", "wen", "ric", "mir", "as", "eth", "wyn", "gar", "ion", "is", "ur", "ith"];
+
+fn pick_name_part(rng: &mut impl RandomSource, table: &[&'static str]) -> &'static str {
+    debug_assert!(!table.is_empty());
+    table[rng.range_inclusive_i32(0, table.len() as i32 - 1) as usize]
+}
+
+/// Builds a fantasy name from the syllable tables, for frontends to offer when
+/// character creation leaves the name blank. The same rng state always yields
+/// the same name.
+pub fn random_player_name(rng: &mut impl RandomSource) -> String {
+    let mut name = pick_name_part(rng, &NAME_ONSETS).to_string();
+    if rng.range_inclusive_i32(0, 1) == 1 {
+        name.push_str(pick_name_part(rng, &NAME_MIDDLES));
+    }
+    name.push_str(pick_name_part(rng, &NAME_CODAS));
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+        None => default_player_name(),
+    }
+}
+
+fn default_gold() -> i32 {
+    250
 }
 
 fn default_food() -> i32 {
@@ -1771,6 +2440,10 @@ fn default_action_points_per_turn() -> u16 {
     100
 }
 
+fn default_autosave_gold_threshold() -> i32 {
+    500
+}
+
 fn default_pack_capacity() -> usize {
     26
 }
@@ -1783,6 +2456,13 @@ fn default_encounter_monsters() -> Vec<String> {
     vec!["wolf".to_string(), "bandit".to_string(), "goblin".to_string(), "stalker".to_string()]
 }
 
+fn default_environment_turn_minutes() -> Vec<EnvironmentTurnMinutes> {
+    vec![EnvironmentTurnMinutes {
+        environment: LegacyEnvironment::Countryside,
+        minutes_per_turn: 60,
+    }]
+}
+
 fn default_spellbook_spells() -> Vec<SpellState> {
     LEGACY_SPELL_COSTS
         .iter()
@@ -1805,6 +2485,9 @@ impl GameState {
             city_site_grid: Vec::new(),
             country_site_grid: Vec::new(),
             country_grid: CountryGrid::default(),
+            country_regions: BTreeMap::new(),
+            country_region_rows: BTreeMap::new(),
+            glyph_legend: GlyphLegend::default(),
             city_map_id: 0,
             city_level_index: 0,
             city_map_source: String::new(),
@@ -1845,9 +2528,16 @@ impl GameState {
             pending_activation_interaction: None,
             pending_quit_interaction: None,
             pending_talk_direction: None,
+            pending_dialogue: None,
+            tunnel_progress: None,
             pending_inventory_interaction: None,
             pending_item_prompt: None,
+            pending_call_label: None,
             pending_targeting_interaction: None,
+            pending_look_interaction: None,
+            pending_guild_action: None,
+            active_duel: None,
+            pending_combat_alarm: None,
             pending_projectile_action: None,
             transient_projectile_path: Vec::new(),
             transient_projectile_impact: None,
@@ -1864,7 +2554,9 @@ impl GameState {
             attributes: PrimaryAttributes::default(),
             resistances: ResistanceProfile::default(),
             immunities: ImmunityFlags::default(),
+            questionnaire_traits: QuestionnaireTraits::default(),
             encounter_monsters: default_encounter_monsters(),
+            environment_turn_minutes: default_environment_turn_minutes(),
             topology: WorldTopology::default(),
             scheduler: TurnScheduler::default(),
             spellbook: SpellbookState::default(),
@@ -1872,8 +2564,12 @@ impl GameState {
             gold: default_gold(),
             bank_gold: 0,
             food: default_food(),
+            hunger_recovery_warned: false,
             legal_heat: 0,
+            shop_stock: BTreeMap::new(),
+            pawn_stock: Vec::new(),
             known_sites: Vec::new(),
+            explored_tiles: Vec::new(),
             pending_confirmation: None,
             pending_site_interaction: None,
             combat_sequence: default_combat_sequence(),
@@ -1882,7 +2578,11 @@ impl GameState {
             action_points_per_turn: default_action_points_per_turn(),
             carry_burden: 0,
             traps: Vec::new(),
+            containers: Vec::new(),
+            engravings: Vec::new(),
             monsters: Vec::new(),
+            companions: Vec::new(),
+            companion_wages_due: 0,
             ground_items: Vec::new(),
             log: Vec::new(),
             status: SessionStatus::InProgress,
@@ -1910,6 +2610,8 @@ impl GameState {
         self.next_entity_id += 1;
         let name = name.into();
         let (behavior, faction) = infer_monster_profile(&name);
+        let special_attacks = infer_special_attacks(&name);
+        let duelist = infer_monster_is_duelist(&name);
         self.monsters.push(Monster {
             id,
             name,
@@ -1919,10 +2621,73 @@ impl GameState {
             faction,
             display_glyph: None,
             on_death_drops: Vec::new(),
+            special_attacks,
+            allied: false,
+            pack_id: None,
+            status_effects: Vec::new(),
+            awareness: Awareness::Aware,
+            duelist,
         });
         id
     }
 
+    /// Spawns a monster together with the gear it was fighting with, rolled
+    /// from its loot table.
+    pub fn spawn_equipped_monster(
+        &mut self,
+        name: impl Into<String>,
+        position: Position,
+        stats: Stats,
+        rng: &mut impl RandomSource,
+    ) -> u64 {
+        let name = name.into();
+        let gear = self.roll_equipped_drops(&name, rng);
+        let id = self.spawn_monster(name, position, stats);
+        if let Some(monster) = self.monsters.iter_mut().find(|monster| monster.id == id) {
+            monster.on_death_drops = gear;
+        }
+        id
+    }
+
+    pub fn spawn_pack(
+        &mut self,
+        name: &str,
+        center: Position,
+        size: usize,
+        rng: &mut impl RandomSource,
+    ) -> Vec<u64> {
+        let mut open = Vec::new();
+        for dy in -PACK_SPAWN_RADIUS..=PACK_SPAWN_RADIUS {
+            for dx in -PACK_SPAWN_RADIUS..=PACK_SPAWN_RADIUS {
+                let pos = Position { x: center.x + dx, y: center.y + dy };
+                if self.bounds.contains(pos)
+                    && self.tile_is_walkable(pos)
+                    && !is_occupied(self, pos)
+                {
+                    open.push(pos);
+                }
+            }
+        }
+        open.sort_by_key(|pos| {
+            ((pos.x - center.x).abs().max((pos.y - center.y).abs()), pos.y, pos.x)
+        });
+
+        let (behavior, _faction) = infer_monster_profile(name);
+        let mut ids = Vec::new();
+        while ids.len() < size && !open.is_empty() {
+            // Pick among the nearest free tiles so the pack stays bunched but not in a fixed shape.
+            let window = open.len().min((size - ids.len()) * 2);
+            let pick = rng.range_inclusive_i32(0, window as i32 - 1).clamp(0, window as i32 - 1);
+            let pos = open.remove(pick as usize);
+            ids.push(self.spawn_equipped_monster(name, pos, wizard_spawn_stats(behavior), rng));
+        }
+        let pack_id = ids.first().copied();
+        for monster in self.monsters.iter_mut().filter(|monster| ids.contains(&monster.id)) {
+            monster.pack_id = pack_id;
+        }
+        ids
+    }
+
     pub fn place_item(&mut self, name: impl Into<String>, position: Position) -> u32 {
         let id = self.next_item_id;
         self.next_item_id += 1;
@@ -1932,6 +2697,300 @@ impl GameState {
         id
     }
 
+    pub fn to_canonical_json(&self) -> String {
+        let value = serde_json::to_value(self).expect("game state should serialize to json");
+        sort_json_keys(value).to_string()
+    }
+
+    /// Renders an event as a log line, phrased like the in-game `log`.
+    /// Monsters are named from the current state; ones already removed (for
+    /// example after `MonsterDefeated`) fall back to their id.
+    pub fn format_event(&self, event: &Event) -> String {
+        let monster_name = |monster_id: u64| {
+            self.monsters
+                .iter()
+                .chain(self.companions.iter())
+                .find(|monster| monster.id == monster_id)
+                .map_or_else(|| format!("monster #{monster_id}"), |monster| monster.name.clone())
+        };
+        match event {
+            Event::Waited => "You wait.".to_string(),
+            Event::WaitCompleted { requested, waited } if waited < requested => {
+                format!("You wait {waited} of {requested} turns before being interrupted.")
+            }
+            Event::WaitCompleted { waited, .. } => format!("You wait {waited} turns."),
+            Event::AutoExploreStep { to } => format!("You explore toward ({}, {}).", to.x, to.y),
+            Event::AutoExploreDone => "You stop exploring.".to_string(),
+            Event::Teleported { to, .. } => format!("You teleport to ({}, {}).", to.x, to.y),
+            Event::Moved { .. } => "You move.".to_string(),
+            Event::MoveBlocked { .. } => "Movement blocked.".to_string(),
+            Event::AttackMissed { .. } => "You swing at empty space.".to_string(),
+            Event::Attacked { monster_id, damage, .. } => {
+                format!("You hit {} for {damage} damage.", monster_name(*monster_id))
+            }
+            Event::MonsterMoved { monster_id, .. } => {
+                format!("{} moves.", monster_name(*monster_id))
+            }
+            Event::MonsterAttacked { monster_id, damage, .. } => {
+                format!("{} hits you for {damage} damage.", monster_name(*monster_id))
+            }
+            Event::MonsterDefeated { name, .. } => format!("{name} is defeated."),
+            Event::PlayerDefeated => "You are defeated.".to_string(),
+            Event::VictoryAchieved => "You are victorious!".to_string(),
+            Event::CommandIgnoredTerminal { status } => format!(
+                "The game is over ({}); the command is ignored.",
+                session_status_display_name(*status)
+            ),
+            Event::PickedUp { name, .. } => format!("Picked up {name}."),
+            Event::Dropped { name, .. } => format!("Dropped {name}."),
+            Event::InventoryFull { .. } => "Inventory is full.".to_string(),
+            Event::NoItemToPickUp => "Nothing to pick up.".to_string(),
+            Event::InvalidDropSlot { .. } => "Invalid inventory slot.".to_string(),
+            Event::LegacyHandled { note, .. } => note.clone(),
+            Event::ConfirmationRequired { token } => format!("Confirm `{token}`? (y/n)"),
+            Event::EconomyUpdated { source, gold, bank_gold } => {
+                format!("Gold {gold}, bank {bank_gold} ({source}).")
+            }
+            Event::DialogueAdvanced { speaker, .. } => format!("{speaker} speaks with you."),
+            Event::QuestAdvanced { state, steps_completed } => format!(
+                "Quest {}: {steps_completed} steps completed.",
+                quest_state_display_name(*state)
+            ),
+            Event::ProgressionUpdated { guild_rank, priest_rank, alignment } => format!(
+                "Guild rank {guild_rank}, priest rank {priest_rank}, alignment {}.",
+                alignment_display_name(*alignment)
+            ),
+            Event::EndingResolved { ending, score, high_score_eligible } => {
+                let headline = match ending {
+                    EndingKind::None => "The game ends.",
+                    EndingKind::Defeat => "Your adventure ends in defeat.",
+                    EndingKind::Victory => "You have won!",
+                    EndingKind::TotalWinner => "You are a Total Winner!",
+                };
+                let eligibility = if *high_score_eligible { "" } else { " (not eligible)" };
+                format!("{headline} Final score: {score}{eligibility}.")
+            }
+            Event::ActionPointsSpent { cost, total_spent, .. } => {
+                format!("Spent {cost} action points ({total_spent} total).")
+            }
+            Event::StatusTick { effect_id, remaining_turns, .. } => {
+                format!("{}: {remaining_turns} turns left.", status_display_name(effect_id))
+            }
+            Event::StatusExpired { effect_id } => {
+                format!("{} has worn off.", status_display_name(effect_id))
+            }
+            Event::TurnAdvanced { turn, minutes } => format!("Turn {turn} ({minutes} minutes)."),
+            Event::EnvironmentChanged { from, to } => format!(
+                "You leave {} for {}.",
+                environment_display_name(*from),
+                environment_display_name(*to)
+            ),
+            Event::BashResult { success: true, .. } => "You smash through!".to_string(),
+            Event::BashResult { success: false, .. } => "Your bash fails.".to_string(),
+            Event::DeathAverted { name, .. } => {
+                format!("Your {name} crumbles to dust, and you cling to life!")
+            }
+            Event::MapRevealed { tiles } => format!("You map {tiles} new tiles."),
+            Event::InventoryOverflow { name, .. } => {
+                format!("Inventory full; {name} left on ground.")
+            }
+            Event::ItemSweptAway { name, .. } => format!("The current sweeps away your {name}!"),
+        }
+    }
+
+    pub fn sensed_monsters(&self) -> Vec<(u64, Position)> {
+        let radius = self
+            .status_effects
+            .iter()
+            .filter(|effect| effect.id == AURA_SIGHT_STATUS)
+            .map(|effect| effect.magnitude.max(1))
+            .max()
+            .unwrap_or(if self.questionnaire_traits.can_see_auras {
+                AURA_SIGHT_TRAIT_RADIUS
+            } else {
+                0
+            });
+        if radius == 0 {
+            return Vec::new();
+        }
+        // Auras ignore walls, so this is a plain distance check rather than line of sight.
+        let origin = self.player.position;
+        self.monsters
+            .iter()
+            .filter(|monster| monster.stats.hp > 0)
+            .filter(|monster| {
+                let dx = (monster.position.x - origin.x).abs();
+                let dy = (monster.position.y - origin.y).abs();
+                dx.max(dy) <= radius
+            })
+            .map(|monster| (monster.id, monster.position))
+            .collect()
+    }
+
+    pub fn active_objective_hints(&self) -> Vec<ObjectiveHint> {
+        let sites: &[i32] = match self.progression.main_quest.stage {
+            LegacyQuestState::NotStarted => &[SITE_AUX_SERVICE_TAVERN],
+            LegacyQuestState::Active => {
+                return self
+                    .nearest_glyph('>')
+                    .map(|target| ObjectiveHint {
+                        label: "Descend in search of the artifact.".to_string(),
+                        target: Some(target),
+                    })
+                    .into_iter()
+                    .collect();
+            }
+            LegacyQuestState::ArtifactRecovered if self.progression.main_quest.palace_access => {
+                &[SITE_AUX_SERVICE_CASTLE, SITE_AUX_SERVICE_PALACE]
+            }
+            LegacyQuestState::ArtifactRecovered => &[SITE_AUX_SERVICE_CASTLE],
+            LegacyQuestState::ReturnToPatron => &[SITE_AUX_SERVICE_MERC_GUILD],
+            LegacyQuestState::Completed | LegacyQuestState::Failed => return Vec::new(),
+        };
+        // City services are only on the city map; from the countryside, point at Rampart itself.
+        if self.world_mode == WorldMode::Countryside {
+            return self
+                .topology
+                .country_rampart_position
+                .map(|target| ObjectiveHint {
+                    label: "Return to Rampart.".to_string(),
+                    target: Some(target),
+                })
+                .into_iter()
+                .collect();
+        }
+        sites
+            .iter()
+            .filter_map(|&aux| {
+                let label = site_aux_to_objective_label(aux)?;
+                let target = objective_site_position(self, aux)?;
+                Some(ObjectiveHint { label: label.to_string(), target: Some(target) })
+            })
+            .collect()
+    }
+
+    fn nearest_glyph(&self, glyph: char) -> Option<Position> {
+        let origin = self.player.position;
+        self.map_rows
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.chars()
+                    .enumerate()
+                    .filter(move |(_, ch)| *ch == glyph)
+                    .map(move |(x, _)| Position { x: x as i32, y: y as i32 })
+            })
+            .min_by_key(|pos| (pos.manhattan_distance(origin), pos.y, pos.x))
+    }
+
+    pub fn shuffle_encounters(&mut self, rng: &mut impl RandomSource) {
+        for i in (1..self.encounter_monsters.len()).rev() {
+            let j = rng.range_inclusive_i32(0, i as i32) as usize;
+            self.encounter_monsters.swap(i, j);
+        }
+    }
+
+    /// Gear a monster was fighting with. The legacy loot table's starting item
+    /// comes first; armed humanoids without one draw a weapon from the floor
+    /// loot pool, and may carry armor besides. Other monsters carry nothing.
+    pub fn roll_equipped_drops(&mut self, name: &str, rng: &mut impl RandomSource) -> Vec<Item> {
+        let start_item = legacy_monster_row(name).start_item;
+        let armed = infer_monster_is_armed(name);
+        if !armed && start_item.is_none() {
+            return Vec::new();
+        }
+        let catalog = legacy_wish_item_catalog();
+        let mut drops = Vec::new();
+        if rng.range_inclusive_i32(1, 100) <= EQUIPPED_WEAPON_DROP_CHANCE {
+            let id = self.next_item_id;
+            let carried = match start_item {
+                Some(legacy_id) => instantiate_item_from_legacy_id(id, legacy_id),
+                None if !catalog.weapons.is_empty() => {
+                    let pick = rng.range_inclusive_i32(0, catalog.weapons.len() as i32 - 1);
+                    Some(instantiate_item_from_name(id, &catalog.weapons[pick as usize].name))
+                }
+                None => None,
+            };
+            if let Some(item) = carried {
+                self.next_item_id += 1;
+                drops.push(item);
+            }
+        }
+        if armed
+            && !catalog.armor.is_empty()
+            && rng.range_inclusive_i32(1, 100) <= EQUIPPED_ARMOR_DROP_CHANCE
+        {
+            let pick = rng.range_inclusive_i32(0, catalog.armor.len() as i32 - 1);
+            let id = self.next_item_id;
+            self.next_item_id += 1;
+            drops.push(instantiate_item_from_name(id, &catalog.armor[pick as usize].name));
+        }
+        drops
+    }
+
+    pub fn roll_item_for_depth(&mut self, depth: i16, rng: &mut impl RandomSource) -> Item {
+        let depth = i32::from(depth.max(1));
+        let catalog = legacy_wish_item_catalog();
+        // Common families stay flat while gear and magic grow more likely deeper down.
+        let weighted: [(&[WishCatalogEntry], i32); 10] = [
+            (&catalog.foods, 10),
+            (&catalog.potions, 8),
+            (&catalog.scrolls, 8),
+            (&catalog.weapons, 5 + depth / 3),
+            (&catalog.armor, 3 + depth / 2),
+            (&catalog.shields, 2 + depth / 4),
+            (&catalog.boots, 1 + depth / 4),
+            (&catalog.cloaks, 1 + depth / 4),
+            (&catalog.rings, 1 + depth / 2),
+            (&catalog.sticks, 1 + depth / 2),
+        ];
+        let total: i32 =
+            weighted.iter().filter(|(pool, _)| !pool.is_empty()).map(|(_, weight)| weight).sum();
+        let mut roll = rng.range_inclusive_i32(1, total.max(1));
+        let mut pool: &[WishCatalogEntry] = &[];
+        for (candidates, weight) in weighted {
+            if candidates.is_empty() {
+                continue;
+            }
+            if roll <= weight {
+                pool = candidates;
+                break;
+            }
+            roll -= weight;
+        }
+
+        let id = self.next_item_id;
+        self.next_item_id += 1;
+        let mut item = if pool.is_empty() {
+            Item::basic(id, "food ration")
+        } else {
+            let pick = rng.range_inclusive_i32(0, pool.len() as i32 - 1) as usize;
+            instantiate_item_from_name(id, &pool[pick].name)
+        };
+
+        if matches!(
+            item.family,
+            ItemFamily::Weapon
+                | ItemFamily::Armor
+                | ItemFamily::Shield
+                | ItemFamily::Boots
+                | ItemFamily::Cloak
+                | ItemFamily::Ring
+        ) {
+            let magnitude = rng.range_inclusive_i32(0, 1 + depth / 2);
+            if rng.range_inclusive_i32(1, 100) <= (15 - depth).max(3) {
+                item.plus -= magnitude.max(1);
+                item.blessing = item.blessing.min(0) - 1;
+            } else {
+                item.plus += magnitude;
+                if rng.range_inclusive_i32(1, 100) <= (5 + depth * 3).min(60) {
+                    item.blessing = item.blessing.max(0) + 1 + depth / 10;
+                }
+            }
+        }
+        item
+    }
+
     pub fn place_trap(
         &mut self,
         position: Position,
@@ -1946,6 +3005,7 @@ impl GameState {
             damage: damage.max(0),
             effect_id: effect_id.into(),
             armed: true,
+            discovered: false,
         });
         id
     }
@@ -1954,32 +3014,392 @@ impl GameState {
         self.status != SessionStatus::InProgress
     }
 
+    pub fn active_combat_step(&self) -> CombatStep {
+        if self.combat_sequence.is_empty() {
+            return CombatStep::default();
+        }
+        self.combat_sequence[self.combat_sequence_cursor % self.combat_sequence.len()].clone()
+    }
+
+    pub fn hour_of_day(&self) -> u8 {
+        let minute_of_day =
+            (self.clock.minutes + LEGACY_CLOCK_START_MINUTE_OF_DAY) % MINUTES_PER_DAY;
+        (minute_of_day * 24 / MINUTES_PER_DAY) as u8
+    }
+
+    pub fn time_of_day(&self) -> TimeOfDay {
+        match self.hour_of_day() {
+            5..=6 => TimeOfDay::Dawn,
+            7..=17 => TimeOfDay::Day,
+            18..=20 => TimeOfDay::Dusk,
+            _ => TimeOfDay::Night,
+        }
+    }
+
+    pub fn day_number(&self) -> u64 {
+        (self.clock.minutes + LEGACY_CLOCK_START_MINUTE_OF_DAY) / MINUTES_PER_DAY + 1
+    }
+
+    /// Brings the shop whose entrance is tile `site_id` of the current map up to
+    /// today's stock, opening its shelves on the first visit.
+    fn maybe_restock_shop(&mut self, site_id: u32) {
+        let site_key = shop_stock_key(self.map_binding.map_id, site_id);
+        self.restock_shops();
+        self.ensure_shop_stock(site_key);
+    }
+
+    fn ensure_shop_stock(&mut self, site_key: u64) {
+        let day = self.day_number();
+        self.shop_stock.entry(site_key).or_insert_with(|| shop_stock_for_day(site_key, day));
+    }
+
+    // Stock only turns over once per day as time passes, whether or not anyone is shopping.
+    fn restock_shops(&mut self) {
+        let day = self.day_number();
+        for (site_key, stock) in &mut self.shop_stock {
+            if stock.day != day {
+                *stock = shop_stock_for_day(*site_key, day);
+            }
+        }
+    }
+
+    /// Takes `amount` from the purse, refusing an overdraft. Every debit goes
+    /// through here so each one reports a single `EconomyUpdated`.
+    #[must_use]
+    fn spend_gold(&mut self, amount: i32, source: &str, events: &mut Vec<Event>) -> bool {
+        if amount < 0 || amount > self.gold {
+            return false;
+        }
+        self.gold -= amount;
+        self.push_economy_update(source, events);
+        true
+    }
+
+    /// Spends `amount` from the purse and credits `banked` to the bank account,
+    /// reporting both balances in a single economy update.
+    #[must_use]
+    fn pay_into_bank(
+        &mut self,
+        amount: i32,
+        banked: i32,
+        source: &str,
+        events: &mut Vec<Event>,
+    ) -> bool {
+        if !self.spend_gold(amount, source, events) {
+            return false;
+        }
+        self.bank_gold += banked;
+        if let Some(Event::EconomyUpdated { bank_gold, .. }) = events.last_mut() {
+            *bank_gold = self.bank_gold;
+        }
+        true
+    }
+
+    fn gain_gold(&mut self, amount: i32, source: &str, events: &mut Vec<Event>) {
+        self.gold = self.gold.saturating_add(amount.max(0));
+        self.push_economy_update(source, events);
+    }
+
+    fn push_economy_update(&self, source: &str, events: &mut Vec<Event>) {
+        events.push(Event::EconomyUpdated {
+            source: source.to_string(),
+            gold: self.gold,
+            bank_gold: self.bank_gold,
+        });
+    }
+
+    /// Base resistances plus whatever worn gear and active status effects
+    /// grant. The stored `resistances` are left untouched.
+    pub fn effective_resistances(&self) -> ResistanceProfile {
+        fn add(slot: &mut i16, bonus: i32) {
+            *slot =
+                (i32::from(*slot) + bonus).clamp(i32::from(i16::MIN), i32::from(i16::MAX)) as i16;
+        }
+        let gear = equipment_effect_profile(self);
+        let mut total = self.resistances.clone();
+        add(&mut total.fire, gear.fire_resist_bonus);
+        add(&mut total.poison, gear.poison_resist_bonus);
+        add(&mut total.magic, gear.magic_resist_bonus);
+        for effect in &self.status_effects {
+            let slot = match effect.id.as_str() {
+                "fire_resist" => &mut total.fire,
+                "cold_resist" => &mut total.cold,
+                "shock_resist" => &mut total.electricity,
+                "poison_resist" => &mut total.poison,
+                "magic_resist" => &mut total.magic,
+                _ => continue,
+            };
+            add(slot, effect.magnitude);
+        }
+        total
+    }
+
+    /// Checks whether `item_id` may go into inventory `slot` without
+    /// changing anything, so frontends can pre-validate equip requests.
+    pub fn can_equip(&self, item_id: u32, slot: usize) -> Result<(), EquipError> {
+        let item = self
+            .player
+            .inventory
+            .iter()
+            .find(|entry| entry.id == item_id)
+            .ok_or(EquipError::ItemNotFound)?;
+        if !slot_accepts_item(slot, item) {
+            return Err(EquipError::WrongFamily);
+        }
+        if slot == SLOT_SHIELD && equipped_weapon_is_two_handed(self) {
+            return Err(EquipError::TwoHandedConflict);
+        }
+        let worn_elsewhere = (SLOT_UP_IN_AIR..=SLOT_RING_4).any(|other| {
+            other != slot
+                && inventory_slot_item_id(self, other) == Some(item_id)
+                && item_is_cursed_in_use(item, other)
+        });
+        if worn_elsewhere {
+            return Err(EquipError::Cursed);
+        }
+        if let Some(existing_id) = inventory_slot_item_id(self, slot)
+            && existing_id != item_id
+            && self
+                .player
+                .inventory
+                .iter()
+                .find(|entry| entry.id == existing_id)
+                .is_some_and(|existing| item_is_cursed_in_use(existing, slot))
+        {
+            return Err(EquipError::SlotOccupiedByCursed);
+        }
+        Ok(())
+    }
+
+    pub fn encumbrance_tier(&self) -> EncumbranceTier {
+        let equipped_weight: i32 = equipped_item_ids(&self.player.equipment)
+            .into_iter()
+            .filter_map(|item_id| self.player.inventory.iter().find(|entry| entry.id == item_id))
+            .map(|item| item.weight.max(0))
+            .sum();
+        // Equipped load is measured against strength, separate from the pack burden limit.
+        let limit = self.attributes.strength.max(1) * 40;
+        if equipped_weight <= limit / 2 {
+            EncumbranceTier::Unencumbered
+        } else if equipped_weight <= limit {
+            EncumbranceTier::Burdened
+        } else if equipped_weight <= limit * 3 / 2 {
+            EncumbranceTier::Strained
+        } else {
+            EncumbranceTier::Overloaded
+        }
+    }
+
+    pub fn scheduler_snapshot(&self) -> TurnScheduler {
+        self.scheduler.clone()
+    }
+
+    pub fn describe_current_tile(&self) -> TileDescription {
+        self.describe_tile(self.player.position)
+    }
+
+    pub fn describe_tile(&self, position: Position) -> TileDescription {
+        let items = self
+            .ground_items
+            .iter()
+            .filter(|ground| ground.position == position)
+            .map(|ground| ground.item.name.clone())
+            .collect();
+        let traps = self
+            .traps
+            .iter()
+            .filter(|trap| trap.armed && trap.discovered && trap.position == position)
+            .map(|trap| trap.effect_id.clone())
+            .collect();
+        let site = self
+            .tile_site_at(position)
+            .and_then(|cell| interaction_kind_for_site_aux(self, cell.aux))
+            .map(|kind| site_interaction_label(&kind));
+        let engraving = self.engraving_at(position).map(|engraving| engraving.text.clone());
+        TileDescription {
+            position,
+            glyph: self.map_glyph_at(position),
+            items,
+            traps,
+            site,
+            engraving,
+        }
+    }
+
+    /// Describes the monster at `pos`, or `None` when there is none the player
+    /// can see or sense there.
+    pub fn examine_monster(&self, pos: Position) -> Option<String> {
+        let monster = self.monsters.iter().find(|monster| monster.position == pos)?;
+        let sensed = self.sensed_monsters().iter().any(|(id, _)| *id == monster.id);
+        if !sensed && !line_of_sight(self, self.player.position, pos) {
+            return None;
+        }
+        let stance = if monster.allied {
+            "allied"
+        } else if monster.is_hostile_to_player(self) {
+            "hostile"
+        } else {
+            "peaceful"
+        };
+        let health = match monster.stats.hp * 4 / monster.stats.max_hp.max(1) {
+            4.. => "unhurt",
+            2..=3 => "wounded",
+            _ => "badly wounded",
+        };
+        Some(format!("A {} ({stance}, {health}).", monster.name))
+    }
+
+    pub fn engraving_at(&self, pos: Position) -> Option<&Engraving> {
+        let map_id = self.map_binding.map_id;
+        self.engravings
+            .iter()
+            .find(|engraving| engraving.map_id == map_id && engraving.position == pos)
+    }
+
+    pub fn engrave_at(&mut self, pos: Position, text: impl Into<String>) {
+        let map_id = self.map_binding.map_id;
+        let engraving =
+            Engraving { map_id, position: pos, text: text.into(), turn_engraved: self.clock.turn };
+        self.engravings.retain(|existing| existing.map_id != map_id || existing.position != pos);
+        self.engravings.push(engraving);
+        // Kept in map then row-major order so saves stay stable regardless of engraving order.
+        self.engravings.sort_by_key(|engraving| {
+            (engraving.map_id, engraving.position.y, engraving.position.x)
+        });
+    }
+
+    pub fn apply_effects(&mut self, effects: &[StatusEffect]) {
+        let mut staged: Vec<StatusEffect> = Vec::new();
+        for effect in effects {
+            if status_blocked_by_immunity(self, &effect.id) {
+                continue;
+            }
+            match staged.iter_mut().find(|existing| existing.id == effect.id) {
+                Some(existing) if effect.id == "poison" => {
+                    existing.magnitude =
+                        (existing.magnitude.max(0) + effect.magnitude).min(POISON_MAX_MAGNITUDE);
+                    existing.remaining_turns = existing.remaining_turns.max(effect.remaining_turns);
+                }
+                Some(existing) => {
+                    existing.magnitude = existing.magnitude.max(effect.magnitude);
+                    existing.remaining_turns = existing.remaining_turns.max(effect.remaining_turns);
+                }
+                None => staged.push(effect.clone()),
+            }
+        }
+        // Opposing effects cancel out, whether both arrive together or one is already active.
+        for (first, second) in OPPOSING_STATUS_PAIRS {
+            let has_first = staged.iter().any(|effect| effect.id == first);
+            let has_second = staged.iter().any(|effect| effect.id == second);
+            if has_first && has_second {
+                staged.retain(|effect| effect.id != first && effect.id != second);
+            }
+        }
+        for effect in staged {
+            if let Some(opposite) = opposing_status(&effect.id)
+                && self.status_effects.iter().any(|active| active.id == opposite)
+            {
+                consume_status(self, opposite);
+                continue;
+            }
+            if effect.id == "poison" {
+                let _ = apply_poison(self, effect.remaining_turns, effect.magnitude);
+            } else {
+                push_or_refresh_status(
+                    &mut self.status_effects,
+                    &effect.id,
+                    effect.remaining_turns,
+                    effect.magnitude,
+                );
+            }
+        }
+    }
+
+    pub fn status_summaries(&self) -> Vec<StatusSummary> {
+        self.status_effects
+            .iter()
+            .map(|effect| StatusSummary {
+                id: effect.id.clone(),
+                name: status_display_name(&effect.id),
+                remaining_turns: effect.remaining_turns,
+                magnitude: effect.magnitude,
+                category: status_category(&effect.id, effect.magnitude),
+            })
+            .collect()
+    }
+
+    pub fn combat_sequence_preview(&self) -> Vec<CombatStep> {
+        if self.combat_sequence.is_empty() {
+            return default_combat_sequence();
+        }
+        let len = self.combat_sequence.len();
+        (0..len)
+            .map(|offset| {
+                self.combat_sequence[(self.combat_sequence_cursor + offset) % len].clone()
+            })
+            .collect()
+    }
+
+    pub fn apply_commands(
+        &mut self,
+        commands: &[Command],
+        rng: &mut impl RandomSource,
+    ) -> Vec<Outcome> {
+        commands.iter().map(|command| step(self, command.clone(), rng)).collect()
+    }
+
+    /// Installs `rows` without validation; callers holding untrusted rows
+    /// should go through [`GameState::try_set_map_rows`].
     pub fn set_map_rows(&mut self, rows: Vec<String>) {
+        debug_assert!(
+            !matches!(map_rows_bounds(&rows), Err(MapError::RaggedRow { .. })),
+            "set_map_rows given ragged rows"
+        );
         if let Some(first) = rows.first() {
             self.bounds =
                 MapBounds { width: first.chars().count() as i32, height: rows.len() as i32 };
         }
         self.map_rows = rows;
+        self.explored_tiles.clear();
     }
 
-    pub fn tile_site_at(&self, pos: Position) -> Option<&TileSiteCell> {
+    pub fn try_set_map_rows(&mut self, rows: Vec<String>) -> Result<(), MapError> {
+        let bounds = map_rows_bounds(&rows)?;
+        if !bounds.contains(self.player.position) {
+            return Err(MapError::SpawnOutOfBounds { spawn: self.player.position, bounds });
+        }
+        self.set_map_rows(rows);
+        Ok(())
+    }
+
+    /// Row-major `site_grid` index for `pos`, or `None` outside `bounds`.
+    pub fn index_for(&self, pos: Position) -> Option<usize> {
         if !self.bounds.contains(pos) {
             return None;
         }
         let x = usize::try_from(pos.x).ok()?;
         let y = usize::try_from(pos.y).ok()?;
         let width = usize::try_from(self.bounds.width).ok()?;
-        self.site_grid.get(y.saturating_mul(width).saturating_add(x))
+        Some(y.saturating_mul(width).saturating_add(x))
     }
 
-    pub fn tile_site_at_mut(&mut self, pos: Position) -> Option<&mut TileSiteCell> {
-        if !self.bounds.contains(pos) {
+    /// Inverse of [`GameState::index_for`].
+    pub fn pos_for_index(&self, idx: usize) -> Option<Position> {
+        let width = usize::try_from(self.bounds.width).ok().filter(|width| *width > 0)?;
+        let height = usize::try_from(self.bounds.height).ok()?;
+        if idx >= width.saturating_mul(height) {
             return None;
         }
-        let x = usize::try_from(pos.x).ok()?;
-        let y = usize::try_from(pos.y).ok()?;
-        let width = usize::try_from(self.bounds.width).ok()?;
-        let idx = y.saturating_mul(width).saturating_add(x);
+        Some(Position { x: i32::try_from(idx % width).ok()?, y: i32::try_from(idx / width).ok()? })
+    }
+
+    pub fn tile_site_at(&self, pos: Position) -> Option<&TileSiteCell> {
+        let idx = self.index_for(pos)?;
+        self.site_grid.get(idx)
+    }
+
+    pub fn tile_site_at_mut(&mut self, pos: Position) -> Option<&mut TileSiteCell> {
+        let idx = self.index_for(pos)?;
         self.site_grid.get_mut(idx)
     }
 
@@ -2027,18 +3447,32 @@ impl GameState {
         }
     }
 
+    pub fn tile_is_water(&self, pos: Position) -> bool {
+        self.world_mode == WorldMode::DungeonCity
+            && self.bounds.contains(pos)
+            && self.map_glyph_at(pos) == WATER_GLYPH
+    }
+
+    /// The player can swim unless strained by their load, or float across regardless.
+    fn player_can_cross_water(&self) -> bool {
+        player_is_levitating(self) || self.encumbrance_tier() < EncumbranceTier::Strained
+    }
+
+    pub fn tile_is_lit(&self, pos: Position) -> bool {
+        let glyph_flags = self.glyph_legend.flags(self.map_glyph_at(pos), self.world_mode);
+        glyph_flags & TILE_FLAG_LIT != 0
+            || self.tile_site_at(pos).is_some_and(|cell| cell.flags & TILE_FLAG_LIT != 0)
+    }
+
     pub fn tile_is_walkable(&self, pos: Position) -> bool {
         if !self.bounds.contains(pos) {
             return false;
         }
         let glyph = self.map_glyph_at(pos);
-        if glyph == '#' {
-            return false;
-        }
-        if self.world_mode == WorldMode::DungeonCity && glyph == '=' {
+        if !self.glyph_legend.is_walkable(glyph, self.world_mode) {
             return false;
         }
-        if self.world_mode == WorldMode::DungeonCity && matches!(glyph, '-' | 'D' | 'J') {
+        if self.tile_is_water(pos) && !self.player_can_cross_water() {
             return false;
         }
         if let Some(site) = self.tile_site_at(pos)
@@ -2049,26 +3483,80 @@ impl GameState {
         true
     }
 
-    pub fn activate_city_view(&mut self) {
-        if !self.city_map_rows.is_empty() {
-            self.set_map_rows(self.city_map_rows.clone());
-        }
-        self.site_grid = self.city_site_grid.clone();
-        self.monsters.clear();
+    fn reset_transient_interactions(&mut self) {
         self.pending_site_interaction = None;
         self.pending_spell_interaction = None;
         self.pending_activation_interaction = None;
         self.pending_quit_interaction = None;
         self.pending_talk_direction = None;
+        self.pending_dialogue = None;
+        self.tunnel_progress = None;
         self.pending_inventory_interaction = None;
         self.pending_item_prompt = None;
+        self.pending_call_label = None;
         self.pending_targeting_interaction = None;
+        self.pending_look_interaction = None;
+        self.pending_guild_action = None;
         self.pending_projectile_action = None;
+        self.pending_combat_alarm = None;
+        self.active_duel = None;
         self.transient_projectile_path.clear();
         self.transient_projectile_impact = None;
         self.spell_input_buffer.clear();
         self.interaction_buffer.clear();
         self.target_input_buffer.clear();
+    }
+
+    pub fn on_environment_change(
+        &mut self,
+        from: LegacyEnvironment,
+        to: LegacyEnvironment,
+        events: &mut Vec<Event>,
+    ) {
+        if from == to {
+            return;
+        }
+        match to {
+            LegacyEnvironment::Countryside => {
+                self.navigation_lost = false;
+            }
+            LegacyEnvironment::Astral => {
+                self.log.push("The world dissolves into the shimmering astral plane.".to_string());
+            }
+            LegacyEnvironment::Abyss => {
+                self.log.push("A chill of utter emptiness surrounds you.".to_string());
+            }
+            LegacyEnvironment::Volcano => {
+                self.log.push("Waves of heat roll up from the volcanic depths.".to_string());
+            }
+            LegacyEnvironment::DragonLair => {
+                self.log.push("The stench of dragon hangs heavy in the air.".to_string());
+            }
+            _ => {}
+        }
+        events.push(Event::EnvironmentChanged { from, to });
+    }
+
+    pub fn check_main_quest_victory(&mut self, events: &mut Vec<Event>) {
+        if self.status != SessionStatus::InProgress {
+            return;
+        }
+        let quest = &self.progression.main_quest;
+        if quest.stage == LegacyQuestState::Completed
+            && (quest.completion_flags & MAIN_QUEST_FINAL_RITES_FLAGS) != 0
+        {
+            self.log.push("Your quest is complete; the land hails a new champion.".to_string());
+            apply_explicit_victory_trigger(self, VictoryTrigger::ExplicitQuestCompletion, events);
+        }
+    }
+
+    pub fn activate_city_view(&mut self) {
+        if !self.city_map_rows.is_empty() {
+            self.set_map_rows(self.city_map_rows.clone());
+        }
+        self.site_grid = self.city_site_grid.clone();
+        self.monsters.clear();
+        self.reset_transient_interactions();
         self.navigation_lost = false;
         self.world_mode = WorldMode::DungeonCity;
         self.environment = LegacyEnvironment::City;
@@ -2081,26 +3569,49 @@ impl GameState {
         let _ = self.spawn_guard_monsters_from_markers();
     }
 
+    // Each exit map leads to one region, numbered in the order exits are first used.
+    fn country_region_for_exit(&mut self, map_id: u16) -> u8 {
+        if let Some(region_id) = self.topology.country_region_exits.get(&map_id) {
+            return *region_id;
+        }
+        let next = self.topology.country_region_exits.len() + 1;
+        let region_id = u8::try_from(next).unwrap_or(u8::MAX);
+        self.topology.country_region_exits.insert(map_id, region_id);
+        region_id
+    }
+
+    // A region seen for the first time carries over the active grid and position.
+    pub fn travel_to_country_region(&mut self, region_id: u8) {
+        let current = self.topology.country_region_id;
+        if region_id == current {
+            return;
+        }
+        self.country_regions.insert(current, self.country_grid.clone());
+        self.country_region_rows.insert(current, self.country_map_rows.clone());
+        if let Some(position) = self.topology.last_country_position {
+            self.topology.country_region_positions.insert(current, position);
+        }
+        if let Some(grid) = self.country_regions.get(&region_id) {
+            self.country_grid = grid.clone();
+            if let Some(rows) = self.country_region_rows.get(&region_id).cloned() {
+                if self.world_mode == WorldMode::Countryside && !rows.is_empty() {
+                    self.set_map_rows(rows.clone());
+                }
+                self.country_map_rows = rows;
+            }
+            self.topology.last_country_position =
+                self.topology.country_region_positions.get(&region_id).copied();
+        }
+        self.topology.country_region_id = region_id;
+    }
+
     pub fn activate_country_view(&mut self) {
         if !self.country_map_rows.is_empty() {
             self.set_map_rows(self.country_map_rows.clone());
         }
         self.site_grid = self.country_site_grid.clone();
         self.monsters.clear();
-        self.pending_site_interaction = None;
-        self.pending_spell_interaction = None;
-        self.pending_activation_interaction = None;
-        self.pending_quit_interaction = None;
-        self.pending_talk_direction = None;
-        self.pending_inventory_interaction = None;
-        self.pending_item_prompt = None;
-        self.pending_targeting_interaction = None;
-        self.pending_projectile_action = None;
-        self.transient_projectile_path.clear();
-        self.transient_projectile_impact = None;
-        self.spell_input_buffer.clear();
-        self.interaction_buffer.clear();
-        self.target_input_buffer.clear();
+        self.reset_transient_interactions();
         self.world_mode = WorldMode::Countryside;
         self.environment = LegacyEnvironment::Countryside;
         self.map_binding = MapBinding {
@@ -2122,6 +3633,9 @@ impl GameState {
             return false;
         };
 
+        if map_rows_bounds(&site_map.rows).is_err() {
+            return false;
+        }
         self.set_map_rows(site_map.rows.clone());
         if site_map.site_grid.is_empty() {
             let cell_count =
@@ -2131,20 +3645,7 @@ impl GameState {
             self.site_grid = site_map.site_grid.clone();
         }
         self.monsters.clear();
-        self.pending_site_interaction = None;
-        self.pending_spell_interaction = None;
-        self.pending_activation_interaction = None;
-        self.pending_quit_interaction = None;
-        self.pending_talk_direction = None;
-        self.pending_inventory_interaction = None;
-        self.pending_item_prompt = None;
-        self.pending_targeting_interaction = None;
-        self.pending_projectile_action = None;
-        self.transient_projectile_path.clear();
-        self.transient_projectile_impact = None;
-        self.spell_input_buffer.clear();
-        self.interaction_buffer.clear();
-        self.target_input_buffer.clear();
+        self.reset_transient_interactions();
         self.navigation_lost = false;
         self.world_mode = WorldMode::DungeonCity;
         self.environment = site_map.environment;
@@ -2164,6 +3665,18 @@ impl GameState {
         true
     }
 
+    /// Activates `map_id`, or a plain walled interior generated in its place
+    /// when the loaded content lacks it, so entering a site never strands the
+    /// player. The generated map is kept in `site_maps` for later visits.
+    fn activate_or_generate_site(&mut self, map_id: u16, env: LegacyEnvironment, spawn: Position) {
+        if self.activate_site_map_by_id(map_id, Some(spawn)) {
+            return;
+        }
+        self.site_maps.push(generated_site_map(map_id, env, spawn));
+        let activated = self.activate_site_map_by_id(map_id, Some(spawn));
+        debug_assert!(activated, "generated site map {map_id} should activate");
+    }
+
     pub fn spawn_guard_monsters_from_markers(&mut self) -> usize {
         let markers = guard_marker_positions(&self.map_rows, self.bounds);
         let mut spawned = 0usize;
@@ -2190,6 +3703,23 @@ impl GameState {
     }
 }
 
+/// Rebuilds every object with its keys in sorted order. `serde_json::Map`
+/// keeps insertion order once any crate enables `preserve_order`, so the
+/// canonical encoding sorts explicitly instead of relying on the default map.
+fn sort_json_keys(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let sorted: BTreeMap<String, serde_json::Value> =
+                map.into_iter().map(|(key, value)| (key, sort_json_keys(value))).collect();
+            serde_json::Value::Object(sorted.into_iter().collect())
+        }
+        serde_json::Value::Array(items) => {
+            serde_json::Value::Array(items.into_iter().map(sort_json_keys).collect())
+        }
+        other => other,
+    }
+}
+
 impl Default for GameState {
     fn default() -> Self {
         Self::new(MapBounds { width: 80, height: 25 })
@@ -2211,6 +3741,16 @@ pub enum Direction {
     West,
 }
 
+fn cardinal_direction_from_delta(dx: i32, dy: i32) -> Option<Direction> {
+    match (dx, dy) {
+        (0, -1) => Some(Direction::North),
+        (0, 1) => Some(Direction::South),
+        (1, 0) => Some(Direction::East),
+        (-1, 0) => Some(Direction::West),
+        _ => None,
+    }
+}
+
 fn random_cardinal_direction<R: RandomSource>(rng: &mut R) -> Direction {
     match rng.range_inclusive_i32(0, 3) {
         0 => Direction::North,
@@ -2252,23 +3792,51 @@ pub fn legacy_inventory_slot_to_key(slot: usize) -> Option<char> {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Command {
     Wait,
+    WaitTurns { count: u32 },
     Move(Direction),
     Attack(Direction),
+    Engage(Direction),
+    Bash(Direction),
+    ApplyLockpick(Direction),
+    ApplyTool { item_id: u32, target: Option<Direction> },
+    AutoExplore,
+    Engrave { text: String },
+    Dip { item_id: u32, potion_id: u32 },
+    Zap { item_id: u32 },
+    Throw { item_id: u32 },
+    GuildAction { guild: SiteInteractionKind, action: GuildAction },
+    RecallCompanions,
+    Butcher,
+    OfferSurrender,
+    Look,
     Pickup,
     Drop { slot: usize },
+    DropQuantity { item_id: u32, count: i32 },
+    Bank { op: BankOp, amount: i32 },
     Legacy { token: String },
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum BankOp {
+    Deposit,
+    Withdraw,
+    PostSurety,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Event {
     Waited,
+    WaitCompleted { requested: u32, waited: u32 },
+    AutoExploreStep { to: Position },
+    AutoExploreDone,
+    Teleported { from: Position, to: Position },
     Moved { from: Position, to: Position },
     MoveBlocked { target: Position },
     AttackMissed { target: Position },
     Attacked { monster_id: u64, damage: i32, remaining_hp: i32 },
     MonsterMoved { monster_id: u64, from: Position, to: Position },
     MonsterAttacked { monster_id: u64, damage: i32, remaining_hp: i32 },
-    MonsterDefeated { monster_id: u64 },
+    MonsterDefeated { monster_id: u64, name: String },
     PlayerDefeated,
     VictoryAchieved,
     CommandIgnoredTerminal { status: SessionStatus },
@@ -2288,6 +3856,12 @@ pub enum Event {
     StatusTick { effect_id: String, magnitude: i32, remaining_turns: u32 },
     StatusExpired { effect_id: String },
     TurnAdvanced { turn: u64, minutes: u64 },
+    EnvironmentChanged { from: LegacyEnvironment, to: LegacyEnvironment },
+    BashResult { target: Position, success: bool },
+    DeathAverted { item_id: u32, name: String },
+    MapRevealed { tiles: usize },
+    InventoryOverflow { item_id: u32, name: String },
+    ItemSweptAway { item_id: u32, name: String },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -2296,6 +3870,38 @@ pub struct Outcome {
     pub minutes: u64,
     pub status: SessionStatus,
     pub events: Vec<Event>,
+    /// Set when the step crossed a transition worth persisting (level change,
+    /// death, victory, or a wealth swing past `autosave_gold_threshold`).
+    #[serde(default)]
+    pub should_autosave: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct AutosaveSnapshot {
+    environment: LegacyEnvironment,
+    dungeon_level: i16,
+    status: SessionStatus,
+    wealth: i32,
+}
+
+impl AutosaveSnapshot {
+    fn capture(state: &GameState) -> Self {
+        Self {
+            environment: state.environment,
+            dungeon_level: state.topology.dungeon_level,
+            status: state.status,
+            wealth: state.gold.saturating_add(state.bank_gold),
+        }
+    }
+
+    fn warrants_autosave(&self, state: &GameState) -> bool {
+        let after = Self::capture(state);
+        after.environment != self.environment
+            || after.dungeon_level != self.dungeon_level
+            || after.status != self.status
+            || after.wealth.abs_diff(self.wealth)
+                >= state.options.autosave_gold_threshold.max(1).unsigned_abs()
+    }
 }
 
 pub trait RandomSource {
@@ -2312,6 +3918,14 @@ impl DeterministicRng {
         Self { state: seed }
     }
 
+    pub fn from_state(state: u64) -> Self {
+        Self { state }
+    }
+
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
     fn next_u32(&mut self) -> u32 {
         self.state = self.state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
         (self.state >> 32) as u32
@@ -2334,12 +3948,45 @@ impl RandomSource for DeterministicRng {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("replay desynced at command {index}")]
+pub struct DesyncReport {
+    pub index: usize,
+    pub expected: Option<Outcome>,
+    pub actual: Option<Outcome>,
+}
+
+pub fn verify_replay(
+    initial: &GameState,
+    seed: u64,
+    commands: &[Command],
+    expected: &[Outcome],
+) -> Result<(), DesyncReport> {
+    let mut state = initial.clone();
+    let mut rng = DeterministicRng::seeded(seed);
+    for (index, command) in commands.iter().enumerate() {
+        let actual = step(&mut state, command.clone(), &mut rng);
+        if expected.get(index) != Some(&actual) {
+            return Err(DesyncReport {
+                index,
+                expected: expected.get(index).cloned(),
+                actual: Some(actual),
+            });
+        }
+    }
+    if expected.len() > commands.len() {
+        return Err(DesyncReport {
+            index: commands.len(),
+            expected: expected.get(commands.len()).cloned(),
+            actual: None,
+        });
+    }
+    Ok(())
+}
+
 pub fn step<R: RandomSource>(state: &mut GameState, command: Command, rng: &mut R) -> Outcome {
     let mut events = Vec::new();
-    let mut turn_minutes = apply_speed_modifiers(
-        state,
-        estimate_turn_minutes(&command, state.world_mode, state.options.searchnum),
-    );
+    let mut turn_minutes = apply_speed_modifiers(state, estimate_turn_minutes(&command, state));
     let mut command_for_accounting = command.clone();
     let mut bonus_minutes = 0u64;
     let mut freeze_world_progression = false;
@@ -2352,9 +3999,15 @@ pub fn step<R: RandomSource>(state: &mut GameState, command: Command, rng: &mut
             minutes: state.clock.minutes,
             status: state.status,
             events,
+            should_autosave: false,
         };
     }
+    if let Command::WaitTurns { count } = command {
+        return step_wait_turns(state, count, rng);
+    }
+    let autosave_before = AutosaveSnapshot::capture(state);
 
+    let environment_before = state.environment;
     let mode_policies = core::mode::policy_set_for(state.mode);
     core::mode::apply_before_command(mode_policies, state, &command, &mut events);
 
@@ -2364,7 +4017,6 @@ pub fn step<R: RandomSource>(state: &mut GameState, command: Command, rng: &mut
     sync_spellbook_state(state);
     state.transient_projectile_path.clear();
     state.transient_projectile_impact = None;
-    state.scheduler.player_phase = state.scheduler.player_phase.saturating_add(1);
 
     if let Some(wizard_resolution) =
         resolve_pending_wizard_interaction(state, &command, &mut events, &mut bonus_minutes)
@@ -2387,7 +4039,7 @@ pub fn step<R: RandomSource>(state: &mut GameState, command: Command, rng: &mut
 
     if !command_consumed
         && let Some(talk_resolution) =
-            resolve_pending_talk_direction_interaction(state, &command, &mut events)
+            resolve_pending_talk_direction_interaction(state, &command, &mut events, rng)
     {
         command_consumed = true;
         freeze_world_progression = talk_resolution.freeze_world_progression;
@@ -2395,9 +4047,19 @@ pub fn step<R: RandomSource>(state: &mut GameState, command: Command, rng: &mut
         turn_minutes = talk_resolution.turn_minutes;
     }
 
+    if !command_consumed
+        && let Some(dialogue_resolution) =
+            resolve_pending_dialogue_interaction(state, &command, &mut events)
+    {
+        command_consumed = true;
+        freeze_world_progression = dialogue_resolution.freeze_world_progression;
+        command_for_accounting = dialogue_resolution.command_for_accounting;
+        turn_minutes = dialogue_resolution.turn_minutes;
+    }
+
     if !command_consumed
         && let Some(spell_resolution) =
-            resolve_pending_spell_interaction(state, &command, &mut events)
+            resolve_pending_spell_interaction(state, &command, &mut events, rng)
     {
         command_consumed = true;
         freeze_world_progression = spell_resolution.freeze_world_progression;
@@ -2440,6 +4102,16 @@ pub fn step<R: RandomSource>(state: &mut GameState, command: Command, rng: &mut
         turn_minutes = item_prompt_resolution.turn_minutes;
     }
 
+    if !command_consumed
+        && let Some(call_resolution) =
+            resolve_pending_call_label_interaction(state, &command, &mut events)
+    {
+        command_consumed = true;
+        freeze_world_progression = call_resolution.freeze_world_progression;
+        command_for_accounting = call_resolution.command_for_accounting;
+        turn_minutes = call_resolution.turn_minutes;
+    }
+
     if !command_consumed
         && let Some(targeting_resolution) =
             resolve_pending_targeting_interaction(state, &command, &mut events, rng)
@@ -2450,8 +4122,29 @@ pub fn step<R: RandomSource>(state: &mut GameState, command: Command, rng: &mut
         turn_minutes = targeting_resolution.turn_minutes;
     }
 
+    if !command_consumed
+        && let Some(look_resolution) =
+            resolve_pending_look_interaction(state, &command, &mut events)
+    {
+        command_consumed = true;
+        freeze_world_progression = look_resolution.freeze_world_progression;
+        command_for_accounting = look_resolution.command_for_accounting;
+        turn_minutes = look_resolution.turn_minutes;
+    }
+
+    if !command_consumed
+        && let Some(guild_resolution) =
+            resolve_pending_guild_action_interaction(state, &command, &mut events, rng)
+    {
+        command_consumed = true;
+        freeze_world_progression = guild_resolution.freeze_world_progression;
+        command_for_accounting = guild_resolution.command_for_accounting;
+        turn_minutes = guild_resolution.turn_minutes;
+    }
+
     if !command_consumed {
-        let interaction_consumed = resolve_pending_site_interaction(state, &command, &mut events);
+        let interaction_consumed =
+            resolve_pending_site_interaction(state, &command, rng, &mut events);
         if interaction_consumed {
             command_consumed = true;
             // Site menus are modal interactions; keep world/time frozen until
@@ -2459,51 +4152,93 @@ pub fn step<R: RandomSource>(state: &mut GameState, command: Command, rng: &mut
             freeze_world_progression = true;
             turn_minutes = 0;
             command_for_accounting = Command::Legacy { token: "F".to_string() };
+            if state.status != SessionStatus::InProgress {
+                // A choice that ends the session (condo retirement) must still resolve the ending.
+                freeze_world_progression = false;
+            }
         }
     }
 
     if !command_consumed {
         match command {
-            Command::Wait => {
-                state.log.push("You wait.".to_string());
+            Command::Wait | Command::WaitTurns { .. } => {
+                state.log.push(state.format_event(&Event::Waited));
                 events.push(Event::Waited);
             }
             Command::Move(direction) => {
-                let from = state.player.position;
-                let move_direction =
-                    apply_lost_navigation_direction(state, direction, rng, &mut events);
-                let target = from.offset(move_direction);
-                if try_bump_attack_on_move(state, move_direction, rng, &mut events) {
-                    // Legacy parity: walking into an occupied tile resolves melee instead of block.
-                } else {
-                    let burden_limit = (effective_inventory_capacity(state) as i32) * 12;
-                    let overburdened = state.carry_burden > burden_limit;
-                    if overburdened {
-                        state.log.push("You are too burdened to move.".to_string());
-                        events.push(Event::MoveBlocked { target: from });
-                    } else if !state.tile_is_walkable(target) {
-                        if !try_bump_interaction_on_blocked_move(
-                            state,
-                            from,
-                            target,
-                            rng,
-                            &mut events,
-                            &mut bonus_minutes,
-                        ) {
-                            state.log.push("Movement blocked.".to_string());
-                            events.push(Event::MoveBlocked { target });
-                        }
-                    } else {
-                        state.player.position = target;
-                        state.log.push("You move.".to_string());
-                        events.push(Event::Moved { from, to: target });
-                        apply_post_move_effects(state, rng, &mut events, &mut bonus_minutes);
-                    }
+                resolve_move_command(state, direction, rng, &mut events, &mut bonus_minutes);
+            }
+            Command::AutoExplore => {
+                resolve_auto_explore(state, rng, &mut events, &mut bonus_minutes);
+            }
+            Command::Engrave { text } => {
+                let note = engrave_under_player(state, &text);
+                state.log.push(note);
+            }
+            Command::Dip { item_id, potion_id } => {
+                let note = dip_item_in_potion(state, item_id, potion_id);
+                state.log.push(note);
+            }
+            Command::Zap { item_id } => {
+                let note = zap_stick(state, item_id, rng, &mut events);
+                state.log.push(note);
+                if state.pending_targeting_interaction.is_some() {
+                    freeze_world_progression = true;
+                    turn_minutes = 0;
+                    command_for_accounting = Command::Legacy { token: "F".to_string() };
+                }
+            }
+            Command::Throw { item_id } => {
+                let note = begin_fire_throw_for_item(state, item_id, &mut events);
+                state.log.push(note);
+                freeze_world_progression = true;
+                turn_minutes = 0;
+                command_for_accounting = Command::Legacy { token: "F".to_string() };
+            }
+            Command::Look => {
+                begin_look_interaction(state, &mut events);
+                freeze_world_progression = true;
+                turn_minutes = 0;
+                command_for_accounting = Command::Legacy { token: "F".to_string() };
+            }
+            Command::GuildAction { guild, action } => {
+                let note = resolve_guild_action(state, guild, action, rng, &mut events);
+                state.log.push(note);
+            }
+            Command::RecallCompanions => {
+                let note = recall_companions(state, &mut events);
+                state.log.push(note);
+            }
+            Command::Butcher => match butcher_corpse(state, &mut events) {
+                Ok(note) => state.log.push(note),
+                Err(note) => {
+                    state.log.push(note);
+                    freeze_world_progression = true;
+                    turn_minutes = 0;
+                    command_for_accounting = Command::Legacy { token: "F".to_string() };
                 }
+            },
+            Command::OfferSurrender => {
+                let note = offer_arena_surrender(state, &mut events);
+                state.log.push(note);
             }
             Command::Attack(direction) => {
                 resolve_attack_command(state, direction, rng, &mut events);
             }
+            Command::Engage(direction) => {
+                resolve_engage_command(state, direction, rng, &mut events);
+            }
+            Command::Bash(direction) => {
+                resolve_bash_command(state, direction, rng, &mut events);
+            }
+            Command::ApplyLockpick(direction) => {
+                let note = apply_lockpick(state, direction, rng);
+                state.log.push(note);
+            }
+            Command::ApplyTool { item_id, target } => {
+                let note = apply_tool(state, item_id, target, rng, &mut events);
+                state.log.push(note);
+            }
             Command::Pickup => {
                 try_pickup_at_player(state, &mut events);
             }
@@ -2512,39 +4247,44 @@ pub fn step<R: RandomSource>(state: &mut GameState, command: Command, rng: &mut
                     state.log.push("Invalid inventory slot.".to_string());
                     events.push(Event::InvalidDropSlot { slot });
                 } else {
-                    let item = state.player.inventory.remove(slot);
-                    unequip_item_id(&mut state.player.equipment, item.id);
-                    remove_item_from_pack_order(state, item.id);
-                    state.carry_burden =
-                        state.carry_burden.saturating_sub(item_burden(&item)).max(0);
-                    state.log.push(format!("Dropped {}.", item.name));
-                    events.push(Event::Dropped { item_id: item.id, name: item.name.clone() });
-                    state.ground_items.push(GroundItem { position: state.player.position, item });
+                    drop_inventory_slot(state, slot, &mut events);
                 }
             }
+            Command::DropQuantity { item_id, count } => {
+                drop_item_quantity(state, item_id, count, &mut events);
+            }
+            Command::Bank { op, amount } => {
+                let note = apply_bank_command(state, op, amount, &mut events);
+                state.log.push(note);
+            }
             Command::Legacy { token } => {
                 apply_legacy_command(state, &token, &mut events, rng, &mut bonus_minutes);
             }
         }
     }
 
+    let environment_after = state.environment;
+    state.on_environment_change(environment_before, environment_after, &mut events);
+
     if !freeze_world_progression && let Command::Legacy { token } = &command_for_accounting {
         let trimmed = token.trim();
         let opened_wizard_prompt = state.pending_wizard_interaction.is_some()
-            && matches!(trimmed, "^g" | "^x" | "^k" | "#" | "z");
+            && matches!(trimmed, "^g" | "^x" | "^k" | "^t" | "^n" | "#" | "z");
         let opened_spell_prompt = state.pending_spell_interaction.is_some() && trimmed == "m";
         let opened_activation_prompt =
             state.pending_activation_interaction.is_some() && trimmed == "a";
         let opened_quit_prompt = state.pending_quit_interaction.is_some() && trimmed == "Q";
-        let opened_talk_prompt =
-            state.pending_talk_direction.is_some() && matches!(trimmed, "t" | "T");
+        let opened_talk_prompt = state.pending_talk_direction.is_some()
+            && matches!(trimmed, "t" | "T" | "o" | "c" | "J");
         let opened_inventory_prompt =
             state.pending_inventory_interaction.is_some() && matches!(trimmed, "i" | "I");
         let opened_item_prompt = state.pending_item_prompt.is_some()
             && matches!(trimmed, "q" | "r" | "e" | "d" | "f" | "z" | "A" | "C" | "G");
         let opened_targeting_prompt =
             state.pending_targeting_interaction.is_some() && matches!(trimmed, "f" | "m" | "z");
-        let non_advancing_wizard_token = matches!(trimmed, "^g" | "^w" | "^k" | "#");
+        let opened_look_prompt = state.pending_look_interaction.is_some() && trimmed == ";";
+        let opened_guild_prompt = state.pending_guild_action.is_some() && trimmed == "U";
+        let non_advancing_wizard_token = matches!(trimmed, "^g" | "^w" | "^k" | "^t" | "^n" | "#");
         if opened_wizard_prompt
             || opened_spell_prompt
             || opened_activation_prompt
@@ -2553,6 +4293,8 @@ pub fn step<R: RandomSource>(state: &mut GameState, command: Command, rng: &mut
             || opened_inventory_prompt
             || opened_item_prompt
             || opened_targeting_prompt
+            || opened_look_prompt
+            || opened_guild_prompt
             || non_advancing_wizard_token
         {
             freeze_world_progression = true;
@@ -2566,7 +4308,9 @@ pub fn step<R: RandomSource>(state: &mut GameState, command: Command, rng: &mut
     }
 
     if !freeze_world_progression && state.status == SessionStatus::InProgress {
-        apply_status_effects(state, &mut events);
+        apply_status_effects(state, rng, &mut events);
+        expire_overdue_heist(state, &mut events);
+        apply_companion_upkeep(state, &mut events);
     }
 
     if !freeze_world_progression && state.status == SessionStatus::InProgress && !state.ai_paused {
@@ -2591,23 +4335,86 @@ pub fn step<R: RandomSource>(state: &mut GameState, command: Command, rng: &mut
     sync_pack_order(state);
     core::mode::apply_after_command(mode_policies, state, &command_for_accounting, &mut events);
 
-    Outcome { turn: state.clock.turn, minutes: state.clock.minutes, status: state.status, events }
+    Outcome {
+        turn: state.clock.turn,
+        minutes: state.clock.minutes,
+        status: state.status,
+        events,
+        should_autosave: autosave_before.warrants_autosave(state),
+    }
+}
+
+fn step_wait_turns<R: RandomSource>(state: &mut GameState, count: u32, rng: &mut R) -> Outcome {
+    let mut events = Vec::new();
+    let known_monsters: Vec<u64> = state.monsters.iter().map(|monster| monster.id).collect();
+    let mut waited = 0u32;
+    let mut should_autosave = false;
+    while waited < count && !state.is_terminal() {
+        let hp_before = state.player.stats.hp;
+        let outcome = step(state, Command::Wait, rng);
+        waited += 1;
+        should_autosave |= outcome.should_autosave;
+        events.extend(outcome.events);
+        if wait_is_interrupted(state, &known_monsters, hp_before) {
+            break;
+        }
+    }
+    events.push(Event::WaitCompleted { requested: count, waited });
+    Outcome {
+        turn: state.clock.turn,
+        minutes: state.clock.minutes,
+        status: state.status,
+        events,
+        should_autosave,
+    }
+}
+
+fn wait_is_interrupted(state: &GameState, known_monsters: &[u64], hp_before: i32) -> bool {
+    if state.is_terminal() || state.player.stats.hp < hp_before {
+        return true;
+    }
+    if state.player.stats.hp <= state.player.stats.max_hp / 4 {
+        return true;
+    }
+    state.monsters.iter().any(|monster| {
+        !known_monsters.contains(&monster.id)
+            || (projectile_distance(monster.position, state.player.position) <= 1
+                && monster.is_hostile_to_player(state))
+    })
 }
 
-fn estimate_turn_minutes(command: &Command, world_mode: WorldMode, searchnum: u8) -> u64 {
+fn estimate_turn_minutes(command: &Command, state: &GameState) -> u64 {
+    let world_mode = state.world_mode;
     match command {
-        Command::Wait => 6,
-        Command::Move(_) => match world_mode {
-            WorldMode::DungeonCity => 5,
-            WorldMode::Countryside => 60,
-        },
-        Command::Attack(_) => 10,
+        Command::Wait | Command::WaitTurns { .. } => 6,
+        Command::Move(_) | Command::AutoExplore => environment_move_minutes(state),
+        Command::Attack(_) | Command::Engage(_) | Command::Bash(_) => 10,
+        Command::ApplyLockpick(_) => 15,
+        Command::Butcher => 30,
+        Command::ApplyTool { .. } | Command::Engrave { .. } | Command::Dip { .. } => 10,
+        Command::Zap { .. } | Command::Throw { .. } | Command::OfferSurrender => 5,
+        Command::GuildAction { .. } | Command::RecallCompanions => 10,
+        Command::Look => 0,
         Command::Pickup => 10,
-        Command::Drop { .. } => 5,
-        Command::Legacy { token } => estimate_legacy_turn_minutes(token, world_mode, searchnum),
+        Command::Drop { .. } | Command::DropQuantity { .. } | Command::Bank { .. } => 5,
+        Command::Legacy { token } => {
+            estimate_legacy_turn_minutes(token, world_mode, state.options.searchnum)
+        }
     }
 }
 
+fn environment_move_minutes(state: &GameState) -> u64 {
+    state
+        .environment_turn_minutes
+        .iter()
+        .find(|entry| entry.environment == state.environment)
+        .map(|entry| entry.minutes_per_turn)
+        .unwrap_or(match state.world_mode {
+            WorldMode::DungeonCity => 5,
+            WorldMode::Countryside => 60,
+        })
+}
+
 fn estimate_legacy_turn_minutes(token: &str, world_mode: WorldMode, searchnum: u8) -> u64 {
     match token.trim() {
         "." => 10,
@@ -2637,17 +4444,17 @@ fn estimate_legacy_turn_minutes(token: &str, world_mode: WorldMode, searchnum: u
         "d" => 5,
         "q" => 10,
         "m" => 20,
-        "t" => 10,
+        "t" | "K" | "J" => 10,
         "b" | "n" | "u" | "y" => 5,
         "G" => 15,
         "D" => 30,
-        "F" => 0,
+        "F" | ";" | "U" => 0,
         "S" => 0,
         "Q" => 0,
         "i" | "I" => 0,
         "^p" | "^o" | "^r" | "^l" | "?" | "/" | "P" | "V" => 0,
-        "^g" | "^w" | "^k" | "#" => 0,
-        "^x" => 5,
+        "^g" | "^w" | "^k" | "^t" | "^n" | "#" => 0,
+        "^x" | "Y" => 5,
         "^f" | "^i" | "C" | "R" => 5,
         "O" => 0,
         "@" => 5,
@@ -2717,16 +4524,21 @@ fn apply_legacy_command<R: RandomSource>(
             state.log.push("You rest for an extended period.".to_string());
             events.push(Event::Waited);
             if state.player.stats.hp < state.player.stats.max_hp {
-                state.player.stats.hp += 1;
+                if too_hungry_to_recover(state) {
+                    warn_too_hungry_to_recover(state);
+                } else {
+                    state.player.stats.heal(1);
+                }
             }
             ("sleep resolved with minor recovery".to_string(), true)
         }
         "<" => {
             state.topology.last_city_position = Some(state.player.position);
+            let region_id = state.country_region_for_exit(state.map_binding.map_id);
             ensure_country_bootstrap(state);
             state.activate_country_view();
             ensure_known_site(state, state.player.position);
-            state.topology.country_region_id = state.topology.country_region_id.wrapping_add(1);
+            state.travel_to_country_region(region_id);
             let fallback = Position { x: state.bounds.width / 2, y: state.bounds.height / 2 };
             let target = state
                 .topology
@@ -2738,7 +4550,7 @@ fn apply_legacy_command<R: RandomSource>(
             }
             ("entered countryside mode".to_string(), true)
         }
-        ">" => resolve_enter_command(state, events),
+        ">" => resolve_enter_command(state, events, rng),
         "M" => {
             if state.world_mode == WorldMode::DungeonCity
                 && state
@@ -2753,14 +4565,7 @@ fn apply_legacy_command<R: RandomSource>(
                 }
                 let idx = (state.clock.turn as usize) % state.known_sites.len();
                 state.player.position = state.known_sites[idx];
-                if state.gold >= 5 {
-                    state.gold -= 5;
-                }
-                events.push(Event::EconomyUpdated {
-                    source: "fast_travel".to_string(),
-                    gold: state.gold,
-                    bank_gold: state.bank_gold,
-                });
+                let _ = state.spend_gold(5, "fast_travel", events);
                 ("fast travel to discovered site".to_string(), true)
             }
         }
@@ -2789,6 +4594,12 @@ fn apply_legacy_command<R: RandomSource>(
                 *bonus_minutes = bonus_minutes.saturating_add(bonus);
                 ("countryside search discovered a new trace".to_string(), true)
             } else {
+                let player = state.player.position;
+                for trap in state.traps.iter_mut() {
+                    if trap.position.manhattan_distance(player) <= 1 {
+                        trap.discovered = true;
+                    }
+                }
                 let loops = state.options.searchnum.max(1);
                 for i in 0..loops {
                     let item_name = format!("cache provision {}-{}", state.next_item_id, i + 1);
@@ -2820,16 +4631,10 @@ fn apply_legacy_command<R: RandomSource>(
         "t" => begin_talk_direction_interaction(state, TalkDirectionInteraction::Talk),
         "G" => {
             if state.player.inventory.is_empty() {
-                if state.gold >= 10 {
-                    state.gold -= 10;
+                if state.spend_gold(10, "donation", events) {
                     state.progression.deity_favor += 3;
                     state.progression.law_chaos_score += 2;
                 }
-                events.push(Event::EconomyUpdated {
-                    source: "donation".to_string(),
-                    gold: state.gold,
-                    bank_gold: state.bank_gold,
-                });
                 ("gift resolved; favor and alignment adjusted".to_string(), true)
             } else {
                 begin_item_prompt(
@@ -2841,6 +4646,14 @@ fn apply_legacy_command<R: RandomSource>(
             }
         }
         "D" => disarm_adjacent_trap(state, events),
+        "Y" => (offer_arena_surrender(state, events), true),
+        "K" => (recall_companions(state, events), true),
+        "U" => begin_guild_action_interaction(state),
+        ";" => {
+            let interaction = LookInteraction { cursor: state.player.position };
+            state.pending_look_interaction = Some(interaction);
+            (look_interaction_prompt(&interaction), true)
+        }
         "F" => {
             rotate_combat_sequence(state);
             ("combat sequence preset updated".to_string(), true)
@@ -2865,7 +4678,7 @@ fn apply_legacy_command<R: RandomSource>(
                 )
             } else if state.food > 0 {
                 state.food -= 1;
-                state.player.stats.hp = (state.player.stats.hp + 2).min(state.player.stats.max_hp);
+                state.player.stats.heal(2);
                 ("ate rations and recovered health".to_string(), true)
             } else {
                 ("eat requested but no food available".to_string(), true)
@@ -2944,7 +4757,7 @@ fn apply_legacy_command<R: RandomSource>(
         }
         "^w" => {
             if state.wizard.enabled {
-                reveal_map_for_wizard(state);
+                reveal_map_for_wizard(state, events);
                 ("wizard map revealed the full current environment".to_string(), true)
             } else {
                 ("wizard-only command denied".to_string(), true)
@@ -2972,6 +4785,28 @@ fn apply_legacy_command<R: RandomSource>(
                 ("wizard-only command denied".to_string(), true)
             }
         }
+        "^t" => {
+            if state.wizard.enabled {
+                begin_wizard_interaction(
+                    state,
+                    WizardInteraction::TeleportEntry,
+                    "Teleport to (x,y): _".to_string(),
+                )
+            } else {
+                ("wizard-only command denied".to_string(), true)
+            }
+        }
+        "^n" => {
+            if state.wizard.enabled {
+                begin_wizard_interaction(
+                    state,
+                    WizardInteraction::SpawnMonsterEntry,
+                    "Spawn which monster? _".to_string(),
+                )
+            } else {
+                ("wizard-only command denied".to_string(), true)
+            }
+        }
         "#" => {
             if state.wizard.enabled {
                 begin_wizard_interaction(
@@ -2984,24 +4819,10 @@ fn apply_legacy_command<R: RandomSource>(
                 ("wizard-only command denied".to_string(), true)
             }
         }
-        "o" => (apply_door_interaction(state, false), true),
-        "c" => (apply_door_interaction(state, true), true),
+        "o" => begin_talk_direction_interaction(state, TalkDirectionInteraction::OpenDoor),
+        "c" => begin_talk_direction_interaction(state, TalkDirectionInteraction::CloseDoor),
         "E" => ("mount/dismount action resolved".to_string(), true),
-        "p" => {
-            if has_adjacent_monster(state) {
-                state.gold += 20;
-                state.progression.law_chaos_score -= 2;
-                events.push(Event::EconomyUpdated {
-                    source: "pickpocket".to_string(),
-                    gold: state.gold,
-                    bank_gold: state.bank_gold,
-                });
-                ("pickpocket succeeded".to_string(), true)
-            } else {
-                state.legal_heat += 1;
-                ("pickpocket failed; legal heat increased".to_string(), true)
-            }
-        }
+        "p" => resolve_pickpocket(state, rng, events),
         "f" => begin_item_prompt(
             state,
             ItemPromptContext::FireThrow,
@@ -3025,6 +4846,7 @@ fn apply_legacy_command<R: RandomSource>(
             "Zap which stick?".to_string(),
         ),
         "T" => begin_talk_direction_interaction(state, TalkDirectionInteraction::Tunnel),
+        "J" => begin_talk_direction_interaction(state, TalkDirectionInteraction::Engage),
         "Z" => {
             if trimmed == "Z" && state.environment == LegacyEnvironment::City {
                 begin_wizard_interaction(
@@ -3081,11 +4903,15 @@ fn apply_legacy_command<R: RandomSource>(
     events.push(Event::LegacyHandled { token: trimmed.to_string(), note, fully_modeled });
 }
 
-fn resolve_enter_command(state: &mut GameState, events: &mut Vec<Event>) -> (String, bool) {
+fn resolve_enter_command<R: RandomSource>(
+    state: &mut GameState,
+    events: &mut Vec<Event>,
+    rng: &mut R,
+) -> (String, bool) {
     if state.world_mode == WorldMode::Countryside {
         return resolve_enter_country_site(state);
     }
-    resolve_enter_local_site(state, events)
+    resolve_enter_local_site(state, events, rng)
 }
 
 fn ensure_country_bootstrap(state: &mut GameState) {
@@ -3193,12 +5019,9 @@ fn resolve_enter_country_site(state: &mut GameState) -> (String, bool) {
             let Some((map_id, spawn, village_name)) = village_map_for_aux(cell.aux) else {
                 return ("village entry failed: unknown village identifier".to_string(), true);
             };
-            if state.activate_site_map_by_id(map_id, Some(spawn)) {
-                state.topology.dungeon_level = 0;
-                (format!("entered village {village_name}"), true)
-            } else {
-                ("village map missing from loaded content".to_string(), true)
-            }
+            state.activate_or_generate_site(map_id, LegacyEnvironment::Village, spawn);
+            state.topology.dungeon_level = 0;
+            (format!("entered village {village_name}"), true)
         }
         CountryTerrainKind::Temple => {
             if cell.aux == DEITY_ID_DESTINY
@@ -3206,76 +5029,116 @@ fn resolve_enter_country_site(state: &mut GameState) -> (String, bool) {
             {
                 state.topology.dungeon_level = 0;
                 ("entered the Hedge Maze".to_string(), true)
-            } else if state.activate_site_map_by_id(16, Some(Position { x: 32, y: 15 })) {
+            } else {
+                state.activate_or_generate_site(
+                    16,
+                    LegacyEnvironment::Temple,
+                    Position { x: 32, y: 15 },
+                );
                 state.topology.dungeon_level = 0;
                 (format!("entered temple {}", cell.aux), true)
-            } else {
-                ("temple map missing from loaded content".to_string(), true)
             }
         }
         CountryTerrainKind::Castle => {
-            if state.activate_site_map_by_id(5, Some(Position { x: 32, y: 2 })) {
-                state.topology.dungeon_level = 0;
-                remap_active_site_aux(state, SITE_AUX_SERVICE_PALACE, SITE_AUX_SERVICE_CASTLE);
-                ("entered the royal court".to_string(), true)
-            } else {
-                ("court map missing from loaded content".to_string(), true)
-            }
+            state.activate_or_generate_site(5, LegacyEnvironment::Castle, Position { x: 32, y: 2 });
+            state.topology.dungeon_level = 0;
+            remap_active_site_aux(state, SITE_AUX_SERVICE_PALACE, SITE_AUX_SERVICE_CASTLE);
+            ("entered the royal court".to_string(), true)
         }
         CountryTerrainKind::Palace => {
-            if state.activate_site_map_by_id(5, Some(Position { x: 32, y: 2 })) {
-                state.topology.dungeon_level = 0;
-                state.environment = LegacyEnvironment::Palace;
-                remap_active_site_aux(state, SITE_AUX_SERVICE_CASTLE, SITE_AUX_SERVICE_PALACE);
-                ("entered the imperial palace".to_string(), true)
-            } else {
-                ("court map missing from loaded content".to_string(), true)
-            }
+            state.activate_or_generate_site(5, LegacyEnvironment::Castle, Position { x: 32, y: 2 });
+            state.topology.dungeon_level = 0;
+            state.environment = LegacyEnvironment::Palace;
+            remap_active_site_aux(state, SITE_AUX_SERVICE_CASTLE, SITE_AUX_SERVICE_PALACE);
+            ("entered the imperial palace".to_string(), true)
         }
         CountryTerrainKind::Caves => {
-            if state.activate_site_map_by_id(2, Some(Position { x: 2, y: 2 })) {
-                state.topology.dungeon_level = 0;
-                ("entered the caves".to_string(), true)
-            } else {
-                ("caves map missing from loaded content".to_string(), true)
-            }
+            state.activate_or_generate_site(2, LegacyEnvironment::Caves, Position { x: 2, y: 2 });
+            state.topology.dungeon_level = 0;
+            ("entered the caves".to_string(), true)
         }
         CountryTerrainKind::Volcano => {
-            if state.activate_site_map_by_id(4, Some(Position { x: 32, y: 8 })) {
-                state.topology.dungeon_level = 0;
-                ("entered the volcano".to_string(), true)
-            } else {
-                ("volcano map missing from loaded content".to_string(), true)
-            }
+            state.activate_or_generate_site(
+                4,
+                LegacyEnvironment::Volcano,
+                Position { x: 32, y: 8 },
+            );
+            state.topology.dungeon_level = 0;
+            ("entered the volcano".to_string(), true)
         }
         CountryTerrainKind::DragonLair => {
-            if state.activate_site_map_by_id(6, Some(Position { x: 8, y: 0 })) {
-                state.topology.dungeon_level = 0;
-                ("entered dragon lair".to_string(), true)
-            } else {
-                ("dragon lair map missing from loaded content".to_string(), true)
-            }
+            state.activate_or_generate_site(
+                6,
+                LegacyEnvironment::DragonLair,
+                Position { x: 8, y: 0 },
+            );
+            state.topology.dungeon_level = 0;
+            ("entered dragon lair".to_string(), true)
         }
         CountryTerrainKind::StarPeak => {
-            if state.activate_site_map_by_id(13, Some(Position { x: 2, y: 9 })) {
-                state.topology.dungeon_level = 0;
-                ("entered Star Peak".to_string(), true)
-            } else {
-                ("star peak map missing from loaded content".to_string(), true)
-            }
+            state.activate_or_generate_site(
+                13,
+                LegacyEnvironment::StarPeak,
+                Position { x: 2, y: 9 },
+            );
+            state.topology.dungeon_level = 0;
+            ("entered Star Peak".to_string(), true)
         }
         CountryTerrainKind::MagicIsle => {
-            if state.activate_site_map_by_id(11, Some(Position { x: 62, y: 14 })) {
-                state.topology.dungeon_level = 0;
-                ("entered Magic Isle".to_string(), true)
-            } else {
-                ("magic isle map missing from loaded content".to_string(), true)
-            }
+            state.activate_or_generate_site(
+                11,
+                LegacyEnvironment::MagicIsle,
+                Position { x: 62, y: 14 },
+            );
+            state.topology.dungeon_level = 0;
+            ("entered Magic Isle".to_string(), true)
         }
         _ => ("there is nothing to enter here".to_string(), true),
     }
 }
 
+const GENERATED_SITE_WIDTH: i32 = 64;
+const GENERATED_SITE_HEIGHT: i32 = 16;
+
+/// A walled room the size of a legacy site map. The spawn tile is always open
+/// floor marked as the exit back to the countryside.
+fn generated_site_map(map_id: u16, env: LegacyEnvironment, spawn: Position) -> SiteMapDefinition {
+    let spawn = Position {
+        x: spawn.x.clamp(0, GENERATED_SITE_WIDTH - 1),
+        y: spawn.y.clamp(0, GENERATED_SITE_HEIGHT - 1),
+    };
+    let mut rows = Vec::new();
+    let mut site_grid = Vec::new();
+    for y in 0..GENERATED_SITE_HEIGHT {
+        let mut row = String::new();
+        for x in 0..GENERATED_SITE_WIDTH {
+            let pos = Position { x, y };
+            let wall =
+                x == 0 || y == 0 || x == GENERATED_SITE_WIDTH - 1 || y == GENERATED_SITE_HEIGHT - 1;
+            let cell = if pos == spawn {
+                TileSiteCell { aux: SITE_AUX_EXIT_COUNTRYSIDE, ..TileSiteCell::default() }
+            } else if wall {
+                TileSiteCell { glyph: '#', flags: TILE_FLAG_BLOCK_MOVE, ..TileSiteCell::default() }
+            } else {
+                TileSiteCell::default()
+            };
+            row.push(cell.glyph);
+            site_grid.push(cell);
+        }
+        rows.push(row);
+    }
+    SiteMapDefinition {
+        map_id,
+        level_index: 0,
+        source: "generated".to_string(),
+        environment: env,
+        semantic: MapSemanticKind::Site,
+        spawn,
+        rows,
+        site_grid,
+    }
+}
+
 fn remap_active_site_aux(state: &mut GameState, from_aux: i32, to_aux: i32) {
     for cell in &mut state.site_grid {
         if cell.aux == from_aux {
@@ -3323,7 +5186,11 @@ fn apply_garden_local_interaction(
     Some(note)
 }
 
-fn resolve_enter_local_site(state: &mut GameState, events: &mut Vec<Event>) -> (String, bool) {
+fn resolve_enter_local_site<R: RandomSource>(
+    state: &mut GameState,
+    events: &mut Vec<Event>,
+    rng: &mut R,
+) -> (String, bool) {
     let site_aux = state.tile_site_at(state.player.position).map(|site| site.aux).unwrap_or(0);
 
     if site_aux == SITE_AUX_EXIT_ARENA {
@@ -3348,10 +5215,11 @@ fn resolve_enter_local_site(state: &mut GameState, events: &mut Vec<Event>) -> (
 
     if site_aux == SITE_AUX_EXIT_COUNTRYSIDE {
         state.topology.last_city_position = Some(state.player.position);
+        let region_id = state.country_region_for_exit(state.map_binding.map_id);
         ensure_country_bootstrap(state);
         state.activate_country_view();
         ensure_known_site(state, state.player.position);
-        state.topology.country_region_id = state.topology.country_region_id.wrapping_add(1);
+        state.travel_to_country_region(region_id);
         let fallback = Position { x: state.bounds.width / 2, y: state.bounds.height / 2 };
         let target = state
             .topology
@@ -3368,6 +5236,10 @@ fn resolve_enter_local_site(state: &mut GameState, events: &mut Vec<Event>) -> (
         return (note, true);
     }
 
+    if let Some(note) = closed_site_notice(state, site_aux) {
+        return (note, true);
+    }
+
     if state.options.interactive_sites
         && let Some(kind) = interaction_kind_for_site_aux(state, site_aux)
     {
@@ -3375,7 +5247,7 @@ fn resolve_enter_local_site(state: &mut GameState, events: &mut Vec<Event>) -> (
         return (note, true);
     }
 
-    if let Some(note) = apply_site_service(state, site_aux, events) {
+    if let Some(note) = apply_site_service(state, site_aux, rng, events) {
         return (note, true);
     }
 
@@ -3418,17 +5290,87 @@ fn resolve_enter_local_site(state: &mut GameState, events: &mut Vec<Event>) -> (
     ("descended to deeper area".to_string(), true)
 }
 
-fn arena_rival_profile(opponent: u8, arena_rank: i8) -> (String, Stats) {
-    let challenger_names = [
-        "Ari", "Borek", "Cira", "Dolan", "Edda", "Fenn", "Garth", "Hale", "Iris", "Jorek", "Kara",
-        "Lorn", "Mira", "Nox", "Orin", "Pax",
-    ];
-    let roster = [
-        "pencil-necked geek",
-        "hornet",
-        "hyena",
-        "goblin",
-        "grunt",
+const DUNGEON_LEVEL_MAP_BASE: u16 = 1000;
+const LEVEL_LOOT_BASE: i16 = 2;
+const LEVEL_LOOT_MAX: i16 = 6;
+
+/// Scatters depth-appropriate loot over the open floor of a freshly generated
+/// level, away from the player and from any item already lying there.
+fn scatter_level_loot<R: RandomSource>(state: &mut GameState, depth: i16, rng: &mut R) {
+    let count = (LEVEL_LOOT_BASE + depth / 3).min(LEVEL_LOOT_MAX);
+    let mut placed = 0;
+    for _ in 0..count * 8 {
+        if placed >= count {
+            break;
+        }
+        let position = Position {
+            x: rng.range_inclusive_i32(1, (state.bounds.width - 2).max(1)),
+            y: rng.range_inclusive_i32(1, (state.bounds.height - 2).max(1)),
+        };
+        if position == state.player.position
+            || !state.tile_is_walkable(position)
+            || state.ground_items.iter().any(|ground| ground.position == position)
+        {
+            continue;
+        }
+        let item = state.roll_item_for_depth(depth, rng);
+        state.ground_items.push(GroundItem { position, item });
+        placed += 1;
+    }
+}
+
+/// Drops the player onto the level below a chasm, generating it on the first
+/// visit and scattering depth-appropriate loot over it. A generated level
+/// keeps an up staircase in a corner that leads back out to the countryside,
+/// so a fall never strands the player.
+fn descend_one_level<R: RandomSource>(state: &mut GameState, rng: &mut R) -> i16 {
+    let depth = state.topology.dungeon_level.saturating_add(1);
+    let map_id = DUNGEON_LEVEL_MAP_BASE.saturating_add(depth.max(0) as u16);
+    let arrival = state.player.position;
+    if !state.activate_site_map_by_id(map_id, Some(arrival)) {
+        let mut level = generated_site_map(map_id, state.environment, arrival);
+        level.semantic = MapSemanticKind::Dungeon;
+        level.level_index = depth.max(0) as u16;
+        let stairs = if level.spawn == (Position { x: 1, y: 1 }) {
+            Position { x: GENERATED_SITE_WIDTH - 2, y: GENERATED_SITE_HEIGHT - 2 }
+        } else {
+            Position { x: 1, y: 1 }
+        };
+        for (index, cell) in level.site_grid.iter_mut().enumerate() {
+            let pos = Position {
+                x: index as i32 % GENERATED_SITE_WIDTH,
+                y: index as i32 / GENERATED_SITE_WIDTH,
+            };
+            if pos == stairs {
+                *cell = TileSiteCell {
+                    glyph: '<',
+                    aux: SITE_AUX_EXIT_COUNTRYSIDE,
+                    ..TileSiteCell::default()
+                };
+            } else {
+                cell.aux = 0;
+            }
+        }
+        let _ = set_row_char(&mut level.rows, stairs, '<');
+        state.site_maps.push(level);
+        let _ = state.activate_site_map_by_id(map_id, Some(arrival));
+        scatter_level_loot(state, depth, rng);
+    }
+    state.topology.dungeon_level = depth;
+    depth
+}
+
+fn arena_rival_profile(opponent: u8, arena_rank: i8) -> (String, Stats) {
+    let challenger_names = [
+        "Ari", "Borek", "Cira", "Dolan", "Edda", "Fenn", "Garth", "Hale", "Iris", "Jorek", "Kara",
+        "Lorn", "Mira", "Nox", "Orin", "Pax",
+    ];
+    let roster = [
+        "pencil-necked geek",
+        "hornet",
+        "hyena",
+        "goblin",
+        "grunt",
         "tove",
         "apprentice ninja",
         "salamander",
@@ -3561,9 +5503,10 @@ fn start_arena_challenge(state: &mut GameState) -> String {
     "The arena challenge cannot begin: no free tile for challenger.".to_string()
 }
 
-fn apply_site_service(
+fn apply_site_service<R: RandomSource>(
     state: &mut GameState,
     site_aux: i32,
+    rng: &mut R,
     events: &mut Vec<Event>,
 ) -> Option<String> {
     let kind = interaction_kind_for_site_aux(state, site_aux)?;
@@ -3827,7 +5770,215 @@ fn apply_site_service(
         _ => 1,
     };
 
-    Some(apply_site_interaction_choice(state, kind, choice, events, false))
+    Some(apply_site_interaction_choice(state, kind, choice, rng, events, false))
+}
+
+/// Maps a named guild action onto the numbered menu choice that performs it.
+fn guild_action_choice(guild: &SiteInteractionKind, action: GuildAction) -> Option<usize> {
+    match (guild, action) {
+        (SiteInteractionKind::MercGuild, GuildAction::Train) => Some(1),
+        (SiteInteractionKind::MercGuild, GuildAction::TakeContract) => Some(2),
+        (SiteInteractionKind::MercGuild, GuildAction::Promote) => Some(3),
+        (SiteInteractionKind::MercGuild, GuildAction::Join) => Some(6),
+        (SiteInteractionKind::ThievesGuild, GuildAction::Join) => Some(1),
+        (SiteInteractionKind::ThievesGuild, GuildAction::TakeContract) => Some(2),
+        (SiteInteractionKind::ThievesGuild, GuildAction::Promote) => Some(3),
+        (SiteInteractionKind::College, GuildAction::Train) => Some(1),
+        _ => None,
+    }
+}
+
+fn guild_membership_rank(state: &GameState, guild: &SiteInteractionKind) -> Option<i16> {
+    match guild {
+        SiteInteractionKind::MercGuild => Some(i16::from(state.progression.guild_rank)),
+        SiteInteractionKind::ThievesGuild => Some(state.progression.quests.thieves.rank.max(0)),
+        _ => None,
+    }
+}
+
+fn resolve_guild_action<R: RandomSource>(
+    state: &mut GameState,
+    guild: SiteInteractionKind,
+    action: GuildAction,
+    rng: &mut R,
+    events: &mut Vec<Event>,
+) -> String {
+    let label = site_interaction_label(&guild);
+    let standing_at = state
+        .tile_site_at(state.player.position)
+        .and_then(|cell| interaction_kind_for_site_aux(state, cell.aux));
+    let rank = guild_membership_rank(state, &guild);
+    let note = if standing_at.as_ref() != Some(&guild) {
+        format!("You must be inside {label} to do that.")
+    } else if !site_is_open(&guild, state.time_of_day()) {
+        "The door is locked; come back during the day.".to_string()
+    } else if let Some(choice) = guild_action_choice(&guild, action) {
+        match (action, rank) {
+            (GuildAction::Join, Some(rank)) if rank > 0 => {
+                format!("You're already a member of {label}.")
+            }
+            (GuildAction::TakeContract | GuildAction::Promote | GuildAction::Train, Some(0)) => {
+                format!("You must join {label} first.")
+            }
+            _ => apply_site_interaction_choice(state, guild, choice, rng, events, false),
+        }
+    } else {
+        format!("There is no such service at {label}.")
+    };
+    events.push(Event::LegacyHandled {
+        token: "guild_action".to_string(),
+        note: note.clone(),
+        fully_modeled: true,
+    });
+    note
+}
+
+fn guild_action_prompt(guild: &SiteInteractionKind) -> String {
+    let label = site_interaction_label(guild);
+    format!("At {label}: [j]oin, take a [c]ontract, [p]romote, or [t]rain? (ESCAPE aborts).")
+}
+
+fn guild_action_help_hint() -> String {
+    "Guild prompt active: j join, c contract, p promote, t train, q/esc cancels.".to_string()
+}
+
+pub fn active_guild_action_prompt(state: &GameState) -> Option<String> {
+    state.pending_guild_action.as_ref().map(guild_action_prompt)
+}
+
+pub fn active_guild_action_help_hint(state: &GameState) -> Option<String> {
+    state.pending_guild_action.as_ref().map(|_| guild_action_help_hint())
+}
+
+fn begin_guild_action_interaction(state: &mut GameState) -> (String, bool) {
+    let guild = state
+        .tile_site_at(state.player.position)
+        .and_then(|cell| interaction_kind_for_site_aux(state, cell.aux))
+        .filter(|kind| {
+            matches!(
+                kind,
+                SiteInteractionKind::MercGuild
+                    | SiteInteractionKind::ThievesGuild
+                    | SiteInteractionKind::College
+            )
+        });
+    let Some(guild) = guild else {
+        return ("There is no guild here.".to_string(), true);
+    };
+    let prompt = guild_action_prompt(&guild);
+    state.pending_guild_action = Some(guild);
+    (prompt, true)
+}
+
+fn resolve_pending_guild_action_interaction<R: RandomSource>(
+    state: &mut GameState,
+    command: &Command,
+    events: &mut Vec<Event>,
+    rng: &mut R,
+) -> Option<GuildActionInteractionResolution> {
+    let guild = state.pending_guild_action.clone()?;
+    let mut resolution = GuildActionInteractionResolution {
+        freeze_world_progression: true,
+        command_for_accounting: Command::Legacy { token: "F".to_string() },
+        turn_minutes: 0,
+    };
+
+    let action = match parse_wizard_input_token(command) {
+        WizardInputToken::Cancel => {
+            state.pending_guild_action = None;
+            events.push(Event::LegacyHandled {
+                token: "U".to_string(),
+                note: "guild business canceled".to_string(),
+                fully_modeled: true,
+            });
+            return Some(resolution);
+        }
+        WizardInputToken::Text(text) => match text.chars().next() {
+            Some('j') => Some(GuildAction::Join),
+            Some('c') => Some(GuildAction::TakeContract),
+            Some('p') => Some(GuildAction::Promote),
+            Some('t') => Some(GuildAction::Train),
+            _ => None,
+        },
+        _ => None,
+    };
+    let Some(action) = action else {
+        events.push(Event::LegacyHandled {
+            token: "U".to_string(),
+            note: guild_action_help_hint(),
+            fully_modeled: true,
+        });
+        return Some(resolution);
+    };
+
+    state.pending_guild_action = None;
+    let command = Command::GuildAction { guild: guild.clone(), action };
+    let note = resolve_guild_action(state, guild, action, rng, events);
+    push_timeline_line(state, note);
+    resolution.freeze_world_progression = false;
+    resolution.turn_minutes = estimate_turn_minutes(&command, state);
+    resolution.command_for_accounting = command;
+    Some(resolution)
+}
+
+// Legacy time starts at noon on the first day.
+const LEGACY_CLOCK_START_MINUTE_OF_DAY: u64 = 12 * 60;
+
+fn site_interaction_label(kind: &SiteInteractionKind) -> String {
+    let label = match kind {
+        SiteInteractionKind::Shop => "the shop",
+        SiteInteractionKind::Armorer => "the armorer",
+        SiteInteractionKind::Club => "the explorers' club",
+        SiteInteractionKind::Gym => "the gym",
+        SiteInteractionKind::Healer => "the healer",
+        SiteInteractionKind::Casino => "the casino",
+        SiteInteractionKind::Commandant => "the commandant's office",
+        SiteInteractionKind::Diner => "the diner",
+        SiteInteractionKind::Craps => "the craps table",
+        SiteInteractionKind::Tavern => "the tavern",
+        SiteInteractionKind::PawnShop => "the pawn shop",
+        SiteInteractionKind::Brothel => "the brothel",
+        SiteInteractionKind::Condo => "the condo",
+        SiteInteractionKind::Bank => "the bank",
+        SiteInteractionKind::MercGuild => "the merc guild",
+        SiteInteractionKind::ThievesGuild => "the thieves guild",
+        SiteInteractionKind::Temple => "the temple",
+        SiteInteractionKind::College => "the college",
+        SiteInteractionKind::Sorcerors => "the sorcerors' guild",
+        SiteInteractionKind::Castle => "the castle",
+        SiteInteractionKind::Palace => "the palace",
+        SiteInteractionKind::Order => "the order hall",
+        SiteInteractionKind::Charity => "the charity house",
+        SiteInteractionKind::Monastery => "the monastery",
+        SiteInteractionKind::Arena => "the arena",
+        SiteInteractionKind::Altar { deity_id } => {
+            return format!("an altar of {}", deity_name(*deity_id));
+        }
+    };
+    label.to_string()
+}
+
+fn site_opening_hours(kind: &SiteInteractionKind) -> Option<&'static [TimeOfDay]> {
+    match kind {
+        SiteInteractionKind::Shop
+        | SiteInteractionKind::Armorer
+        | SiteInteractionKind::Diner
+        | SiteInteractionKind::PawnShop => Some(&[TimeOfDay::Day, TimeOfDay::Dusk]),
+        // Healers, guilds and the other civic services never lock their doors.
+        _ => None,
+    }
+}
+
+fn site_is_open(kind: &SiteInteractionKind, time_of_day: TimeOfDay) -> bool {
+    site_opening_hours(kind).is_none_or(|open| open.contains(&time_of_day))
+}
+
+fn closed_site_notice(state: &GameState, site_aux: i32) -> Option<String> {
+    let kind = interaction_kind_for_site_aux(state, site_aux)?;
+    if site_is_open(&kind, state.time_of_day()) {
+        return None;
+    }
+    Some("The door is locked; come back during the day.".to_string())
 }
 
 fn interaction_kind_for_site_aux(state: &GameState, site_aux: i32) -> Option<SiteInteractionKind> {
@@ -3926,8 +6077,8 @@ fn sacrilege_penalty(state: &mut GameState, deity_id: u8) -> String {
     state.progression.deity_favor = 0;
     state.progression.deity_blessing_ready = false;
     state.spellbook.max_mana = (state.spellbook.max_mana - 12).max(24);
-    state.spellbook.mana = state.spellbook.mana.min(state.spellbook.max_mana);
-    state.player.stats.hp = (state.player.stats.hp - 6).max(1);
+    state.spellbook.set_mana(state.spellbook.mana);
+    state.player.stats.set_hp((state.player.stats.hp - 6).max(1));
     format!(
         "Sacrilege! {} strips your patronage as you pray to {}.",
         deity_name(patron),
@@ -3982,15 +6133,9 @@ fn apply_altar_prayer(state: &mut GameState, deity_id: u8, events: &mut Vec<Even
 }
 
 fn apply_altar_sacrifice(state: &mut GameState, deity_id: u8, events: &mut Vec<Event>) -> String {
-    if state.gold < 50 {
+    if !state.spend_gold(50, "altar", events) {
         return "Not enough gold for a sacrifice.".to_string();
     }
-    state.gold -= 50;
-    events.push(Event::EconomyUpdated {
-        source: "altar".to_string(),
-        gold: state.gold,
-        bank_gold: state.bank_gold,
-    });
 
     if state.progression.patron_deity == 0 {
         return "A sacrifice alone is not enough; establish devotion first.".to_string();
@@ -4033,8 +6178,8 @@ fn apply_altar_blessing(state: &mut GameState, deity_id: u8, events: &mut Vec<Ev
     if state.progression.deity_blessing_ready || state.progression.deity_favor >= 8 {
         state.progression.deity_blessing_ready = false;
         state.progression.deity_favor = state.progression.deity_favor.saturating_sub(8);
-        state.player.stats.hp = (state.player.stats.hp + 10).min(state.player.stats.max_hp);
-        state.spellbook.mana = (state.spellbook.mana + 20).min(state.spellbook.max_mana);
+        state.player.stats.heal(10);
+        state.spellbook.restore_mana(20);
         return "A shaft of lucent radiance lances down from the heavens! You feel uplifted...."
             .to_string();
     }
@@ -4045,7 +6190,13 @@ fn apply_altar_blessing(state: &mut GameState, deity_id: u8, events: &mut Vec<Ev
 fn site_interaction_prompt(state: &GameState, kind: &SiteInteractionKind) -> String {
     match kind {
         SiteInteractionKind::Shop => format!(
-            "Shop: [1/r] ration (12g) [2/p] healing potion (30g) [3/i] identify scroll (40g) [4/x] leave | gold={}",
+            "Shop: [1/r] ration ({}g) x{} [2/p] healing potion ({}g) x{} [3/i] identify scroll ({}g) x{} [4/x] leave [5/h] haggle | gold={}",
+            shop_price(state, 12),
+            shop_stock_left(state, 1),
+            shop_price(state, 30),
+            shop_stock_left(state, 2),
+            shop_price(state, 40),
+            shop_stock_left(state, 3),
             state.gold
         ),
         SiteInteractionKind::Armorer => format!(
@@ -4053,8 +6204,8 @@ fn site_interaction_prompt(state: &GameState, kind: &SiteInteractionKind) -> Str
             state.gold
         ),
         SiteInteractionKind::Club => format!(
-            "Club: [1/m] membership drink (20g) [2/l] legal favor (20g) [3/x] leave | gold={} heat={}",
-            state.gold, state.legal_heat
+            "Club: [1/m] membership drink (20g) [2/l] legal favor (20g) [3/x] leave [4/a] absolution ({}g) | gold={} heat={}",
+            CLUB_ABSOLUTION_COST, state.gold, state.legal_heat
         ),
         SiteInteractionKind::Gym => format!(
             "Gym: [1/d] drills (30g) [2/s] spar contract (35g) [3/x] leave | gold={} hp={}/{}",
@@ -4084,26 +6235,33 @@ fn site_interaction_prompt(state: &GameState, kind: &SiteInteractionKind) -> Str
             "Tavern: [1/a] ale (6g) [2/m] stew (10g) [3/r] rumor (8g) [4/x] leave | gold={} food={} heat={}",
             state.gold, state.food, state.legal_heat
         ),
-        SiteInteractionKind::PawnShop => format!(
-            "Pawn shop: [1/b] buy oddity (15g) [2/s] sell first item [3/x] leave | gold={} pack={}",
-            state.gold,
-            state.player.inventory.len()
-        ),
+        SiteInteractionKind::PawnShop => {
+            let (offer, price) = pawn_shop_offer(state);
+            format!(
+                "Pawn shop: [1/b] buy {} ({price}g) [2/s] sell first item [3/x] leave | gold={} pack={}",
+                item_condition_label(&offer),
+                state.gold,
+                state.player.inventory.len()
+            )
+        }
         SiteInteractionKind::Brothel => format!(
             "Brothel: [1/r] rent room (25g) [2/g] pay for gossip (10g) [3/x] leave | gold={} hp={}/{}",
             state.gold, state.player.stats.hp, state.player.stats.max_hp
         ),
         SiteInteractionKind::Condo => format!(
-            "Condo: [1/r] rent room (40g) [2/s] secure stash (15g) [3/x] leave | gold={} bank={}",
-            state.gold, state.bank_gold
+            "Condo: [1/r] rent room (40g) [2/s] secure stash (15g) [3/x] leave [4/t] retire ({}g hoard) | gold={} bank={}",
+            CONDO_RETIREMENT_HOARD, state.gold, state.bank_gold
         ),
         SiteInteractionKind::Bank => format!(
             "Bank: [1/d] deposit 50 [2/w] withdraw 50 [3/s] post surety 25 [4/x] leave | gold={} bank={} legal_heat={}",
             state.gold, state.bank_gold, state.legal_heat
         ),
         SiteInteractionKind::MercGuild => format!(
-            "Merc guild: [1/t] train arms (40g) [2/c] take contract (40g) [3/p] promotion board (60g) [4/x] leave | gold={} rank={}",
-            state.gold, state.progression.guild_rank
+            "Merc guild: [1/t] train arms (40g) [2/c] take contract (40g) [3/p] promotion board (60g) [4/x] leave [5/h] hire mercenary ({}g) [6/j] enlist (40g) | gold={} rank={} companions={}",
+            companion_hire_cost(i32::from(state.progression.guild_rank)),
+            state.gold,
+            state.progression.guild_rank,
+            state.companions.len()
         ),
         SiteInteractionKind::ThievesGuild => format!(
             "Thieves guild: [1/j] join (30g) [2/h] take heist (25g) [3/p] promotion board (55g) [4/x] leave | gold={} rank={} heat={}",
@@ -4121,7 +6279,7 @@ fn site_interaction_prompt(state: &GameState, kind: &SiteInteractionKind) -> Str
         ),
         SiteInteractionKind::Sorcerors => {
             format!(
-                "Sorcerors: [1/r] recharge (30g) [2/d] deep lore (50g) [3/t] transmute focus (45g) [4/x] leave | gold={}",
+                "Sorcerors: [1/r] recharge (30g) [2/d] deep lore (50g) [3/t] enchant gear (45g) [4/x] leave | gold={}",
                 state.gold
             )
         }
@@ -4152,13 +6310,18 @@ fn site_interaction_prompt(state: &GameState, kind: &SiteInteractionKind) -> Str
         SiteInteractionKind::Arena => {
             if state.progression.arena_rank > 0 {
                 format!(
-                    "Rampart Coliseum: Enter the games? [1/y]es [2/n]o | rank={} opponent={} active={}",
+                    "Rampart Coliseum: Enter the games? [1/y]es [2/n]o [3/b]et on yourself ({}g) | rank={} opponent={} active={} wager={}",
+                    ARENA_WAGER_STAKE,
                     state.progression.arena_rank,
                     state.progression.arena_opponent,
-                    state.progression.arena_match_active
+                    state.progression.arena_match_active,
+                    state.progression.arena_wager
                 )
             } else {
-                "Rampart Coliseum: Enter the games, or Register as a Gladiator? [1/e]nter [2/r]egister [3/x]leave".to_string()
+                format!(
+                    "Rampart Coliseum: Enter the games, or Register as a Gladiator? [1/e]nter [2/r]egister [3/x]leave [4/b]et on yourself ({}g) | wager={}",
+                    ARENA_WAGER_STAKE, state.progression.arena_wager
+                )
             }
         }
         SiteInteractionKind::Altar { deity_id } => {
@@ -4191,11 +6354,11 @@ fn site_interaction_prompt(state: &GameState, kind: &SiteInteractionKind) -> Str
 fn site_interaction_help_hint(state: &GameState, kind: &SiteInteractionKind) -> String {
     match kind {
         SiteInteractionKind::Arena if state.progression.arena_rank > 0 => {
-            "Rampart Coliseum prompt: choose 1/y to enter or 2/n to decline (q/x closes)."
+            "Rampart Coliseum prompt: choose 1/y to enter, 2/n to decline, or 3/b to bet (q/x closes)."
                 .to_string()
         }
         SiteInteractionKind::Arena => {
-            "Rampart Coliseum prompt: choose 1/e to enter or 2/r to register (q/x closes)."
+            "Rampart Coliseum prompt: choose 1/e to enter, 2/r to register, or 4/b to bet (q/x closes)."
                 .to_string()
         }
         SiteInteractionKind::Temple => {
@@ -4234,7 +6397,7 @@ fn inventory_slot_snapshot(state: &GameState, slot: usize) -> String {
     let label = inventory_slot_name(slot);
     let value = inventory_slot_item_id(state, slot)
         .and_then(|item_id| state.player.inventory.iter().find(|item| item.id == item_id))
-        .map(|item| item.name.clone())
+        .map(item_condition_label)
         .unwrap_or_else(|| "(vacant)".to_string());
     format!("{key}) {label}: {value}")
 }
@@ -4365,6 +6528,18 @@ pub fn active_item_prompt_help_hint(state: &GameState) -> Option<String> {
     state.pending_item_prompt.as_ref().map(item_prompt_help_hint)
 }
 
+pub fn active_call_label_prompt(state: &GameState) -> Option<String> {
+    state.pending_call_label.as_ref().map(|interaction| {
+        format!("Call {} what? {}_", interaction.item_name, state.interaction_buffer)
+    })
+}
+
+pub fn active_call_label_help_hint(state: &GameState) -> Option<String> {
+    state.pending_call_label.as_ref().map(|_| {
+        "Call item: type a label, Backspace edits, Enter applies, ESC cancels.".to_string()
+    })
+}
+
 fn activation_interaction_prompt(interaction: &ActivationInteraction) -> String {
     match interaction {
         ActivationInteraction::ChooseKind => {
@@ -4420,6 +6595,15 @@ fn talk_direction_interaction_prompt(interaction: TalkDirectionInteraction) -> S
         TalkDirectionInteraction::Tunnel => {
             "Tunnel -- choose direction (hjklyubn or keypad; ESCAPE aborts).".to_string()
         }
+        TalkDirectionInteraction::OpenDoor => {
+            "Open -- choose direction (hjklyubn or keypad; ESCAPE aborts).".to_string()
+        }
+        TalkDirectionInteraction::CloseDoor => {
+            "Close -- choose direction (hjklyubn or keypad; ESCAPE aborts).".to_string()
+        }
+        TalkDirectionInteraction::Engage => {
+            "Duel -- choose direction (hjkl or keypad; ESCAPE aborts).".to_string()
+        }
     }
 }
 
@@ -4432,6 +6616,25 @@ fn talk_direction_interaction_help_hint(interaction: TalkDirectionInteraction) -
             "Tunnel prompt active: choose direction (hjklyubn or arrows), q/esc cancels."
                 .to_string()
         }
+        TalkDirectionInteraction::OpenDoor => {
+            "Open prompt active: choose direction (hjklyubn or arrows), q/esc cancels.".to_string()
+        }
+        TalkDirectionInteraction::CloseDoor => {
+            "Close prompt active: choose direction (hjklyubn or arrows), q/esc cancels.".to_string()
+        }
+        TalkDirectionInteraction::Engage => {
+            "Duel prompt active: choose direction (hjkl or arrows), q/esc cancels.".to_string()
+        }
+    }
+}
+
+fn talk_direction_interaction_token(interaction: TalkDirectionInteraction) -> &'static str {
+    match interaction {
+        TalkDirectionInteraction::Talk => "t",
+        TalkDirectionInteraction::Tunnel => "T",
+        TalkDirectionInteraction::OpenDoor => "o",
+        TalkDirectionInteraction::CloseDoor => "c",
+        TalkDirectionInteraction::Engage => "J",
     }
 }
 
@@ -4443,6 +6646,29 @@ pub fn active_talk_direction_help_hint(state: &GameState) -> Option<String> {
     state.pending_talk_direction.map(talk_direction_interaction_help_hint)
 }
 
+fn dialogue_interaction_prompt(interaction: &DialogueInteraction) -> String {
+    let options = interaction
+        .node
+        .options
+        .iter()
+        .enumerate()
+        .map(|(idx, (label, _))| format!("[{}] {label}", idx + 1))
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!("{}: \"{}\" {options}", interaction.speaker, interaction.node.text)
+}
+
+pub fn active_dialogue_prompt(state: &GameState) -> Option<String> {
+    state.pending_dialogue.as_ref().map(dialogue_interaction_prompt)
+}
+
+pub fn active_dialogue_help_hint(state: &GameState) -> Option<String> {
+    state
+        .pending_dialogue
+        .as_ref()
+        .map(|_| "Conversation: choose a numbered reply, ESC to walk away.".to_string())
+}
+
 fn targeting_interaction_prompt(_state: &GameState, interaction: &TargetingInteraction) -> String {
     let mode = match interaction.mode {
         ProjectileKind::ThrownItem => "Throw",
@@ -4451,6 +6677,7 @@ fn targeting_interaction_prompt(_state: &GameState, interaction: &TargetingInter
         ProjectileKind::MagicMissile => "Cast magic missile",
         ProjectileKind::FireBolt => "Cast firebolt",
         ProjectileKind::LightningBolt => "Cast lightning bolt",
+        ProjectileKind::Healing => "Cast healing",
     };
     format!(
         "Targeting... {mode} at ({}, {}). Move cursor, '.' confirm, '?' help, ESC cancel.",
@@ -4465,9 +6692,10 @@ fn targeting_interaction_help_hint(
     let mode = match interaction.mode {
         ProjectileKind::ThrownItem => "throw",
         ProjectileKind::Arrow | ProjectileKind::Bolt => "fire",
-        ProjectileKind::MagicMissile | ProjectileKind::FireBolt | ProjectileKind::LightningBolt => {
-            "cast"
-        }
+        ProjectileKind::MagicMissile
+        | ProjectileKind::FireBolt
+        | ProjectileKind::LightningBolt
+        | ProjectileKind::Healing => "cast",
     };
     format!("Targeting active: use hjklyubn or arrows, '.' to {mode}, '?' for help, ESC to cancel.")
 }
@@ -4483,6 +6711,26 @@ pub fn active_targeting_interaction_help_hint(state: &GameState) -> Option<Strin
         .map(|it| targeting_interaction_help_hint(state, it))
 }
 
+fn look_interaction_prompt(interaction: &LookInteraction) -> String {
+    format!(
+        "Looking at ({}, {}). Move cursor, '.' describe, '?' help, ESC to stop looking.",
+        interaction.cursor.x, interaction.cursor.y
+    )
+}
+
+fn look_interaction_help_hint() -> String {
+    "Look mode: use hjklyubn or arrows to move the cursor, '.' to describe, ESC to stop looking."
+        .to_string()
+}
+
+pub fn active_look_interaction_prompt(state: &GameState) -> Option<String> {
+    state.pending_look_interaction.as_ref().map(look_interaction_prompt)
+}
+
+pub fn active_look_interaction_help_hint(state: &GameState) -> Option<String> {
+    state.pending_look_interaction.as_ref().map(|_| look_interaction_help_hint())
+}
+
 fn spell_name_by_id(spell_id: usize) -> &'static str {
     LEGACY_SPELL_NAMES.get(spell_id).copied().unwrap_or("unknown spell")
 }
@@ -4580,6 +6828,12 @@ fn wizard_interaction_prompt(state: &GameState, interaction: &WizardInteraction)
         WizardInteraction::BashDirectionSelect => {
             "Bashing -- choose direction (hjklyubn or keypad; ESCAPE aborts).".to_string()
         }
+        WizardInteraction::TeleportEntry => {
+            format!("Teleport to (x,y): {}_", state.wizard_input_buffer)
+        }
+        WizardInteraction::SpawnMonsterEntry => {
+            format!("Spawn which monster? {}_", state.wizard_input_buffer)
+        }
     }
 }
 
@@ -4629,6 +6883,14 @@ fn wizard_interaction_help_hint(state: &GameState, interaction: &WizardInteracti
             "Wizard backdoor check: choose direction (hjklyubn or keypad), q/esc aborts."
                 .to_string()
         }
+        WizardInteraction::TeleportEntry => format!(
+            "Wizard teleport: enter x,y within {}x{}, Enter jumps, Backspace edits, q/esc cancels.",
+            state.bounds.width, state.bounds.height
+        ),
+        WizardInteraction::SpawnMonsterEntry => {
+            "Wizard spawn: type a monster name, Backspace edits, Enter spawns, q/esc cancels."
+                .to_string()
+        }
     }
 }
 
@@ -4656,9 +6918,18 @@ pub fn modal_input_profile(state: &GameState) -> ModalInputProfile {
     if state.pending_talk_direction.is_some() {
         return ModalInputProfile::DirectionEntry;
     }
-    if state.pending_targeting_interaction.is_some() {
+    if state.pending_dialogue.is_some() {
+        return ModalInputProfile::ChoiceEntry;
+    }
+    if state.pending_targeting_interaction.is_some() || state.pending_look_interaction.is_some() {
         return ModalInputProfile::DirectionEntry;
     }
+    if state.pending_guild_action.is_some() {
+        return ModalInputProfile::ChoiceEntry;
+    }
+    if state.pending_call_label.is_some() {
+        return ModalInputProfile::TextEntry;
+    }
     if state.pending_inventory_interaction.is_some()
         || state.pending_item_prompt.is_some()
         || state.pending_site_interaction.is_some()
@@ -4673,7 +6944,9 @@ fn wizard_modal_input_profile(interaction: &WizardInteraction) -> ModalInputProf
         WizardInteraction::WishTextEntry { .. }
         | WizardInteraction::WishAcquisitionItemSelect { .. }
         | WizardInteraction::StatusFlagIndexEntry { .. }
-        | WizardInteraction::StatEditorValueEntry { .. } => ModalInputProfile::TextEntry,
+        | WizardInteraction::StatEditorValueEntry { .. }
+        | WizardInteraction::TeleportEntry
+        | WizardInteraction::SpawnMonsterEntry => ModalInputProfile::TextEntry,
         WizardInteraction::BashDirectionSelect => ModalInputProfile::DirectionEntry,
         WizardInteraction::EnterWizardConfirm { .. }
         | WizardInteraction::WishAcquisitionKindSelect { .. }
@@ -4726,6 +6999,20 @@ struct TalkDirectionInteractionResolution {
     turn_minutes: u64,
 }
 
+#[derive(Debug, Clone)]
+struct LookInteractionResolution {
+    freeze_world_progression: bool,
+    command_for_accounting: Command,
+    turn_minutes: u64,
+}
+
+#[derive(Debug, Clone)]
+struct GuildActionInteractionResolution {
+    freeze_world_progression: bool,
+    command_for_accounting: Command,
+    turn_minutes: u64,
+}
+
 #[derive(Debug, Clone)]
 struct ActivationInteractionResolution {
     freeze_world_progression: bool,
@@ -4816,14 +7103,37 @@ fn parse_wizard_input_token(command: &Command) -> WizardInputToken {
             WizardInputToken::None
         }
         Command::Drop { slot } => WizardInputToken::Text(slot.saturating_add(1).to_string()),
-        Command::Move(dir) | Command::Attack(dir) => match dir {
+        Command::Move(dir)
+        | Command::Attack(dir)
+        | Command::Engage(dir)
+        | Command::Bash(dir)
+        | Command::ApplyLockpick(dir) => match dir {
+            Direction::North => WizardInputToken::DirectionDelta { dx: 0, dy: -1 },
+            Direction::South => WizardInputToken::DirectionDelta { dx: 0, dy: 1 },
+            Direction::East => WizardInputToken::DirectionDelta { dx: 1, dy: 0 },
+            Direction::West => WizardInputToken::DirectionDelta { dx: -1, dy: 0 },
+        },
+        Command::Wait | Command::WaitTurns { .. } => WizardInputToken::Text(" ".to_string()),
+        Command::ApplyTool { target: Some(dir), .. } => match dir {
             Direction::North => WizardInputToken::DirectionDelta { dx: 0, dy: -1 },
             Direction::South => WizardInputToken::DirectionDelta { dx: 0, dy: 1 },
             Direction::East => WizardInputToken::DirectionDelta { dx: 1, dy: 0 },
             Direction::West => WizardInputToken::DirectionDelta { dx: -1, dy: 0 },
         },
-        Command::Wait => WizardInputToken::Text(" ".to_string()),
-        Command::Pickup => WizardInputToken::None,
+        Command::Pickup
+        | Command::DropQuantity { .. }
+        | Command::Bank { .. }
+        | Command::AutoExplore
+        | Command::Dip { .. }
+        | Command::Zap { .. }
+        | Command::Throw { .. }
+        | Command::GuildAction { .. }
+        | Command::RecallCompanions
+        | Command::Butcher
+        | Command::OfferSurrender
+        | Command::Look
+        | Command::ApplyTool { target: None, .. } => WizardInputToken::None,
+        Command::Engrave { text } => WizardInputToken::Text(text.clone()),
     }
 }
 
@@ -4977,10 +7287,11 @@ fn begin_talk_direction_interaction(
     (talk_direction_interaction_prompt(interaction), true)
 }
 
-fn resolve_pending_talk_direction_interaction(
+fn resolve_pending_talk_direction_interaction<R: RandomSource>(
     state: &mut GameState,
     command: &Command,
     events: &mut Vec<Event>,
+    rng: &mut R,
 ) -> Option<TalkDirectionInteractionResolution> {
     let interaction = state.pending_talk_direction?;
 
@@ -4995,15 +7306,16 @@ fn resolve_pending_talk_direction_interaction(
     match input {
         WizardInputToken::Cancel => {
             state.pending_talk_direction = None;
-            let token = if interaction == TalkDirectionInteraction::Talk { "t" } else { "T" };
-            let note = if interaction == TalkDirectionInteraction::Talk {
-                "talk canceled".to_string()
-            } else {
-                "tunnel canceled".to_string()
+            let note = match interaction {
+                TalkDirectionInteraction::Talk => "talk canceled",
+                TalkDirectionInteraction::Tunnel => "tunnel canceled",
+                TalkDirectionInteraction::OpenDoor => "open canceled",
+                TalkDirectionInteraction::CloseDoor => "close canceled",
+                TalkDirectionInteraction::Engage => "duel canceled",
             };
             events.push(Event::LegacyHandled {
-                token: token.to_string(),
-                note,
+                token: talk_direction_interaction_token(interaction).to_string(),
+                note: note.to_string(),
                 fully_modeled: true,
             });
             return Some(resolution);
@@ -5023,15 +7335,23 @@ fn resolve_pending_talk_direction_interaction(
 
     let target = Position { x: state.player.position.x + dx, y: state.player.position.y + dy };
     state.pending_talk_direction = None;
-    let (token, note, fully_modeled) = match interaction {
-        TalkDirectionInteraction::Talk => {
-            let (note, fully_modeled) = resolve_talk_direction(state, target, events);
-            ("t".to_string(), note, fully_modeled)
-        }
-        TalkDirectionInteraction::Tunnel => {
-            let (note, fully_modeled) = resolve_tunnel_direction(state, target);
-            ("T".to_string(), note, fully_modeled)
-        }
+    let token = talk_direction_interaction_token(interaction).to_string();
+    let (note, fully_modeled) = match interaction {
+        TalkDirectionInteraction::Talk => resolve_talk_direction(state, target, events),
+        TalkDirectionInteraction::Tunnel => resolve_tunnel_direction(state, target, rng),
+        TalkDirectionInteraction::OpenDoor if container_index_at(state, target).is_some() => {
+            (open_container_at(state, target, events), true)
+        }
+        TalkDirectionInteraction::OpenDoor => (apply_door_interaction(state, target, false), true),
+        TalkDirectionInteraction::CloseDoor => (apply_door_interaction(state, target, true), true),
+        TalkDirectionInteraction::Engage => match cardinal_direction_from_delta(dx, dy) {
+            Some(direction) => {
+                resolve_engage_command(state, direction, rng, events);
+                // The duel logs its own lines; its last one doubles as the note.
+                (state.log.last().cloned().unwrap_or_default(), true)
+            }
+            None => ("You can only duel an opponent squarely before you.".to_string(), true),
+        },
     };
 
     push_timeline_line(state, note.clone());
@@ -5071,6 +7391,16 @@ fn compute_spell_drain(state: &GameState, spell_id: usize) -> i32 {
     }
 }
 
+fn spell_failure_chance(state: &GameState) -> i32 {
+    let armor_weight: i32 = equipped_item_ids(&state.player.equipment)
+        .into_iter()
+        .filter_map(|item_id| state.player.inventory.iter().find(|entry| entry.id == item_id))
+        .filter(|item| matches!(item.family, ItemFamily::Armor | ItemFamily::Shield))
+        .map(|item| item.weight.max(0))
+        .sum();
+    (armor_weight / 15 - statmod(state.attributes.iq) * 5).clamp(0, 95)
+}
+
 fn begin_spell_interaction(state: &mut GameState) -> (String, bool) {
     sync_spellbook_state(state);
     if has_active_fear(state) {
@@ -5117,10 +7447,11 @@ fn show_known_spells(state: &GameState, filtered: &[usize]) -> String {
     format!("Possible spells: {}", parts.join(", "))
 }
 
-fn resolve_pending_spell_interaction(
+fn resolve_pending_spell_interaction<R: RandomSource>(
     state: &mut GameState,
     command: &Command,
     events: &mut Vec<Event>,
+    rng: &mut R,
 ) -> Option<SpellInteractionResolution> {
     let interaction = state.pending_spell_interaction.clone()?;
 
@@ -5154,7 +7485,7 @@ fn resolve_pending_spell_interaction(
                         );
                     } else {
                         let spell_id = filtered[0];
-                        let (note, _modeled) = cast_spell_by_id(state, events, spell_id);
+                        let (note, _modeled) = cast_spell_by_id(state, events, spell_id, rng);
                         state.pending_spell_interaction = None;
                         state.spell_input_buffer.clear();
                         record_spell_note(state, events, note);
@@ -5733,29 +8064,138 @@ fn resolve_pending_wizard_interaction(
                 record_wizard_note(state, events, note);
             }
         },
+        WizardInteraction::TeleportEntry => match input {
+            WizardInputToken::Cancel => {
+                state.pending_wizard_interaction = None;
+                state.wizard_input_buffer.clear();
+                record_wizard_note(state, events, "Teleport canceled.".to_string());
+            }
+            WizardInputToken::Backspace => {
+                state.wizard_input_buffer.pop();
+            }
+            WizardInputToken::Enter => {
+                let note = match parse_teleport_coordinates(&state.wizard_input_buffer) {
+                    Some(target) => apply_wizard_teleport(state, target),
+                    None => "Invalid coordinates; enter them as x,y.".to_string(),
+                };
+                state.pending_wizard_interaction = None;
+                state.wizard_input_buffer.clear();
+                record_wizard_note(state, events, note);
+            }
+            WizardInputToken::Text(text) => {
+                for ch in text.chars() {
+                    if ch.is_ascii_digit() || ch == ',' || ch == '-' {
+                        append_wizard_buffer(state, &ch.to_string(), 12);
+                    }
+                }
+            }
+            _ => {
+                let note = wizard_interaction_help_hint(state, &WizardInteraction::TeleportEntry);
+                record_wizard_note(state, events, note);
+            }
+        },
+        WizardInteraction::SpawnMonsterEntry => match input {
+            WizardInputToken::Cancel => {
+                state.pending_wizard_interaction = None;
+                state.wizard_input_buffer.clear();
+                record_wizard_note(state, events, "Spawn canceled.".to_string());
+            }
+            WizardInputToken::Backspace => {
+                state.wizard_input_buffer.pop();
+            }
+            WizardInputToken::Enter => {
+                let name = state.wizard_input_buffer.trim().to_string();
+                state.pending_wizard_interaction = None;
+                state.wizard_input_buffer.clear();
+                let note = if name.is_empty() {
+                    "Spawn canceled.".to_string()
+                } else {
+                    apply_wizard_spawn_monster(state, &name)
+                };
+                record_wizard_note(state, events, note);
+            }
+            WizardInputToken::Text(text) => {
+                append_wizard_buffer(state, &text, 40);
+            }
+            _ => {
+                let note =
+                    wizard_interaction_help_hint(state, &WizardInteraction::SpawnMonsterEntry);
+                record_wizard_note(state, events, note);
+            }
+        },
     }
 
     sync_wizard_flag_with_legacy_bits(state);
     Some(resolution)
 }
 
-fn begin_inventory_interaction(state: &mut GameState, top_mode: bool) -> (String, bool) {
-    let selected_slot = SLOT_READY_HAND;
-    let interaction = InventoryInteraction::Control { top_mode, selected_slot };
-    state.pending_inventory_interaction = Some(interaction.clone());
-    state.interaction_buffer.clear();
-    (inventory_interaction_prompt(state, &interaction), true)
-}
-
-fn begin_activation_interaction(state: &mut GameState) -> (String, bool) {
-    let interaction = ActivationInteraction::ChooseKind;
-    state.pending_activation_interaction = Some(interaction.clone());
-    state.interaction_buffer.clear();
-    (activation_interaction_prompt(&interaction), true)
+fn parse_teleport_coordinates(raw: &str) -> Option<Position> {
+    let (x, y) = raw.split_once(',')?;
+    Some(Position { x: x.trim().parse().ok()?, y: y.trim().parse().ok()? })
 }
 
-fn begin_item_prompt(
-    state: &mut GameState,
+fn apply_wizard_teleport(state: &mut GameState, target: Position) -> String {
+    if !state.bounds.contains(target) {
+        return format!(
+            "Teleport refused: ({},{}) lies outside the {}x{} map.",
+            target.x, target.y, state.bounds.width, state.bounds.height
+        );
+    }
+    match sanitize_spawn(state, target) {
+        Some(landing) => {
+            state.player.position = landing;
+            if landing == target {
+                format!("You teleport to ({},{}).", landing.x, landing.y)
+            } else {
+                format!(
+                    "({},{}) is blocked; you teleport to ({},{}) instead.",
+                    target.x, target.y, landing.x, landing.y
+                )
+            }
+        }
+        None => "Teleport failed: no walkable tile on this map.".to_string(),
+    }
+}
+
+fn wizard_spawn_stats(behavior: MonsterBehavior) -> Stats {
+    match behavior {
+        MonsterBehavior::Social => guard_marker_stats(),
+        MonsterBehavior::Caster => {
+            Stats { hp: 10, max_hp: 10, attack_min: 2, attack_max: 6, defense: 1, weight: 50 }
+        }
+        MonsterBehavior::Skirmisher => {
+            Stats { hp: 10, max_hp: 10, attack_min: 2, attack_max: 4, defense: 1, weight: 40 }
+        }
+        _ => Stats { hp: 12, max_hp: 12, attack_min: 2, attack_max: 5, defense: 1, weight: 60 },
+    }
+}
+
+fn apply_wizard_spawn_monster(state: &mut GameState, name: &str) -> String {
+    let Some(position) = free_tile_adjacent_to_player(state) else {
+        return format!("No room beside you to spawn {name}.");
+    };
+    let (behavior, _faction) = infer_monster_profile(name);
+    let id = state.spawn_monster(name, position, wizard_spawn_stats(behavior));
+    format!("Spawned {name} (id {id}) at ({},{}).", position.x, position.y)
+}
+
+fn begin_inventory_interaction(state: &mut GameState, top_mode: bool) -> (String, bool) {
+    let selected_slot = SLOT_READY_HAND;
+    let interaction = InventoryInteraction::Control { top_mode, selected_slot };
+    state.pending_inventory_interaction = Some(interaction.clone());
+    state.interaction_buffer.clear();
+    (inventory_interaction_prompt(state, &interaction), true)
+}
+
+fn begin_activation_interaction(state: &mut GameState) -> (String, bool) {
+    let interaction = ActivationInteraction::ChooseKind;
+    state.pending_activation_interaction = Some(interaction.clone());
+    state.interaction_buffer.clear();
+    (activation_interaction_prompt(&interaction), true)
+}
+
+fn begin_item_prompt(
+    state: &mut GameState,
     context: ItemPromptContext,
     filter: ItemPromptFilter,
     prompt: String,
@@ -5851,6 +8291,101 @@ fn record_targeting_note(state: &mut GameState, events: &mut Vec<Event>, note: S
     events.push(Event::LegacyHandled { token: "targeting".to_string(), note, fully_modeled: true });
 }
 
+fn record_look_note(state: &mut GameState, events: &mut Vec<Event>, note: String) {
+    let class = classify_note_against_active_interactions(state, &note);
+    push_ui_log(state, class, note.clone());
+    events.push(Event::LegacyHandled { token: "look".to_string(), note, fully_modeled: true });
+}
+
+fn look_description_at(state: &GameState, pos: Position) -> String {
+    let tile = state.describe_tile(pos);
+    let mut parts = Vec::new();
+    parts.extend(state.examine_monster(pos));
+    if tile.is_notable() {
+        parts.push(tile.summary());
+    }
+    if parts.is_empty() {
+        return format!("({}, {}): {}", pos.x, pos.y, glyph_look_label(tile.glyph));
+    }
+    format!("({}, {}): {}", pos.x, pos.y, parts.join(" "))
+}
+
+fn glyph_look_label(glyph: char) -> &'static str {
+    match glyph {
+        '#' => "solid rock.",
+        '>' => "a staircase leading down.",
+        '<' => "a staircase leading up.",
+        '~' => "water.",
+        _ => "nothing of interest.",
+    }
+}
+
+fn begin_look_interaction(state: &mut GameState, events: &mut Vec<Event>) {
+    let interaction = LookInteraction { cursor: state.player.position };
+    state.pending_look_interaction = Some(interaction);
+    record_look_note(state, events, look_interaction_prompt(&interaction));
+}
+
+fn resolve_pending_look_interaction(
+    state: &mut GameState,
+    command: &Command,
+    events: &mut Vec<Event>,
+) -> Option<LookInteractionResolution> {
+    let interaction = state.pending_look_interaction?;
+    let resolution = LookInteractionResolution {
+        freeze_world_progression: true,
+        command_for_accounting: Command::Legacy { token: "F".to_string() },
+        turn_minutes: 0,
+    };
+
+    let mut describe = false;
+    let mut show_hint = false;
+    let mut maybe_delta: Option<(i32, i32)> = None;
+    match parse_wizard_input_token(command) {
+        WizardInputToken::Cancel => {
+            state.pending_look_interaction = None;
+            record_look_note(state, events, "You stop looking around.".to_string());
+            return Some(resolution);
+        }
+        WizardInputToken::Enter => describe = true,
+        WizardInputToken::DirectionDelta { dx, dy } => maybe_delta = Some((dx, dy)),
+        WizardInputToken::Text(text) => {
+            for ch in text.chars() {
+                match ch {
+                    '?' => show_hint = true,
+                    '.' => describe = true,
+                    _ => {
+                        if let Some(delta) = direction_delta_from_char(ch)
+                            .or_else(|| parse_direction_delta_from_text(&ch.to_string()))
+                        {
+                            maybe_delta = Some(delta);
+                        }
+                    }
+                }
+            }
+        }
+        _ => show_hint = true,
+    }
+
+    let mut cursor = interaction.cursor;
+    if let Some((dx, dy)) = maybe_delta {
+        cursor = Position {
+            x: (cursor.x + dx).clamp(0, state.bounds.width.saturating_sub(1)),
+            y: (cursor.y + dy).clamp(0, state.bounds.height.saturating_sub(1)),
+        };
+        state.pending_look_interaction = Some(LookInteraction { cursor });
+        describe = true;
+    }
+    if show_hint {
+        record_look_note(state, events, look_interaction_help_hint());
+    }
+    if describe {
+        let note = look_description_at(state, cursor);
+        record_look_note(state, events, note);
+    }
+    Some(resolution)
+}
+
 fn statmod(stat: i32) -> i32 {
     (stat - 10) / 2
 }
@@ -5859,6 +8394,17 @@ fn projectile_distance(origin: Position, target: Position) -> i32 {
     (origin.x - target.x).abs().max((origin.y - target.y).abs())
 }
 
+fn default_heal_target_cursor(state: &GameState, origin: Position, max_range: i32) -> Position {
+    state
+        .companions
+        .iter()
+        .filter(|companion| companion.stats.hp < companion.stats.max_hp)
+        .filter(|companion| projectile_distance(origin, companion.position) <= max_range.max(1))
+        .min_by_key(|companion| projectile_distance(origin, companion.position))
+        .map(|companion| companion.position)
+        .unwrap_or(origin)
+}
+
 fn default_target_cursor(state: &GameState, origin: Position, max_range: i32) -> Position {
     if let Some(monster) = state
         .monsters
@@ -5875,7 +8421,11 @@ fn default_target_cursor(state: &GameState, origin: Position, max_range: i32) ->
 
 fn begin_targeting_interaction(state: &mut GameState, action: PendingProjectileAction) -> String {
     let origin = state.player.position;
-    let cursor = default_target_cursor(state, origin, action.max_range);
+    let cursor = if action.mode == ProjectileKind::Healing {
+        default_heal_target_cursor(state, origin, action.max_range)
+    } else {
+        default_target_cursor(state, origin, action.max_range)
+    };
     let interaction = TargetingInteraction { origin, cursor, mode: action.mode };
     state.pending_projectile_action = Some(action);
     state.pending_targeting_interaction = Some(interaction.clone());
@@ -5917,6 +8467,98 @@ fn pack_item_ids(state: &GameState) -> Vec<u32> {
     ids
 }
 
+fn usef_condition_suffix(usef: &str) -> Option<&'static str> {
+    match usef {
+        "I_PERM_FIRE_RESIST" => Some("of fire resistance"),
+        "I_PERM_POISON_RESIST" => Some("of poison resistance"),
+        "I_PERM_ENERGY_RESIST" => Some("of energy resistance"),
+        "I_FEAR_RESIST" | "I_PERM_FEAR_RESIST" => Some("of fear resistance"),
+        "I_PERM_REGENERATE" | "I_REGENERATE" => Some("of regeneration"),
+        "I_PERM_PROTECTION" | "I_PERM_DEFLECT" => Some("of protection"),
+        "I_SPEED" | "I_PERM_SPEED" => Some("of speed"),
+        "I_PERM_STRENGTH" => Some("of strength"),
+        "I_PERM_AGILITY" => Some("of agility"),
+        "I_PERM_TRUESIGHT" => Some("of true sight"),
+        _ => None,
+    }
+}
+
+const PAWN_FLOOR_PRICE: i32 = 12;
+// Pawnbrokers resell at twice what they pay, so buying back never turns a profit.
+const PAWN_RESALE_MARKUP: i32 = 2;
+// Legacy `PAWNITEMS`: the shelf only holds so many pledges before the oldest goes.
+const PAWN_STOCK_LIMIT: usize = 20;
+
+/// Shopkeepers know their wares: the appraised copy is fully identified and
+/// priced at half its true value, never below the pawn floor.
+fn appraise_item(item: &Item) -> (Item, i32) {
+    let mut appraised = item.clone();
+    appraised.known = true;
+    if !appraised.truename.is_empty() {
+        appraised.name = appraised.truename.clone();
+    }
+    let mut value = appraised.basevalue.max(0) / 2 + i64::from(appraised.plus.max(0)) * 10;
+    if appraised.blessing < 0 {
+        value /= 4;
+    }
+    let price = i32::try_from(value).unwrap_or(i32::MAX).max(PAWN_FLOOR_PRICE);
+    (appraised, price)
+}
+
+/// The next piece on the pawnbroker's shelf — the latest pledge, otherwise a
+/// catalog oddity — already appraised, with its asking price. A catalog
+/// oddity is only a preview here and has no item id until it is bought.
+fn pawn_shop_offer(state: &GameState) -> (Item, i32) {
+    let item = match state.pawn_stock.last() {
+        Some(item) => item.clone(),
+        None => instantiate_item_from_name(0, &pawn_shop_oddity_name(state)),
+    };
+    let (appraised, price) = appraise_item(&item);
+    (appraised, price.saturating_mul(PAWN_RESALE_MARKUP))
+}
+
+/// Takes the offered piece off the shelf, giving a catalog oddity its own
+/// item id.
+fn take_pawn_shop_offer(state: &mut GameState) -> Item {
+    let item = match state.pawn_stock.pop() {
+        Some(item) => item,
+        None => {
+            let name = pawn_shop_oddity_name(state);
+            let id = state.next_item_id;
+            state.next_item_id += 1;
+            instantiate_item_from_name(id, &name)
+        }
+    };
+    appraise_item(&item).0
+}
+
+fn pawn_shop_oddity_name(state: &GameState) -> String {
+    choose_pawn_stock_item_name(state).unwrap_or_else(|| "food ration".to_string())
+}
+
+// Unidentified items keep their plain name; bonuses only show once the item is known.
+pub fn item_condition_label(item: &Item) -> String {
+    if !item.known {
+        return item.name.clone();
+    }
+    let mut parts = Vec::new();
+    if item.plus != 0 {
+        parts.push(format!("{:+}", item.plus));
+    }
+    match item.blessing.signum() {
+        1 => parts.push("blessed".to_string()),
+        -1 => parts.push("cursed".to_string()),
+        _ => {}
+    }
+    parts.push(item.name.clone());
+    if let Some(suffix) = usef_condition_suffix(&item.usef)
+        && !item.name.to_ascii_lowercase().contains(suffix)
+    {
+        parts.push(suffix.to_string());
+    }
+    parts.join(" ")
+}
+
 fn pack_listing(state: &GameState) -> String {
     let mut entries = Vec::new();
     for (idx, item_id) in pack_item_ids(state).into_iter().enumerate() {
@@ -5926,7 +8568,7 @@ fn pack_listing(state: &GameState) -> String {
         let Some(item) = state.player.inventory.iter().find(|entry| entry.id == item_id) else {
             continue;
         };
-        entries.push(format!("{choice}) {}", item.name));
+        entries.push(format!("{choice}) {}", item_condition_label(item)));
     }
     if entries.is_empty() {
         "Pack: (empty)".to_string()
@@ -5995,6 +8637,9 @@ fn inventory_put_slot_item_to_pack(state: &mut GameState, slot: usize) -> String
     if item_is_cursed_in_use(&item, slot) {
         return format!("{} is cursed and cannot be removed.", item.name);
     }
+    if state.player.inventory.len() > inventory_capacity_without(state, &item) {
+        return format!("Removing {} would overflow your pack.", item.name);
+    }
     unequip_item_id(&mut state.player.equipment, item_id);
     push_item_to_pack_front(state, item_id);
     sync_pack_order(state);
@@ -6028,25 +8673,19 @@ fn inventory_drop_slot_item(state: &mut GameState, slot: usize, events: &mut Vec
 }
 
 fn inventory_equip_pack_item_to_slot(state: &mut GameState, slot: usize, item_id: u32) -> String {
+    if let Err(err) = state.can_equip(item_id, slot) {
+        return equip_error_note(state, err, slot, item_id);
+    }
     let Some(item) = state.player.inventory.iter().find(|entry| entry.id == item_id).cloned()
     else {
         return "That pack entry is no longer available.".to_string();
     };
-    if !slot_accepts_item(slot, &item) {
-        return format!("{} cannot be equipped in {} slot.", item.name, inventory_slot_name(slot));
-    }
-    if slot == SLOT_SHIELD && equipped_weapon_is_two_handed(state) {
-        return "Cannot equip a shield while wielding a two-handed weapon.".to_string();
-    }
     if let Some(existing_id) = inventory_slot_item_id(state, slot)
         && existing_id != item_id
+        && let Some(existing) = state.player.inventory.iter().find(|entry| entry.id == existing_id)
+        && state.player.inventory.len() > inventory_capacity_without(state, existing)
     {
-        let existing = state.player.inventory.iter().find(|entry| entry.id == existing_id).cloned();
-        if let Some(existing) = existing
-            && item_is_cursed_in_use(&existing, slot)
-        {
-            return format!("{} is cursed and cannot be removed.", existing.name);
-        }
+        return format!("Removing {} would overflow your pack.", existing.name);
     }
 
     let displaced = inventory_slot_item_id(state, slot);
@@ -6072,20 +8711,36 @@ fn inventory_equip_pack_item_to_slot(state: &mut GameState, slot: usize, item_id
         }
     }
 
-    if slot == SLOT_SHIELD
-        && let Some(weapon_id) = state.player.equipment.weapon_hand
-        && let Some(weapon) = state.player.inventory.iter().find(|entry| entry.id == weapon_id)
-        && is_two_handed_weapon(weapon)
-    {
-        state.player.equipment.shield = None;
-        push_item_to_pack_front(state, item_id);
-        return "Cannot equip a shield while wielding a two-handed weapon.".to_string();
-    }
-
     sync_pack_order(state);
     format!("{} equipped to {} slot.", item.name, inventory_slot_name(slot))
 }
 
+fn equip_error_note(state: &GameState, err: EquipError, slot: usize, item_id: u32) -> String {
+    let name_of = |id: Option<u32>| {
+        id.and_then(|id| state.player.inventory.iter().find(|entry| entry.id == id))
+            .map(|entry| entry.name.clone())
+            .unwrap_or_default()
+    };
+    match err {
+        EquipError::ItemNotFound => "That pack entry is no longer available.".to_string(),
+        EquipError::WrongFamily => format!(
+            "{} cannot be equipped in {} slot.",
+            name_of(Some(item_id)),
+            inventory_slot_name(slot)
+        ),
+        EquipError::TwoHandedConflict => {
+            "Cannot equip a shield while wielding a two-handed weapon.".to_string()
+        }
+        EquipError::Cursed => {
+            format!("{} is cursed and cannot be removed.", name_of(Some(item_id)))
+        }
+        EquipError::SlotOccupiedByCursed => format!(
+            "{} is cursed and cannot be removed.",
+            name_of(inventory_slot_item_id(state, slot))
+        ),
+    }
+}
+
 fn resolve_pending_activation_interaction(
     state: &mut GameState,
     command: &Command,
@@ -6375,6 +9030,7 @@ fn item_prompt_context_token(context: &ItemPromptContext) -> &'static str {
         ItemPromptContext::ActivateArtifact => "A",
         ItemPromptContext::CallItem => "C",
         ItemPromptContext::Give => "G",
+        ItemPromptContext::Recharge | ItemPromptContext::Enchant => "F",
     }
 }
 
@@ -6383,7 +9039,7 @@ fn item_prompt_turn_minutes(context: &ItemPromptContext) -> u64 {
         ItemPromptContext::Drop => 1,
         ItemPromptContext::Eat => 2,
         ItemPromptContext::CallItem => 0,
-        ItemPromptContext::Give => 5,
+        ItemPromptContext::Give | ItemPromptContext::Recharge | ItemPromptContext::Enchant => 5,
         ItemPromptContext::Quaff
         | ItemPromptContext::Read
         | ItemPromptContext::FireThrow
@@ -6476,6 +9132,44 @@ fn projectile_kind_for_item(item: &Item) -> ProjectileKind {
     }
 }
 
+fn drop_inventory_slot(state: &mut GameState, slot: usize, events: &mut Vec<Event>) {
+    let item = state.player.inventory.remove(slot);
+    unequip_item_id(&mut state.player.equipment, item.id);
+    remove_item_from_pack_order(state, item.id);
+    state.carry_burden = state.carry_burden.saturating_sub(item_burden(&item)).max(0);
+    state.log.push(format!("Dropped {}.", item.name));
+    events.push(Event::Dropped { item_id: item.id, name: item.name.clone() });
+    state.ground_items.push(GroundItem { position: state.player.position, item });
+}
+
+fn drop_item_quantity(state: &mut GameState, item_id: u32, count: i32, events: &mut Vec<Event>) {
+    let Some(slot) = state.player.inventory.iter().position(|entry| entry.id == item_id) else {
+        state.log.push("You don't have that item.".to_string());
+        return;
+    };
+    if count <= 0 {
+        state.log.push("You drop nothing.".to_string());
+        return;
+    }
+    if count >= state.player.inventory[slot].number {
+        drop_inventory_slot(state, slot, events);
+        return;
+    }
+
+    // A stack's burden is counted once, so the dropped units shed their share of it.
+    let stack = &state.player.inventory[slot];
+    let shed = item_burden(stack) * count / stack.number;
+    state.carry_burden = state.carry_burden.saturating_sub(shed).max(0);
+    state.player.inventory[slot].number -= count;
+    let mut dropped = state.player.inventory[slot].clone();
+    dropped.number = count;
+    dropped.id = state.next_item_id;
+    state.next_item_id = state.next_item_id.wrapping_add(1);
+    state.log.push(format!("Dropped {count} {}.", dropped.name));
+    events.push(Event::Dropped { item_id: dropped.id, name: dropped.name.clone() });
+    state.ground_items.push(GroundItem { position: state.player.position, item: dropped });
+}
+
 fn remove_single_inventory_unit_by_id(state: &mut GameState, item_id: u32) -> Option<Item> {
     let idx = state.player.inventory.iter().position(|entry| entry.id == item_id)?;
     if state.player.inventory[idx].number > 1 {
@@ -6525,7 +9219,8 @@ fn begin_fire_throw_for_item(
         mode,
         item_id: Some(item.id),
         item_name: item.name.clone(),
-        hit_bonus: profile.to_hit_bonus + item.hit + statmod(state.attributes.dexterity.max(1)),
+        hit_bonus: profile.to_hit_bonus + item.hit + statmod(state.attributes.dexterity.max(1))
+            - state.encumbrance_tier().agility_penalty(),
         damage_bonus: item.plus.max(0),
         damage_min,
         damage_max,
@@ -6537,25 +9232,35 @@ fn begin_fire_throw_for_item(
     format!("You ready {}.", item.name)
 }
 
-fn apply_item_prompt_selection(
+fn apply_item_prompt_selection<R: RandomSource>(
     state: &mut GameState,
     interaction: &ItemPromptInteraction,
     item_id: u32,
     events: &mut Vec<Event>,
+    rng: &mut R,
 ) -> String {
     match interaction.context {
         ItemPromptContext::Quaff => {
             let Some(item) = remove_inventory_item_by_id(state, item_id) else {
                 return "That item is no longer available.".to_string();
             };
-            let effect_note = apply_item_usef_effect(state, &item, events);
+            if item_lacks_effect(&item) {
+                return no_effect_note(&item);
+            }
+            let effect_note = apply_item_usef_effect(state, &item, rng, events);
+            if !item.known && item.family == ItemFamily::Potion {
+                learn_potion_kind(state, &item);
+            }
             format!("Quaffed {} ({effect_note}).", item.name)
         }
         ItemPromptContext::Read => {
             let Some(item) = remove_inventory_item_by_id(state, item_id) else {
                 return "That item is no longer available.".to_string();
             };
-            let effect_note = apply_item_usef_effect(state, &item, events);
+            if item_lacks_effect(&item) {
+                return no_effect_note(&item);
+            }
+            let effect_note = apply_item_usef_effect(state, &item, rng, events);
             if state.progression.quest_state == LegacyQuestState::NotStarted {
                 state.progression.quest_state = LegacyQuestState::Active;
                 state.progression.quest_steps_completed = 1;
@@ -6570,7 +9275,10 @@ fn apply_item_prompt_selection(
             let Some(item) = remove_inventory_item_by_id(state, item_id) else {
                 return "That item is no longer available.".to_string();
             };
-            let effect_note = apply_item_usef_effect(state, &item, events);
+            if item_lacks_effect(&item) {
+                return no_effect_note(&item);
+            }
+            let effect_note = apply_item_usef_effect(state, &item, rng, events);
             format!("Ate {} ({effect_note}).", item.name)
         }
         ItemPromptContext::Drop => {
@@ -6592,22 +9300,21 @@ fn apply_item_prompt_selection(
             let Some(item) = remove_inventory_item_by_id(state, item_id) else {
                 return "That item is no longer available.".to_string();
             };
-            let effect_note = apply_item_usef_effect(state, &item, events);
-            format!("Activated {} ({effect_note}).", item.name)
-        }
-        ItemPromptContext::ZapStick => {
-            let Some(item) = remove_inventory_item_by_id(state, item_id) else {
-                return "That item is no longer available.".to_string();
-            };
-            push_or_refresh_status(&mut state.status_effects, "wand_charge", 1, 0);
-            let effect_note = apply_item_usef_effect(state, &item, events);
+            if item_lacks_effect(&item) {
+                return no_effect_note(&item);
+            }
+            let effect_note = apply_item_usef_effect(state, &item, rng, events);
             format!("Activated {} ({effect_note}).", item.name)
         }
+        ItemPromptContext::ZapStick => zap_stick(state, item_id, rng, events),
         ItemPromptContext::ActivateArtifact => {
             let Some(item) = remove_inventory_item_by_id(state, item_id) else {
                 return "That item is no longer available.".to_string();
             };
-            let effect_note = apply_item_usef_effect(state, &item, events);
+            if item_lacks_effect(&item) {
+                return no_effect_note(&item);
+            }
+            let effect_note = apply_item_usef_effect(state, &item, rng, events);
             state.progression.quest_state = LegacyQuestState::ArtifactRecovered;
             state.progression.quest_steps_completed =
                 state.progression.quest_steps_completed.max(2);
@@ -6620,87 +9327,334 @@ fn apply_item_prompt_selection(
             format!("Activated {} ({effect_note}).", item.name)
         }
         ItemPromptContext::CallItem => {
-            let Some(item) = state.player.inventory.iter_mut().find(|entry| entry.id == item_id)
-            else {
+            let Some(item) = state.player.inventory.iter().find(|entry| entry.id == item_id) else {
                 return "That item is no longer available.".to_string();
             };
-            if !item.name.starts_with("inscribed ") {
-                item.name = format!("inscribed {}", item.name);
-            }
-            format!("Named item: {}.", item.name)
+            let interaction = CallLabelInteraction { item_id, item_name: item.name.clone() };
+            state.pending_call_label = Some(interaction);
+            state.interaction_buffer.clear();
+            active_call_label_prompt(state).unwrap_or_default()
         }
-        ItemPromptContext::Give => {
-            let Some(item) = remove_inventory_item_by_id(state, item_id) else {
-                return "That item is no longer available.".to_string();
-            };
-            state.progression.deity_favor += 2;
-            state.progression.law_chaos_score += 1;
-            format!("Gifted {}.", item.name)
+        ItemPromptContext::Give => give_item_in_context(state, item_id, events),
+        ItemPromptContext::Recharge => recharge_stick_at_sorcerors(state, item_id, events, rng),
+        ItemPromptContext::Enchant => enchant_item_at_sorcerors(state, item_id, events, rng),
+    }
+}
+
+fn adjacent_guard_index(state: &GameState) -> Option<usize> {
+    state.monsters.iter().position(|monster| {
+        monster.is_guard() && monster.position.manhattan_distance(state.player.position) == 1
+    })
+}
+
+fn give_item_in_context(state: &mut GameState, item_id: u32, events: &mut Vec<Event>) -> String {
+    let altar = state.tile_site_at(state.player.position).and_then(|cell| {
+        match interaction_kind_for_site_aux(state, cell.aux) {
+            Some(SiteInteractionKind::Altar { deity_id }) => Some(deity_id),
+            _ => None,
         }
+    });
+    let guard = adjacent_guard_index(state);
+    let Some(item) = remove_inventory_item_by_id(state, item_id) else {
+        return "That item is no longer available.".to_string();
+    };
+
+    if let Some(deity_id) = altar {
+        let favor = (item.basevalue / 50).clamp(1, 10) as i32;
+        state.progression.deity_favor += favor;
+        return format!("{} accepts your sacrifice of {}.", deity_name(deity_id), item.name);
+    }
+    if let Some(guard_idx) = guard {
+        let relief = (item.basevalue / 20).clamp(1, 50) as i32;
+        state.legal_heat = (state.legal_heat - relief).max(0);
+        return format!(
+            "The {} pockets {} and looks the other way.",
+            state.monsters[guard_idx].name, item.name
+        );
     }
+
+    let name = item.name.clone();
+    events.push(Event::Dropped { item_id: item.id, name: name.clone() });
+    state.ground_items.push(GroundItem { position: state.player.position, item });
+    format!("Nobody here to take it; you set down {name}.")
 }
 
-fn resolve_pending_item_prompt_interaction<R: RandomSource>(
+const SORCERORS_RECHARGE_FEE: i32 = 30;
+const SORCERORS_ENCHANT_FEE: i32 = 45;
+const SORCERORS_ENCHANT_PLUS_CAP: i32 = 5;
+
+/// Blank scrolls, empty vials and plain sticks carry no effect to apply.
+fn item_lacks_effect(item: &Item) -> bool {
+    matches!(item.usef.trim(), "" | "I_NOTHING" | "I_NO_OP")
+}
+
+/// Shared message for a consumable that does nothing: one with no effect or
+/// a stick with no charges left. Anything else is still used up; sticks are
+/// kept and spend no charge.
+fn no_effect_note(item: &Item) -> String {
+    debug_assert!(item_lacks_effect(item) || item.charge == 0);
+    "Nothing happens.".to_string()
+}
+
+/// Stick effects that land on the wielder rather than needing a target.
+const SELF_EFFECT_STICK_USEFS: [&str; 4] = ["I_HEAL", "I_SPEED", "I_INVISIBLE", "I_HIDE"];
+
+fn zap_stick<R: RandomSource>(
     state: &mut GameState,
-    command: &Command,
-    events: &mut Vec<Event>,
+    item_id: u32,
     rng: &mut R,
-    bonus_minutes: &mut u64,
-) -> Option<ItemPromptInteractionResolution> {
-    let interaction = state.pending_item_prompt.clone()?;
-    let _ = (rng, bonus_minutes);
-    let mut resolution = ItemPromptInteractionResolution {
-        freeze_world_progression: true,
-        command_for_accounting: Command::Legacy { token: "F".to_string() },
-        turn_minutes: 0,
+    events: &mut Vec<Event>,
+) -> String {
+    let Some(stick) = state.player.inventory.iter().find(|entry| entry.id == item_id).cloned()
+    else {
+        return "That item is no longer available.".to_string();
     };
+    if stick.family != ItemFamily::Stick {
+        return format!("You can't zap {}.", stick.name);
+    }
+    if item_lacks_effect(&stick) || stick.charge == 0 {
+        return no_effect_note(&stick);
+    }
+    let self_effect = SELF_EFFECT_STICK_USEFS.contains(&stick.usef.as_str());
+    if !self_effect {
+        push_or_refresh_status(&mut state.status_effects, "wand_charge", 1, 0);
+    }
+    let effect_note = apply_item_usef_effect(state, &stick, rng, events);
+    if let Some(entry) = state.player.inventory.iter_mut().find(|entry| entry.id == item_id)
+        && entry.charge > 0
+    {
+        entry.charge -= 1;
+    }
+    if self_effect {
+        format!("Zapped {} ({effect_note}).", stick.name)
+    } else {
+        format!("Activated {} ({effect_note}).", stick.name)
+    }
+}
 
-    if let Command::Drop { slot } = command {
-        if let Some(item_id) = item_prompt_selection_from_index(state, &interaction, *slot) {
-            let note = apply_item_prompt_selection(state, &interaction, item_id, events);
-            state.pending_item_prompt = None;
-            state.interaction_buffer.clear();
-            record_item_prompt_note(state, events, note);
-            if state.pending_targeting_interaction.is_some() {
-                resolution.freeze_world_progression = true;
-                resolution.command_for_accounting = Command::Legacy { token: "F".to_string() };
-                resolution.turn_minutes = 0;
+/// Drinking an unknown potion teaches its kind, so every carried copy with
+/// the same legacy id (or name, for uncatalogued potions) becomes known.
+fn learn_potion_kind(state: &mut GameState, quaffed: &Item) {
+    let same_kind = |entry: &Item| {
+        entry.family == ItemFamily::Potion
+            && if quaffed.legacy_id != 0 {
+                entry.legacy_id == quaffed.legacy_id
             } else {
-                resolution.freeze_world_progression = false;
-                resolution.command_for_accounting = Command::Legacy {
-                    token: item_prompt_context_token(&interaction.context).to_string(),
-                };
-                resolution.turn_minutes = item_prompt_turn_minutes(&interaction.context);
+                entry.name == quaffed.name
             }
-        } else {
-            let note = item_prompt_help_hint(&interaction);
-            record_item_prompt_note(state, events, note);
-        }
-        return Some(resolution);
+    };
+    for entry in state.player.inventory.iter_mut().filter(|entry| same_kind(entry)) {
+        entry.known = true;
     }
+}
 
-    let input = parse_wizard_input_token(command);
-    match input {
-        WizardInputToken::Cancel => {
-            state.pending_item_prompt = None;
-            state.interaction_buffer.clear();
-            record_item_prompt_note(state, events, "Item prompt canceled.".to_string());
-        }
-        WizardInputToken::Enter => {
-            let choices = item_prompt_choice_pairs(state, &interaction);
-            if choices.len() == 1 {
-                let item_id = choices[0].1;
-                let note = apply_item_prompt_selection(state, &interaction, item_id, events);
-                state.pending_item_prompt = None;
-                state.interaction_buffer.clear();
-                record_item_prompt_note(state, events, note);
-                if state.pending_targeting_interaction.is_some() {
-                    resolution.freeze_world_progression = true;
-                    resolution.command_for_accounting = Command::Legacy { token: "F".to_string() };
-                    resolution.turn_minutes = 0;
-                } else {
-                    resolution.freeze_world_progression = false;
-                    resolution.command_for_accounting = Command::Legacy {
+fn stick_charge_cap(item: &Item) -> i32 {
+    5 + i32::from(item.level)
+}
+
+fn recharge_stick_at_sorcerors<R: RandomSource>(
+    state: &mut GameState,
+    item_id: u32,
+    events: &mut Vec<Event>,
+    rng: &mut R,
+) -> String {
+    let Some(stick) = state.player.inventory.iter().find(|entry| entry.id == item_id) else {
+        return "That item is no longer available.".to_string();
+    };
+    let cap = stick_charge_cap(stick);
+    let near_cap = stick.charge + 2 >= cap;
+
+    if !state.spend_gold(SORCERORS_RECHARGE_FEE, "sorcerors", events) {
+        return "Not enough gold for recharge.".to_string();
+    }
+    let sorcerors = &mut state.progression.quests.sorcerors;
+    sorcerors.rank = sorcerors.rank.max(1);
+    sorcerors.xp = sorcerors.xp.saturating_add(30);
+    sorcerors.dues_paid = sorcerors.dues_paid.saturating_add(SORCERORS_RECHARGE_FEE as i64);
+    sorcerors.quest_flags |= 0x0001;
+    events.push(Event::ProgressionUpdated {
+        guild_rank: state.progression.guild_rank,
+        priest_rank: state.progression.priest_rank,
+        alignment: state.progression.alignment,
+    });
+
+    if near_cap && rng.range_inclusive_i32(1, 100) <= 50 {
+        let destroyed = remove_inventory_item_by_id(state, item_id);
+        let name = destroyed.map(|item| item.name).unwrap_or_default();
+        return format!("The {name} overcharges and explodes in a shower of sparks!");
+    }
+    let added = rng.range_inclusive_i32(1, 6);
+    let Some(stick) = state.player.inventory.iter_mut().find(|entry| entry.id == item_id) else {
+        return "That item is no longer available.".to_string();
+    };
+    stick.charge = (stick.charge + added).min(cap);
+    format!("Recharged {} to {} charges.", stick.name, stick.charge)
+}
+
+/// Each existing plus makes the rite riskier; a botched rite curses the item.
+fn enchant_failure_chance(item: &Item) -> i32 {
+    10 + item.plus.max(0) * 15
+}
+
+fn enchant_item_at_sorcerors<R: RandomSource>(
+    state: &mut GameState,
+    item_id: u32,
+    events: &mut Vec<Event>,
+    rng: &mut R,
+) -> String {
+    let Some(item) = state.player.inventory.iter().find(|entry| entry.id == item_id) else {
+        return "That item is no longer available.".to_string();
+    };
+    if !matches!(item.family, ItemFamily::Weapon | ItemFamily::Armor | ItemFamily::Shield) {
+        return format!("The sorcerors can't enchant {}.", item.name);
+    }
+    if item.plus >= SORCERORS_ENCHANT_PLUS_CAP {
+        return format!("{} can hold no more enchantment.", item.name);
+    }
+    let fail_chance = enchant_failure_chance(item);
+    if !state.spend_gold(SORCERORS_ENCHANT_FEE, "sorcerors", events) {
+        return "Not enough gold for enchantment.".to_string();
+    }
+    let sorcerors = &mut state.progression.quests.sorcerors;
+    sorcerors.quest_flags |= 0x0004;
+    sorcerors.xp = sorcerors.xp.saturating_add(35);
+    if state.progression.alignment == Alignment::Lawful {
+        state.progression.law_chaos_score -= 1;
+    }
+
+    let failed = rng.range_inclusive_i32(1, 100) <= fail_chance;
+    let Some(item) = state.player.inventory.iter_mut().find(|entry| entry.id == item_id) else {
+        return "That item is no longer available.".to_string();
+    };
+    item.known = true;
+    if failed {
+        item.blessing = item.blessing.min(0) - 1;
+        return format!("The rite goes awry; {} darkens with a curse.", item.name);
+    }
+    item.plus += 1;
+    format!("The sorcerors enchant {} to {:+}.", item.name, item.plus)
+}
+
+fn resolve_pending_call_label_interaction(
+    state: &mut GameState,
+    command: &Command,
+    events: &mut Vec<Event>,
+) -> Option<ItemPromptInteractionResolution> {
+    let interaction = state.pending_call_label.clone()?;
+    let mut resolution = ItemPromptInteractionResolution {
+        freeze_world_progression: true,
+        command_for_accounting: Command::Legacy { token: "F".to_string() },
+        turn_minutes: 0,
+    };
+
+    match parse_wizard_input_token(command) {
+        WizardInputToken::Cancel => {
+            state.pending_call_label = None;
+            state.interaction_buffer.clear();
+            record_item_prompt_note(state, events, "Call canceled.".to_string());
+        }
+        WizardInputToken::Backspace => {
+            state.interaction_buffer.pop();
+        }
+        WizardInputToken::Enter => {
+            let label = state.interaction_buffer.trim().to_string();
+            state.pending_call_label = None;
+            state.interaction_buffer.clear();
+            let note = if label.is_empty() {
+                "Call canceled.".to_string()
+            } else {
+                resolution.freeze_world_progression = false;
+                resolution.command_for_accounting = Command::Legacy { token: "C".to_string() };
+                apply_call_label(state, interaction.item_id, &label)
+            };
+            record_item_prompt_note(state, events, note);
+        }
+        WizardInputToken::Text(text) => {
+            if state.interaction_buffer.len() + text.len() <= 40 {
+                state.interaction_buffer.push_str(&text);
+            }
+        }
+        _ => {
+            let note = active_call_label_help_hint(state).unwrap_or_default();
+            record_item_prompt_note(state, events, note);
+        }
+    }
+    Some(resolution)
+}
+
+fn apply_call_label(state: &mut GameState, item_id: u32, label: &str) -> String {
+    let Some(item) = state.player.inventory.iter_mut().find(|entry| entry.id == item_id) else {
+        return "That item is no longer available.".to_string();
+    };
+    let base = item.name.split(" called ").next().unwrap_or_default().to_string();
+    item.objstr = label.to_string();
+    item.name = format!("{base} called {label}");
+    format!("Named item: {}.", item.name)
+}
+
+fn resolve_pending_item_prompt_interaction<R: RandomSource>(
+    state: &mut GameState,
+    command: &Command,
+    events: &mut Vec<Event>,
+    rng: &mut R,
+    bonus_minutes: &mut u64,
+) -> Option<ItemPromptInteractionResolution> {
+    let interaction = state.pending_item_prompt.clone()?;
+    let _ = bonus_minutes;
+    let mut resolution = ItemPromptInteractionResolution {
+        freeze_world_progression: true,
+        command_for_accounting: Command::Legacy { token: "F".to_string() },
+        turn_minutes: 0,
+    };
+
+    if let Command::Drop { slot } = command {
+        if let Some(item_id) = item_prompt_selection_from_index(state, &interaction, *slot) {
+            let note = apply_item_prompt_selection(state, &interaction, item_id, events, rng);
+            state.pending_item_prompt = None;
+            state.interaction_buffer.clear();
+            record_item_prompt_note(state, events, note);
+            if state.pending_targeting_interaction.is_some() || state.pending_call_label.is_some() {
+                resolution.freeze_world_progression = true;
+                resolution.command_for_accounting = Command::Legacy { token: "F".to_string() };
+                resolution.turn_minutes = 0;
+            } else {
+                resolution.freeze_world_progression = false;
+                resolution.command_for_accounting = Command::Legacy {
+                    token: item_prompt_context_token(&interaction.context).to_string(),
+                };
+                resolution.turn_minutes = item_prompt_turn_minutes(&interaction.context);
+            }
+        } else {
+            let note = item_prompt_help_hint(&interaction);
+            record_item_prompt_note(state, events, note);
+        }
+        return Some(resolution);
+    }
+
+    let input = parse_wizard_input_token(command);
+    match input {
+        WizardInputToken::Cancel => {
+            state.pending_item_prompt = None;
+            state.interaction_buffer.clear();
+            record_item_prompt_note(state, events, "Item prompt canceled.".to_string());
+        }
+        WizardInputToken::Enter => {
+            let choices = item_prompt_choice_pairs(state, &interaction);
+            if choices.len() == 1 {
+                let item_id = choices[0].1;
+                let note = apply_item_prompt_selection(state, &interaction, item_id, events, rng);
+                state.pending_item_prompt = None;
+                state.interaction_buffer.clear();
+                record_item_prompt_note(state, events, note);
+                if state.pending_targeting_interaction.is_some()
+                    || state.pending_call_label.is_some()
+                {
+                    resolution.freeze_world_progression = true;
+                    resolution.command_for_accounting = Command::Legacy { token: "F".to_string() };
+                    resolution.turn_minutes = 0;
+                } else {
+                    resolution.freeze_world_progression = false;
+                    resolution.command_for_accounting = Command::Legacy {
                         token: item_prompt_context_token(&interaction.context).to_string(),
                     };
                     resolution.turn_minutes = item_prompt_turn_minutes(&interaction.context);
@@ -6716,11 +9670,14 @@ fn resolve_pending_item_prompt_interaction<R: RandomSource>(
                 record_item_prompt_note(state, events, note);
             } else if let Some(ch) = text.chars().next() {
                 if let Some(item_id) = item_prompt_selection_from_key(state, &interaction, ch) {
-                    let note = apply_item_prompt_selection(state, &interaction, item_id, events);
+                    let note =
+                        apply_item_prompt_selection(state, &interaction, item_id, events, rng);
                     state.pending_item_prompt = None;
                     state.interaction_buffer.clear();
                     record_item_prompt_note(state, events, note);
-                    if state.pending_targeting_interaction.is_some() {
+                    if state.pending_targeting_interaction.is_some()
+                        || state.pending_call_label.is_some()
+                    {
                         resolution.freeze_world_progression = true;
                         resolution.command_for_accounting =
                             Command::Legacy { token: "F".to_string() };
@@ -6766,6 +9723,9 @@ fn resolve_projectile_action<R: RandomSource>(
     events: &mut Vec<Event>,
     rng: &mut R,
 ) -> ProjectileResolution {
+    if action.mode == ProjectileKind::Healing {
+        return resolve_healing_target(state, action, target);
+    }
     let origin = state.player.position;
     let bounded_target = clamp_target_to_range(origin, target, action.max_range.max(1));
     let final_pos = projectile_trace_to_target(state, origin, bounded_target, true);
@@ -6785,9 +9745,10 @@ fn resolve_projectile_action<R: RandomSource>(
         ProjectileKind::Arrow | ProjectileKind::Bolt => {
             lines.push(format!("You fire {}.", action.item_name))
         }
-        ProjectileKind::MagicMissile | ProjectileKind::FireBolt | ProjectileKind::LightningBolt => {
-            lines.push(format!("You cast {}.", action.item_name))
-        }
+        ProjectileKind::MagicMissile
+        | ProjectileKind::FireBolt
+        | ProjectileKind::LightningBolt
+        | ProjectileKind::Healing => lines.push(format!("You cast {}.", action.item_name)),
     }
 
     let mut launched_item =
@@ -6803,6 +9764,17 @@ fn resolve_projectile_action<R: RandomSource>(
         };
     }
 
+    if let Some(potion) = launched_item.as_ref().filter(|item| item.family == ItemFamily::Potion) {
+        lines.extend(shatter_potion_at(state, potion, final_pos, events));
+        return ProjectileResolution {
+            final_pos,
+            hit_monster_id: None,
+            dropped_item: None,
+            consumed_item: true,
+            log_lines: lines,
+        };
+    }
+
     let crossbow_loaded_before_shot = weapon_hand_crossbow_loaded(state);
     let forced_scythe_miss = launched_item.as_ref().is_some_and(is_scythe_throw);
     let maybe_monster_idx = monster_index_at(state, final_pos);
@@ -6854,8 +9826,10 @@ fn resolve_projectile_action<R: RandomSource>(
             if defeated {
                 let _ = remove_monster_with_drops(state, monster_idx, events);
                 state.monsters_defeated = state.monsters_defeated.saturating_add(1);
-                events.push(Event::MonsterDefeated { monster_id });
                 lines.push(format!("{monster_name} is defeated."));
+                events.push(Event::MonsterDefeated { monster_id, name: monster_name });
+            } else if action.mode == ProjectileKind::LightningBolt {
+                lines.extend(apply_knockback(state, monster_idx, origin, events));
             }
         } else {
             lines.push("You miss it.".to_string());
@@ -6889,6 +9863,44 @@ fn resolve_projectile_action<R: RandomSource>(
     }
 }
 
+fn resolve_healing_target(
+    state: &mut GameState,
+    action: &PendingProjectileAction,
+    target: Position,
+) -> ProjectileResolution {
+    let origin = state.player.position;
+    let final_pos = clamp_target_to_range(origin, target, action.max_range.max(1));
+    state.transient_projectile_path.clear();
+    state.transient_projectile_impact = None;
+    let amount = action.damage_max.max(1);
+    let mut lines = vec![format!("You cast {}.", action.item_name)];
+    let mut hit_monster_id = None;
+    if final_pos == origin {
+        state.player.stats.heal(amount);
+        lines.push("You feel major vitality restored.".to_string());
+    } else if let Some(companion) =
+        state.companions.iter_mut().find(|companion| companion.position == final_pos)
+    {
+        companion.stats.heal(amount);
+        hit_monster_id = Some(companion.id);
+        lines.push(format!("The {} looks much better.", companion.name));
+    } else if let Some(monster_idx) = monster_index_at(state, final_pos) {
+        lines.push(format!(
+            "You refuse to mend the hostile {}; the healing dissipates.",
+            state.monsters[monster_idx].name
+        ));
+    } else {
+        lines.push("The healing energy dissipates harmlessly.".to_string());
+    }
+    ProjectileResolution {
+        final_pos,
+        hit_monster_id,
+        dropped_item: None,
+        consumed_item: false,
+        log_lines: lines,
+    }
+}
+
 fn resolve_pending_targeting_interaction<R: RandomSource>(
     state: &mut GameState,
     command: &Command,
@@ -7009,6 +10021,7 @@ fn parse_site_interaction_choice(
             'p' => Some(2),
             'i' => Some(3),
             'l' => Some(4),
+            'h' => Some(5),
             _ => None,
         },
         SiteInteractionKind::Armorer => match key {
@@ -7022,6 +10035,7 @@ fn parse_site_interaction_choice(
             'm' => Some(1),
             'l' => Some(2),
             'x' | 'q' => Some(3),
+            'a' => Some(4),
             _ => None,
         },
         SiteInteractionKind::Gym => match key {
@@ -7083,6 +10097,7 @@ fn parse_site_interaction_choice(
             'r' => Some(1),
             's' => Some(2),
             'l' | 'x' => Some(3),
+            't' => Some(4),
             _ => None,
         },
         SiteInteractionKind::Bank => match key {
@@ -7097,6 +10112,8 @@ fn parse_site_interaction_choice(
             'c' => Some(2),
             'p' => Some(3),
             'l' => Some(4),
+            'h' => Some(5),
+            'j' => Some(6),
             _ => None,
         },
         SiteInteractionKind::ThievesGuild => match key {
@@ -7167,6 +10184,7 @@ fn parse_site_interaction_choice(
                 match key {
                     'y' | 'e' => Some(1),
                     'n' | 'l' => Some(2),
+                    'b' => Some(3),
                     _ => None,
                 }
             } else {
@@ -7174,6 +10192,7 @@ fn parse_site_interaction_choice(
                     'e' | 'y' => Some(1),
                     'r' => Some(2),
                     'n' | 'l' => Some(3),
+                    'b' => Some(4),
                     _ => None,
                 }
             }
@@ -7204,6 +10223,10 @@ fn begin_site_interaction(
     events: &mut Vec<Event>,
     origin: &str,
 ) -> String {
+    settle_guild_dues(state, &kind, events);
+    if kind == SiteInteractionKind::Shop {
+        state.maybe_restock_shop(shop_site_id(state));
+    }
     let prompt = site_interaction_prompt(state, &kind);
     state.pending_site_interaction = Some(kind);
     events.push(Event::LegacyHandled {
@@ -7214,6 +10237,81 @@ fn begin_site_interaction(
     prompt
 }
 
+const SHOP_RESTOCK_SEED: u64 = 0x5709_C0DE;
+const SHOP_RESTOCK_MIN: i32 = 2;
+const SHOP_RESTOCK_MAX: i32 = 6;
+
+/// The entrance tile of the shop the player is standing in.
+fn shop_site_id(state: &GameState) -> u32 {
+    tile_index(state.bounds, state.player.position).unwrap_or(0) as u32
+}
+
+fn shop_stock_key(map_id: u16, site_id: u32) -> u64 {
+    (u64::from(map_id) << 32) | u64::from(site_id)
+}
+
+/// Identifies the shop the player is standing in by its map and entrance tile,
+/// so every shop keeps its own shelves.
+fn shop_site_key(state: &GameState) -> u64 {
+    shop_stock_key(state.map_binding.map_id, shop_site_id(state))
+}
+
+/// The new stock depends only on the shop and the day.
+fn shop_stock_for_day(site_key: u64, day: u64) -> ShopStock {
+    let mut rng = DeterministicRng::seeded(SHOP_RESTOCK_SEED ^ site_key.rotate_left(24) ^ day);
+    let mut wares = [0u8; 3];
+    for count in &mut wares {
+        *count = rng.range_inclusive_i32(SHOP_RESTOCK_MIN, SHOP_RESTOCK_MAX) as u8;
+    }
+    ShopStock { day, wares, haggle_percent: None }
+}
+
+fn shop_stock_left(state: &GameState, choice: usize) -> u8 {
+    state
+        .shop_stock
+        .get(&shop_site_key(state))
+        .and_then(|stock| stock.wares.get(choice - 1).copied())
+        .unwrap_or(0)
+}
+
+fn take_shop_stock(state: &mut GameState, choice: usize) {
+    let site_id = shop_site_key(state);
+    if let Some(count) =
+        state.shop_stock.get_mut(&site_id).and_then(|stock| stock.wares.get_mut(choice - 1))
+    {
+        *count = count.saturating_sub(1);
+    }
+}
+
+const SHOP_HAGGLE_TARGET: i32 = 12;
+const SHOP_HAGGLE_DISCOUNT_PERCENT: i32 = 80;
+const SHOP_HAGGLE_OFFENSE_PERCENT: i32 = 125;
+
+fn shop_price(state: &GameState, base: i32) -> i32 {
+    let percent = state
+        .shop_stock
+        .get(&shop_site_key(state))
+        .and_then(|stock| stock.haggle_percent)
+        .unwrap_or(100);
+    (base * percent / 100).max(1)
+}
+
+fn haggle_shop_prices(state: &mut GameState, rng: &mut impl RandomSource) -> String {
+    let key = shop_site_key(state);
+    if state.shop_stock.get(&key).is_some_and(|stock| stock.haggle_percent.is_some()) {
+        return "The merchant has already named a final price.".to_string();
+    }
+    let roll = rng.range_inclusive_i32(1, 20) + statmod(state.attributes.iq);
+    let stock = state.shop_stock.entry(key).or_default();
+    if roll >= SHOP_HAGGLE_TARGET {
+        stock.haggle_percent = Some(SHOP_HAGGLE_DISCOUNT_PERCENT);
+        "You talk the merchant down; prices drop.".to_string()
+    } else {
+        stock.haggle_percent = Some(SHOP_HAGGLE_OFFENSE_PERCENT);
+        "The merchant takes offense and raises the prices.".to_string()
+    }
+}
+
 fn push_log_line(state: &mut GameState, line: String) {
     if state.log.last().map(String::as_str) != Some(line.as_str()) {
         state.log.push(line);
@@ -7235,10 +10333,14 @@ fn classify_note_against_active_interactions(state: &GameState, note: &str) -> U
         || active_spell_interaction_prompt(state).as_deref() == Some(note)
         || active_quit_interaction_prompt(state).as_deref() == Some(note)
         || active_talk_direction_prompt(state).as_deref() == Some(note)
+        || active_dialogue_prompt(state).as_deref() == Some(note)
         || active_activation_interaction_prompt(state).as_deref() == Some(note)
         || active_targeting_interaction_prompt(state).as_deref() == Some(note)
+        || active_look_interaction_prompt(state).as_deref() == Some(note)
+        || active_guild_action_prompt(state).as_deref() == Some(note)
         || active_inventory_interaction_prompt(state).as_deref() == Some(note)
         || active_item_prompt(state).as_deref() == Some(note)
+        || active_call_label_prompt(state).as_deref() == Some(note)
         || active_site_interaction_prompt(state).as_deref() == Some(note)
     {
         return UiLogClass::Prompt;
@@ -7247,10 +10349,14 @@ fn classify_note_against_active_interactions(state: &GameState, note: &str) -> U
         || active_spell_interaction_help_hint(state).as_deref() == Some(note)
         || active_quit_interaction_help_hint(state).as_deref() == Some(note)
         || active_talk_direction_help_hint(state).as_deref() == Some(note)
+        || active_dialogue_help_hint(state).as_deref() == Some(note)
         || active_activation_interaction_help_hint(state).as_deref() == Some(note)
         || active_targeting_interaction_help_hint(state).as_deref() == Some(note)
+        || active_look_interaction_help_hint(state).as_deref() == Some(note)
+        || active_guild_action_help_hint(state).as_deref() == Some(note)
         || active_inventory_interaction_help_hint(state).as_deref() == Some(note)
         || active_item_prompt_help_hint(state).as_deref() == Some(note)
+        || active_call_label_help_hint(state).as_deref() == Some(note)
         || active_site_interaction_help_hint(state).as_deref() == Some(note)
     {
         return UiLogClass::Hint;
@@ -7323,9 +10429,10 @@ pub fn renderable_timeline_lines(state: &GameState, limit: usize) -> Vec<String>
     lines
 }
 
-fn resolve_pending_site_interaction(
+fn resolve_pending_site_interaction<R: RandomSource>(
     state: &mut GameState,
     command: &Command,
+    rng: &mut R,
     events: &mut Vec<Event>,
 ) -> bool {
     let Some(kind) = state.pending_site_interaction.clone() else {
@@ -7369,7 +10476,7 @@ fn resolve_pending_site_interaction(
     };
 
     let choice = choice.unwrap_or(0);
-    let action_note = apply_site_interaction_choice(state, kind, choice, events, true);
+    let action_note = apply_site_interaction_choice(state, kind, choice, rng, events, true);
     push_log_line(state, action_note.clone());
     let note = format!("Selected option {choice}. {action_note}");
     events.push(Event::LegacyHandled {
@@ -7380,25 +10487,28 @@ fn resolve_pending_site_interaction(
     true
 }
 
-fn apply_site_interaction_choice(
+fn apply_site_interaction_choice<R: RandomSource>(
     state: &mut GameState,
     kind: SiteInteractionKind,
     choice: usize,
+    rng: &mut R,
     events: &mut Vec<Event>,
     reopen_prompt: bool,
 ) -> String {
     let mut keep_open = true;
+    if kind == SiteInteractionKind::Shop {
+        state.maybe_restock_shop(shop_site_id(state));
+    }
     let note = match kind {
         SiteInteractionKind::Shop => match choice {
+            1 if shop_stock_left(state, 1) == 0 => {
+                "The rations are sold out until tomorrow.".to_string()
+            }
             1 => {
-                if state.gold >= 12 {
-                    state.gold -= 12;
+                let price = shop_price(state, 12);
+                if state.spend_gold(price, "shop", events) {
+                    take_shop_stock(state, 1);
                     let result = add_item_to_inventory_or_ground(state, "food ration", events);
-                    events.push(Event::EconomyUpdated {
-                        source: "shop".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     events.push(Event::ProgressionUpdated {
                         guild_rank: state.progression.guild_rank,
                         priest_rank: state.progression.priest_rank,
@@ -7409,29 +10519,27 @@ fn apply_site_interaction_choice(
                     "Not enough gold for ration.".to_string()
                 }
             }
+            2 if shop_stock_left(state, 2) == 0 => {
+                "The potions are sold out until tomorrow.".to_string()
+            }
             2 => {
-                if state.gold >= 30 {
-                    state.gold -= 30;
+                let price = shop_price(state, 30);
+                if state.spend_gold(price, "shop", events) {
+                    take_shop_stock(state, 2);
                     let result = add_item_to_inventory_or_ground(state, "healing potion", events);
-                    events.push(Event::EconomyUpdated {
-                        source: "shop".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     format!("Bought potion ({result}).")
                 } else {
                     "Not enough gold for potion.".to_string()
                 }
             }
+            3 if shop_stock_left(state, 3) == 0 => {
+                "The identify scrolls are sold out until tomorrow.".to_string()
+            }
             3 => {
-                if state.gold >= 40 {
-                    state.gold -= 40;
+                let price = shop_price(state, 40);
+                if state.spend_gold(price, "shop", events) {
+                    take_shop_stock(state, 3);
                     let result = add_item_to_inventory_or_ground(state, "scroll-identify", events);
-                    events.push(Event::EconomyUpdated {
-                        source: "shop".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     format!("Bought identify scroll ({result}).")
                 } else {
                     "Not enough gold for identify scroll.".to_string()
@@ -7441,46 +10549,29 @@ fn apply_site_interaction_choice(
                 keep_open = false;
                 "Left shop.".to_string()
             }
+            5 => haggle_shop_prices(state, rng),
             _ => "Invalid shop choice.".to_string(),
         },
         SiteInteractionKind::Armorer => match choice {
             1 => {
-                if state.gold >= 70 {
-                    state.gold -= 70;
+                if state.spend_gold(70, "armorer", events) {
                     let result = add_item_to_inventory_or_ground(state, "chain mail", events);
-                    events.push(Event::EconomyUpdated {
-                        source: "armorer".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     format!("Bought chain mail ({result}).")
                 } else {
                     "Not enough gold for chain mail.".to_string()
                 }
             }
             2 => {
-                if state.gold >= 65 {
-                    state.gold -= 65;
+                if state.spend_gold(65, "armorer", events) {
                     let result = add_item_to_inventory_or_ground(state, "long sword", events);
-                    events.push(Event::EconomyUpdated {
-                        source: "armorer".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     format!("Bought a weapon ({result}).")
                 } else {
                     "Not enough gold for weapon purchase.".to_string()
                 }
             }
             3 => {
-                if state.gold >= 30 {
-                    state.gold -= 30;
+                if state.spend_gold(30, "armorer", events) {
                     state.player.stats.defense += 1;
-                    events.push(Event::EconomyUpdated {
-                        source: "armorer".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     "Armorer refit improves your defenses.".to_string()
                 } else {
                     "Not enough gold for armor refit.".to_string()
@@ -7494,32 +10585,19 @@ fn apply_site_interaction_choice(
         },
         SiteInteractionKind::Club => match choice {
             1 => {
-                if state.gold >= 20 {
-                    state.gold -= 20;
-                    state.player.stats.hp =
-                        (state.player.stats.hp + 1).min(state.player.stats.max_hp);
+                if state.spend_gold(20, "club", events) {
+                    state.player.stats.heal(1);
                     state.food = state.food.saturating_add(1);
-                    events.push(Event::EconomyUpdated {
-                        source: "club".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     "Club hospitality steadies your nerves.".to_string()
                 } else {
                     "Not enough gold for club membership.".to_string()
                 }
             }
             2 => {
-                if state.gold >= 20 {
-                    state.gold -= 20;
+                if state.spend_gold(20, "club", events) {
                     state.legal_heat = state.legal_heat.saturating_sub(1);
                     state.progression.quests.order.xp =
                         state.progression.quests.order.xp.saturating_add(12);
-                    events.push(Event::EconomyUpdated {
-                        source: "club".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     "A quiet favor eases legal scrutiny.".to_string()
                 } else {
                     "Not enough gold for legal favor.".to_string()
@@ -7529,31 +10607,31 @@ fn apply_site_interaction_choice(
                 keep_open = false;
                 "Left club.".to_string()
             }
+            4 => {
+                if state.spend_gold(CLUB_ABSOLUTION_COST, "club", events) {
+                    let rite = perform_absolution_rite(state, rng);
+                    format!("The club chaplain performs an absolution. {rite}")
+                } else {
+                    "Not enough gold for absolution.".to_string()
+                }
+            }
             _ => "Invalid club choice.".to_string(),
         },
         SiteInteractionKind::Gym => match choice {
             1 => {
-                if state.gold >= 30 {
-                    state.gold -= 30;
+                if state.spend_gold(30, "gym", events) {
                     state.player.stats.max_hp += 1;
-                    state.player.stats.hp =
-                        (state.player.stats.hp + 1).min(state.player.stats.max_hp);
+                    state.player.stats.heal(1);
                     state.progression.quests.merc.rank = state.progression.quests.merc.rank.max(1);
                     state.progression.quests.merc.xp =
                         state.progression.quests.merc.xp.saturating_add(20);
-                    events.push(Event::EconomyUpdated {
-                        source: "gym".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     "Gym drills improve your conditioning.".to_string()
                 } else {
                     "Not enough gold for gym drills.".to_string()
                 }
             }
             2 => {
-                if state.gold >= 35 {
-                    state.gold -= 35;
+                if state.spend_gold(35, "gym", events) {
                     state.monsters_defeated = state.monsters_defeated.saturating_add(1);
                     if state.progression.quest_state == LegacyQuestState::NotStarted {
                         let _ = start_main_quest_from_dialogue(state, events);
@@ -7567,11 +10645,6 @@ fn apply_site_interaction_choice(
                         .max(i16::from(state.progression.guild_rank));
                     state.progression.quests.merc.xp =
                         state.progression.quests.merc.xp.saturating_add(30);
-                    events.push(Event::EconomyUpdated {
-                        source: "gym".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     "Gym sparring contract recorded.".to_string()
                 } else {
                     "Not enough gold for spar contract.".to_string()
@@ -7585,29 +10658,17 @@ fn apply_site_interaction_choice(
         },
         SiteInteractionKind::Healer => match choice {
             1 => {
-                if state.gold >= 18 {
-                    state.gold -= 18;
+                if state.spend_gold(18, "healer", events) {
                     state.player.stats.hp = state.player.stats.max_hp;
-                    events.push(Event::EconomyUpdated {
-                        source: "healer".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     "The healer restores your wounds.".to_string()
                 } else {
                     "Not enough gold for healing.".to_string()
                 }
             }
             2 => {
-                if state.gold >= 25 {
-                    state.gold -= 25;
+                if state.spend_gold(25, "healer", events) {
                     let before = state.status_effects.len();
                     state.status_effects.retain(|effect| effect.id != "poison");
-                    events.push(Event::EconomyUpdated {
-                        source: "healer".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     if state.status_effects.len() < before {
                         "The healer purges poison from your system.".to_string()
                     } else {
@@ -7625,30 +10686,19 @@ fn apply_site_interaction_choice(
         },
         SiteInteractionKind::Casino => match choice {
             1 => {
-                if state.gold >= 25 {
-                    state.gold -= 25;
-                    state.bank_gold += 5;
-                    events.push(Event::EconomyUpdated {
-                        source: "casino".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
+                if state.pay_into_bank(25, 5, "casino", events) {
                     "You buy chips at the casino tables.".to_string()
                 } else {
                     "Not enough gold for casino chips.".to_string()
                 }
             }
             2 => {
-                if state.gold >= 10 {
-                    state.gold -= 10;
-                    let payout = if state.clock.turn.is_multiple_of(2) { 18 } else { 0 };
-                    state.gold += payout;
-                    events.push(Event::EconomyUpdated {
-                        source: "casino".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
+                if state.spend_gold(10, "casino", events) {
+                    let luck_bonus = if state.questionnaire_traits.is_irish { 4 } else { 0 };
+                    let payout =
+                        if state.clock.turn.is_multiple_of(2) { 18 + luck_bonus } else { 0 };
                     if payout > 0 {
+                        state.gain_gold(payout, "casino", events);
                         "The tables run hot; you cash out with winnings.".to_string()
                     } else {
                         "The house edge prevails this round.".to_string()
@@ -7665,16 +10715,10 @@ fn apply_site_interaction_choice(
         },
         SiteInteractionKind::Commandant => match choice {
             1 => {
-                if state.gold >= 20 {
-                    state.gold -= 20;
+                if state.spend_gold(20, "commandant", events) {
                     state.food = state.food.saturating_add(5);
                     let result =
                         add_item_to_inventory_or_ground(state, "bucket of rations", events);
-                    events.push(Event::EconomyUpdated {
-                        source: "commandant".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     format!("Buy a bucket! ({result})")
                 } else {
                     "Not enough gold for a bucket.".to_string()
@@ -7694,30 +10738,17 @@ fn apply_site_interaction_choice(
         },
         SiteInteractionKind::Diner => match choice {
             1 => {
-                if state.gold >= 8 {
-                    state.gold -= 8;
+                if state.spend_gold(8, "diner", events) {
                     state.food = state.food.saturating_add(2);
-                    state.player.stats.hp =
-                        (state.player.stats.hp + 1).min(state.player.stats.max_hp);
-                    events.push(Event::EconomyUpdated {
-                        source: "diner".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
+                    state.player.stats.heal(1);
                     "You finish a hot meal at the diner.".to_string()
                 } else {
                     "Not enough gold for a meal.".to_string()
                 }
             }
             2 => {
-                if state.gold >= 6 {
-                    state.gold -= 6;
-                    state.spellbook.mana = (state.spellbook.mana + 6).min(state.spellbook.max_mana);
-                    events.push(Event::EconomyUpdated {
-                        source: "diner".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
+                if state.spend_gold(6, "diner", events) {
+                    state.spellbook.restore_mana(6);
                     "Strong coffee sharpens your focus.".to_string()
                 } else {
                     "Not enough gold for coffee.".to_string()
@@ -7731,14 +10762,8 @@ fn apply_site_interaction_choice(
         },
         SiteInteractionKind::Craps => match choice {
             1 => {
-                if state.gold >= 15 {
-                    state.gold -= 15;
+                if state.spend_gold(15, "craps", events) {
                     state.legal_heat = state.legal_heat.saturating_add(1);
-                    events.push(Event::EconomyUpdated {
-                        source: "craps".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     "You buy into a back-room dice game.".to_string()
                 } else {
                     "Not enough gold to buy into craps.".to_string()
@@ -7747,12 +10772,7 @@ fn apply_site_interaction_choice(
             2 => {
                 if state.legal_heat > 0 {
                     state.legal_heat = state.legal_heat.saturating_sub(1);
-                    state.gold += 10;
-                    events.push(Event::EconomyUpdated {
-                        source: "craps".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
+                    state.gain_gold(10, "craps", events);
                     "You cash out before the watch arrives.".to_string()
                 } else {
                     "No active game to cash out from.".to_string()
@@ -7766,46 +10786,27 @@ fn apply_site_interaction_choice(
         },
         SiteInteractionKind::Tavern => match choice {
             1 => {
-                if state.gold >= 6 {
-                    state.gold -= 6;
+                if state.spend_gold(6, "tavern", events) {
                     state.food = state.food.saturating_add(1);
                     state.legal_heat = state.legal_heat.saturating_add(1);
-                    events.push(Event::EconomyUpdated {
-                        source: "tavern".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     "The tavern ale restores your spirits.".to_string()
                 } else {
                     "Not enough gold for ale.".to_string()
                 }
             }
             2 => {
-                if state.gold >= 10 {
-                    state.gold -= 10;
+                if state.spend_gold(10, "tavern", events) {
                     state.food = state.food.saturating_add(3);
-                    state.player.stats.hp =
-                        (state.player.stats.hp + 1).min(state.player.stats.max_hp);
-                    events.push(Event::EconomyUpdated {
-                        source: "tavern".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
+                    state.player.stats.heal(1);
                     "You eat a heavy tavern stew.".to_string()
                 } else {
                     "Not enough gold for stew.".to_string()
                 }
             }
             3 => {
-                if state.gold >= 8 {
-                    state.gold -= 8;
+                if state.spend_gold(8, "tavern", events) {
                     let started = start_main_quest_from_dialogue(state, events);
                     let rumor = tavern_rumor_line(state);
-                    events.push(Event::EconomyUpdated {
-                        source: "tavern".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     if started {
                         format!("You overhear a rumor: {rumor} Quest updated.")
                     } else {
@@ -7823,20 +10824,12 @@ fn apply_site_interaction_choice(
         },
         SiteInteractionKind::PawnShop => match choice {
             1 => {
-                if state.gold >= 15 {
-                    state.gold -= 15;
-                    let oddity_name = choose_pawn_stock_item_name(state)
-                        .unwrap_or_else(|| "food ration".to_string());
-                    let mut oddity = instantiate_item_from_name(state.next_item_id, &oddity_name);
-                    oddity.known = true;
-                    state.next_item_id += 1;
-                    let result = add_existing_item_to_inventory_or_ground(state, oddity, events);
-                    events.push(Event::EconomyUpdated {
-                        source: "pawn_shop".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
-                    format!("Bought {oddity_name} ({result}).")
+                let (_, price) = pawn_shop_offer(state);
+                if state.spend_gold(price, "pawn_shop", events) {
+                    let offer = take_pawn_shop_offer(state);
+                    let label = item_condition_label(&offer);
+                    let result = add_existing_item_to_inventory_or_ground(state, offer, events);
+                    format!("Bought {label} for {price} gold ({result}).")
                 } else {
                     "Not enough gold for pawned goods.".to_string()
                 }
@@ -7850,13 +10843,15 @@ fn apply_site_interaction_choice(
                     remove_item_from_pack_order(state, item.id);
                     state.carry_burden =
                         state.carry_burden.saturating_sub(item_burden(&item)).max(0);
-                    state.gold += 12;
-                    events.push(Event::EconomyUpdated {
-                        source: "pawn_shop".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
-                    format!("Pawned {} for 12 gold.", item.name)
+                    let (appraised, price) = appraise_item(&item);
+                    state.gain_gold(price, "pawn_shop", events);
+                    let line =
+                        format!("Pawned {} for {price} gold.", item_condition_label(&appraised));
+                    state.pawn_stock.push(appraised);
+                    if state.pawn_stock.len() > PAWN_STOCK_LIMIT {
+                        state.pawn_stock.remove(0);
+                    }
+                    line
                 }
             }
             3 => {
@@ -7867,31 +10862,18 @@ fn apply_site_interaction_choice(
         },
         SiteInteractionKind::Brothel => match choice {
             1 => {
-                if state.gold >= 25 {
-                    state.gold -= 25;
+                if state.spend_gold(25, "brothel", events) {
                     state.player.stats.hp = state.player.stats.max_hp;
-                    state.spellbook.mana =
-                        (state.spellbook.mana + 10).min(state.spellbook.max_mana);
-                    events.push(Event::EconomyUpdated {
-                        source: "brothel".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
+                    state.spellbook.restore_mana(10);
                     "You rent a room and recover fully.".to_string()
                 } else {
                     "Not enough gold for a private room.".to_string()
                 }
             }
             2 => {
-                if state.gold >= 10 {
-                    state.gold -= 10;
+                if state.spend_gold(10, "brothel", events) {
                     state.progression.quests.thieves.quest_flags |= 0x0002;
                     state.legal_heat = state.legal_heat.saturating_add(1);
-                    events.push(Event::EconomyUpdated {
-                        source: "brothel".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     "Costly gossip opens a few shadowy leads.".to_string()
                 } else {
                     "Not enough gold for gossip.".to_string()
@@ -7905,29 +10887,17 @@ fn apply_site_interaction_choice(
         },
         SiteInteractionKind::Condo => match choice {
             1 => {
-                if state.gold >= 40 {
-                    state.gold -= 40;
+                if state.spend_gold(40, "condo", events) {
                     state.player.stats.hp = state.player.stats.max_hp;
                     state.status_effects.clear();
-                    events.push(Event::EconomyUpdated {
-                        source: "condo".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     "You rest at your condo and recover.".to_string()
                 } else {
                     "Not enough gold to rent the condo.".to_string()
                 }
             }
             2 => {
-                if state.gold >= 15 {
-                    state.gold -= 15;
+                if state.spend_gold(15, "condo", events) {
                     state.bank_gold += 15;
-                    events.push(Event::EconomyUpdated {
-                        source: "condo".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     "Condo steward secures valuables in your lockbox.".to_string()
                 } else {
                     "Not enough gold to secure a stash.".to_string()
@@ -7937,22 +10907,24 @@ fn apply_site_interaction_choice(
                 keep_open = false;
                 "Left condo.".to_string()
             }
+            4 => {
+                if state.gold + state.bank_gold >= CONDO_RETIREMENT_HOARD {
+                    keep_open = false;
+                    apply_explicit_victory_trigger(state, VictoryTrigger::RetireCondo, events);
+                    "You retire to your condo to live off your hoard.".to_string()
+                } else {
+                    format!("You need a hoard of {CONDO_RETIREMENT_HOARD} gold to retire.")
+                }
+            }
             _ => "Invalid condo choice.".to_string(),
         },
         SiteInteractionKind::Bank => match choice {
             1 => {
-                if state.gold > 0 {
-                    let deposit = state.gold.clamp(0, 50);
-                    state.gold -= deposit;
-                    state.bank_gold += deposit;
+                let deposit = state.gold.clamp(0, 50);
+                if deposit > 0 && state.pay_into_bank(deposit, deposit, "bank", events) {
                     state.progression.quests.bank.rank = state.progression.quests.bank.rank.max(1);
                     state.progression.quests.bank.xp =
                         state.progression.quests.bank.xp.saturating_add(i64::from(deposit));
-                    events.push(Event::EconomyUpdated {
-                        source: "bank".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     events.push(Event::ProgressionUpdated {
                         guild_rank: state.progression.guild_rank,
                         priest_rank: state.progression.priest_rank,
@@ -7967,30 +10939,19 @@ fn apply_site_interaction_choice(
                 if state.bank_gold > 0 {
                     let withdrawal = state.bank_gold.clamp(0, 50);
                     state.bank_gold -= withdrawal;
-                    state.gold += withdrawal;
                     state.progression.quests.bank.quest_flags |= 0x0001;
-                    events.push(Event::EconomyUpdated {
-                        source: "bank".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
+                    state.gain_gold(withdrawal, "bank", events);
                     format!("Withdrew {withdrawal} gold.")
                 } else {
                     "No bank balance available.".to_string()
                 }
             }
             3 => {
-                if state.gold >= 25 {
-                    state.gold -= 25;
+                if state.spend_gold(25, "bank", events) {
                     state.legal_heat = state.legal_heat.saturating_sub(1);
                     state.progression.law_chaos_score =
                         state.progression.law_chaos_score.max(0) + 1;
                     state.progression.quests.bank.quest_flags |= 0x0002;
-                    events.push(Event::EconomyUpdated {
-                        source: "bank".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     events.push(Event::ProgressionUpdated {
                         guild_rank: state.progression.guild_rank,
                         priest_rank: state.progression.priest_rank,
@@ -8009,8 +10970,7 @@ fn apply_site_interaction_choice(
         },
         SiteInteractionKind::MercGuild => match choice {
             1 => {
-                if state.gold >= 40 {
-                    state.gold -= 40;
+                if state.spend_gold(40, "merc_guild", events) {
                     state.player.stats.attack_max += 1;
                     state.progression.guild_rank = state.progression.guild_rank.max(1);
                     state.progression.quests.merc.rank = state
@@ -8021,11 +10981,6 @@ fn apply_site_interaction_choice(
                         .max(i16::from(state.progression.guild_rank));
                     state.progression.quests.merc.xp =
                         state.progression.quests.merc.xp.saturating_add(40);
-                    events.push(Event::EconomyUpdated {
-                        source: "merc_guild".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     events.push(Event::ProgressionUpdated {
                         guild_rank: state.progression.guild_rank,
                         priest_rank: state.progression.priest_rank,
@@ -8037,8 +10992,7 @@ fn apply_site_interaction_choice(
                 }
             }
             2 => {
-                if state.gold >= 40 {
-                    state.gold -= 40;
+                if state.spend_gold(40, "merc_guild", events) {
                     let before_state = state.progression.quest_state;
                     state.progression.guild_rank = state.progression.guild_rank.max(1);
                     state.progression.quests.merc.rank = state
@@ -8060,11 +11014,6 @@ fn apply_site_interaction_choice(
                     let objective = merc_contract_objective(state);
                     state.progression.main_quest.objective = objective.clone();
                     state.progression.quests.merc.quest_flags |= 0x0001;
-                    events.push(Event::EconomyUpdated {
-                        source: "merc_guild".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     events.push(Event::ProgressionUpdated {
                         guild_rank: state.progression.guild_rank,
                         priest_rank: state.progression.priest_rank,
@@ -8082,60 +11031,69 @@ fn apply_site_interaction_choice(
                 }
             }
             3 => {
-                if state.gold < 60 {
+                let required_kills = u64::from(state.progression.guild_rank.max(1)) * 3;
+                if state.monsters_defeated < required_kills {
+                    format!(
+                        "Promotion denied: defeat {} more foes.",
+                        required_kills.saturating_sub(state.monsters_defeated)
+                    )
+                } else if state.progression.guild_rank >= 4 {
+                    "Guild rank is already at maximum.".to_string()
+                } else if !state.spend_gold(60, "merc_guild", events) {
                     "Not enough gold for promotion review.".to_string()
                 } else {
-                    let required_kills = u64::from(state.progression.guild_rank.max(1)) * 3;
-                    if state.monsters_defeated < required_kills {
-                        format!(
-                            "Promotion denied: defeat {} more foes.",
-                            required_kills.saturating_sub(state.monsters_defeated)
-                        )
-                    } else if state.progression.guild_rank >= 4 {
-                        "Guild rank is already at maximum.".to_string()
-                    } else {
-                        state.gold -= 60;
-                        state.progression.guild_rank += 1;
-                        state.progression.quests.merc.rank = state
-                            .progression
-                            .quests
-                            .merc
-                            .rank
-                            .max(i16::from(state.progression.guild_rank));
-                        state.progression.quests.merc.xp =
-                            state.progression.quests.merc.xp.saturating_add(120);
-                        state.player.stats.attack_max += 1;
-                        events.push(Event::EconomyUpdated {
-                            source: "merc_guild".to_string(),
-                            gold: state.gold,
-                            bank_gold: state.bank_gold,
-                        });
-                        events.push(Event::ProgressionUpdated {
-                            guild_rank: state.progression.guild_rank,
-                            priest_rank: state.progression.priest_rank,
-                            alignment: state.progression.alignment,
-                        });
-                        if state.progression.quest_state == LegacyQuestState::ReturnToPatron
-                            && state.progression.guild_rank >= 2
-                            && state.progression.priest_rank >= 1
-                        {
-                            state.progression.quest_state = LegacyQuestState::Completed;
-                            state.progression.quest_steps_completed = 4;
-                            state.progression.main_quest.stage = state.progression.quest_state;
-                            state.progression.main_quest.completion_flags |= 0x0001;
-                            events.push(Event::QuestAdvanced {
-                                state: state.progression.quest_state,
-                                steps_completed: state.progression.quest_steps_completed,
-                            });
-                        }
-                        "Guild promotion granted.".to_string()
-                    }
-                }
-            }
-            4 => {
+                    state.progression.guild_rank += 1;
+                    state.progression.quests.merc.rank = state
+                        .progression
+                        .quests
+                        .merc
+                        .rank
+                        .max(i16::from(state.progression.guild_rank));
+                    state.progression.quests.merc.xp =
+                        state.progression.quests.merc.xp.saturating_add(120);
+                    state.player.stats.attack_max += 1;
+                    events.push(Event::ProgressionUpdated {
+                        guild_rank: state.progression.guild_rank,
+                        priest_rank: state.progression.priest_rank,
+                        alignment: state.progression.alignment,
+                    });
+                    if state.progression.quest_state == LegacyQuestState::ReturnToPatron
+                        && state.progression.guild_rank >= 2
+                        && state.progression.priest_rank >= 1
+                    {
+                        state.progression.quest_state = LegacyQuestState::Completed;
+                        state.progression.quest_steps_completed = 4;
+                        state.progression.main_quest.stage = state.progression.quest_state;
+                        state.progression.main_quest.completion_flags |= 0x0001;
+                        events.push(Event::QuestAdvanced {
+                            state: state.progression.quest_state,
+                            steps_completed: state.progression.quest_steps_completed,
+                        });
+                    }
+                    "Guild promotion granted.".to_string()
+                }
+            }
+            4 => {
                 keep_open = false;
                 "Left merc guild.".to_string()
             }
+            5 => hire_merc_companion(state, events),
+            6 => {
+                if state.progression.guild_rank > 0 {
+                    "You're already a legionnaire.".to_string()
+                } else if !state.spend_gold(40, "merc_guild", events) {
+                    "Not enough gold to enlist.".to_string()
+                } else {
+                    state.progression.guild_rank = 1;
+                    state.progression.quests.merc.rank = state.progression.quests.merc.rank.max(1);
+                    events.push(Event::ProgressionUpdated {
+                        guild_rank: state.progression.guild_rank,
+                        priest_rank: state.progression.priest_rank,
+                        alignment: state.progression.alignment,
+                    });
+                    "You enlist in the legion.".to_string()
+                }
+            }
             _ => "Invalid merc guild choice.".to_string(),
         },
         SiteInteractionKind::ThievesGuild => match choice {
@@ -8148,10 +11106,9 @@ fn apply_site_interaction_choice(
                     "The thieves guild refuses active order enforcers.".to_string()
                 } else if state.legal_heat > 12 {
                     "The guild cools your petition until the city watch pressure fades.".to_string()
-                } else if state.gold < 30 {
+                } else if !state.spend_gold(30, "thieves_guild", events) {
                     "Not enough gold to pay guild dues.".to_string()
                 } else {
-                    state.gold -= 30;
                     state.progression.quests.thieves.rank = 1;
                     state.progression.quests.thieves.dues_paid =
                         state.progression.quests.thieves.dues_paid.saturating_add(30);
@@ -8161,11 +11118,6 @@ fn apply_site_interaction_choice(
                         state.progression.law_chaos_score.min(0) - 1;
                     state.progression.quests.thieves.xp =
                         state.progression.quests.thieves.xp.saturating_add(20);
-                    events.push(Event::EconomyUpdated {
-                        source: "thieves_guild".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     events.push(Event::ProgressionUpdated {
                         guild_rank: state.progression.guild_rank,
                         priest_rank: state.progression.priest_rank,
@@ -8177,12 +11129,13 @@ fn apply_site_interaction_choice(
             2 => {
                 if state.progression.quests.thieves.rank <= 0 {
                     "Only guild members can take a heist contract.".to_string()
-                } else if state.gold < 25 {
-                    "Not enough gold to seed a heist.".to_string()
+                } else if state.progression.quests.thieves.active_contract.is_some() {
+                    "Finish your current heist before taking another.".to_string()
                 } else if state.legal_heat > 14 {
                     "Too much city heat; lie low before attempting another heist.".to_string()
+                } else if !state.spend_gold(25, "thieves_guild", events) {
+                    "Not enough gold to seed a heist.".to_string()
                 } else {
-                    state.gold -= 25;
                     let base_payout =
                         45 + i32::from(state.progression.quests.thieves.rank.max(1) as i8) * 20;
                     let stealth_bonus = match state.progression.alignment {
@@ -8192,33 +11145,29 @@ fn apply_site_interaction_choice(
                     };
                     let heat_penalty = if state.legal_heat > 8 { 10 } else { 0 };
                     let payout = (base_payout + stealth_bonus - heat_penalty).max(20);
-                    state.gold += payout;
-                    state.legal_heat = state.legal_heat.saturating_add(1);
-                    state.progression.quests.thieves.xp =
-                        state.progression.quests.thieves.xp.saturating_add(i64::from(payout));
-                    state.progression.quests.thieves.quest_flags |= 0x0002;
-                    if payout >= 70 {
-                        state.progression.quests.thieves.promotion_flags |= 1 << 1;
-                    }
-                    if state.progression.quest_state == LegacyQuestState::Active {
-                        state.progression.quest_steps_completed =
-                            state.progression.quest_steps_completed.max(2);
-                    }
-                    events.push(Event::EconomyUpdated {
-                        source: "thieves_guild".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
+                    let target = heist_target_position(state);
+                    let deadline_turn = state.clock.turn.saturating_add(HEIST_TURN_LIMIT);
+                    state.progression.quests.thieves.active_contract = Some(GuildContract {
+                        map_id: state.map_binding.map_id,
+                        target,
+                        deadline_turn,
+                        reward_gold: payout,
                     });
-                    format!("Heist completed. Fence payout: {payout} gold.")
+                    format!(
+                        "Heist contract taken. Slip into the mark at ({}, {}) before turn {}.",
+                        target.x, target.y, deadline_turn
+                    )
                 }
             }
             3 => {
                 if state.progression.quests.thieves.rank <= 0 {
                     "Promotion denied: join the guild first.".to_string()
-                } else if state.gold < 55 {
-                    "Not enough gold for promotion review.".to_string()
                 } else if state.progression.quests.thieves.quest_flags & 0x0002 == 0 {
                     "Promotion denied: complete at least one heist contract first.".to_string()
+                } else if state.progression.quests.thieves.promotion_flags & GUILD_FLAG_DUES_ARREARS
+                    != 0
+                {
+                    "Promotion denied: settle your back dues first.".to_string()
                 } else {
                     let required =
                         u64::from(state.progression.quests.thieves.rank.max(1) as u16) * 4;
@@ -8231,8 +11180,9 @@ fn apply_site_interaction_choice(
                         "Promotion denied: too much legal pressure on your cell.".to_string()
                     } else if state.progression.quests.thieves.rank >= 5 {
                         "Thieves guild rank is already at maximum.".to_string()
+                    } else if !state.spend_gold(55, "thieves_guild", events) {
+                        "Not enough gold for promotion review.".to_string()
                     } else {
-                        state.gold -= 55;
                         state.progression.quests.thieves.rank += 1;
                         state.progression.quests.thieves.promotion_flags |=
                             1u64 << state.progression.quests.thieves.rank.min(63);
@@ -8244,11 +11194,6 @@ fn apply_site_interaction_choice(
                         if state.progression.quests.thieves.rank >= 3 {
                             state.progression.main_quest.completion_flags |= 0x0010;
                         }
-                        events.push(Event::EconomyUpdated {
-                            source: "thieves_guild".to_string(),
-                            gold: state.gold,
-                            bank_gold: state.bank_gold,
-                        });
                         format!(
                             "Thieves guild promotion granted (rank {}).",
                             state.progression.quests.thieves.rank
@@ -8264,8 +11209,7 @@ fn apply_site_interaction_choice(
         },
         SiteInteractionKind::Temple => match choice {
             1 => {
-                if state.gold >= 15 {
-                    state.gold -= 15;
+                if state.spend_gold(15, "temple", events) {
                     state.progression.deity_favor += 4;
                     state.progression.priest_rank = state.progression.priest_rank.max(1);
                     state.progression.quests.temple.rank = state
@@ -8277,11 +11221,6 @@ fn apply_site_interaction_choice(
                     state.progression.quests.temple.xp =
                         state.progression.quests.temple.xp.saturating_add(20);
                     state.progression.main_quest.law_path = true;
-                    events.push(Event::EconomyUpdated {
-                        source: "temple".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     events.push(Event::ProgressionUpdated {
                         guild_rank: state.progression.guild_rank,
                         priest_rank: state.progression.priest_rank,
@@ -8297,38 +11236,27 @@ fn apply_site_interaction_choice(
                 talk_note
             }
             3 => {
-                if state.gold >= 35 {
-                    if state.progression.deity_favor < 3 {
-                        "Blessing denied: insufficient favor.".to_string()
-                    } else {
-                        state.gold -= 35;
-                        state.progression.deity_favor =
-                            state.progression.deity_favor.saturating_sub(3);
-                        state.player.stats.hp =
-                            (state.player.stats.hp + 6).min(state.player.stats.max_hp);
-                        state.spellbook.mana =
-                            (state.spellbook.mana + 12).min(state.spellbook.max_mana);
-                        state.progression.priest_rank = state.progression.priest_rank.max(1);
-                        state.progression.quests.temple.rank = state
-                            .progression
-                            .quests
-                            .temple
-                            .rank
-                            .max(i16::from(state.progression.priest_rank));
-                        state.progression.quests.temple.xp =
-                            state.progression.quests.temple.xp.saturating_add(45);
-                        events.push(Event::EconomyUpdated {
-                            source: "temple".to_string(),
-                            gold: state.gold,
-                            bank_gold: state.bank_gold,
-                        });
-                        events.push(Event::ProgressionUpdated {
-                            guild_rank: state.progression.guild_rank,
-                            priest_rank: state.progression.priest_rank,
-                            alignment: state.progression.alignment,
-                        });
-                        "Temple blessing restored health and mana.".to_string()
-                    }
+                if state.progression.deity_favor < 3 {
+                    "Blessing denied: insufficient favor.".to_string()
+                } else if state.spend_gold(35, "temple", events) {
+                    state.progression.deity_favor = state.progression.deity_favor.saturating_sub(3);
+                    state.player.stats.heal(6);
+                    state.spellbook.restore_mana(12);
+                    state.progression.priest_rank = state.progression.priest_rank.max(1);
+                    state.progression.quests.temple.rank = state
+                        .progression
+                        .quests
+                        .temple
+                        .rank
+                        .max(i16::from(state.progression.priest_rank));
+                    state.progression.quests.temple.xp =
+                        state.progression.quests.temple.xp.saturating_add(45);
+                    events.push(Event::ProgressionUpdated {
+                        guild_rank: state.progression.guild_rank,
+                        priest_rank: state.progression.priest_rank,
+                        alignment: state.progression.alignment,
+                    });
+                    "Temple blessing restored health and mana.".to_string()
                 } else {
                     "Not enough gold for blessing.".to_string()
                 }
@@ -8337,7 +11265,7 @@ fn apply_site_interaction_choice(
                 let had_poison = state.status_effects.iter().any(|effect| effect.id == "poison");
                 state.status_effects.retain(|effect| effect.id != "poison");
                 state.legal_heat = state.legal_heat.saturating_sub(1);
-                state.player.stats.hp = (state.player.stats.hp + 2).min(state.player.stats.max_hp);
+                state.player.stats.heal(2);
                 state.progression.quests.temple.quest_flags |= 0x0002;
                 if had_poison {
                     "Temple sanctuary cleansed poison and calmed legal trouble.".to_string()
@@ -8357,8 +11285,7 @@ fn apply_site_interaction_choice(
                     && state.progression.quests.college.rank <= 0
                 {
                     "The collegium refuses chaotic applicants without sponsorship.".to_string()
-                } else if state.gold >= 25 {
-                    state.gold -= 25;
+                } else if state.spend_gold(25, "college", events) {
                     state.spellbook.max_mana = (state.spellbook.max_mana + 5).min(300);
                     state.spellbook.mana = state.spellbook.max_mana;
                     state.progression.quests.college.rank =
@@ -8368,11 +11295,6 @@ fn apply_site_interaction_choice(
                     state.progression.quests.college.dues_paid =
                         state.progression.quests.college.dues_paid.saturating_add(25);
                     state.progression.quests.college.quest_flags |= 0x0001;
-                    events.push(Event::EconomyUpdated {
-                        source: "college".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     events.push(Event::ProgressionUpdated {
                         guild_rank: state.progression.guild_rank,
                         priest_rank: state.progression.priest_rank,
@@ -8386,12 +11308,10 @@ fn apply_site_interaction_choice(
             2 => {
                 if state.progression.quests.college.rank <= 0 {
                     "Only enrolled collegium members can request advanced instruction.".to_string()
-                } else if state.gold >= 40 {
-                    state.gold -= 40;
+                } else if state.spend_gold(40, "college", events) {
                     let learned =
                         teach_first_unknown_from_pool(state, &[12, 3, 2, 11, 16, 30, 36, 21, 40]);
-                    state.spellbook.mana =
-                        (state.spellbook.mana + 15).min(state.spellbook.max_mana);
+                    state.spellbook.restore_mana(15);
                     state.progression.quests.college.rank =
                         state.progression.quests.college.rank.max(2);
                     state.progression.quests.college.xp =
@@ -8399,16 +11319,14 @@ fn apply_site_interaction_choice(
                     state.progression.quests.college.quest_flags |= 0x0002;
                     if state.progression.quests.college.rank < 3
                         && state.progression.quests.college.xp >= 150
+                        && state.progression.quests.college.promotion_flags
+                            & GUILD_FLAG_DUES_ARREARS
+                            == 0
                     {
                         state.progression.quests.college.rank += 1;
                         state.progression.quests.college.promotion_flags |=
                             1 << state.progression.quests.college.rank.min(63);
                     }
-                    events.push(Event::EconomyUpdated {
-                        source: "college".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     if let Some(spell_id) = learned {
                         format!("Learned advanced spellcraft: {}.", spell_name_by_id(spell_id))
                     } else {
@@ -8419,17 +11337,11 @@ fn apply_site_interaction_choice(
                 }
             }
             3 => {
-                if state.gold < 30 {
-                    "Not enough gold for identification.".to_string()
-                } else if state.player.inventory.is_empty() {
+                if state.player.inventory.is_empty() {
                     "No item available for identification.".to_string()
+                } else if !state.spend_gold(30, "college", events) {
+                    "Not enough gold for identification.".to_string()
                 } else {
-                    state.gold -= 30;
-                    events.push(Event::EconomyUpdated {
-                        source: "college".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     state.progression.quests.college.quest_flags |= 0x0004;
                     if let Some(item) = state.player.inventory.first_mut() {
                         if !item.name.starts_with("identified ") {
@@ -8457,28 +11369,17 @@ fn apply_site_interaction_choice(
                 {
                     "The circle denies initiation to lawful petitioners without black sponsorship."
                         .to_string()
-                } else if state.gold >= 30 {
-                    state.gold -= 30;
-                    state.spellbook.mana =
-                        (state.spellbook.mana + 20).min(state.spellbook.max_mana);
-                    state.progression.quests.sorcerors.rank =
-                        state.progression.quests.sorcerors.rank.max(1);
-                    state.progression.quests.sorcerors.xp =
-                        state.progression.quests.sorcerors.xp.saturating_add(30);
-                    state.progression.quests.sorcerors.dues_paid =
-                        state.progression.quests.sorcerors.dues_paid.saturating_add(30);
-                    state.progression.quests.sorcerors.quest_flags |= 0x0001;
-                    events.push(Event::EconomyUpdated {
-                        source: "sorcerors".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
-                    events.push(Event::ProgressionUpdated {
-                        guild_rank: state.progression.guild_rank,
-                        priest_rank: state.progression.priest_rank,
-                        alignment: state.progression.alignment,
-                    });
-                    "Arcane recharge completed.".to_string()
+                } else if state.gold >= SORCERORS_RECHARGE_FEE {
+                    let (note, _modeled) = begin_item_prompt(
+                        state,
+                        ItemPromptContext::Recharge,
+                        ItemPromptFilter::Families(vec![ItemFamily::Stick]),
+                        "Recharge which stick?".to_string(),
+                    );
+                    if state.pending_item_prompt.is_some() {
+                        keep_open = false;
+                    }
+                    note
                 } else {
                     "Not enough gold for recharge.".to_string()
                 }
@@ -8486,8 +11387,7 @@ fn apply_site_interaction_choice(
             2 => {
                 if state.progression.quests.sorcerors.rank <= 0 {
                     "Only initiated sorcerors can study deep lore.".to_string()
-                } else if state.gold >= 50 {
-                    state.gold -= 50;
+                } else if state.spend_gold(50, "sorcerors", events) {
                     let learned =
                         teach_first_unknown_from_pool(state, &[31, 32, 33, 34, 37, 38, 39, 15, 40]);
                     state.progression.quests.sorcerors.rank =
@@ -8498,11 +11398,6 @@ fn apply_site_interaction_choice(
                     if state.progression.quests.sorcerors.xp >= 180 {
                         state.progression.quests.sorcerors.promotion_flags |= 1 << 2;
                     }
-                    events.push(Event::EconomyUpdated {
-                        source: "sorcerors".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     if let Some(spell_id) = learned {
                         format!("Deep lore unlocked: {}.", spell_name_by_id(spell_id))
                     } else {
@@ -8515,24 +11410,23 @@ fn apply_site_interaction_choice(
             3 => {
                 if state.progression.quests.sorcerors.rank <= 0 {
                     "Initiation is required before transmutation rites.".to_string()
-                } else if state.gold >= 45 {
-                    state.gold -= 45;
-                    let result = add_item_to_inventory_or_ground(state, "charged stick", events);
-                    state.spellbook.mana = (state.spellbook.mana + 5).min(state.spellbook.max_mana);
-                    state.progression.quests.sorcerors.quest_flags |= 0x0004;
-                    state.progression.quests.sorcerors.xp =
-                        state.progression.quests.sorcerors.xp.saturating_add(35);
-                    if state.progression.alignment == Alignment::Lawful {
-                        state.progression.law_chaos_score -= 1;
+                } else if state.gold >= SORCERORS_ENCHANT_FEE {
+                    let (note, _modeled) = begin_item_prompt(
+                        state,
+                        ItemPromptContext::Enchant,
+                        ItemPromptFilter::Families(vec![
+                            ItemFamily::Weapon,
+                            ItemFamily::Armor,
+                            ItemFamily::Shield,
+                        ]),
+                        "Enchant which item?".to_string(),
+                    );
+                    if state.pending_item_prompt.is_some() {
+                        keep_open = false;
                     }
-                    events.push(Event::EconomyUpdated {
-                        source: "sorcerors".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
-                    format!("Sorcerors transmuted a focus ({result}).")
+                    note
                 } else {
-                    "Not enough gold for transmutation.".to_string()
+                    "Not enough gold for enchantment.".to_string()
                 }
             }
             4 => {
@@ -8546,14 +11440,10 @@ fn apply_site_interaction_choice(
                 if state.legal_heat > 0 {
                     let fine = (state.legal_heat * 3).max(5);
                     let paid = fine.min(state.gold.max(0));
-                    state.gold -= paid;
                     state.legal_heat = state.legal_heat.saturating_sub(2);
                     state.progression.quests.castle.quest_flags |= 0x0001;
-                    events.push(Event::EconomyUpdated {
-                        source: "castle".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
+                    // `paid` never exceeds the purse.
+                    let _ = state.spend_gold(paid, "castle", events);
                     format!("Paid {paid} gold in fines.")
                 } else {
                     "No legal fines pending.".to_string()
@@ -8575,7 +11465,6 @@ fn apply_site_interaction_choice(
                     state.progression.quests.castle.rank =
                         state.progression.quests.castle.rank.max(2);
                     state.progression.main_quest.completion_flags |= 0x0002;
-                    state.gold += 120;
                     events.push(Event::QuestAdvanced {
                         state: state.progression.quest_state,
                         steps_completed: state.progression.quest_steps_completed,
@@ -8585,11 +11474,7 @@ fn apply_site_interaction_choice(
                         priest_rank: state.progression.priest_rank,
                         alignment: state.progression.alignment,
                     });
-                    events.push(Event::EconomyUpdated {
-                        source: "castle".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
+                    state.gain_gold(120, "castle", events);
                     "Royal petition accepted; return to your patron for final rites.".to_string()
                 }
                 LegacyQuestState::ReturnToPatron => {
@@ -8600,30 +11485,20 @@ fn apply_site_interaction_choice(
                         state.progression.total_winner_unlocked = true;
                         state.progression.main_quest.completion_flags |= 0x0004;
                         state.progression.score += 500;
-                        state.gold += 200;
                         state.progression.quests.castle.rank =
                             state.progression.quests.castle.rank.max(4);
                         events.push(Event::QuestAdvanced {
                             state: state.progression.quest_state,
                             steps_completed: state.progression.quest_steps_completed,
                         });
-                        events.push(Event::EconomyUpdated {
-                            source: "castle".to_string(),
-                            gold: state.gold,
-                            bank_gold: state.bank_gold,
-                        });
+                        state.gain_gold(200, "castle", events);
                         "Royal charter granted. Quest line completed.".to_string()
                     } else {
                         "Petition deferred: gain rank with guild and temple first.".to_string()
                     }
                 }
                 LegacyQuestState::Completed => {
-                    state.gold += 60;
-                    events.push(Event::EconomyUpdated {
-                        source: "castle".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
+                    state.gain_gold(60, "castle", events);
                     "The crown recognizes prior service with a stipend.".to_string()
                 }
                 _ => "No active royal petition is available.".to_string(),
@@ -8657,16 +11532,11 @@ fn apply_site_interaction_choice(
                     state.progression.quest_steps_completed =
                         state.progression.quest_steps_completed.max(3);
                     state.progression.quests.palace.quest_flags |= 0x0001;
-                    state.gold += 160;
                     events.push(Event::QuestAdvanced {
                         state: state.progression.quest_state,
                         steps_completed: state.progression.quest_steps_completed,
                     });
-                    events.push(Event::EconomyUpdated {
-                        source: "palace".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
+                    state.gain_gold(160, "palace", events);
                     "Palace petition accepted. Return to your patron for final investiture."
                         .to_string()
                 } else if state.progression.main_quest.stage == LegacyQuestState::ReturnToPatron
@@ -8680,16 +11550,11 @@ fn apply_site_interaction_choice(
                     state.progression.quests.palace.rank =
                         state.progression.quests.palace.rank.max(4);
                     state.progression.score += 800;
-                    state.gold += 240;
                     events.push(Event::QuestAdvanced {
                         state: state.progression.quest_state,
                         steps_completed: state.progression.quest_steps_completed.max(4),
                     });
-                    events.push(Event::EconomyUpdated {
-                        source: "palace".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
+                    state.gain_gold(240, "palace", events);
                     "Royal investiture complete. Your palace quest line is fulfilled.".to_string()
                 } else {
                     "The palace petition is deferred pending greater deeds.".to_string()
@@ -8722,26 +11587,20 @@ fn apply_site_interaction_choice(
                 }
             }
             2 => {
-                if state.gold >= 25 {
-                    state.gold -= 25;
-                    state.legal_heat = state.legal_heat.saturating_sub(2);
+                if state.spend_gold(25, "order", events) {
+                    let rite = perform_absolution_rite(state, rng);
                     if state.progression.alignment == Alignment::Chaotic {
                         state.progression.alignment = Alignment::Neutral;
                     }
                     state.progression.law_chaos_score =
                         state.progression.law_chaos_score.max(-1) + 2;
                     state.progression.quests.order.quest_flags |= 0x0001;
-                    events.push(Event::EconomyUpdated {
-                        source: "order".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     events.push(Event::ProgressionUpdated {
                         guild_rank: state.progression.guild_rank,
                         priest_rank: state.progression.priest_rank,
                         alignment: state.progression.alignment,
                     });
-                    "Order absolution reduced your legal burden.".to_string()
+                    format!("Order absolution reduced your legal burden. {rite}")
                 } else {
                     "Not enough gold for absolution rites.".to_string()
                 }
@@ -8758,7 +11617,7 @@ fn apply_site_interaction_choice(
         },
         SiteInteractionKind::Charity => match choice {
             1 => {
-                state.player.stats.hp = (state.player.stats.hp + 4).min(state.player.stats.max_hp);
+                state.player.stats.heal(4);
                 state.food += 1;
                 state.progression.quests.charity.rank =
                     state.progression.quests.charity.rank.max(1);
@@ -8804,14 +11663,13 @@ fn apply_site_interaction_choice(
         },
         SiteInteractionKind::Monastery => match choice {
             1 => {
-                if state.gold < 20 {
-                    "Not enough gold for monastery meditation.".to_string()
-                } else if state.progression.alignment == Alignment::Chaotic
+                if state.progression.alignment == Alignment::Chaotic
                     && state.progression.quests.monastery.rank <= 1
                 {
                     "The masters deny meditation until you complete an atonement vow.".to_string()
+                } else if !state.spend_gold(20, "monastery", events) {
+                    "Not enough gold for monastery meditation.".to_string()
                 } else {
-                    state.gold -= 20;
                     state.progression.quests.monastery.rank =
                         state.progression.quests.monastery.rank.max(1);
                     state.progression.quests.monastery.xp =
@@ -8830,8 +11688,7 @@ fn apply_site_interaction_choice(
                     }
                     state.spellbook.max_mana = (state.spellbook.max_mana + 2).min(320);
                     state.spellbook.mana = state.spellbook.max_mana;
-                    state.player.stats.hp =
-                        (state.player.stats.hp + 3).min(state.player.stats.max_hp);
+                    state.player.stats.heal(3);
                     if state.legal_heat > 0 {
                         state.legal_heat = state.legal_heat.saturating_sub(1);
                         state.progression.quests.monastery.quest_flags |= 0x0002;
@@ -8847,11 +11704,6 @@ fn apply_site_interaction_choice(
                         state.progression.quests.monastery.rank = 2;
                         state.progression.quests.monastery.promotion_flags |= 1 << 2;
                     }
-                    events.push(Event::EconomyUpdated {
-                        source: "monastery".to_string(),
-                        gold: state.gold,
-                        bank_gold: state.bank_gold,
-                    });
                     events.push(Event::ProgressionUpdated {
                         guild_rank: state.progression.guild_rank,
                         priest_rank: state.progression.priest_rank,
@@ -8920,9 +11772,8 @@ fn apply_site_interaction_choice(
                 }
                 if state.progression.quests.monastery.rank >= 2
                     && state.progression.deity_favor >= 5
-                    && state.gold >= 10
+                    && state.spend_gold(10, "monastery", events)
                 {
-                    state.gold -= 10;
                     state.spellbook.max_mana = (state.spellbook.max_mana + 1).min(340);
                     state.spellbook.mana = state.spellbook.max_mana;
                     state.progression.quests.monastery.quest_flags |= 0x0080;
@@ -8932,11 +11783,6 @@ fn apply_site_interaction_choice(
                     priest_rank: state.progression.priest_rank,
                     alignment: state.progression.alignment,
                 });
-                events.push(Event::EconomyUpdated {
-                    source: "monastery".to_string(),
-                    gold: state.gold,
-                    bank_gold: state.bank_gold,
-                });
                 if before == Alignment::Lawful {
                     "You renew your discipline before the masters.".to_string()
                 } else {
@@ -8966,6 +11812,7 @@ fn apply_site_interaction_choice(
                         keep_open = false;
                         "You decline the arena challenge.".to_string()
                     }
+                    3 => place_arena_wager(state, events),
                     _ => "Invalid arena choice.".to_string(),
                 }
             } else {
@@ -8995,6 +11842,7 @@ fn apply_site_interaction_choice(
                         keep_open = false;
                         "You leave the Coliseum.".to_string()
                     }
+                    4 => place_arena_wager(state, events),
                     _ => "Invalid arena choice.".to_string(),
                 }
             }
@@ -9032,7 +11880,11 @@ fn apply_site_interaction_choice(
     note
 }
 
-fn trigger_step_site_interaction(state: &mut GameState, events: &mut Vec<Event>) {
+fn trigger_step_site_interaction<R: RandomSource>(
+    state: &mut GameState,
+    events: &mut Vec<Event>,
+    rng: &mut R,
+) {
     if state.world_mode != WorldMode::DungeonCity {
         return;
     }
@@ -9044,6 +11896,12 @@ fn trigger_step_site_interaction(state: &mut GameState, events: &mut Vec<Event>)
         return;
     }
 
+    if let Some(note) = closed_site_notice(state, site_aux) {
+        push_timeline_line(state, note.clone());
+        events.push(Event::LegacyHandled { token: "step".to_string(), note, fully_modeled: true });
+        return;
+    }
+
     if state.options.interactive_sites
         && let Some(kind) = interaction_kind_for_site_aux(state, site_aux)
     {
@@ -9051,7 +11909,7 @@ fn trigger_step_site_interaction(state: &mut GameState, events: &mut Vec<Event>)
         return;
     }
 
-    if let Some(note) = apply_site_service(state, site_aux, events) {
+    if let Some(note) = apply_site_service(state, site_aux, rng, events) {
         let class = classify_note_against_active_interactions(state, &note);
         push_ui_log(state, class, note.clone());
         events.push(Event::LegacyHandled { token: "step".to_string(), note, fully_modeled: true });
@@ -9062,7 +11920,7 @@ fn trigger_step_site_interaction(state: &mut GameState, events: &mut Vec<Event>)
         || site_aux == SITE_AUX_EXIT_ARENA
         || open_arena_gateway
     {
-        let (note, _) = resolve_enter_local_site(state, events);
+        let (note, _) = resolve_enter_local_site(state, events, rng);
         let class = classify_note_against_active_interactions(state, &note);
         push_ui_log(state, class, note.clone());
         events.push(Event::LegacyHandled { token: "step".to_string(), note, fully_modeled: true });
@@ -9079,6 +11937,9 @@ fn open_door_at(state: &mut GameState, pos: Position) -> bool {
     }
 
     let mut flags = state.tile_site_at(pos).map(|cell| cell.flags).unwrap_or(0);
+    if (flags & TILE_FLAG_LOCKED_DOOR) != 0 {
+        return false;
+    }
     let _ = state.set_map_glyph_at(pos, '/');
     flags &= !TILE_FLAG_BLOCK_MOVE;
     flags |= TILE_FLAG_OPENED_DOOR;
@@ -9086,6 +11947,62 @@ fn open_door_at(state: &mut GameState, pos: Position) -> bool {
     true
 }
 
+const BASH_DIFFICULTY: i32 = 15;
+const BASH_NOISE_RADIUS: i32 = 8;
+
+fn resolve_bash_command<R: RandomSource>(
+    state: &mut GameState,
+    direction: Direction,
+    rng: &mut R,
+    events: &mut Vec<Event>,
+) {
+    let target = state.player.position.offset(direction);
+    if state.monsters.iter().any(|monster| monster.position == target) {
+        resolve_attack_command(state, direction, rng, events);
+        return;
+    }
+    if !state.bounds.contains(target) {
+        state.log.push("There is nothing there to bash.".to_string());
+        events.push(Event::BashResult { target, success: false });
+        return;
+    }
+
+    let glyph = state.map_glyph_at(target);
+    let flags = state.tile_site_at(target).map(|cell| cell.flags).unwrap_or(0);
+    let closed_door =
+        matches!(glyph, '-' | 'D' | 'J') || (glyph == '+' && (flags & TILE_FLAG_BLOCK_MOVE) != 0);
+    let container_index = container_index_at(state, target);
+    if !closed_door && container_index.is_none() {
+        state.log.push("You kick at empty air.".to_string());
+        events.push(Event::BashResult { target, success: false });
+        return;
+    }
+
+    let roll = rng.range_inclusive_i32(1, 20) + statmod(state.attributes.strength);
+    let success = roll >= BASH_DIFFICULTY;
+    if success {
+        if closed_door {
+            set_site_flags_at(
+                state,
+                target,
+                flags & !(TILE_FLAG_BLOCK_MOVE | TILE_FLAG_OPENED_DOOR | TILE_FLAG_LOCKED_DOOR),
+            );
+            set_site_glyph_at(state, target, '.');
+            let _ = state.set_map_glyph_at(target, '.');
+            state.log.push("You smash the door to splinters!".to_string());
+        } else if let Some(index) = container_index {
+            state.containers[index].locked = false;
+            state.log.push("You smash the lock off the chest.".to_string());
+        }
+    } else {
+        let damage = rng.range_inclusive_i32(1, 3);
+        state.player.stats.set_hp((state.player.stats.hp - damage).max(1));
+        state.log.push(format!("Ouch! You hurt your foot ({damage} damage)."));
+    }
+    make_noise(state, target, BASH_NOISE_RADIUS);
+    events.push(Event::BashResult { target, success });
+}
+
 fn try_bump_interaction_on_blocked_move<R: RandomSource>(
     state: &mut GameState,
     from: Position,
@@ -9112,6 +12029,204 @@ fn try_bump_interaction_on_blocked_move<R: RandomSource>(
     true
 }
 
+fn resolve_move_command<R: RandomSource>(
+    state: &mut GameState,
+    direction: Direction,
+    rng: &mut R,
+    events: &mut Vec<Event>,
+    bonus_minutes: &mut u64,
+) {
+    let from = state.player.position;
+    let move_direction = apply_lost_navigation_direction(state, direction, rng, events);
+    let target = from.offset(move_direction);
+    if try_bump_attack_on_move(state, move_direction, rng, events) {
+        // Legacy parity: walking into an occupied tile resolves melee instead of block.
+    } else {
+        let burden_limit = (effective_inventory_capacity(state) as i32) * 12;
+        let overburdened = state.carry_burden > burden_limit;
+        if overburdened {
+            state.log.push("You are too burdened to move.".to_string());
+            events.push(Event::MoveBlocked { target: from });
+        } else if state.tile_is_water(target) && !state.player_can_cross_water() {
+            state.log.push("You are too heavily laden to swim.".to_string());
+            events.push(Event::MoveBlocked { target });
+        } else if !state.tile_is_walkable(target) {
+            if !try_bump_interaction_on_blocked_move(
+                state,
+                from,
+                target,
+                rng,
+                events,
+                bonus_minutes,
+            ) {
+                state.log.push("Movement blocked.".to_string());
+                events.push(Event::MoveBlocked { target });
+            }
+        } else if notice_trap_before_stepping(state, target, rng) {
+            events.push(Event::MoveBlocked { target });
+        } else {
+            if let Some(companion) =
+                state.companions.iter_mut().find(|companion| companion.position == target)
+            {
+                companion.position = from;
+            }
+            state.player.position = target;
+            let moved = Event::Moved { from, to: target };
+            state.log.push(state.format_event(&moved));
+            events.push(moved);
+            apply_post_move_effects(state, rng, events, bonus_minutes);
+        }
+    }
+}
+
+const TRAP_NOTICE_TARGET: i32 = 18;
+const LIT_TILE_NOTICE_BONUS: i32 = 4;
+
+/// Gives the player one perception roll against a hidden armed trap on
+/// `target`, easier on a lit tile; a success reveals it and the step is refused.
+fn notice_trap_before_stepping<R: RandomSource>(
+    state: &mut GameState,
+    target: Position,
+    rng: &mut R,
+) -> bool {
+    if player_is_levitating(state) {
+        return false;
+    }
+    let lighting = if state.tile_is_lit(target) { LIT_TILE_NOTICE_BONUS } else { 0 };
+    let perception = statmod(state.attributes.iq)
+        + i32::from(state.progression.quests.thieves.rank.max(0))
+        + lighting;
+    let Some(trap) = state
+        .traps
+        .iter_mut()
+        .find(|trap| trap.armed && !trap.discovered && trap.position == target)
+    else {
+        return false;
+    };
+    if rng.range_inclusive_i32(1, 20) + perception < TRAP_NOTICE_TARGET {
+        return false;
+    }
+    trap.discovered = true;
+    let effect = trap.effect_id.clone();
+    state.log.push(format!("You notice a {effect} trap just in time and stop short."));
+    true
+}
+
+const AUTO_EXPLORE_SIGHT_RADIUS: i32 = 5;
+const ENGRAVING_MAX_LEN: usize = 60;
+const ENGRAVING_LIFETIME_TURNS: u64 = 500;
+
+fn engrave_under_player(state: &mut GameState, text: &str) -> String {
+    let text: String = text.trim().chars().take(ENGRAVING_MAX_LEN).collect();
+    if text.is_empty() {
+        return "You write in the dust with your finger, but leave nothing legible.".to_string();
+    }
+    let note = format!("You engrave \"{text}\" into the floor.");
+    state.engrave_at(state.player.position, text);
+    note
+}
+
+fn resolve_auto_explore<R: RandomSource>(
+    state: &mut GameState,
+    rng: &mut R,
+    events: &mut Vec<Event>,
+    bonus_minutes: &mut u64,
+) {
+    if state.world_mode != WorldMode::DungeonCity {
+        state.log.push("There is nothing to explore out here.".to_string());
+        events.push(Event::AutoExploreDone);
+        return;
+    }
+    reveal_tiles_around_player(state);
+    let origin = state.player.position;
+    if let Some(monster) = state.monsters.iter().find(|monster| {
+        monster.stats.hp > 0
+            && projectile_distance(monster.position, origin) <= AUTO_EXPLORE_SIGHT_RADIUS
+            && monster.is_hostile_to_player(state)
+            && line_of_sight(state, origin, monster.position)
+    }) {
+        state.log.push(format!("You spot a {} and stop exploring.", monster.name));
+        events.push(Event::AutoExploreDone);
+        return;
+    }
+    if state.ground_items.iter().any(|entry| entry.position == origin) {
+        state.log.push("You find something here and stop exploring.".to_string());
+        events.push(Event::AutoExploreDone);
+        return;
+    }
+    let Some(path) = find_path(state, origin, |pos| !state.explored_tiles.contains(&pos)) else {
+        state.log.push("There is nothing left to explore.".to_string());
+        events.push(Event::AutoExploreDone);
+        return;
+    };
+    let Some(direction) = [Direction::North, Direction::South, Direction::East, Direction::West]
+        .into_iter()
+        .find(|direction| origin.offset(*direction) == path[0])
+    else {
+        events.push(Event::AutoExploreDone);
+        return;
+    };
+    resolve_move_command(state, direction, rng, events, bonus_minutes);
+    if state.player.position == origin {
+        events.push(Event::AutoExploreDone);
+        return;
+    }
+    reveal_tiles_around_player(state);
+    events.push(Event::AutoExploreStep { to: state.player.position });
+}
+
+fn reveal_tiles_around_player(state: &mut GameState) {
+    let origin = state.player.position;
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            let pos = Position { x: origin.x + dx, y: origin.y + dy };
+            if state.bounds.contains(pos) && !state.explored_tiles.contains(&pos) {
+                state.explored_tiles.push(pos);
+            }
+        }
+    }
+}
+
+// Breadth-first search over walkable, unoccupied tiles; the returned path excludes `from`.
+fn find_path(
+    state: &GameState,
+    from: Position,
+    is_goal: impl Fn(Position) -> bool,
+) -> Option<Vec<Position>> {
+    let start = tile_index(state.bounds, from)?;
+    let area = usize::try_from(state.bounds.width * state.bounds.height).ok()?;
+    let mut came_from: Vec<Option<Position>> = vec![None; area];
+    came_from[start] = Some(from);
+    let mut queue = VecDeque::from([from]);
+    while let Some(pos) = queue.pop_front() {
+        if pos != from && is_goal(pos) {
+            let mut path = vec![pos];
+            let mut cursor = pos;
+            while let Some(prev) = tile_index(state.bounds, cursor).and_then(|idx| came_from[idx])
+                && prev != from
+            {
+                path.push(prev);
+                cursor = prev;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for direction in [Direction::North, Direction::South, Direction::East, Direction::West] {
+            let next = pos.offset(direction);
+            let Some(idx) = tile_index(state.bounds, next) else {
+                continue;
+            };
+            if came_from[idx].is_some() || !state.tile_is_walkable(next) || is_occupied(state, next)
+            {
+                continue;
+            }
+            came_from[idx] = Some(pos);
+            queue.push_back(next);
+        }
+    }
+    None
+}
+
 fn try_bump_attack_on_move<R: RandomSource>(
     state: &mut GameState,
     direction: Direction,
@@ -9119,8 +12234,15 @@ fn try_bump_attack_on_move<R: RandomSource>(
     events: &mut Vec<Event>,
 ) -> bool {
     let target = state.player.position.offset(direction);
-    if monster_index_at(state, target).is_none() {
+    let Some(idx) = monster_index_at(state, target) else {
         return false;
+    };
+    let monster = &state.monsters[idx];
+    // Legacy parity: bumping still attacks peaceful monsters, but never allies.
+    if monster.allied {
+        state.log.push(format!("You bump into the {}.", monster.name));
+        events.push(Event::MoveBlocked { target });
+        return true;
     }
     resolve_attack_command(state, direction, rng, events);
     true
@@ -9153,11 +12275,22 @@ fn apply_post_move_effects<R: RandomSource>(
     if state.world_mode == WorldMode::Countryside {
         *bonus_minutes = bonus_minutes.saturating_add(apply_countryside_travel(state, rng, events));
     } else {
-        trigger_step_site_interaction(state, events);
+        resolve_chasm_arrival(state, rng, events);
+        if state.status != SessionStatus::InProgress {
+            return;
+        }
+        resolve_water_arrival(state, rng, events, bonus_minutes);
+        reveal_lit_tiles_in_sight(state);
+        trigger_step_site_interaction(state, events, rng);
+        resolve_heist_arrival(state, rng, events);
     }
     if state.options.pickup {
         try_pickup_at_player(state, events);
     }
+    let description = state.describe_current_tile();
+    if description.is_notable() {
+        state.log.push(description.summary());
+    }
     if state.options.belligerent
         && let Some(direction) = adjacent_monster_direction(state)
     {
@@ -9165,52 +12298,294 @@ fn apply_post_move_effects<R: RandomSource>(
     }
 }
 
-fn apply_door_interaction(state: &mut GameState, close: bool) -> String {
-    if state.world_mode != WorldMode::DungeonCity {
-        return "door interaction only works in dungeon/city maps".to_string();
-    }
+pub const WATER_GLYPH: char = '~';
+const WATER_EXTRA_MINUTES: u64 = 10;
+const WATER_ITEM_LOSS_CHANCE: i32 = 25;
 
-    let candidates = [
-        Position { x: state.player.position.x, y: state.player.position.y - 1 },
-        Position { x: state.player.position.x + 1, y: state.player.position.y },
-        Position { x: state.player.position.x, y: state.player.position.y + 1 },
-        Position { x: state.player.position.x - 1, y: state.player.position.y },
-    ];
+fn resolve_water_arrival<R: RandomSource>(
+    state: &mut GameState,
+    rng: &mut R,
+    events: &mut Vec<Event>,
+    bonus_minutes: &mut u64,
+) {
+    if !state.tile_is_water(state.player.position) || player_is_levitating(state) {
+        return;
+    }
+    *bonus_minutes = bonus_minutes.saturating_add(WATER_EXTRA_MINUTES);
+    state.log.push("You swim through the water.".to_string());
 
-    for pos in candidates {
-        if !state.bounds.contains(pos) {
-            continue;
-        }
-        let glyph = state.map_glyph_at(pos);
-        let mut flags = state.tile_site_at(pos).map(|cell| cell.flags).unwrap_or(0);
+    // Only a crammed pack risks losing something to the current.
+    if state.player.inventory.len() < effective_inventory_capacity(state)
+        || rng.range_inclusive_i32(1, 100) > WATER_ITEM_LOSS_CHANCE
+    {
+        return;
+    }
+    let equipped = equipped_item_ids(&state.player.equipment);
+    let Some(item_id) =
+        state.player.pack_order.iter().rev().copied().find(|id| !equipped.contains(id))
+    else {
+        return;
+    };
+    if let Some(item) = remove_inventory_item_by_id(state, item_id) {
+        let swept = Event::ItemSweptAway { item_id: item.id, name: item.name };
+        state.log.push(state.format_event(&swept));
+        events.push(swept);
+    }
+}
 
-        if close {
-            let is_open_door = glyph == '/' || glyph == '|' || (flags & TILE_FLAG_OPENED_DOOR) != 0;
-            if !is_open_door {
-                continue;
-            }
-            let _ = state.set_map_glyph_at(pos, '-');
-            flags |= TILE_FLAG_BLOCK_MOVE;
-            flags &= !TILE_FLAG_OPENED_DOOR;
-            set_site_flags_at(state, pos, flags);
-            return format!("closed door at ({}, {})", pos.x, pos.y);
-        }
+fn resolve_chasm_arrival<R: RandomSource>(
+    state: &mut GameState,
+    rng: &mut R,
+    events: &mut Vec<Event>,
+) {
+    if state.map_glyph_at(state.player.position) != CHASM_GLYPH {
+        return;
+    }
+    if player_is_levitating(state) {
+        state.log.push("You float serenely over the chasm.".to_string());
+        return;
+    }
+    if state.player.inventory.iter().any(|item| item.usef == ROPE_USEF) {
+        state.log.push("You rope your way safely across the chasm.".to_string());
+        return;
+    }
 
-        let is_closed_door = glyph == '-' || glyph == 'D' || glyph == 'J';
-        if !is_closed_door {
-            continue;
+    let damage = rng.range_inclusive_i32(1, 6) + rng.range_inclusive_i32(1, 6);
+    let applied = state.player.stats.apply_damage(damage);
+    let depth = descend_one_level(state, rng);
+    state
+        .log
+        .push(format!("You plunge into the chasm and land on level {depth} for {applied} damage."));
+    if !state.player.stats.is_alive() {
+        mark_player_defeated(state, "a fall into a chasm", events);
+    }
+}
+
+fn apply_door_interaction(state: &mut GameState, pos: Position, close: bool) -> String {
+    if state.world_mode != WorldMode::DungeonCity {
+        return "door interaction only works in dungeon/city maps".to_string();
+    }
+    if !state.bounds.contains(pos) {
+        return "There's no door there.".to_string();
+    }
+
+    let glyph = state.map_glyph_at(pos);
+    let mut flags = state.tile_site_at(pos).map(|cell| cell.flags).unwrap_or(0);
+
+    if close {
+        let is_open_door = glyph == '/' || glyph == '|' || (flags & TILE_FLAG_OPENED_DOOR) != 0;
+        if !is_open_door {
+            return "There's no open door there.".to_string();
         }
-        let _ = state.set_map_glyph_at(pos, '/');
-        flags &= !TILE_FLAG_BLOCK_MOVE;
-        flags |= TILE_FLAG_OPENED_DOOR;
+        if monster_index_at(state, pos).is_some() {
+            return "Something is standing in the doorway!".to_string();
+        }
+        let _ = state.set_map_glyph_at(pos, '-');
+        flags |= TILE_FLAG_BLOCK_MOVE;
+        flags &= !TILE_FLAG_OPENED_DOOR;
         set_site_flags_at(state, pos, flags);
-        return format!("opened door at ({}, {})", pos.x, pos.y);
+        return format!("closed door at ({}, {})", pos.x, pos.y);
     }
 
-    if close {
-        "no adjacent open door to close".to_string()
+    let is_closed_door = glyph == '-' || glyph == 'D' || glyph == 'J';
+    if !is_closed_door {
+        return "There's no closed door there.".to_string();
+    }
+    if (flags & TILE_FLAG_LOCKED_DOOR) != 0 {
+        return "The door is locked.".to_string();
+    }
+    let _ = state.set_map_glyph_at(pos, '/');
+    flags &= !TILE_FLAG_BLOCK_MOVE;
+    flags |= TILE_FLAG_OPENED_DOOR;
+    set_site_flags_at(state, pos, flags);
+    format!("opened door at ({}, {})", pos.x, pos.y)
+}
+
+const CONTAINER_TRAP_DAMAGE: i32 = 4;
+
+fn container_index_at(state: &GameState, pos: Position) -> Option<usize> {
+    state.containers.iter().position(|container| container.position == pos)
+}
+
+// The catalog magic key, or any item actually named a key ("brass key", not "whiskey").
+fn player_has_key(state: &GameState) -> bool {
+    state.player.inventory.iter().any(|item| {
+        item.usef == "I_KEY"
+            || item
+                .name
+                .split(|c: char| !c.is_alphanumeric())
+                .any(|word| word.eq_ignore_ascii_case("key"))
+    })
+}
+
+fn open_container_at(state: &mut GameState, pos: Position, events: &mut Vec<Event>) -> String {
+    let Some(index) = container_index_at(state, pos) else {
+        return "There's no container there.".to_string();
+    };
+    if state.containers[index].locked {
+        if !player_has_key(state) {
+            return "The chest is locked.".to_string();
+        }
+        state.containers[index].locked = false;
+        state.log.push("You unlock the chest.".to_string());
+    }
+    if state.containers[index].trapped {
+        state.containers[index].trapped = false;
+        let applied = state.player.stats.apply_damage(CONTAINER_TRAP_DAMAGE);
+        state.log.push(format!("A needle trap in the chest triggers for {applied} damage."));
+        events.push(Event::LegacyHandled {
+            token: "trap".to_string(),
+            note: format!("chest trap at ({}, {}) triggered", pos.x, pos.y),
+            fully_modeled: true,
+        });
+        if !state.player.stats.is_alive() {
+            mark_player_defeated(state, "chest trap".to_string(), events);
+            return "The chest trap was fatal.".to_string();
+        }
+    }
+    let contents = std::mem::take(&mut state.containers[index].contents);
+    if contents.is_empty() {
+        return "The chest is empty.".to_string();
+    }
+    let count = contents.len();
+    state.ground_items.extend(contents.into_iter().map(|item| GroundItem { position: pos, item }));
+    format!("opened chest at ({}, {}); {count} item(s) inside", pos.x, pos.y)
+}
+
+const LOCKPICK_DIFFICULTY: i32 = 15;
+const LOCKPICK_BREAK_CHANCE: i32 = 25;
+
+fn is_lockpick_item(item: &Item) -> bool {
+    item.usef == "I_PICK"
+}
+
+fn apply_lockpick<R: RandomSource>(
+    state: &mut GameState,
+    direction: Direction,
+    rng: &mut R,
+) -> String {
+    let Some(pick_id) =
+        state.player.inventory.iter().find(|item| is_lockpick_item(item)).map(|item| item.id)
+    else {
+        return "You have no lockpick.".to_string();
+    };
+    pick_lock_with(state, pick_id, direction, rng)
+}
+
+fn pick_lock_with<R: RandomSource>(
+    state: &mut GameState,
+    pick_id: u32,
+    direction: Direction,
+    rng: &mut R,
+) -> String {
+    let target = state.player.position.offset(direction);
+    let container_index =
+        container_index_at(state, target).filter(|index| state.containers[*index].locked);
+    let door_flags = state
+        .tile_site_at(target)
+        .map(|cell| cell.flags)
+        .filter(|flags| (flags & TILE_FLAG_LOCKED_DOOR) != 0);
+    if container_index.is_none() && door_flags.is_none() {
+        return "There's no lock there to pick.".to_string();
+    }
+
+    let thieves_rank = i32::from(state.progression.quests.thieves.rank.max(0));
+    let roll = rng.range_inclusive_i32(1, 20) + statmod(state.attributes.dexterity) + thieves_rank;
+    if roll < LOCKPICK_DIFFICULTY {
+        if rng.range_inclusive_i32(1, 100) <= LOCKPICK_BREAK_CHANCE {
+            let _ = remove_inventory_item_by_id(state, pick_id);
+            return "You fail to pick the lock, and your lockpick snaps.".to_string();
+        }
+        return "You fail to pick the lock.".to_string();
+    }
+
+    if let Some(index) = container_index {
+        state.containers[index].locked = false;
+        "You pick the lock on the chest.".to_string()
     } else {
-        "no adjacent closed door to open".to_string()
+        let flags = door_flags.unwrap_or(0);
+        set_site_flags_at(state, target, flags & !TILE_FLAG_LOCKED_DOOR);
+        "You pick the lock on the door.".to_string()
+    }
+}
+
+const TORCH_USEF: &str = "I_PERM_ILLUMINATE";
+const TORCH_LIGHT_RADIUS: i32 = 3;
+const ROPE_USEF: &str = "I_ROPE";
+pub const CHASM_GLYPH: char = ':';
+
+fn apply_tool<R: RandomSource>(
+    state: &mut GameState,
+    item_id: u32,
+    target: Option<Direction>,
+    rng: &mut R,
+    events: &mut Vec<Event>,
+) -> String {
+    let Some(item) = state.player.inventory.iter().find(|entry| entry.id == item_id).cloned()
+    else {
+        return "You don't have that item.".to_string();
+    };
+
+    if item.usef == TORCH_USEF {
+        let lit = light_tiles_around(state, state.player.position, TORCH_LIGHT_RADIUS);
+        reveal_lit_tiles_in_sight(state);
+        return format!("The {} flares, lighting {lit} tiles around you.", item.name);
+    }
+    if is_lockpick_item(&item) {
+        let Some(direction) = target else {
+            return "Pick which lock?".to_string();
+        };
+        return pick_lock_with(state, item_id, direction, rng);
+    }
+    if item.usef == ROPE_USEF {
+        let Some(direction) = target else {
+            return "Throw the rope which way?".to_string();
+        };
+        let from = state.player.position;
+        let chasm = from.offset(direction);
+        let landing = chasm.offset(direction);
+        if !state.bounds.contains(chasm) || state.map_glyph_at(chasm) != CHASM_GLYPH {
+            return "There's no chasm there to cross.".to_string();
+        }
+        if !state.tile_is_walkable(landing) || is_occupied(state, landing) {
+            return "Your rope finds no purchase on the far side.".to_string();
+        }
+        state.player.position = landing;
+        events.push(Event::Moved { from, to: landing });
+        return "You swing across the chasm on your rope.".to_string();
+    }
+    "You can't figure out how to use that.".to_string()
+}
+
+fn light_tiles_around(state: &mut GameState, center: Position, radius: i32) -> usize {
+    let mut lit = 0usize;
+    for y in (center.y - radius)..=(center.y + radius) {
+        for x in (center.x - radius)..=(center.x + radius) {
+            let pos = Position { x, y };
+            let Some(flags) = state.tile_site_at(pos).map(|cell| cell.flags) else {
+                continue;
+            };
+            if flags & TILE_FLAG_LIT == 0 {
+                set_site_flags_at(state, pos, flags | TILE_FLAG_LIT);
+                lit += 1;
+            }
+        }
+    }
+    lit
+}
+
+const LIT_TILE_SIGHT_RADIUS: i32 = 8;
+
+// Lit tiles are seen from well beyond the player's immediate surroundings.
+fn reveal_lit_tiles_in_sight(state: &mut GameState) {
+    let origin = state.player.position;
+    for y in (origin.y - LIT_TILE_SIGHT_RADIUS)..=(origin.y + LIT_TILE_SIGHT_RADIUS) {
+        for x in (origin.x - LIT_TILE_SIGHT_RADIUS)..=(origin.x + LIT_TILE_SIGHT_RADIUS) {
+            let pos = Position { x, y };
+            if state.bounds.contains(pos) && state.tile_is_lit(pos) {
+                ensure_known_site(state, pos);
+            }
+        }
     }
 }
 
@@ -9258,21 +12633,7 @@ fn set_site_glyph_at(state: &mut GameState, pos: Position, glyph: char) {
     }
 }
 
-fn monster_is_hostile_to_player(
-    state: &GameState,
-    behavior: MonsterBehavior,
-    faction: Faction,
-) -> bool {
-    match (faction, state.progression.alignment, behavior) {
-        (Faction::Law, Alignment::Lawful, _) => false,
-        (Faction::Chaos, Alignment::Chaotic, _) => false,
-        (Faction::Neutral, _, MonsterBehavior::Social) => false,
-        (Faction::Wild, _, MonsterBehavior::Social) => false,
-        (Faction::Neutral, _, _) => true,
-        (Faction::Wild, _, _) => true,
-        _ => true,
-    }
-}
+const GUARD_HOSTILE_HEAT: i32 = 10;
 
 fn resolve_talk_direction(
     state: &mut GameState,
@@ -9288,7 +12649,7 @@ fn resolve_talk_direction(
     };
 
     let monster = state.monsters[idx].clone();
-    let hostile = monster_is_hostile_to_player(state, monster.behavior, monster.faction);
+    let hostile = monster.is_hostile_to_player(state);
     if hostile {
         return (format!("{} refuses to parley and keeps their distance.", monster.name), true);
     }
@@ -9297,10 +12658,172 @@ fn resolve_talk_direction(
         speaker: monster.name.clone(),
         quest_state: state.progression.quest_state,
     });
+    let interaction = DialogueInteraction {
+        speaker: monster.name.clone(),
+        monster_id: monster.id,
+        node: dialogue_root_node(state, &monster),
+    };
+    state.pending_dialogue = Some(interaction);
     (format!("You talk with {}.", monster.name), true)
 }
 
-fn resolve_tunnel_direction(state: &mut GameState, target: Position) -> (String, bool) {
+fn dialogue_root_node(state: &GameState, monster: &Monster) -> DialogueNode {
+    let lowered = monster.name.to_ascii_lowercase();
+    let mut options = vec![("Heard any rumors?".to_string(), DialogueAction::Rumor)];
+    let text = if lowered.contains("guard") {
+        options.push(("About my record...".to_string(), DialogueAction::AskAboutLaw));
+        "Move along, citizen. Unless you have business with the watch."
+    } else if lowered.contains("merchant") || lowered.contains("peddler") {
+        options.push(("Show me your wares.".to_string(), DialogueAction::Trade));
+        "Fine goods at honest prices!"
+    } else {
+        if state.progression.quest_state == LegacyQuestState::NotStarted {
+            options.push(("Any work for an adventurer?".to_string(), DialogueAction::QuestOffer));
+        }
+        "Well met, traveler."
+    };
+    options.push(("Farewell.".to_string(), DialogueAction::Farewell));
+    DialogueNode { text: text.to_string(), options }
+}
+
+fn legal_fine_for_heat(heat: i32) -> i32 {
+    heat.max(0) * 10
+}
+
+fn apply_dialogue_action(
+    state: &mut GameState,
+    interaction: &DialogueInteraction,
+    action: DialogueAction,
+    events: &mut Vec<Event>,
+) -> (String, Option<DialogueNode>) {
+    let root = |state: &GameState| {
+        state
+            .monsters
+            .iter()
+            .find(|monster| monster.id == interaction.monster_id)
+            .map(|monster| dialogue_root_node(state, monster))
+    };
+    match action {
+        DialogueAction::Rumor => {
+            let rumor = tavern_rumor_line(state);
+            (format!("{} whispers: {rumor}", interaction.speaker), root(state))
+        }
+        DialogueAction::QuestOffer => {
+            if start_main_quest_from_dialogue(state, events) {
+                ("You are pointed toward a task worthy of your talents.".to_string(), None)
+            } else {
+                ("There is no work to offer you right now.".to_string(), root(state))
+            }
+        }
+        DialogueAction::Trade => {
+            let prompt = begin_site_interaction(state, SiteInteractionKind::Shop, events, "talk");
+            (prompt, None)
+        }
+        DialogueAction::AskAboutLaw => {
+            if state.legal_heat <= 0 {
+                return ("Your record is clean. Keep it that way.".to_string(), root(state));
+            }
+            let fine = legal_fine_for_heat(state.legal_heat);
+            let node = DialogueNode {
+                text: format!("You're wanted for questioning. {fine} gold would settle it."),
+                options: vec![
+                    (format!("Pay the {fine} gold fine."), DialogueAction::PayFine),
+                    ("Farewell.".to_string(), DialogueAction::Farewell),
+                ],
+            };
+            ("The guard consults a tattered ledger.".to_string(), Some(node))
+        }
+        DialogueAction::PayFine => {
+            let fine = legal_fine_for_heat(state.legal_heat);
+            if !state.spend_gold(fine, "fine", events) {
+                return ("You can't cover the fine.".to_string(), root(state));
+            }
+            state.legal_heat = 0;
+            ("The guard strikes your name from the ledger.".to_string(), root(state))
+        }
+        DialogueAction::Farewell => {
+            (format!("You take your leave of {}.", interaction.speaker), None)
+        }
+    }
+}
+
+fn resolve_pending_dialogue_interaction(
+    state: &mut GameState,
+    command: &Command,
+    events: &mut Vec<Event>,
+) -> Option<TalkDirectionInteractionResolution> {
+    let interaction = state.pending_dialogue.clone()?;
+    let resolution = TalkDirectionInteractionResolution {
+        freeze_world_progression: true,
+        command_for_accounting: Command::Legacy { token: "F".to_string() },
+        turn_minutes: 0,
+    };
+
+    let action = match parse_wizard_input_token(command) {
+        WizardInputToken::Cancel => Some(DialogueAction::Farewell),
+        WizardInputToken::Text(text) => text
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .and_then(|choice| choice.checked_sub(1))
+            .and_then(|idx| interaction.node.options.get(idx))
+            .map(|(_, action)| *action),
+        _ => None,
+    };
+    let Some(action) = action else {
+        push_timeline_line(state, "Choose a numbered reply.".to_string());
+        return Some(resolution);
+    };
+
+    let (note, next) = apply_dialogue_action(state, &interaction, action, events);
+    events.push(Event::DialogueAdvanced {
+        speaker: interaction.speaker.clone(),
+        quest_state: state.progression.quest_state,
+    });
+    push_timeline_line(state, note);
+    state.pending_dialogue = next.map(|node| DialogueInteraction { node, ..interaction });
+    Some(resolution)
+}
+
+fn tunnel_rock_is_undiggable(state: &GameState) -> bool {
+    matches!(
+        state.environment,
+        LegacyEnvironment::Arena
+            | LegacyEnvironment::Astral
+            | LegacyEnvironment::Abyss
+            | LegacyEnvironment::Circle
+    )
+}
+
+fn tunnel_effort_required(glyph: char) -> i32 {
+    match glyph {
+        '#' | '=' => 100,
+        _ => 40,
+    }
+}
+
+fn tunnel_digging_bonus(state: &GameState) -> i32 {
+    let Some(weapon) = state
+        .player
+        .equipment
+        .weapon_hand
+        .and_then(|item_id| state.player.inventory.iter().find(|entry| entry.id == item_id))
+    else {
+        return 0;
+    };
+    let lowered = weapon.name.to_ascii_lowercase();
+    if ["pick", "mattock", "shovel", "spade"].iter().any(|tool| lowered.contains(tool)) {
+        weapon.dmg.max(1) * 2 + 10
+    } else {
+        weapon.dmg.max(0) / 2
+    }
+}
+
+fn resolve_tunnel_direction<R: RandomSource>(
+    state: &mut GameState,
+    target: Position,
+    rng: &mut R,
+) -> (String, bool) {
     if !state.bounds.contains(target) {
         return ("You can't tunnel through that!".to_string(), true);
     }
@@ -9316,7 +12839,26 @@ fn resolve_tunnel_direction(state: &mut GameState, target: Position) -> (String,
     if !tunnelable {
         return ("You can't tunnel through that!".to_string(), true);
     }
+    if tunnel_rock_is_undiggable(state) {
+        return ("This rock is too hard to dig through.".to_string(), true);
+    }
+
+    let prior = state
+        .tunnel_progress
+        .filter(|progress| progress.target == target)
+        .map(|progress| progress.effort)
+        .unwrap_or(0);
+    let effort = prior
+        + 10
+        + state.attributes.strength.max(0) / 3
+        + tunnel_digging_bonus(state)
+        + rng.range_inclusive_i32(0, 10);
+    if effort < tunnel_effort_required(glyph) {
+        state.tunnel_progress = Some(TunnelProgress { target, effort });
+        return ("You have no success as yet.".to_string(), true);
+    }
 
+    state.tunnel_progress = None;
     let mut flags = state.tile_site_at(target).map(|site| site.flags).unwrap_or(0);
     flags &= !(TILE_FLAG_BLOCK_MOVE | TILE_FLAG_OPENED_DOOR);
     set_site_flags_at(state, target, flags);
@@ -9445,52 +12987,256 @@ fn merc_contract_objective(state: &GameState) -> String {
     }
 }
 
-fn inventory_has_item_with_fragments(state: &GameState, fragments: &[&str]) -> bool {
-    state.player.inventory.iter().any(|item| {
-        let lower = item.name.to_ascii_lowercase();
-        fragments.iter().all(|fragment| lower.contains(fragment))
-    })
-}
-
-fn remove_inventory_item_with_fragments(state: &mut GameState, fragments: &[&str]) -> Option<Item> {
-    let item_id = state.player.inventory.iter().find_map(|item| {
-        let lower = item.name.to_ascii_lowercase();
-        fragments.iter().all(|fragment| lower.contains(fragment)).then_some(item.id)
-    })?;
-    remove_inventory_item_by_id(state, item_id)
-}
+const HEIST_TURN_LIMIT: u64 = 200;
 
-fn castle_quest_briefing_for_rank(rank: i16) -> String {
-    match rank {
-        i16::MIN..=0 => {
-            "His Grace decrees your first quest: bring the head of the Goblin King.".to_string()
-        }
-        1 => "Bring to me a Holy Defender from the depths below Rampart.".to_string(),
-        2 => "Bring me a suit of dragonscale armor to prove your knighthood.".to_string(),
-        3 => "A final service is required: recover the Orb of Mastery from the Astral reaches."
-            .to_string(),
-        _ => "The Duchy acknowledges your prior service; your court duties now concern governance."
-            .to_string(),
+fn heist_target_position(state: &GameState) -> Position {
+    if let Some(bank) = objective_site_position(state, SITE_AUX_SERVICE_BANK)
+        && bank != state.player.position
+    {
+        return bank;
     }
+    let mirrored = Position {
+        x: state.bounds.width - 1 - state.player.position.x,
+        y: state.bounds.height - 1 - state.player.position.y,
+    };
+    sanitize_spawn(state, mirrored).unwrap_or(mirrored)
 }
 
-fn order_next_duty_briefing(state: &GameState) -> String {
-    let rank = state.progression.quests.order.rank.max(0);
-    if rank <= 0 {
-        return "The Order offers induction if you renounce rival blood-oaths and uphold Law."
-            .to_string();
+fn resolve_heist_arrival<R: RandomSource>(
+    state: &mut GameState,
+    rng: &mut R,
+    events: &mut Vec<Event>,
+) {
+    let Some(contract) = state.progression.quests.thieves.active_contract else {
+        return;
+    };
+    if contract.map_id != state.map_binding.map_id || contract.target != state.player.position {
+        return;
     }
-    if rank >= 4 {
-        if inventory_has_item_with_fragments(state, &["star", "gem"]) {
-            return "You bear the Star Gem; present it to claim the Justiciar succession."
-                .to_string();
+    state.progression.quests.thieves.active_contract = None;
+
+    let roll = rng.range_inclusive_i32(1, 20);
+    let skill = state.attributes.dexterity + i32::from(state.progression.quests.thieves.rank);
+    let note = if roll <= skill {
+        let payout = contract.reward_gold;
+        state.gain_gold(payout, "thieves_guild", events);
+        state.legal_heat = state.legal_heat.saturating_add(2);
+        let thieves = &mut state.progression.quests.thieves;
+        thieves.xp = thieves.xp.saturating_add(i64::from(payout));
+        thieves.quest_flags |= 0x0002;
+        thieves.rank = (thieves.rank + 1).min(5);
+        if payout >= 70 {
+            thieves.promotion_flags |= 1 << 1;
+        }
+        if state.progression.quest_state == LegacyQuestState::Active {
+            state.progression.quest_steps_completed =
+                state.progression.quest_steps_completed.max(2);
         }
-        return "Paladin duty remains: recover the Star Gem and return it to the LawBringer."
-            .to_string();
-    }
-    if rank == 3 {
-        return "Paladin review pending: lawful standing and field record must exceed Justiciar standards."
-            .to_string();
+        format!("Heist completed. Fence payout: {payout} gold.")
+    } else {
+        state.legal_heat = state.legal_heat.saturating_add(6);
+        "The heist goes wrong; you flee as the alarm is raised.".to_string()
+    };
+    events.push(Event::QuestAdvanced {
+        state: state.progression.quest_state,
+        steps_completed: state.progression.quest_steps_completed,
+    });
+    state.log.push(note.clone());
+    events.push(Event::LegacyHandled { token: "heist".to_string(), note, fully_modeled: true });
+}
+
+const COMPANION_WAGE: i64 = 15;
+const COMPANION_PAY_INTERVAL_TURNS: u64 = 50;
+const COMPANION_REGROUP_DISTANCE: i32 = 6;
+
+fn companion_hire_cost(rank: i32) -> i32 {
+    50 + rank.max(1) * 25
+}
+
+fn free_tile_adjacent_to_player(state: &GameState) -> Option<Position> {
+    let origin = state.player.position;
+    [(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (-1, 1), (1, -1), (-1, -1)]
+        .into_iter()
+        .map(|(dx, dy)| Position { x: origin.x + dx, y: origin.y + dy })
+        .find(|pos| {
+            state.bounds.contains(*pos) && state.tile_is_walkable(*pos) && !is_occupied(state, *pos)
+        })
+}
+
+/// Whistles every living companion that isn't already at the player's side
+/// onto a free adjacent tile, wherever on the map it has wandered.
+fn recall_companions(state: &mut GameState, events: &mut Vec<Event>) -> String {
+    if !state.companions.iter().any(|companion| companion.stats.is_alive()) {
+        return "You whistle, but no companions answer.".to_string();
+    }
+    let mut answered = 0;
+    let mut stranded = 0;
+    for idx in 0..state.companions.len() {
+        let companion = &state.companions[idx];
+        let from = companion.position;
+        if !companion.stats.is_alive() || projectile_distance(from, state.player.position) <= 1 {
+            continue;
+        }
+        let Some(to) = free_tile_adjacent_to_player(state) else {
+            stranded += 1;
+            continue;
+        };
+        state.companions[idx].position = to;
+        events.push(Event::MonsterMoved { monster_id: state.companions[idx].id, from, to });
+        answered += 1;
+    }
+    match (answered, stranded) {
+        (0, 0) => "You whistle; your companions are already at your side.".to_string(),
+        (_, 0) => "You whistle and your companions hurry to your side.".to_string(),
+        _ => "You whistle, but there is no room here for everyone to gather.".to_string(),
+    }
+}
+
+fn hire_merc_companion(state: &mut GameState, events: &mut Vec<Event>) -> String {
+    let rank = i32::from(state.progression.guild_rank);
+    if rank < 1 {
+        return "Only guild members may hire mercenaries.".to_string();
+    }
+    if state.companions.len() >= rank as usize {
+        return "Your rank does not allow you to command more mercenaries.".to_string();
+    }
+    let cost = companion_hire_cost(rank);
+    let Some(position) = free_tile_adjacent_to_player(state) else {
+        return "There is no room here for a mercenary to join you.".to_string();
+    };
+    if !state.spend_gold(cost, "merc_guild", events) {
+        return "Not enough gold to hire a mercenary.".to_string();
+    }
+    let id = state.next_entity_id;
+    state.next_entity_id = state.next_entity_id.wrapping_add(1);
+    let hp = 12 + rank * 6;
+    state.companions.push(Monster {
+        id,
+        name: "mercenary".to_string(),
+        position,
+        stats: Stats {
+            hp,
+            max_hp: hp,
+            attack_min: 2 + rank,
+            attack_max: 4 + rank * 2,
+            defense: rank,
+            weight: 60,
+        },
+        behavior: MonsterBehavior::Brute,
+        faction: Faction::Law,
+        display_glyph: Some('@'),
+        on_death_drops: Vec::new(),
+        special_attacks: Vec::new(),
+        allied: true,
+        pack_id: None,
+        status_effects: Vec::new(),
+        awareness: Awareness::Aware,
+        duelist: false,
+    });
+    format!(
+        "A mercenary signs on for {cost}g and {COMPANION_WAGE}g every {COMPANION_PAY_INTERVAL_TURNS} turns."
+    )
+}
+
+fn apply_companion_upkeep(state: &mut GameState, events: &mut Vec<Event>) {
+    if state.companions.is_empty()
+        || state.clock.turn == 0
+        || !state.clock.turn.is_multiple_of(COMPANION_PAY_INTERVAL_TURNS)
+    {
+        return;
+    }
+    let wages = COMPANION_WAGE * state.companions.len() as i64;
+    let owed = state.companion_wages_due.saturating_add(wages);
+    if i32::try_from(owed).is_ok_and(|owed| state.spend_gold(owed, "companion_wages", events)) {
+        state.companion_wages_due = 0;
+        state.log.push(format!("You pay your mercenaries {owed}g."));
+        return;
+    }
+    state.companion_wages_due = owed;
+    if owed >= wages * 2 {
+        let departed = state.companions.len();
+        state.companions.clear();
+        state.companion_wages_due = 0;
+        let note = if departed == 1 {
+            "Unpaid, your mercenary deserts you.".to_string()
+        } else {
+            "Unpaid, your mercenaries desert you.".to_string()
+        };
+        state.log.push(note.clone());
+        events.push(Event::LegacyHandled {
+            token: "companion".to_string(),
+            note,
+            fully_modeled: true,
+        });
+    } else {
+        state.log.push("Your mercenaries grumble about their unpaid wages.".to_string());
+    }
+}
+
+fn expire_overdue_heist(state: &mut GameState, events: &mut Vec<Event>) {
+    let Some(contract) = state.progression.quests.thieves.active_contract else {
+        return;
+    };
+    if state.clock.turn < contract.deadline_turn {
+        return;
+    }
+    state.progression.quests.thieves.active_contract = None;
+    state.legal_heat = state.legal_heat.saturating_add(6);
+    let note = "The heist window closes; word of the botched job reaches the watch.".to_string();
+    state.log.push(note.clone());
+    events.push(Event::QuestAdvanced {
+        state: state.progression.quest_state,
+        steps_completed: state.progression.quest_steps_completed,
+    });
+    events.push(Event::LegacyHandled { token: "heist".to_string(), note, fully_modeled: true });
+}
+
+fn inventory_has_item_with_fragments(state: &GameState, fragments: &[&str]) -> bool {
+    state.player.inventory.iter().any(|item| {
+        let lower = item.name.to_ascii_lowercase();
+        fragments.iter().all(|fragment| lower.contains(fragment))
+    })
+}
+
+fn remove_inventory_item_with_fragments(state: &mut GameState, fragments: &[&str]) -> Option<Item> {
+    let item_id = state.player.inventory.iter().find_map(|item| {
+        let lower = item.name.to_ascii_lowercase();
+        fragments.iter().all(|fragment| lower.contains(fragment)).then_some(item.id)
+    })?;
+    remove_inventory_item_by_id(state, item_id)
+}
+
+fn castle_quest_briefing_for_rank(rank: i16) -> String {
+    match rank {
+        i16::MIN..=0 => {
+            "His Grace decrees your first quest: bring the head of the Goblin King.".to_string()
+        }
+        1 => "Bring to me a Holy Defender from the depths below Rampart.".to_string(),
+        2 => "Bring me a suit of dragonscale armor to prove your knighthood.".to_string(),
+        3 => "A final service is required: recover the Orb of Mastery from the Astral reaches."
+            .to_string(),
+        _ => "The Duchy acknowledges your prior service; your court duties now concern governance."
+            .to_string(),
+    }
+}
+
+fn order_next_duty_briefing(state: &GameState) -> String {
+    let rank = state.progression.quests.order.rank.max(0);
+    if rank <= 0 {
+        return "The Order offers induction if you renounce rival blood-oaths and uphold Law."
+            .to_string();
+    }
+    if rank >= 4 {
+        if inventory_has_item_with_fragments(state, &["star", "gem"]) {
+            return "You bear the Star Gem; present it to claim the Justiciar succession."
+                .to_string();
+        }
+        return "Paladin duty remains: recover the Star Gem and return it to the LawBringer."
+            .to_string();
+    }
+    if rank == 3 {
+        return "Paladin review pending: lawful standing and field record must exceed Justiciar standards."
+            .to_string();
     }
     if rank == 2 {
         return "Chevalier advancement requires stronger lawful standing and greater service record."
@@ -9678,7 +13424,7 @@ pub fn objective_journal(state: &GameState) -> Vec<ObjectiveSnapshot> {
         let engaged = track.rank > 0
             || track.xp > 0
             || track.dues_paid > 0
-            || track.salary_due > 0
+            || track.salary_due != 0
             || track.promotion_flags != 0
             || track.quest_flags != 0;
         if !engaged {
@@ -9691,6 +13437,9 @@ pub fn objective_journal(state: &GameState) -> Vec<ObjectiveSnapshot> {
         if track.dues_paid > 0 {
             summary.push_str(&format!(" DuesPaid {}", track.dues_paid));
         }
+        if track.salary_due < 0 {
+            summary.push_str(&format!(" DuesOwed {}", -track.salary_due));
+        }
         if let Some(extra) = details {
             summary.push_str(": ");
             summary.push_str(extra.trim());
@@ -9742,7 +13491,18 @@ pub fn objective_journal(state: &GameState) -> Vec<ObjectiveSnapshot> {
         )),
     );
     push_track(&mut journal, "temple", "Temple", &state.progression.quests.temple, None);
-    push_track(&mut journal, "thieves", "Thieves Guild", &state.progression.quests.thieves, None);
+    push_track(
+        &mut journal,
+        "thieves",
+        "Thieves Guild",
+        &state.progression.quests.thieves,
+        state.progression.quests.thieves.active_contract.map(|contract| {
+            format!(
+                "Heist at ({}, {}) before turn {}",
+                contract.target.x, contract.target.y, contract.deadline_turn
+            )
+        }),
+    );
     push_track(&mut journal, "college", "College", &state.progression.quests.college, None);
     push_track(&mut journal, "sorcerors", "Sorcerors", &state.progression.quests.sorcerors, None);
     push_track(&mut journal, "palace", "Palace", &state.progression.quests.palace, None);
@@ -10193,6 +13953,55 @@ fn apply_bank_talk_command(state: &mut GameState, events: &mut Vec<Event>) -> St
     notes.join(" ")
 }
 
+const BANK_SURETY_UNIT: i32 = 25;
+
+fn apply_bank_command(
+    state: &mut GameState,
+    op: BankOp,
+    amount: i32,
+    events: &mut Vec<Event>,
+) -> String {
+    let site_aux =
+        state.tile_site_at(state.player.position).map(|site| site.aux).unwrap_or(SITE_AUX_NONE);
+    if site_aux != SITE_AUX_SERVICE_BANK {
+        return "There is no bank here.".to_string();
+    }
+    if amount <= 0 {
+        return "The teller waits for a sensible amount.".to_string();
+    }
+    match op {
+        BankOp::Deposit => {
+            if !state.pay_into_bank(amount, amount, "bank", events) {
+                return format!("You only have {} gold to deposit.", state.gold);
+            }
+            state.progression.quests.bank.rank = state.progression.quests.bank.rank.max(1);
+            state.progression.quests.bank.xp =
+                state.progression.quests.bank.xp.saturating_add(i64::from(amount));
+            format!("Deposited {amount} gold.")
+        }
+        BankOp::Withdraw => {
+            if amount > state.bank_gold {
+                return format!("Your balance is only {} gold.", state.bank_gold);
+            }
+            state.bank_gold -= amount;
+            state.gain_gold(amount, "bank", events);
+            state.progression.quests.bank.quest_flags |= 0x0001;
+            format!("Withdrew {amount} gold.")
+        }
+        BankOp::PostSurety => {
+            if amount < BANK_SURETY_UNIT {
+                return format!("Surety is posted in units of {BANK_SURETY_UNIT} gold.");
+            }
+            if !state.spend_gold(amount, "bank", events) {
+                return format!("You only have {} gold to post.", state.gold);
+            }
+            state.legal_heat = state.legal_heat.saturating_sub(amount / BANK_SURETY_UNIT).max(0);
+            state.progression.quests.bank.quest_flags |= 0x0002;
+            format!("Posted {amount} gold surety with the city bank.")
+        }
+    }
+}
+
 fn apply_merc_talk_command(state: &mut GameState, events: &mut Vec<Event>) -> String {
     let quest_started = start_main_quest_from_dialogue(state, events);
     state.progression.quests.merc.rank = state.progression.quests.merc.rank.max(1);
@@ -10785,7 +14594,10 @@ fn spawn_countryside_encounter<R: RandomSource>(
         }
         _ => Stats { hp: 12, max_hp: 12, attack_min: 2, attack_max: 5, defense: 1, weight: 60 },
     };
-    state.spawn_monster(monster_name, spawn_pos, stats);
+    let monster_id = state.spawn_equipped_monster(monster_name, spawn_pos, stats, rng);
+    if let Some(monster) = state.monsters.iter_mut().find(|monster| monster.id == monster_id) {
+        monster.awareness = roll_spawn_awareness(&monster.name, rng);
+    }
     state.log.push(format!("A wandering threat emerges from the countryside ({terrain:?})."));
     events.push(Event::LegacyHandled {
         token: "encounter".to_string(),
@@ -10967,7 +14779,10 @@ fn apply_destructive_action(state: &mut GameState) -> (String, bool) {
     ("destructive action resolved with legal penalty".to_string(), true)
 }
 
-fn reveal_map_for_wizard(state: &mut GameState) {
+/// Returns how many tiles were newly added to `known_sites`, and reports the
+/// same count as `Event::MapRevealed`.
+fn reveal_map_for_wizard(state: &mut GameState, events: &mut Vec<Event>) -> usize {
+    let known_before = state.known_sites.len();
     // Mirror classic wizard reveal: mark all tiles in the active map as discovered.
     for y in 0..state.bounds.height {
         for x in 0..state.bounds.width {
@@ -10996,6 +14811,42 @@ fn reveal_map_for_wizard(state: &mut GameState) {
             }
         }
     }
+    let tiles = state.known_sites.len() - known_before;
+    events.push(Event::MapRevealed { tiles });
+    tiles
+}
+
+const MAGIC_MAP_COUNTRY_RADIUS: i32 = 8;
+
+fn reveal_magic_map(state: &mut GameState, events: &mut Vec<Event>) -> String {
+    if state.world_mode == WorldMode::Countryside {
+        let origin = state.player.position;
+        let mut revealed = 0;
+        for y in 0..state.bounds.height {
+            for x in 0..state.bounds.width {
+                let pos = Position { x, y };
+                let is_site =
+                    state.tile_site_at(pos).is_some_and(|cell| cell.site_id != 0 || cell.aux != 0);
+                if is_site
+                    && projectile_distance(origin, pos) <= MAGIC_MAP_COUNTRY_RADIUS
+                    && !state.known_sites.contains(&pos)
+                {
+                    ensure_known_site(state, pos);
+                    revealed += 1;
+                }
+            }
+        }
+        events.push(Event::MapRevealed { tiles: revealed });
+        return format!("the map charts {revealed} nearby site(s)");
+    }
+    let known_before = state.known_sites.len();
+    for y in 0..state.bounds.height {
+        for x in 0..state.bounds.width {
+            ensure_known_site(state, Position { x, y });
+        }
+    }
+    events.push(Event::MapRevealed { tiles: state.known_sites.len() - known_before });
+    "the layout of this level etches itself into your mind".to_string()
 }
 
 fn stat_slot_name(slot: u8) -> &'static str {
@@ -11065,21 +14916,21 @@ fn apply_stat_slot_value(state: &mut GameState, slot: u8, raw_value: i32) -> Str
             format!("Power set to {}", state.attributes.power)
         }
         7 => {
-            state.player.stats.hp = raw_value.clamp(0, state.player.stats.max_hp.max(1));
+            state.player.stats.set_hp(raw_value);
             format!("HP set to {}", state.player.stats.hp)
         }
         8 => {
             state.player.stats.max_hp = raw_value.clamp(1, 120);
-            state.player.stats.hp = state.player.stats.hp.clamp(0, state.player.stats.max_hp);
+            state.player.stats.set_hp(state.player.stats.hp);
             format!("Max HP set to {}", state.player.stats.max_hp)
         }
         9 => {
-            state.spellbook.mana = raw_value.clamp(0, state.spellbook.max_mana.max(0));
+            state.spellbook.set_mana(raw_value);
             format!("Mana set to {}", state.spellbook.mana)
         }
         10 => {
             state.spellbook.max_mana = raw_value.clamp(1, 600);
-            state.spellbook.mana = state.spellbook.mana.clamp(0, state.spellbook.max_mana);
+            state.spellbook.set_mana(state.spellbook.mana);
             format!("Max Mana set to {}", state.spellbook.max_mana)
         }
         11 => {
@@ -11092,6 +14943,14 @@ fn apply_stat_slot_value(state: &mut GameState, slot: u8, raw_value: i32) -> Str
 
 const LEGACY_WISH_IINIT_H: &str =
     include_str!("../../../archive/legacy-c-runtime/2026-02-06/iinit.h");
+// Items with no legacy counterpart, written in the `iinit.h` row format.
+const MODERN_ITEM_TEMPLATES: &str = r#"
+{ THINGID+31,60,0,0,0,0,0,1,10,40,0,0,0,0,COMMON,I_BACKPACK,2,THING,"leather pack","backpack","backpack" },
+{ THINGID+32,40,0,0,0,0,0,1,10,15,0,0,0,0,COMMON,I_ROPE,1,THING,"coil of rope","rope","rope" },
+{ SCROLLID+24,5,0,0,0,0,0,1,1,250,0,0,0,0,COMMON,I_MAGIC_MAP,3,SCROLL,"?","scroll of magic mapping","scroll of magic mapping" },
+{ ARTIFACTID+26,10,0,0,0,0,0,1,10,3000,0,0,0,0,COMMON,I_LIFE_SAVE,8,ARTIFACT,"plain silver amulet","amulet of life saving","amulet of life saving" },
+{ POTIONID+18,20,0,0,0,0,0,1,5,60,0,0,0,0,COMMON,I_LIQUID_FIRE,2,POTION,"?","potion of liquid fire","potion of liquid fire" },
+"#;
 const LEGACY_PROJECTILE_DEFS_H: &str =
     include_str!("../../../archive/legacy-c-runtime/2026-02-06/defs.h");
 const LEGACY_PROJECTILE_COMMAND3_C: &str =
@@ -11449,10 +15308,8 @@ fn apply_wish_intent(
     match intent {
         WishIntent::Death => {
             state.player.stats.hp = 0;
-            state.status = SessionStatus::Lost;
-            state.death_source = Some("a deathwish".to_string());
             state.log.push("As you wish, so shall it be.".to_string());
-            events.push(Event::PlayerDefeated);
+            mark_player_defeated(state, "a deathwish", events);
             "As you wish, so shall it be.".to_string()
         }
         WishIntent::Power => {
@@ -11471,12 +15328,7 @@ fn apply_wish_intent(
             "You feel more competent.".to_string()
         }
         WishIntent::Wealth => {
-            state.gold = state.gold.saturating_add(10_000);
-            events.push(Event::EconomyUpdated {
-                source: "wish".to_string(),
-                gold: state.gold,
-                bank_gold: state.bank_gold,
-            });
+            state.gain_gold(10_000, "wish", events);
             "You are submerged in a shower of gold pieces.".to_string()
         }
         WishIntent::Balance => {
@@ -11509,7 +15361,7 @@ fn apply_wish_intent(
             "Magic portals open and cast you toward a known destination.".to_string()
         }
         WishIntent::Knowledge => {
-            state.spellbook.mana = (state.spellbook.mana + 20).min(state.spellbook.max_mana);
+            state.spellbook.restore_mana(20);
             let learned = teach_first_unknown_from_pool(state, &[20, 22, 36, 40, 29]);
             if let Some(spell_id) = learned {
                 format!(
@@ -11529,15 +15381,16 @@ fn apply_wish_intent(
         WishIntent::Destruction => {
             let defeated = state.monsters.len() as u64;
             for monster in &state.monsters {
-                events.push(Event::MonsterDefeated { monster_id: monster.id });
+                events.push(Event::MonsterDefeated {
+                    monster_id: monster.id,
+                    name: monster.name.clone(),
+                });
             }
             state.monsters.clear();
             state.monsters_defeated = state.monsters_defeated.saturating_add(defeated);
             if blessing < 0 {
                 state.player.stats.hp = 0;
-                state.status = SessionStatus::Lost;
-                state.death_source = Some("a cursed wish".to_string());
-                events.push(Event::PlayerDefeated);
+                mark_player_defeated(state, "a cursed wish", events);
             }
             format!("Annihilation erupts. {} hostiles destroyed.", defeated)
         }
@@ -11845,7 +15698,9 @@ fn legacy_item_templates() -> &'static Vec<LegacyItemTemplate> {
 
 fn parse_legacy_item_templates() -> Vec<LegacyItemTemplate> {
     let mut templates = Vec::new();
-    for line in LEGACY_WISH_IINIT_H.lines() {
+    let legacy_rows = LEGACY_WISH_IINIT_H.lines().map(|line| (line, true));
+    let modern_rows = MODERN_ITEM_TEMPLATES.lines().map(|line| (line, false));
+    for (line, is_legacy) in legacy_rows.chain(modern_rows) {
         let trimmed = line.trim();
         if !trimmed.starts_with('{') || !(trimmed.ends_with("},") || trimmed.ends_with('}')) {
             continue;
@@ -11860,7 +15715,11 @@ fn parse_legacy_item_templates() -> Vec<LegacyItemTemplate> {
         let Some((family, family_index)) = parse_legacy_item_family_and_index(&id_expr) else {
             continue;
         };
-        let legacy_id = legacy_item_family_base_id(family) + i32::from(family_index);
+        let legacy_id = if is_legacy {
+            legacy_item_family_base_id(family) + i32::from(family_index)
+        } else {
+            0
+        };
         let Some(weight) = parse_i32_token(&fields[1]) else {
             continue;
         };
@@ -12140,10 +15999,11 @@ const LEGACY_SPELL_SORTED_IDS: [usize; 42] = [
     41, 1, 9, 34, 18, 24, 30, 35, 28, 22, 32, 6, 27, 4, 15, 20, 40,
 ];
 
-fn cast_spell_by_id(
+fn cast_spell_by_id<R: RandomSource>(
     state: &mut GameState,
     events: &mut Vec<Event>,
     spell_index: usize,
+    rng: &mut R,
 ) -> (String, bool) {
     sync_spellbook_state(state);
     let Some(spell_name) = LEGACY_SPELL_NAMES.get(spell_index).copied() else {
@@ -12166,6 +16026,10 @@ fn cast_spell_by_id(
     }
 
     state.spellbook.mana -= spell_cost;
+    let failure_chance = spell_failure_chance(state);
+    if failure_chance > 0 && rng.range_inclusive_i32(1, 100) <= failure_chance {
+        return ("Your concentration falters!".to_string(), true);
+    }
     push_or_refresh_status(&mut state.status_effects, "spell_focus", 1, 0);
     state.spellbook.next_spell_index = (spell_index as u8).wrapping_add(1);
 
@@ -12248,8 +16112,16 @@ fn cast_spell_by_id(
         ),
         "disintegrate" => spell_remove_nearest(state, events, 5, "target annihilated"),
         "polymorph" => spell_polymorph_nearest(state, 6),
+        "healing" if !state.companions.is_empty() => begin_projectile_spell(
+            state,
+            ProjectileKind::Healing,
+            "healing",
+            14,
+            14,
+            ProjectileDamageType::Magic,
+        ),
         "healing" => {
-            state.player.stats.hp = (state.player.stats.hp + 14).min(state.player.stats.max_hp);
+            state.player.stats.heal(14);
             "major vitality restored".to_string()
         }
         "dispelling" => dispel_or_decurse_with_branching(state, 1),
@@ -12352,7 +16224,7 @@ fn cast_spell_by_id(
                 .position(|item| item.position.manhattan_distance(state.player.position) <= 3)
             {
                 let pulled = state.ground_items.remove(idx);
-                state.player.inventory.push(pulled.item);
+                add_existing_item_to_inventory_or_ground(state, pulled.item, events);
                 "an item was pulled into your pack".to_string()
             } else {
                 "no item resonated with the spell".to_string()
@@ -12443,22 +16315,63 @@ fn disarm_adjacent_trap(state: &mut GameState, events: &mut Vec<Event>) -> (Stri
         if state.legal_heat > 0 {
             state.legal_heat -= 1;
         }
-        state.gold += 5;
-        events.push(Event::EconomyUpdated {
-            source: "disarm_reward".to_string(),
-            gold: state.gold,
-            bank_gold: state.bank_gold,
-        });
-        return (format!("trap {} disarmed and bounty collected", trap.id), true);
+        let trap_id = trap.id;
+        state.gain_gold(5, "disarm_reward", events);
+        return (format!("trap {trap_id} disarmed and bounty collected"), true);
     }
-    events.push(Event::EconomyUpdated {
-        source: "disarm_attempt".to_string(),
-        gold: state.gold,
-        bank_gold: state.bank_gold,
-    });
+    state.push_economy_update("disarm_attempt", events);
     ("disarm attempted but no adjacent armed trap".to_string(), true)
 }
 
+fn pickpocket_target_level(monster: &Monster) -> i32 {
+    (monster.stats.max_hp / 10).clamp(1, 10)
+}
+
+fn resolve_pickpocket<R: RandomSource>(
+    state: &mut GameState,
+    rng: &mut R,
+    events: &mut Vec<Event>,
+) -> (String, bool) {
+    let Some(index) = state
+        .monsters
+        .iter()
+        .position(|monster| monster.position.manhattan_distance(state.player.position) == 1)
+    else {
+        state.legal_heat += 1;
+        return ("pickpocket failed; legal heat increased".to_string(), true);
+    };
+
+    let level = pickpocket_target_level(&state.monsters[index]);
+    let skill = 50
+        + statmod(state.attributes.dexterity) * 10
+        + i32::from(state.progression.quests.thieves.rank) * 20
+        + rng.range_inclusive_i32(1, 100);
+    let awareness = rng.range_inclusive_i32(1, 100) + level * 20;
+    if skill > awareness {
+        let stolen = 10 + level * 15;
+        state.gain_gold(stolen, "pickpocket", events);
+        state.progression.law_chaos_score -= 2;
+        let name = state.monsters[index].name.clone();
+        return (format!("pickpocket succeeded; lifted {stolen} gold from the {name}"), true);
+    }
+
+    let annoyance = rng.range_inclusive_i32(1, awareness);
+    if skill < annoyance {
+        state.legal_heat += 1;
+        return ("No luck ... yet.".to_string(), true);
+    }
+    state.legal_heat += 3;
+    let monster = &mut state.monsters[index];
+    if monster.behavior == MonsterBehavior::Social {
+        monster.behavior = MonsterBehavior::Brute;
+    }
+    if matches!(monster.faction, Faction::Law | Faction::Chaos) {
+        monster.faction = Faction::Neutral;
+    }
+    let name = monster.name.clone();
+    (format!("pickpocket failed; the {name} notices and turns on you"), true)
+}
+
 fn has_adjacent_monster(state: &GameState) -> bool {
     state
         .monsters
@@ -12553,6 +16466,17 @@ pub fn projectile_trace_to_target(
     final_pos
 }
 
+// Walls, closed doors and secret passages hide what lies behind them; creatures do not.
+fn line_of_sight(state: &GameState, origin: Position, target: Position) -> bool {
+    line_path(origin, target).into_iter().filter(|pos| *pos != origin && *pos != target).all(
+        |pos| {
+            let flags = state.tile_site_at(pos).map(|cell| cell.flags).unwrap_or(0);
+            (flags & (TILE_FLAG_BLOCK_MOVE | TILE_FLAG_PORTCULLIS | TILE_FLAG_SECRET)) == 0
+                && !matches!(state.map_glyph_at(pos), '#' | '-' | 'D' | 'J' | '=')
+        },
+    )
+}
+
 fn legacy_hit_roll<R: RandomSource>(hit: i32, ac: i32, rng: &mut R) -> bool {
     let roll = rng.range_inclusive_i32(0, 19);
     if roll == 0 {
@@ -12564,6 +16488,35 @@ fn legacy_hit_roll<R: RandomSource>(hit: i32, ac: i32, rng: &mut R) -> bool {
     roll < (hit - ac)
 }
 
+/// Damage roll between `min` and `max` shaped by the configured variance.
+/// `Uniform` draws once so seeded legacy sequences are unchanged.
+fn roll_combat_damage<R: RandomSource>(
+    rng: &mut R,
+    min: i32,
+    max: i32,
+    variance: CombatVariance,
+) -> i32 {
+    let first = rng.range_inclusive_i32(min, max);
+    match variance {
+        CombatVariance::Uniform => first,
+        CombatVariance::Average => {
+            let second = rng.range_inclusive_i32(min, max);
+            // Odd sums split the half point by which roll came first, so
+            // averaging neither drags damage down nor pushes it up.
+            (first + second + i32::from(first > second)) / 2
+        }
+        CombatVariance::Extreme => {
+            let second = rng.range_inclusive_i32(min, max);
+            let midpoint_twice = min + max;
+            if (2 * second - midpoint_twice).abs() > (2 * first - midpoint_twice).abs() {
+                second
+            } else {
+                first
+            }
+        }
+    }
+}
+
 fn weapon_hand_item_id(state: &GameState) -> Option<u32> {
     state.player.equipment.weapon_hand
 }
@@ -12578,6 +16531,143 @@ fn weapon_hand_item_mut(state: &mut GameState) -> Option<&mut Item> {
     state.player.inventory.iter_mut().find(|item| item.id == weapon_id)
 }
 
+const POISON_COATING_DOSES: i32 = 5;
+const MONSTER_POISON_TURNS: u32 = 6;
+const MONSTER_POISON_MAGNITUDE: i32 = 2;
+const POISON_POTION_TURNS: u32 = 8;
+const POISON_POTION_MAGNITUDE: i32 = 4;
+
+fn is_poison_potion(potion: &Item) -> bool {
+    // A cursed neutralize-poison potion is the legacy potion of poison.
+    potion.usef == "I_NEUTRALIZE_POISON" && potion.blessing < 0
+}
+
+/// Extra damage per hit from a weapon coated with `potion`: the potion's level
+/// plus how badly it is cursed.
+fn poison_coating_potency(potion: &Item) -> i32 {
+    (i32::from(potion.level) + potion.blessing.abs()).max(1)
+}
+
+const SHATTER_RADIUS: i32 = 1;
+const LIQUID_FIRE_DAMAGE: i32 = 8;
+
+/// Breaks a thrown potion at `center`, splashing its effect over every
+/// monster within [`SHATTER_RADIUS`] instead of dealing impact damage.
+fn shatter_potion_at(
+    state: &mut GameState,
+    potion: &Item,
+    center: Position,
+    events: &mut Vec<Event>,
+) -> Vec<String> {
+    let mut lines = vec![format!("The {} shatters!", potion.name)];
+    let splashed: Vec<usize> = state
+        .monsters
+        .iter()
+        .enumerate()
+        .filter(|(_, monster)| {
+            (monster.position.x - center.x).abs() <= SHATTER_RADIUS
+                && (monster.position.y - center.y).abs() <= SHATTER_RADIUS
+        })
+        .map(|(idx, _)| idx)
+        .collect();
+    if splashed.is_empty() {
+        lines.push("The contents splash harmlessly on the ground.".to_string());
+        return lines;
+    }
+
+    if is_poison_potion(potion) {
+        lines.push("A noxious cloud billows out.".to_string());
+        for idx in splashed {
+            push_or_refresh_status(
+                &mut state.monsters[idx].status_effects,
+                "poison",
+                MONSTER_POISON_TURNS,
+                MONSTER_POISON_MAGNITUDE,
+            );
+            lines.push(format!("The {} is poisoned.", state.monsters[idx].name));
+        }
+        return lines;
+    }
+    match potion.usef.as_str() {
+        // A cursed alertness potion is the legacy potion of sleep.
+        "I_ALERT" if potion.blessing < 0 => {
+            for idx in splashed {
+                state.monsters[idx].awareness = Awareness::Asleep;
+                lines.push(format!("The {} falls asleep.", state.monsters[idx].name));
+            }
+        }
+        "I_LIQUID_FIRE" => {
+            lines.push("Flames burst across the ground.".to_string());
+            let ids: Vec<u64> = splashed.iter().map(|idx| state.monsters[*idx].id).collect();
+            for monster_id in ids {
+                let Some(idx) = state.monsters.iter().position(|m| m.id == monster_id) else {
+                    continue;
+                };
+                let monster = &mut state.monsters[idx];
+                let applied = monster.stats.apply_damage(LIQUID_FIRE_DAMAGE);
+                let name = monster.name.clone();
+                let remaining_hp = monster.stats.hp;
+                events.push(Event::Attacked { monster_id, damage: applied, remaining_hp });
+                lines.push(format!("The {name} is burned for {applied} damage."));
+                if remaining_hp <= 0 {
+                    let _ = remove_monster_with_drops(state, idx, events);
+                    state.monsters_defeated = state.monsters_defeated.saturating_add(1);
+                    lines.push(format!("{name} is defeated."));
+                    events.push(Event::MonsterDefeated { monster_id, name });
+                }
+            }
+        }
+        "I_HEAL" | "I_RESTORE" => {
+            for idx in splashed {
+                let monster = &mut state.monsters[idx];
+                monster.stats.heal(12);
+                lines.push(format!("The {} looks healthier.", monster.name));
+            }
+        }
+        _ => lines.push("The vapors dissipate without effect.".to_string()),
+    }
+    lines
+}
+
+fn dip_item_in_potion(state: &mut GameState, item_id: u32, potion_id: u32) -> String {
+    if item_id == potion_id {
+        return "You can't dip a potion into itself.".to_string();
+    }
+    let Some(potion) = state.player.inventory.iter().find(|entry| entry.id == potion_id).cloned()
+    else {
+        return "You don't have that potion.".to_string();
+    };
+    if potion.family != ItemFamily::Potion {
+        return format!("The {} is not a potion.", potion.name);
+    }
+    let Some(target_name) =
+        state.player.inventory.iter().find(|entry| entry.id == item_id).map(|i| i.name.clone())
+    else {
+        return "You don't have that item.".to_string();
+    };
+    let _ = remove_inventory_item_by_id(state, potion_id);
+    let Some(target) = state.player.inventory.iter_mut().find(|entry| entry.id == item_id) else {
+        return "You don't have that item.".to_string();
+    };
+    let poisonous = is_poison_potion(&potion);
+    match potion.usef.as_str() {
+        _ if poisonous => {
+            if target.family != ItemFamily::Weapon {
+                return format!("The poison soaks into the {target_name} to no effect.");
+            }
+            target.poison_doses = target.poison_doses.max(0) + POISON_COATING_DOSES;
+            target.poison_potency = target.poison_potency.max(poison_coating_potency(&potion));
+            format!("Your {target_name} now drips with venom.")
+        }
+        "I_NEUTRALIZE_POISON" | "I_CURE" => {
+            target.poison_doses = 0;
+            target.poison_potency = 0;
+            format!("Your {target_name} is rinsed clean.")
+        }
+        _ => format!("You dip the {target_name}, but nothing seems to happen."),
+    }
+}
+
 fn weapon_hand_is_longbow(state: &GameState) -> bool {
     let contract = legacy_projectile_contract();
     weapon_hand_item(state).is_some_and(|item| item.legacy_id == contract.ob_longbow)
@@ -12635,6 +16725,10 @@ fn remove_monster_with_drops(
             });
         }
     }
+    // The carcass lands beneath whatever the monster carried, as in legacy `m_death`.
+    let corpse = monster_corpse(&monster, state.next_item_id);
+    state.next_item_id += 1;
+    state.ground_items.push(GroundItem { position: monster.position, item: corpse });
     Some(monster)
 }
 
@@ -12715,9 +16809,10 @@ fn spell_damage_radius(
             continue;
         }
         let monster_id = state.monsters[idx].id;
+        let name = state.monsters[idx].name.clone();
         let _ = remove_monster_with_drops(state, idx, events);
         state.monsters_defeated = state.monsters_defeated.saturating_add(1);
-        events.push(Event::MonsterDefeated { monster_id });
+        events.push(Event::MonsterDefeated { monster_id, name });
     }
 
     format!("{flavor}: impacted {hit_count} targets")
@@ -12745,8 +16840,9 @@ fn spell_remove_nearest(
         return "disintegrate failed: target vanished".to_string();
     };
     state.monsters_defeated = state.monsters_defeated.saturating_add(1);
-    events.push(Event::MonsterDefeated { monster_id: monster.id });
-    format!("{flavor} ({})", monster.name)
+    let name = monster.name;
+    events.push(Event::MonsterDefeated { monster_id: monster.id, name: name.clone() });
+    format!("{flavor} ({name})")
 }
 
 fn spell_polymorph_nearest(state: &mut GameState, radius: i32) -> String {
@@ -12808,12 +16904,13 @@ fn spell_energy_drain(state: &mut GameState, events: &mut Vec<Event>) -> String
     };
     let remaining_hp = state.monsters[idx].stats.hp;
     events.push(Event::Attacked { monster_id, damage: drained, remaining_hp });
-    state.spellbook.mana = (state.spellbook.mana + drained).min(state.spellbook.max_mana);
+    state.spellbook.restore_mana(drained);
 
     if !state.monsters[idx].stats.is_alive() {
+        let name = state.monsters[idx].name.clone();
         let _ = remove_monster_with_drops(state, idx, events);
         state.monsters_defeated = state.monsters_defeated.saturating_add(1);
-        events.push(Event::MonsterDefeated { monster_id });
+        events.push(Event::MonsterDefeated { monster_id, name });
     }
 
     format!("drained {drained} energy")
@@ -12835,7 +16932,9 @@ fn adjacent_monster_direction(state: &GameState) -> Option<Direction> {
 }
 
 fn is_occupied(state: &GameState, pos: Position) -> bool {
-    state.player.position == pos || state.monsters.iter().any(|monster| monster.position == pos)
+    state.player.position == pos
+        || state.monsters.iter().any(|monster| monster.position == pos)
+        || state.companions.iter().any(|companion| companion.position == pos)
 }
 
 fn infer_monster_profile(name: &str) -> (MonsterBehavior, Faction) {
@@ -12864,28 +16963,155 @@ fn infer_monster_profile(name: &str) -> (MonsterBehavior, Faction) {
     (MonsterBehavior::Brute, Faction::Neutral)
 }
 
-fn item_burden(item: &Item) -> i32 {
-    if item.weight > 0 {
-        let scaled = (item.weight + 9) / 10;
-        return scaled.clamp(1, 50);
-    }
-    match item.family {
-        ItemFamily::Armor | ItemFamily::Shield => 6,
-        ItemFamily::Weapon | ItemFamily::Artifact => 4,
-        ItemFamily::Food | ItemFamily::Potion | ItemFamily::Scroll => 1,
-        _ => 2,
-    }
-}
+const LEGACY_MONSTER_MINIT_H: &str =
+    include_str!("../../../archive/legacy-c-runtime/2026-02-06/minit.h");
+// Positions of the `sleep` percentage, corpse weight, `startthing` item and uniqueness within
+// a `minit.h` row.
+const LEGACY_MONSTER_SLEEP_FIELD: usize = 16;
+const LEGACY_MONSTER_CORPSE_WEIGHT_FIELD: usize = 19;
+const LEGACY_MONSTER_START_ITEM_FIELD: usize = 22;
+const LEGACY_MONSTER_UNIQUENESS_FIELD: usize = 23;
 
-fn canonical_item_alias_name(name: &str) -> Option<&'static str> {
-    let normalized = normalize_item_lookup(name);
-    match normalized.as_str() {
-        "healing potion" => Some("potion of healing"),
-        "scroll identify" | "identify scroll" => Some("scroll of identification"),
-        "charged stick" | "wand" | "staff" => Some("staff of missiles"),
-        "rations pack" | "ration" => Some("food ration"),
+#[derive(Debug, Clone, Copy, Default)]
+struct LegacyMonsterRow {
+    sleep_chance: i32,
+    /// Legacy item id the monster is created carrying, if any.
+    start_item: Option<i32>,
+    /// One of the legacy unique monsters (the Goblin King, the Dragon Lord, ...).
+    unique: bool,
+    corpse_weight: i32,
+    /// What the monster leaves behind, such as "dead goblin" or "venison".
+    corpse_name: Option<&'static str>,
+}
+
+fn legacy_monster_key(name: &str) -> String {
+    let lowered = name.trim().to_ascii_lowercase();
+    lowered.strip_prefix("the ").map(str::to_string).unwrap_or(lowered)
+}
+
+/// The legacy monster table entry for `name`; unknown names get an empty row.
+fn legacy_monster_row(name: &str) -> LegacyMonsterRow {
+    static ROWS: OnceLock<BTreeMap<String, LegacyMonsterRow>> = OnceLock::new();
+    let rows = ROWS.get_or_init(|| {
+        let mut rows = BTreeMap::new();
+        for line in LEGACY_MONSTER_MINIT_H.lines() {
+            let trimmed = line.trim();
+            if !trimmed.starts_with("{ NULL,") {
+                continue;
+            }
+            let fields: Vec<&str> = trimmed.split(',').map(str::trim).collect();
+            let Some(sleep_chance) =
+                fields.get(LEGACY_MONSTER_SLEEP_FIELD).and_then(|field| field.parse::<i32>().ok())
+            else {
+                continue;
+            };
+            let start_item = fields
+                .get(LEGACY_MONSTER_START_ITEM_FIELD)
+                .and_then(|field| parse_legacy_item_family_and_index(field))
+                .map(|(family, index)| legacy_item_family_base_id(family) + i32::from(index));
+            let unique = fields
+                .get(LEGACY_MONSTER_UNIQUENESS_FIELD)
+                .is_some_and(|field| field.starts_with("UNIQUE"));
+            let corpse_weight = fields
+                .get(LEGACY_MONSTER_CORPSE_WEIGHT_FIELD)
+                .and_then(|field| field.parse::<i32>().ok())
+                .unwrap_or(0);
+            let mut strings = trimmed.split('"').skip(1).step_by(2);
+            let Some(name) = strings.next() else {
+                continue;
+            };
+            let corpse_name = strings.next().filter(|corpse| *corpse != "(null)");
+            rows.entry(legacy_monster_key(name)).or_insert(LegacyMonsterRow {
+                sleep_chance,
+                start_item,
+                unique,
+                corpse_weight,
+                corpse_name,
+            });
+        }
+        rows
+    });
+    rows.get(&legacy_monster_key(name)).copied().unwrap_or_default()
+}
+
+/// Story bosses accept a formal duel: the legacy uniques and the arena champion.
+fn infer_monster_is_duelist(name: &str) -> bool {
+    legacy_monster_row(name).unique || name.to_ascii_lowercase().contains("arena champion")
+}
+
+/// Chance (percent) that a freshly created monster starts asleep, read from
+/// the legacy monster table. Unknown names never start asleep.
+fn legacy_monster_sleep_chance(name: &str) -> i32 {
+    legacy_monster_row(name).sleep_chance
+}
+
+/// Rolls whether a newly spawned monster is caught napping. Social monsters
+/// are always up and about.
+fn roll_spawn_awareness<R: RandomSource>(name: &str, rng: &mut R) -> Awareness {
+    let (behavior, _) = infer_monster_profile(name);
+    let chance = legacy_monster_sleep_chance(name);
+    if behavior != MonsterBehavior::Social
+        && chance > 0
+        && rng.range_inclusive_i32(1, 100) <= chance
+    {
+        Awareness::Asleep
+    } else {
+        Awareness::Aware
+    }
+}
+
+const EQUIPPED_WEAPON_DROP_CHANCE: i32 = 40;
+const EQUIPPED_ARMOR_DROP_CHANCE: i32 = 20;
+
+fn infer_monster_is_armed(name: &str) -> bool {
+    let lowered = name.to_ascii_lowercase();
+    ["bandit", "brigand", "guard", "soldier", "knight", "warrior", "mercenary", "goblin", "orc"]
+        .iter()
+        .any(|kind| lowered.contains(kind))
+}
+
+fn infer_special_attacks(name: &str) -> Vec<SpecialAttack> {
+    let lowered = name.to_ascii_lowercase();
+    if lowered.contains("dragon") {
+        let element =
+            if lowered.contains("frost") { BreathElement::Cold } else { BreathElement::Fire };
+        return vec![SpecialAttack::Breath { element, damage: 12 }];
+    }
+    if lowered.contains("basilisk") || lowered.contains("medusa") {
+        return vec![SpecialAttack::ParalyzingGaze { turns: 3 }];
+    }
+    if lowered.contains("wraith") || lowered.contains("vampire") {
+        return vec![SpecialAttack::Drain];
+    }
+    if lowered.contains("thief") || lowered.contains("nymph") || lowered.contains("pickpocket") {
+        return vec![SpecialAttack::Steal];
+    }
+    Vec::new()
+}
+
+fn item_burden(item: &Item) -> i32 {
+    if item.weight > 0 {
+        let scaled = (item.weight + 9) / 10;
+        return scaled.clamp(1, 50);
+    }
+    match item.family {
+        ItemFamily::Armor | ItemFamily::Shield => 6,
+        ItemFamily::Weapon | ItemFamily::Artifact => 4,
+        ItemFamily::Food | ItemFamily::Potion | ItemFamily::Scroll => 1,
+        _ => 2,
+    }
+}
+
+fn canonical_item_alias_name(name: &str) -> Option<&'static str> {
+    let normalized = normalize_item_lookup(name);
+    match normalized.as_str() {
+        "healing potion" => Some("potion of healing"),
+        "scroll identify" | "identify scroll" => Some("scroll of identification"),
+        "charged stick" | "wand" | "staff" => Some("staff of missiles"),
+        "rations pack" | "ration" => Some("food ration"),
         "chain armor" | "chain armour" => Some("chain mail"),
         "artifact star" => Some("Star Gem"),
+        "lock pick" | "lockpick" => Some("thieve's pick"),
         _ => None,
     }
 }
@@ -12934,6 +17160,8 @@ fn instantiate_item_from_name(item_id: u32, requested_name: &str) -> Item {
                 objstr: template.objstr.clone(),
                 truename: template.truename.clone(),
                 cursestr: template.cursestr.clone(),
+                poison_doses: 0,
+                poison_potency: 0,
             };
         }
     }
@@ -12941,6 +17169,14 @@ fn instantiate_item_from_name(item_id: u32, requested_name: &str) -> Item {
     Item::basic(item_id, requested_name)
 }
 
+fn instantiate_item_from_legacy_id(item_id: u32, legacy_id: i32) -> Option<Item> {
+    let template = legacy_item_templates().iter().find(|entry| entry.legacy_id == legacy_id)?;
+    let name = sanitize_catalog_name(&template.truename)
+        .or_else(|| sanitize_catalog_name(&template.objstr))
+        .unwrap_or_else(|| template.truename.clone());
+    Some(instantiate_item_from_name(item_id, &name))
+}
+
 fn add_item_to_inventory_or_ground(
     state: &mut GameState,
     name: impl Into<String>,
@@ -12970,28 +17206,114 @@ fn add_existing_item_to_inventory_or_ground(
         format!("received {name}")
     } else {
         let position = state.player.position;
-        state.ground_items.push(GroundItem { position, item: item.clone() });
         events.push(Event::InventoryFull { capacity });
+        events.push(Event::InventoryOverflow { item_id: item.id, name: name.clone() });
+        state.ground_items.push(GroundItem { position, item });
         format!("inventory full; {name} left on ground")
     }
 }
 
+/// Corpse weight per food ration carved from it; heavier monsters feed more.
+const BUTCHER_WEIGHT_PER_RATION: i32 = 100;
+const BUTCHER_MAX_RATIONS: i32 = 4;
+
+/// The carcass a slain monster leaves, named and weighed from the legacy
+/// monster table (falling back to the monster's own weight). As in legacy
+/// `make_corpse`, `aux` carries the food value: the rations it butchers into.
+fn monster_corpse(monster: &Monster, id: u32) -> Item {
+    let row = legacy_monster_row(&monster.name);
+    let weight = if row.corpse_weight > 0 { row.corpse_weight } else { monster.stats.weight };
+    let name = row.corpse_name.map_or_else(|| format!("dead {}", monster.name), str::to_string);
+    Item {
+        id,
+        name: name.clone(),
+        family: ItemFamily::Corpse,
+        usef: "I_CORPSE".to_string(),
+        item_type: "CORPSE".to_string(),
+        weight,
+        aux: (weight / BUTCHER_WEIGHT_PER_RATION).clamp(0, BUTCHER_MAX_RATIONS),
+        number: 1,
+        known: true,
+        objstr: name.clone(),
+        truename: name.clone(),
+        cursestr: name,
+        ..Item::default()
+    }
+}
+
+/// Carves the corpse under the player, or failing that one on an adjacent
+/// tile, into as many food rations as the monster was worth. Tainted corpses
+/// are discarded with nothing gained. Refusals come back as `Err` and leave
+/// the corpse where it lies.
+fn butcher_corpse(state: &mut GameState, events: &mut Vec<Event>) -> Result<String, String> {
+    let player_pos = state.player.position;
+    let is_corpse = |ground: &GroundItem| {
+        ground.item.family == ItemFamily::Corpse || ground.item.usef == "I_CORPSE"
+    };
+    let Some(index) = state
+        .ground_items
+        .iter()
+        .position(|ground| ground.position == player_pos && is_corpse(ground))
+        .or_else(|| {
+            state.ground_items.iter().position(|ground| {
+                projectile_distance(ground.position, player_pos) == 1 && is_corpse(ground)
+            })
+        })
+    else {
+        return Err("There is no corpse here to butcher.".to_string());
+    };
+    let rations = state.ground_items[index].item.aux.min(BUTCHER_MAX_RATIONS);
+    if rations <= 0 {
+        return Err(format!(
+            "There isn't enough of the {} to butcher.",
+            state.ground_items[index].item.name
+        ));
+    }
+
+    let corpse = state.ground_items.remove(index).item;
+    events.push(Event::LegacyHandled {
+        token: "butcher".to_string(),
+        note: format!("butchered {}", corpse.name),
+        fully_modeled: true,
+    });
+    if corpse.blessing < 0 {
+        return Ok(format!("You butcher the {}, but the meat is too foul to keep.", corpse.name));
+    }
+    for _ in 0..rations {
+        let _ = add_item_to_inventory_or_ground(state, "food ration", events);
+    }
+    let plural = if rations == 1 { "" } else { "s" };
+    Ok(format!("You butcher the {} into {rations} food ration{plural}.", corpse.name))
+}
+
 fn try_pickup_at_player(state: &mut GameState, events: &mut Vec<Event>) {
     let capacity = effective_inventory_capacity(state);
-    if state.player.inventory.len() >= capacity {
-        state.log.push("Inventory is full.".to_string());
-        events.push(Event::InventoryFull { capacity });
-    } else if let Some(ground_index) = ground_item_index_at(state, state.player.position) {
+    // Counted coins (such as a slain thief's haul) go straight into the purse, as legacy
+    // `gain_item` does for cash, instead of taking up a pack slot.
+    if let Some(ground_index) = ground_item_index_at(state, state.player.position)
+        && state.ground_items[ground_index].item.family == ItemFamily::Cash
+        && state.ground_items[ground_index].item.number > 0
+    {
         let ground = state.ground_items.remove(ground_index);
         state.log.push(format!("Picked up {}.", ground.item.name));
-        events.push(Event::PickedUp { item_id: ground.item.id, name: ground.item.name.clone() });
+        events.push(Event::PickedUp { item_id: ground.item.id, name: ground.item.name });
+        state.gain_gold(ground.item.number, "pickup", events);
+    } else if state.player.inventory.len() >= capacity {
+        let full = Event::InventoryFull { capacity };
+        state.log.push(state.format_event(&full));
+        events.push(full);
+    } else if let Some(ground_index) = ground_item_index_at(state, state.player.position) {
+        let ground = state.ground_items.remove(ground_index);
+        let picked_up = Event::PickedUp { item_id: ground.item.id, name: ground.item.name.clone() };
+        state.log.push(state.format_event(&picked_up));
+        events.push(picked_up);
         state.carry_burden = state.carry_burden.saturating_add(item_burden(&ground.item));
         auto_equip_item(state, &ground.item);
         push_item_to_pack_front(state, ground.item.id);
         state.player.inventory.push(ground.item);
         sync_pack_order(state);
     } else {
-        state.log.push("Nothing to pick up.".to_string());
+        state.log.push(state.format_event(&Event::NoItemToPickUp));
         events.push(Event::NoItemToPickUp);
     }
 }
@@ -13107,6 +17429,13 @@ fn equipped_weapon_is_two_handed(state: &GameState) -> bool {
 
 fn auto_equip_item(state: &mut GameState, item: &Item) {
     match item.family {
+        _ if is_backpack_item(item) => {
+            if state.player.equipment.left_shoulder.is_none() {
+                state.player.equipment.left_shoulder = Some(item.id);
+            } else if state.player.equipment.right_shoulder.is_none() {
+                state.player.equipment.right_shoulder = Some(item.id);
+            }
+        }
         ItemFamily::Weapon => {
             if state.player.equipment.weapon_hand.is_none() {
                 state.player.equipment.weapon_hand = Some(item.id);
@@ -13287,6 +17616,12 @@ fn remove_item_by_id(state: &mut GameState, item_id: u32) -> Option<Item> {
     Some(removed)
 }
 
+fn player_defense_total(state: &GameState, profile: &EquipmentEffectProfile) -> i32 {
+    (state.player.stats.defense + profile.defense_bonus
+        - state.encumbrance_tier().defense_penalty())
+    .max(0)
+}
+
 fn equipment_effect_profile(state: &GameState) -> EquipmentEffectProfile {
     let mut profile = EquipmentEffectProfile::default();
     for item_id in equipped_item_ids(&state.player.equipment) {
@@ -13358,6 +17693,9 @@ fn equipment_effect_profile(state: &GameState) -> EquipmentEffectProfile {
             "I_HOLDING" => {
                 profile.carry_capacity_delta += 8;
             }
+            BACKPACK_USEF => {
+                profile.carry_capacity_delta += backpack_capacity_bonus(item);
+            }
             _ => {}
         }
     }
@@ -13370,6 +17708,29 @@ fn effective_inventory_capacity(state: &GameState) -> usize {
     (base + profile.carry_capacity_delta).clamp(1, 64) as usize
 }
 
+const BACKPACK_USEF: &str = "I_BACKPACK";
+const BACKPACK_CAPACITY_BONUS: i32 = 6;
+
+fn is_backpack_item(item: &Item) -> bool {
+    item.usef == BACKPACK_USEF
+}
+
+fn backpack_capacity_bonus(item: &Item) -> i32 {
+    BACKPACK_CAPACITY_BONUS + item.plus.max(0)
+}
+
+// Capacity once `item` is no longer worn; only packs change the answer.
+fn inventory_capacity_without(state: &GameState, item: &Item) -> usize {
+    let capacity = effective_inventory_capacity(state);
+    let worn = equipped_item_ids(&state.player.equipment).contains(&item.id);
+    if !worn || !is_backpack_item(item) {
+        return capacity;
+    }
+    let profile = equipment_effect_profile(state);
+    let base = state.player.inventory_capacity as i32;
+    (base + profile.carry_capacity_delta - backpack_capacity_bonus(item)).clamp(1, 64) as usize
+}
+
 fn identify_inventory_items(state: &mut GameState) -> usize {
     let mut identified = 0usize;
     for entry in &mut state.player.inventory {
@@ -13537,6 +17898,34 @@ fn dispel_or_decurse_with_branching(state: &mut GameState, potency: i32) -> Stri
     "A smell of ozone and positive ions fills the air.".to_string()
 }
 
+const CLUB_ABSOLUTION_COST: i32 = 40;
+const ABSOLUTION_HEAT_RELIEF: i32 = 2;
+const ABSOLUTION_BOTCH_PERCENT: i32 = 5;
+
+/// Shared absolution rite for the club and the order: lifts legal heat and
+/// curses on worn items, but a botched rite also strips one worn blessing.
+fn perform_absolution_rite(state: &mut GameState, rng: &mut impl RandomSource) -> String {
+    state.legal_heat = state.legal_heat.saturating_sub(ABSOLUTION_HEAT_RELIEF).max(0);
+    let mut lifted = 0;
+    for item in state.player.inventory.iter_mut().filter(|item| item.used && item.blessing < 0) {
+        item.blessing = 0;
+        lifted += 1;
+    }
+    let mut note = match lifted {
+        0 => "Your conscience feels lighter.".to_string(),
+        1 => "A curse lifts from your gear.".to_string(),
+        _ => format!("{lifted} curses lift from your gear."),
+    };
+    if rng.range_inclusive_i32(1, 100) <= ABSOLUTION_BOTCH_PERCENT
+        && let Some(item) =
+            state.player.inventory.iter_mut().find(|item| item.used && item.blessing > 0)
+    {
+        item.blessing = 0;
+        note.push_str(&format!(" The rite falters, and {} loses its blessing.", item.name));
+    }
+    note
+}
+
 fn enchant_equipment_piece(
     state: &mut GameState,
     family: ItemFamily,
@@ -13547,7 +17936,11 @@ fn enchant_equipment_piece(
     !note.contains("unlucky")
 }
 
-fn apport_nearby_item(state: &mut GameState, radius: i32) -> Option<String> {
+fn apport_nearby_item(
+    state: &mut GameState,
+    radius: i32,
+    events: &mut Vec<Event>,
+) -> Option<String> {
     let player_pos = state.player.position;
     let idx = state
         .ground_items
@@ -13555,15 +17948,8 @@ fn apport_nearby_item(state: &mut GameState, radius: i32) -> Option<String> {
         .position(|ground| ground.position.manhattan_distance(player_pos) <= radius)?;
     let mut pulled = state.ground_items.remove(idx).item;
     pulled.known = true;
-    if state.player.inventory.len() < effective_inventory_capacity(state) {
-        state.carry_burden = state.carry_burden.saturating_add(item_burden(&pulled));
-        let note = pulled.name.clone();
-        auto_equip_item(state, &pulled);
-        state.player.inventory.push(pulled);
-        return Some(note);
-    }
     let note = pulled.name.clone();
-    state.ground_items.push(GroundItem { position: player_pos, item: pulled });
+    add_existing_item_to_inventory_or_ground(state, pulled, events);
     Some(note)
 }
 
@@ -13674,7 +18060,12 @@ fn count_detected_objects(state: &GameState, radius: i32) -> usize {
         .count()
 }
 
-fn apply_item_usef_effect(state: &mut GameState, item: &Item, events: &mut Vec<Event>) -> String {
+fn apply_item_usef_effect<R: RandomSource>(
+    state: &mut GameState,
+    item: &Item,
+    rng: &mut R,
+    events: &mut Vec<Event>,
+) -> String {
     let begin_item_projectile =
         |state: &mut GameState,
          item: &Item,
@@ -13708,8 +18099,15 @@ fn apply_item_usef_effect(state: &mut GameState, item: &Item, events: &mut Vec<E
         };
 
     match item.usef.as_str() {
+        _ if item.family == ItemFamily::Potion && is_poison_potion(item) => {
+            if apply_poison(state, POISON_POTION_TURNS, POISON_POTION_MAGNITUDE) {
+                "poison courses through your veins".to_string()
+            } else {
+                "you shrug off the poison".to_string()
+            }
+        }
         "I_HEAL" => {
-            state.player.stats.hp = (state.player.stats.hp + 12).min(state.player.stats.max_hp);
+            state.player.stats.heal(12);
             "healing effect applied".to_string()
         }
         "I_CURE" | "I_NEUTRALIZE_POISON" => {
@@ -13761,6 +18159,11 @@ fn apply_item_usef_effect(state: &mut GameState, item: &Item, events: &mut Vec<E
             recompute_derived_combat_and_mana_from_attributes(state);
             "attributes augmented".to_string()
         }
+        "I_LIQUID_FIRE" => {
+            let resist = i32::from(state.effective_resistances().fire.max(0));
+            let applied = state.player.stats.apply_damage((LIQUID_FIRE_DAMAGE - resist).max(0));
+            format!("the liquid fire scorches your throat for {applied} damage")
+        }
         "I_ALERT" => {
             consume_status(state, "poison");
             consume_status(state, "immobile");
@@ -13769,7 +18172,7 @@ fn apply_item_usef_effect(state: &mut GameState, item: &Item, events: &mut Vec<E
         }
         "I_ANTIOCH" => spell_damage_radius(state, events, 5, 40, "holy hand-grenade blast"),
         "I_APPORT" => {
-            if let Some(name) = apport_nearby_item(state, 6) {
+            if let Some(name) = apport_nearby_item(state, 6, events) {
                 format!("apported `{name}` into reach")
             } else {
                 "apportation failed: no nearby object".to_string()
@@ -13810,27 +18213,28 @@ fn apply_item_usef_effect(state: &mut GameState, item: &Item, events: &mut Vec<E
                 "charge dissipated: no stick to receive it".to_string()
             }
         }
+        "I_MAGIC_MAP" => reveal_magic_map(state, events),
         "I_CLAIRVOYANCE" => {
-            reveal_map_for_wizard(state);
+            reveal_map_for_wizard(state, events);
             "clairvoyant vision reveals the current map".to_string()
         }
         "I_CORPSE" => {
             if item.blessing < 0 {
                 let applied = state.player.stats.apply_damage(4);
-                push_or_refresh_status(&mut state.status_effects, "poison", 6, 1);
+                let _ = apply_poison(state, 6, 1);
                 if !state.player.stats.is_alive() {
                     mark_player_defeated(state, "tainted corpse", events);
                 }
                 format!("putrid corpse dealt {applied} damage")
             } else {
                 state.food += item.aux.max(2);
-                state.player.stats.hp = (state.player.stats.hp + 1).min(state.player.stats.max_hp);
+                state.player.stats.heal(1);
                 "corpse consumption restored some nutrition".to_string()
             }
         }
         "I_CRYSTAL" => {
             state.spellbook.max_mana = (state.spellbook.max_mana + 10).clamp(1, 500);
-            state.spellbook.mana = (state.spellbook.mana + 20).min(state.spellbook.max_mana);
+            state.spellbook.restore_mana(20);
             state.resistances.magic = state.resistances.magic.max(2);
             "crystal lattice amplifies magical reserve".to_string()
         }
@@ -13889,7 +18293,7 @@ fn apply_item_usef_effect(state: &mut GameState, item: &Item, events: &mut Vec<E
             format!("identified {identified} carried item(s)")
         }
         "I_ILLUMINATE" | "I_PERM_ILLUMINATE" => {
-            reveal_map_for_wizard(state);
+            reveal_map_for_wizard(state, events);
             push_or_refresh_status(&mut state.status_effects, "truesight", 12, 1);
             "illumination exposed hidden pathways".to_string()
         }
@@ -13923,7 +18327,7 @@ fn apply_item_usef_effect(state: &mut GameState, item: &Item, events: &mut Vec<E
             }
         }
         "I_KNOWLEDGE" => {
-            state.spellbook.mana = (state.spellbook.mana + 12).min(state.spellbook.max_mana);
+            state.spellbook.restore_mana(12);
             state.progression.quest_steps_completed =
                 state.progression.quest_steps_completed.saturating_add(1);
             "knowledge blooms in your mind".to_string()
@@ -13951,12 +18355,24 @@ fn apply_item_usef_effect(state: &mut GameState, item: &Item, events: &mut Vec<E
         }
         "I_MONDET" => {
             let detected = count_detected_monsters(state, 9);
-            format!("monster detection reports {detected} nearby threat(s)")
+            push_or_refresh_status(
+                &mut state.status_effects,
+                AURA_SIGHT_STATUS,
+                20,
+                AURA_SIGHT_RADIUS,
+            );
+            let sensed = state.sensed_monsters().len();
+            format!(
+                "monster detection reports {detected} nearby threat(s); {sensed} aura(s) linger in your sight"
+            )
         }
         "I_NORMAL_ARMOR" | "I_NORMAL_SHIELD" | "I_NORMAL_WEAPON" => {
             "mundane equipment has no activated power".to_string()
         }
         "I_NO_OP" | "I_NOTHING" => "nothing happened".to_string(),
+        BACKPACK_USEF => "a pack only adds room while worn on a shoulder".to_string(),
+        ROPE_USEF => "a rope is best thrown across a chasm".to_string(),
+        LIFE_SAVE_USEF => "the amulet stays cold until death draws near".to_string(),
         "I_OBJDET" => {
             let detected = count_detected_objects(state, 9);
             format!("object detection reports {detected} nearby object(s)")
@@ -13977,7 +18393,7 @@ fn apply_item_usef_effect(state: &mut GameState, item: &Item, events: &mut Vec<E
             "regenerative aura settles in".to_string()
         }
         "I_PERM_TRUESIGHT" | "I_TRUESIGHT" => {
-            reveal_map_for_wizard(state);
+            reveal_map_for_wizard(state, events);
             push_or_refresh_status(&mut state.status_effects, "truesight", 20, 1);
             "true sight pierces obfuscation".to_string()
         }
@@ -14004,7 +18420,7 @@ fn apply_item_usef_effect(state: &mut GameState, item: &Item, events: &mut Vec<E
         }
         "I_SPELLS" => {
             state.spellbook.max_mana = (state.spellbook.max_mana + 5).clamp(1, 500);
-            state.spellbook.mana = (state.spellbook.mana + 15).min(state.spellbook.max_mana);
+            state.spellbook.restore_mana(15);
             "arcane formulae settle into memory".to_string()
         }
         "I_STARGEM" => {
@@ -14014,8 +18430,8 @@ fn apply_item_usef_effect(state: &mut GameState, item: &Item, events: &mut Vec<E
             "stargem radiance binds itself to your quest".to_string()
         }
         "I_TELEPORT" | "I_WARP" => {
-            spell_shift_player(state, 9, 5);
-            "space folds around you".to_string()
+            let to = teleport_player_randomly(state, rng, events);
+            format!("space folds around you and you land at ({}, {})", to.x, to.y)
         }
         "I_TRAP" => {
             let (note, _) = disarm_adjacent_trap(state, events);
@@ -14023,13 +18439,13 @@ fn apply_item_usef_effect(state: &mut GameState, item: &Item, events: &mut Vec<E
         }
         "I_FOOD" | "I_LEMBAS" | "I_STIM" | "I_POW" => {
             state.food += item.aux.max(1);
-            state.player.stats.hp = (state.player.stats.hp + 2).min(state.player.stats.max_hp);
+            state.player.stats.heal(2);
             "nutrition restored".to_string()
         }
         "I_POISON_FOOD" => {
             let damage = (2 + item.aux.abs()).clamp(2, 18);
             let applied = state.player.stats.apply_damage(damage);
-            push_or_refresh_status(&mut state.status_effects, "poison", 10, 1);
+            let _ = apply_poison(state, 10, 1);
             if !state.player.stats.is_alive() {
                 mark_player_defeated(state, "poisoned food", events);
             }
@@ -14189,6 +18605,56 @@ fn push_or_refresh_status(
     effects.push(StatusEffect { id: id.to_string(), remaining_turns, magnitude });
 }
 
+const POISON_MAX_MAGNITUDE: i32 = 10;
+const OPPOSING_STATUS_PAIRS: [(&str, &str); 1] = [("haste", "slow")];
+
+fn opposing_status(id: &str) -> Option<&'static str> {
+    OPPOSING_STATUS_PAIRS.iter().find_map(|(first, second)| {
+        if *first == id {
+            Some(*second)
+        } else if *second == id {
+            Some(*first)
+        } else {
+            None
+        }
+    })
+}
+
+fn status_blocked_by_immunity(state: &GameState, id: &str) -> bool {
+    match id {
+        "poison" => {
+            state.immunities.poison || equipment_effect_profile(state).grants_poison_immunity
+        }
+        "fear" => state.immunities.fear || equipment_effect_profile(state).grants_fear_immunity,
+        "immobile" | "sleep" => state.immunities.sleep,
+        _ => false,
+    }
+}
+
+/// Adds a dose of poison, stacking onto any already in the system. Poison
+/// resistance weakens each dose as it lands; a fully resisted dose (or any
+/// dose against immunity) does nothing and returns `false`.
+fn apply_poison(state: &mut GameState, remaining_turns: u32, magnitude: i32) -> bool {
+    if state.immunities.poison || equipment_effect_profile(state).grants_poison_immunity {
+        return false;
+    }
+    let magnitude = magnitude - i32::from(state.effective_resistances().poison.max(0));
+    if magnitude <= 0 {
+        return false;
+    }
+    if let Some(existing) = state.status_effects.iter_mut().find(|effect| effect.id == "poison") {
+        existing.magnitude = (existing.magnitude.max(0) + magnitude).min(POISON_MAX_MAGNITUDE);
+        existing.remaining_turns = existing.remaining_turns.max(remaining_turns);
+        return true;
+    }
+    state.status_effects.push(StatusEffect {
+        id: "poison".to_string(),
+        remaining_turns,
+        magnitude: magnitude.min(POISON_MAX_MAGNITUDE),
+    });
+    true
+}
+
 fn status_magnitude(state: &GameState, id: &str) -> i32 {
     state.status_effects.iter().find(|effect| effect.id == id).map(|e| e.magnitude).unwrap_or(0)
 }
@@ -14198,7 +18664,7 @@ fn consume_status(state: &mut GameState, id: &str) {
 }
 
 fn mark_player_defeated(state: &mut GameState, source: impl Into<String>, events: &mut Vec<Event>) {
-    if state.status != SessionStatus::InProgress {
+    if state.status != SessionStatus::InProgress || try_avert_death(state, events) {
         return;
     }
     let source = source.into();
@@ -14245,6 +18711,7 @@ fn resolve_attack_command<R: RandomSource>(
     }
 
     let profile = equipment_effect_profile(state);
+    let agility_penalty = state.encumbrance_tier().agility_penalty();
     let effective_attack_min =
         (state.player.stats.attack_min + profile.attack_min_bonus).clamp(1, 400);
     let effective_attack_max = (state.player.stats.attack_max + profile.attack_max_bonus)
@@ -14253,9 +18720,45 @@ fn resolve_attack_command<R: RandomSource>(
 
     let target_pos = state.player.position.offset(direction);
     if let Some(monster_index) = monster_index_at(state, target_pos) {
+        let venom_bonus = match weapon_hand_item_mut(state) {
+            Some(weapon) if weapon.poison_doses > 0 => {
+                weapon.poison_doses -= 1;
+                weapon.poison_potency.max(1)
+            }
+            _ => 0,
+        };
+        if venom_bonus > 0 {
+            push_or_refresh_status(
+                &mut state.monsters[monster_index].status_effects,
+                "poison",
+                MONSTER_POISON_TURNS,
+                MONSTER_POISON_MAGNITUDE,
+            );
+        }
+        let sneak_bonus = if state.monsters[monster_index].awareness == Awareness::Asleep {
+            state
+                .log
+                .push(format!("You strike the sleeping {}!", state.monsters[monster_index].name));
+            SNEAK_ATTACK_BONUS
+        } else {
+            0
+        };
+        state.monsters[monster_index].awareness = Awareness::Aware;
+        let noise_radius = attack_noise_radius(state);
+        make_noise(state, target_pos, noise_radius);
+        state.pending_combat_alarm = Some(CombatAlarm {
+            origin: target_pos,
+            faction: state.monsters[monster_index].faction,
+            radius: noise_radius,
+        });
         let (monster_id, monster_name, monster_faction, damage_done, remaining_hp, defeated) = {
             let monster = &mut state.monsters[monster_index];
-            let rolled = rng.range_inclusive_i32(effective_attack_min, effective_attack_max);
+            let rolled = roll_combat_damage(
+                rng,
+                effective_attack_min,
+                effective_attack_max,
+                state.options.combat_variance,
+            );
             let maneuver_bonus = if combat_step.maneuver == CombatManeuver::Lunge { 2 } else { 0 };
             let line_bonus = match combat_step.line {
                 CombatLine::High => 1,
@@ -14263,8 +18766,11 @@ fn resolve_attack_command<R: RandomSource>(
                 CombatLine::Low => 1,
             };
             let mitigated = (rolled + profile.to_hit_bonus + maneuver_bonus + line_bonus
+                - agility_penalty
                 - monster.stats.defense)
-                .max(1);
+                .max(1)
+                + venom_bonus
+                + sneak_bonus;
             let applied = monster.stats.apply_damage(mitigated);
             (
                 monster.id,
@@ -14293,7 +18799,11 @@ fn resolve_attack_command<R: RandomSource>(
             let _ = remove_monster_with_drops(state, monster_index, events);
             state.monsters_defeated += 1;
             state.log.push(format!("{} is defeated.", monster_name));
-            events.push(Event::MonsterDefeated { monster_id });
+            events.push(Event::MonsterDefeated { monster_id, name: monster_name });
+        } else if rng.range_inclusive_i32(1, 20) >= CRITICAL_HIT_THRESHOLD {
+            let origin = state.player.position;
+            let lines = apply_knockback(state, monster_index, origin, events);
+            state.log.extend(lines);
         }
     } else {
         state.log.push("You swing at empty space.".to_string());
@@ -14301,19 +18811,272 @@ fn resolve_attack_command<R: RandomSource>(
     }
 }
 
+const KNOCKBACK_CRUSH_DAMAGE: i32 = 3;
+/// A surviving target is knocked back when a separate d20 meets this threshold.
+const CRITICAL_HIT_THRESHOLD: i32 = 19;
+
+/// The scripted order in which a duelist picks maneuvers, one per round.
+const DUEL_OPPONENT_SCRIPT: [CombatManeuver; 4] =
+    [CombatManeuver::Attack, CombatManeuver::Block, CombatManeuver::Lunge, CombatManeuver::Riposte];
+const DUEL_LUNGE_BONUS: i32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DuelExchange {
+    PlayerWins,
+    OpponentWins,
+    Trade,
+    Standoff,
+}
+
+fn combat_maneuver_label(maneuver: CombatManeuver) -> &'static str {
+    match maneuver {
+        CombatManeuver::Attack => "attack",
+        CombatManeuver::Block => "block",
+        CombatManeuver::Riposte => "riposte",
+        CombatManeuver::Lunge => "lunge",
+    }
+}
+
+fn duel_exchange_label(exchange: DuelExchange) -> &'static str {
+    match exchange {
+        DuelExchange::PlayerWins => "you have the better of it",
+        DuelExchange::OpponentWins => "you are caught out",
+        DuelExchange::Trade => "you trade blows",
+        DuelExchange::Standoff => "neither gives ground",
+    }
+}
+
+/// Attack breaks Block, Block stops Lunge, Lunge overruns Riposte, and
+/// Riposte punishes Attack. Two offensive moves trade blows; two guarded
+/// moves lead nowhere.
+fn duel_exchange(player: CombatManeuver, opponent: CombatManeuver) -> DuelExchange {
+    use CombatManeuver::{Attack, Block, Lunge, Riposte};
+    let beats = |a, b| {
+        matches!((a, b), (Attack, Block) | (Block, Lunge) | (Lunge, Riposte) | (Riposte, Attack))
+    };
+    if beats(player, opponent) {
+        DuelExchange::PlayerWins
+    } else if beats(opponent, player) {
+        DuelExchange::OpponentWins
+    } else if matches!(player, Attack | Lunge) && matches!(opponent, Attack | Lunge) {
+        DuelExchange::Trade
+    } else {
+        DuelExchange::Standoff
+    }
+}
+
+/// A duel only holds while the opponent stays within reach; stepping away
+/// (or the opponent vanishing) frees it to fight normally again.
+fn break_off_distant_duel(state: &mut GameState) {
+    let Some(duel) = state.active_duel else {
+        return;
+    };
+    let opponent = state.monsters.iter().find(|monster| monster.id == duel.monster_id);
+    if opponent
+        .is_some_and(|monster| monster.position.manhattan_distance(state.player.position) == 1)
+    {
+        return;
+    }
+    state.active_duel = None;
+    if let Some(monster) = opponent {
+        state.log.push(format!("Your duel with the {} is broken off.", monster.name));
+    }
+}
+
+fn resolve_engage_command<R: RandomSource>(
+    state: &mut GameState,
+    direction: Direction,
+    rng: &mut R,
+    events: &mut Vec<Event>,
+) {
+    let target_pos = state.player.position.offset(direction);
+    let Some(idx) = monster_index_at(state, target_pos) else {
+        state.active_duel = None;
+        state.log.push("There is no one there to duel.".to_string());
+        return;
+    };
+    let monster_id = state.monsters[idx].id;
+    let round = match state.active_duel {
+        Some(duel) if duel.monster_id == monster_id => duel.round + 1,
+        _ if !state.monsters[idx].duelist => {
+            state.log.push(format!("The {} will not accept a duel.", state.monsters[idx].name));
+            return;
+        }
+        _ => {
+            state.log.push(format!("You square off against {}.", state.monsters[idx].name));
+            1
+        }
+    };
+    state.active_duel = Some(DuelState { monster_id, round });
+    state.monsters[idx].awareness = Awareness::Aware;
+
+    let player_step = next_combat_step(state);
+    let opponent_move = DUEL_OPPONENT_SCRIPT[(round as usize - 1) % DUEL_OPPONENT_SCRIPT.len()];
+    let exchange = duel_exchange(player_step.maneuver, opponent_move);
+    let monster_name = state.monsters[idx].name.clone();
+    let note = format!(
+        "Round {round}: your {} against the {monster_name}'s {}; {}.",
+        combat_maneuver_label(player_step.maneuver),
+        combat_maneuver_label(opponent_move),
+        duel_exchange_label(exchange)
+    );
+    state.log.push(note.clone());
+    events.push(Event::LegacyHandled { token: "duel".to_string(), note, fully_modeled: true });
+
+    let profile = equipment_effect_profile(state);
+    if matches!(exchange, DuelExchange::PlayerWins | DuelExchange::Trade) {
+        let attack_min = (state.player.stats.attack_min + profile.attack_min_bonus).max(1);
+        let attack_max = (state.player.stats.attack_max + profile.attack_max_bonus).max(attack_min);
+        let lunge =
+            if player_step.maneuver == CombatManeuver::Lunge { DUEL_LUNGE_BONUS } else { 0 };
+        let rolled = roll_combat_damage(rng, attack_min, attack_max, state.options.combat_variance);
+        let monster = &mut state.monsters[idx];
+        let damage = monster.stats.apply_damage((rolled + lunge - monster.stats.defense).max(1));
+        let remaining_hp = monster.stats.hp;
+        state.log.push(format!("You hit {monster_name} for {damage} damage."));
+        events.push(Event::Attacked { monster_id, damage, remaining_hp });
+    }
+    if matches!(exchange, DuelExchange::OpponentWins | DuelExchange::Trade) {
+        let stats = state.monsters[idx].stats;
+        let lunge = if opponent_move == CombatManeuver::Lunge { DUEL_LUNGE_BONUS } else { 0 };
+        let rolled = roll_combat_damage(
+            rng,
+            stats.attack_min,
+            stats.attack_max.max(stats.attack_min),
+            state.options.combat_variance,
+        );
+        let defense = player_defense_total(state, &profile);
+        let damage = state.player.stats.apply_damage((rolled + lunge - defense).max(1));
+        state.log.push(format!("The {monster_name} hits you for {damage} damage."));
+        events.push(Event::MonsterAttacked {
+            monster_id,
+            damage,
+            remaining_hp: state.player.stats.hp,
+        });
+    }
+
+    if !state.monsters[idx].stats.is_alive() {
+        state.active_duel = None;
+        let _ = remove_monster_with_drops(state, idx, events);
+        state.monsters_defeated += 1;
+        state.log.push(format!("{monster_name} is defeated."));
+        events.push(Event::MonsterDefeated { monster_id, name: monster_name });
+    } else if !state.player.stats.is_alive() {
+        state.active_duel = None;
+        mark_player_defeated(state, format!("a duel with {monster_name}"), events);
+    }
+}
+
+const SNEAK_ATTACK_BONUS: i32 = 4;
+const ATTACK_NOISE_RADIUS: i32 = 4;
+const STEALTHY_ATTACK_NOISE_RADIUS: i32 = 1;
+const MONSTER_WAKE_RADIUS: i32 = 2;
+const MONSTER_SEARCH_RADIUS: i32 = 5;
+
+/// How far a searching monster can pick the player out; an unseen player
+/// has to blunder right into it.
+fn monster_search_radius(state: &GameState) -> i32 {
+    if status_magnitude(state, "invisible") > 0 {
+        MONSTER_WAKE_RADIUS
+    } else {
+        MONSTER_SEARCH_RADIUS
+    }
+}
+
+/// Fighting while unseen carries only as far as the monsters beside the target.
+fn attack_noise_radius(state: &GameState) -> i32 {
+    if status_magnitude(state, "invisible") > 0 {
+        STEALTHY_ATTACK_NOISE_RADIUS
+    } else {
+        ATTACK_NOISE_RADIUS
+    }
+}
+
+/// Puts the struck monster's faction on alert: allies within the alarm
+/// radius stop searching or sleeping and become fully aware of the player.
+fn propagate_combat_alarm(state: &mut GameState) {
+    let Some(alarm) = state.pending_combat_alarm.take() else {
+        return;
+    };
+    for monster in &mut state.monsters {
+        if monster.faction == alarm.faction
+            && monster.awareness != Awareness::Aware
+            && monster.position.manhattan_distance(alarm.origin) <= alarm.radius
+        {
+            monster.awareness = Awareness::Aware;
+            state.log.push(format!("The {} is alerted by the fighting!", monster.name));
+        }
+    }
+}
+
+/// Rouses sleeping monsters within `radius` of `origin`. They have heard
+/// something rather than seen the player, so they start out searching.
+fn make_noise(state: &mut GameState, origin: Position, radius: i32) {
+    for monster in &mut state.monsters {
+        if monster.awareness == Awareness::Asleep
+            && monster.position.manhattan_distance(origin) <= radius
+        {
+            monster.awareness = Awareness::Searching;
+        }
+    }
+}
+
+fn apply_knockback(
+    state: &mut GameState,
+    monster_idx: usize,
+    source: Position,
+    events: &mut Vec<Event>,
+) -> Vec<String> {
+    let (monster_id, from, name) = {
+        let monster = &state.monsters[monster_idx];
+        (monster.id, monster.position, monster.name.clone())
+    };
+    let to = Position {
+        x: from.x + (from.x - source.x).signum(),
+        y: from.y + (from.y - source.y).signum(),
+    };
+    if to == from {
+        return Vec::new();
+    }
+    if !state.bounds.contains(to) || !state.tile_is_walkable(to) {
+        let applied = state.monsters[monster_idx].stats.apply_damage(KNOCKBACK_CRUSH_DAMAGE);
+        let remaining_hp = state.monsters[monster_idx].stats.hp;
+        events.push(Event::Attacked { monster_id, damage: applied, remaining_hp });
+        let mut lines = vec![format!("The {name} is slammed into the wall for {applied} damage.")];
+        if !state.monsters[monster_idx].stats.is_alive() {
+            let _ = remove_monster_with_drops(state, monster_idx, events);
+            state.monsters_defeated = state.monsters_defeated.saturating_add(1);
+            lines.push(format!("{name} is defeated."));
+            events.push(Event::MonsterDefeated { monster_id, name });
+        }
+        return lines;
+    }
+    if is_occupied(state, to) {
+        return Vec::new();
+    }
+    state.monsters[monster_idx].position = to;
+    events.push(Event::MonsterMoved { monster_id, from, to });
+    vec![format!("The {name} is knocked back.")]
+}
+
 fn estimate_action_points(command: &Command, world_mode: WorldMode) -> u16 {
     match command {
-        Command::Wait => 100,
-        Command::Move(_) => {
+        Command::Wait | Command::WaitTurns { .. } => 100,
+        Command::Move(_) | Command::AutoExplore => {
             if world_mode == WorldMode::Countryside {
                 125
             } else {
                 80
             }
         }
-        Command::Attack(_) => 125,
+        Command::Attack(_) | Command::Engage(_) | Command::Bash(_) => 125,
+        Command::ApplyLockpick(_) | Command::Butcher => 150,
+        Command::ApplyTool { .. } | Command::Engrave { .. } | Command::Dip { .. } => 100,
+        Command::Zap { .. } | Command::Throw { .. } | Command::OfferSurrender => 100,
+        Command::GuildAction { .. } | Command::RecallCompanions => 100,
+        Command::Look => 0,
         Command::Pickup => 90,
-        Command::Drop { .. } => 70,
+        Command::Drop { .. } | Command::DropQuantity { .. } | Command::Bank { .. } => 70,
         Command::Legacy { token } => match token.trim() {
             "H" => 300,
             "s" => {
@@ -14346,7 +19109,10 @@ fn apply_environment_effects<R: RandomSource>(
     rng: &mut R,
     events: &mut Vec<Event>,
 ) {
-    state.scheduler.environment_phase = state.scheduler.environment_phase.saturating_add(1);
+    let turn = state.clock.turn;
+    state.engravings.retain(|engraving| {
+        turn.saturating_sub(engraving.turn_engraved) < ENGRAVING_LIFETIME_TURNS
+    });
 
     // Fire Propagation Logic
     let mut to_ignite = Vec::new();
@@ -14402,33 +19168,12 @@ fn apply_environment_effects<R: RandomSource>(
         }
     }
 
-    let profile = equipment_effect_profile(state);
-    let poison_resist = i32::from(state.resistances.poison.max(0)) + profile.poison_resist_bonus;
-    let poison_immune = state.immunities.poison || profile.grants_poison_immunity;
-    if let Some(trap) =
-        state.traps.iter_mut().find(|trap| trap.armed && trap.position == state.player.position)
-    {
-        let reduced = (trap.damage - poison_resist).max(0);
-        let applied = if poison_immune { 0 } else { state.player.stats.apply_damage(reduced) };
-        let trap_effect_id = trap.effect_id.clone();
-        state.log.push(format!(
-            "Trap {} triggers for {} damage (effect {}).",
-            trap.id, applied, trap.effect_id
-        ));
-        if applied > 0 && trap.effect_id == "poison" && !poison_immune {
-            push_or_refresh_status(&mut state.status_effects, "poison", 3, 1);
-        }
-        trap.armed = false;
-        events.push(Event::LegacyHandled {
-            token: "trap".to_string(),
-            note: format!("trap {} triggered", trap.id),
-            fully_modeled: true,
-        });
-        if applied > 0 && !state.player.stats.is_alive() {
-            mark_player_defeated(state, format!("{trap_effect_id} trap"), events);
-        }
+    if !player_is_levitating(state) {
+        trigger_trap_under_player(state, rng, events);
     }
 
+    apply_questionnaire_flavor_events(state, rng, events);
+
     // Fire Propagation
     let mut fire_updates = Vec::new();
     let width = state.bounds.width as usize;
@@ -14484,21 +19229,148 @@ fn apply_environment_effects<R: RandomSource>(
     }
 }
 
-fn apply_status_effects(state: &mut GameState, events: &mut Vec<Event>) {
-    state.scheduler.timed_effect_phase = state.scheduler.timed_effect_phase.saturating_add(1);
+fn apply_questionnaire_flavor_events<R: RandomSource>(
+    state: &mut GameState,
+    rng: &mut R,
+    events: &mut Vec<Event>,
+) {
+    if !state.questionnaire_traits.has_esp || state.monsters.is_empty() {
+        return;
+    }
+    if rng.range_inclusive_i32(1, 10) != 1 {
+        return;
+    }
+    let Some(idx) = nearest_monster_index(state, 10) else {
+        return;
+    };
+    let name = state.monsters[idx].name.clone();
+    let note = format!("You sense the presence of a {name} nearby.");
+    state.log.push(note.clone());
+    events.push(Event::LegacyHandled { token: "esp".to_string(), note, fully_modeled: true });
+}
+
+fn player_is_levitating(state: &GameState) -> bool {
+    state.status_effects.iter().any(|effect| effect.id == "levitate")
+}
+
+fn trigger_trap_under_player<R: RandomSource>(
+    state: &mut GameState,
+    rng: &mut R,
+    events: &mut Vec<Event>,
+) {
     let profile = equipment_effect_profile(state);
-    if profile.regen_per_turn > 0 && state.player.stats.hp < state.player.stats.max_hp {
-        let regen = profile.regen_per_turn.max(0);
-        state.player.stats.hp = (state.player.stats.hp + regen).min(state.player.stats.max_hp);
-        state.log.push(format!("Equipped regeneration restores {regen} hp."));
+    let poison_resist = i32::from(state.effective_resistances().poison.max(0));
+    let poison_immune = state.immunities.poison || profile.grants_poison_immunity;
+    let Some(trap) =
+        state.traps.iter_mut().find(|trap| trap.armed && trap.position == state.player.position)
+    else {
+        return;
+    };
+    let reduced = (trap.damage - poison_resist).max(0);
+    let applied = if poison_immune { 0 } else { state.player.stats.apply_damage(reduced) };
+    let trap_effect_id = trap.effect_id.clone();
+    state.log.push(format!(
+        "Trap {} triggers for {} damage (effect {}).",
+        trap.id, applied, trap.effect_id
+    ));
+    trap.armed = false;
+    events.push(Event::LegacyHandled {
+        token: "trap".to_string(),
+        note: format!("trap {} triggered", trap.id),
+        fully_modeled: true,
+    });
+    if applied > 0 && trap_effect_id == "poison" && !poison_immune {
+        let _ = apply_poison(state, 3, 1);
+    }
+    if applied > 0 && !state.player.stats.is_alive() {
+        mark_player_defeated(state, format!("{trap_effect_id} trap"), events);
+        return;
+    }
+    if trap_effect_id == "teleport" {
+        state.log.push("The trap whisks you away!".to_string());
+        teleport_player_randomly(state, rng, events);
+    }
+    if trap_effect_id == "gold_theft" && state.gold > 0 {
+        let lost = (state.gold / 4).max(1);
+        if state.spend_gold(lost, "trap", events) {
+            state.log.push(format!("Hidden fingers lift {lost} gold from your purse!"));
+        }
     }
+}
+
+fn teleport_player_randomly<R: RandomSource>(
+    state: &mut GameState,
+    rng: &mut R,
+    events: &mut Vec<Event>,
+) -> Position {
+    let from = state.player.position;
+    let requested = Position {
+        x: rng.range_inclusive_i32(0, state.bounds.width.saturating_sub(1)),
+        y: rng.range_inclusive_i32(0, state.bounds.height.saturating_sub(1)),
+    };
+    let to = match sanitize_spawn(state, requested) {
+        Some(pos) if pos == from || !is_occupied(state, pos) => pos,
+        _ => from,
+    };
+    state.player.position = to;
+    events.push(Event::Teleported { from, to });
+    to
+}
+
+// Hazards skipped while aloft catch the player the moment levitation ends.
+fn resolve_levitation_lapse<R: RandomSource>(
+    state: &mut GameState,
+    rng: &mut R,
+    events: &mut Vec<Event>,
+) {
+    resolve_chasm_arrival(state, rng, events);
+    if state.status == SessionStatus::InProgress {
+        trigger_trap_under_player(state, rng, events);
+    }
+}
+
+const RECOVERY_MIN_FOOD: i32 = 1;
+const TOO_HUNGRY_TO_RECOVER: &str = "You're too hungry to recover.";
+
+// A starving body can't knit itself back together; paid healing still works.
+fn too_hungry_to_recover(state: &GameState) -> bool {
+    state.food < RECOVERY_MIN_FOOD
+}
+
+fn warn_too_hungry_to_recover(state: &mut GameState) {
+    if !state.hunger_recovery_warned {
+        state.hunger_recovery_warned = true;
+        state.log.push(TOO_HUNGRY_TO_RECOVER.to_string());
+    }
+}
+
+// Regeneration (equipment and status) and poison are summed first and only the net change is
+// applied, so the per-turn hp delta doesn't depend on status order or the max-hp cap.
+fn apply_status_effects<R: RandomSource>(
+    state: &mut GameState,
+    rng: &mut R,
+    events: &mut Vec<Event>,
+) {
+    if !too_hungry_to_recover(state) {
+        state.hunger_recovery_warned = false;
+    }
+    let profile = equipment_effect_profile(state);
+    let equipped_regen = profile.regen_per_turn.max(0);
     if state.status_effects.is_empty() {
+        if equipped_regen > 0 && state.player.stats.hp < state.player.stats.max_hp {
+            if too_hungry_to_recover(state) {
+                warn_too_hungry_to_recover(state);
+                return;
+            }
+            state.player.stats.heal(equipped_regen);
+            state.log.push(format!("Equipped regeneration restores {equipped_regen} hp."));
+        }
         return;
     }
 
     let mut expired = Vec::new();
-    let mut defeat_source: Option<String> = None;
-    let poison_resist = i32::from(state.resistances.poison.max(0)) + profile.poison_resist_bonus;
+    let mut heal_total = equipped_regen;
+    let mut poison_total = 0;
     let poison_immune = state.immunities.poison || profile.grants_poison_immunity;
     for effect in &mut state.status_effects {
         match effect.id.as_str() {
@@ -14506,23 +19378,13 @@ fn apply_status_effects(state: &mut GameState, events: &mut Vec<Event>) {
                 if poison_immune {
                     continue;
                 }
-                let reduced = effect.magnitude.max(0) - poison_resist;
-                let damage = reduced.max(0);
-                if damage > 0 {
-                    let applied = state.player.stats.apply_damage(damage);
-                    state.log.push(format!("Poison deals {applied} damage."));
-                    if applied > 0 && !state.player.stats.is_alive() && defeat_source.is_none() {
-                        defeat_source = Some("poison".to_string());
-                    }
-                }
+                // Resistance already weakened the dose when it was applied.
+                poison_total += effect.magnitude.max(0);
+                // Poison fades as it works through the system.
+                effect.magnitude = (effect.magnitude - 1).max(1);
             }
             "regen" => {
-                let heal = effect.magnitude.max(0);
-                if heal > 0 {
-                    state.player.stats.hp =
-                        (state.player.stats.hp + heal).min(state.player.stats.max_hp);
-                    state.log.push(format!("Regeneration restores {heal} hp."));
-                }
+                heal_total += effect.magnitude.max(0);
             }
             _ => {}
         }
@@ -14542,6 +19404,31 @@ fn apply_status_effects(state: &mut GameState, events: &mut Vec<Event>) {
         }
     }
 
+    let mut defeat_source: Option<String> = None;
+    if poison_total > 0 {
+        state.log.push(format!("Poison deals {poison_total} damage."));
+    }
+    if heal_total > 0
+        && state.player.stats.hp < state.player.stats.max_hp
+        && too_hungry_to_recover(state)
+    {
+        warn_too_hungry_to_recover(state);
+        heal_total = 0;
+    }
+    if heal_total > 0 && (poison_total > 0 || state.player.stats.hp < state.player.stats.max_hp) {
+        state.log.push(format!("Regeneration restores {heal_total} hp."));
+    }
+    let net = heal_total - poison_total;
+    if net < 0 {
+        let applied = state.player.stats.apply_damage(-net);
+        if applied > 0 && !state.player.stats.is_alive() {
+            defeat_source = Some("poison".to_string());
+        }
+    } else if net > 0 {
+        state.player.stats.heal(net);
+    }
+
+    let levitation_lapsed = expired.iter().any(|id| id == "levitate");
     if !expired.is_empty() {
         state.status_effects.retain(|effect| effect.remaining_turns > 0);
         for effect_id in expired {
@@ -14562,6 +19449,9 @@ fn apply_status_effects(state: &mut GameState, events: &mut Vec<Event>) {
             events.push(Event::StatusExpired { effect_id });
         }
     }
+    if levitation_lapsed && !player_is_levitating(state) {
+        resolve_levitation_lapse(state, rng, events);
+    }
 
     if state.player.stats.hp <= 0 && state.status == SessionStatus::InProgress {
         let source = defeat_source.unwrap_or_else(|| "lingering wounds".to_string());
@@ -14623,6 +19513,90 @@ fn update_progression_from_combat(state: &mut GameState, events: &mut Vec<Event>
     });
 }
 
+const ARENA_SURRENDER_FINE: i32 = 50;
+const ARENA_SURRENDER_XP_PENALTY: i64 = 50;
+const ARENA_WAGER_STAKE: i32 = 50;
+const ARENA_WAGER_MIN_ODDS: i32 = 25;
+const ARENA_WAGER_MAX_ODDS: i32 = 400;
+
+fn arena_fighter_rating(stats: &Stats) -> i32 {
+    stats.max_hp + (stats.attack_min + stats.attack_max) * 2 + stats.defense * 3
+}
+
+/// Bookmaker odds, in percent of the stake, for the player beating the next
+/// scheduled challenger. Long shots pay more.
+fn arena_wager_odds(state: &GameState) -> i32 {
+    let (_, rival) =
+        arena_rival_profile(state.progression.arena_opponent, state.progression.arena_rank);
+    let player = arena_fighter_rating(&state.player.stats).max(1);
+    (arena_fighter_rating(&rival) * 100 / player).clamp(ARENA_WAGER_MIN_ODDS, ARENA_WAGER_MAX_ODDS)
+}
+
+fn place_arena_wager(state: &mut GameState, events: &mut Vec<Event>) -> String {
+    if state.progression.arena_match_active {
+        return "Betting closed when the portcullis dropped.".to_string();
+    }
+    if state.progression.arena_wager > 0 {
+        return format!(
+            "You already have {} gold riding on your next bout.",
+            state.progression.arena_wager
+        );
+    }
+    let odds = arena_wager_odds(state);
+    if !state.spend_gold(ARENA_WAGER_STAKE, "arena_wager", events) {
+        return "Not enough gold to cover the bookmaker's stake.".to_string();
+    }
+    state.progression.arena_wager = ARENA_WAGER_STAKE;
+    state.progression.arena_wager_odds = odds;
+    format!("The bookmaker takes {ARENA_WAGER_STAKE} gold on you at {odds}% odds.")
+}
+
+fn settle_arena_wager(state: &mut GameState, won: bool, events: &mut Vec<Event>) {
+    let stake = std::mem::take(&mut state.progression.arena_wager);
+    let odds = std::mem::take(&mut state.progression.arena_wager_odds);
+    if stake <= 0 {
+        return;
+    }
+    if won {
+        let payout = stake.saturating_add(stake * odds / 100);
+        state.gain_gold(payout, "arena_wager", events);
+        state.log.push(format!("The bookmaker pays out {payout} gold on your wager."));
+    } else {
+        state.log.push(format!("Your {stake} gold wager is forfeit."));
+    }
+}
+
+fn offer_arena_surrender(state: &mut GameState, events: &mut Vec<Event>) -> String {
+    if state.environment != LegacyEnvironment::Arena || !state.progression.arena_match_active {
+        return "There is no match to yield.".to_string();
+    }
+    // The crowd only accepts a yield from someone who is visibly losing.
+    if state.player.stats.hp * 2 > state.player.stats.max_hp {
+        return "The crowd jeers; you are hardly beaten yet.".to_string();
+    }
+    state.progression.quests.arena.xp =
+        (state.progression.quests.arena.xp - ARENA_SURRENDER_XP_PENALTY).max(0);
+    state.progression.arena_match_active = false;
+    state.activate_city_view();
+    if let Some(city_pos) = state.topology.last_city_position
+        && let Some(spawn) = sanitize_spawn(state, city_pos)
+    {
+        state.player.position = spawn;
+    }
+    // The fine takes whatever is left in the purse if it cannot be paid in full.
+    let fine = if state.spend_gold(ARENA_SURRENDER_FINE, "arena", events) {
+        ARENA_SURRENDER_FINE
+    } else {
+        let remaining = state.gold.max(0);
+        let _ = state.spend_gold(remaining, "arena", events);
+        remaining
+    };
+    settle_arena_wager(state, false, events);
+    format!(
+        "You yield to the jeers of the crowd. The arena fines you {fine} gold and you are escorted back to Rampart."
+    )
+}
+
 fn resolve_arena_round(state: &mut GameState, events: &mut Vec<Event>) {
     if state.environment != LegacyEnvironment::Arena || !state.progression.arena_match_active {
         return;
@@ -14638,7 +19612,8 @@ fn resolve_arena_round(state: &mut GameState, events: &mut Vec<Event>) {
         state.progression.quests.arena.xp.saturating_add(i64::from(defeated) * 25);
 
     let prize = 25 + i32::from(defeated) * 40;
-    state.gold = state.gold.saturating_add(prize);
+    state.gain_gold(prize, "arena", events);
+    settle_arena_wager(state, true, events);
     if state.progression.arena_rank > 0
         && state.progression.arena_rank < 4
         && state.progression.arena_opponent > 5
@@ -14657,11 +19632,6 @@ fn resolve_arena_round(state: &mut GameState, events: &mut Vec<Event>) {
             "The portcullis remains shut. Find and activate the opener to leave.".to_string(),
         );
     }
-    events.push(Event::EconomyUpdated {
-        source: "arena".to_string(),
-        gold: state.gold,
-        bank_gold: state.bank_gold,
-    });
 }
 
 fn apply_explicit_victory_trigger(
@@ -14683,7 +19653,38 @@ fn is_adept_for_ending(progression: &PlayerProgression) -> bool {
     progression.adept_rank > 0 || progression.total_winner_unlocked
 }
 
+const CONDO_RETIREMENT_HOARD: i32 = 2_000;
+
+// Set by the castle (0x0004) and palace (0x0008) final-rites completions.
+const MAIN_QUEST_FINAL_RITES_FLAGS: u64 = 0x0004 | 0x0008;
+
+const LIFE_SAVE_USEF: &str = "I_LIFE_SAVE";
+
+/// Hit points a life-saving item restores: classic play keeps the legacy
+/// half-health reprieve, modern play brings the player back at full health.
+fn life_save_restored_hp(state: &GameState) -> i32 {
+    match state.mode {
+        GameMode::Classic => (state.player.stats.max_hp / 2).max(1),
+        GameMode::Modern => state.player.stats.max_hp.max(1),
+    }
+}
+
+/// Spends a carried life-saving item to pull the player back from a fatal
+/// blow before the defeat is recorded. Returns `true` when death was averted.
+fn try_avert_death(state: &mut GameState, events: &mut Vec<Event>) -> bool {
+    let Some(item) = state.player.inventory.iter().find(|item| item.usef == LIFE_SAVE_USEF) else {
+        return false;
+    };
+    let (item_id, name) = (item.id, item.name.clone());
+    let _ = destroy_inventory_item_by_id(state, item_id);
+    state.player.stats.set_hp(life_save_restored_hp(state));
+    state.log.push(format!("Your {name} crumbles to dust, and you cling to life!"));
+    events.push(Event::DeathAverted { item_id, name });
+    true
+}
+
 fn resolve_session_outcome(state: &mut GameState, events: &mut Vec<Event>) {
+    state.check_main_quest_victory(events);
     let (ending, base_score) = match state.status {
         SessionStatus::InProgress => return,
         SessionStatus::Lost => (EndingKind::Defeat, (state.monsters_defeated as i64) * 5),
@@ -14691,7 +19692,9 @@ fn resolve_session_outcome(state: &mut GameState, events: &mut Vec<Event>) {
             if state.progression.victory_trigger.is_none() {
                 state.progression.victory_trigger = Some(VictoryTrigger::ExplicitQuestCompletion);
             }
-            if is_adept_for_ending(&state.progression) {
+            if state.progression.victory_trigger == Some(VictoryTrigger::RetireCondo) {
+                (EndingKind::Victory, i64::from(state.gold + state.bank_gold))
+            } else if is_adept_for_ending(&state.progression) {
                 (EndingKind::TotalWinner, 5_000 + (state.monsters_defeated as i64) * 25)
             } else {
                 (EndingKind::Victory, 2_000 + (state.monsters_defeated as i64) * 20)
@@ -14722,6 +19725,10 @@ fn ground_item_index_at(state: &GameState, position: Position) -> Option<usize>
 
 fn is_monster_occupied_except(state: &GameState, position: Position, except_id: u64) -> bool {
     state.monsters.iter().any(|monster| monster.id != except_id && monster.position == position)
+        || state
+            .companions
+            .iter()
+            .any(|companion| companion.id != except_id && companion.position == position)
 }
 
 fn next_monster_step(monster: Position, player: Position) -> Position {
@@ -14767,7 +19774,7 @@ fn resolve_monster_projectile_strike<R: RandomSource>(
         return true;
     }
 
-    let defense_total = state.player.stats.defense + equipment_profile.defense_bonus;
+    let defense_total = player_defense_total(state, equipment_profile);
     let to_hit = attack_max + 6;
     if !legacy_hit_roll(to_hit, defense_total, rng) {
         state.log.push(format!("{monster_name} launches a magic missile, but misses."));
@@ -14779,7 +19786,7 @@ fn resolve_monster_projectile_strike<R: RandomSource>(
         return true;
     }
 
-    let rolled = rng.range_inclusive_i32(attack_min, attack_max);
+    let rolled = roll_combat_damage(rng, attack_min, attack_max, state.options.combat_variance);
     let resolved_damage = (rolled - (defense_total / 2)).max(1);
     let damage = state.player.stats.apply_damage(resolved_damage);
     let remaining_hp = state.player.stats.hp;
@@ -14796,9 +19803,50 @@ fn resolve_monster_projectile_strike<R: RandomSource>(
     true
 }
 
+/// Advances a monster's own status effects at the start of its turn. Returns
+/// `false` when the monster loses the turn, either by dying to poison or by
+/// being too slowed to act this round.
+fn tick_monster_status_effects(state: &mut GameState, idx: usize, events: &mut Vec<Event>) -> bool {
+    if state.monsters[idx].status_effects.is_empty() {
+        return true;
+    }
+    let mut poison_total = 0;
+    let mut slowed = false;
+    for effect in &mut state.monsters[idx].status_effects {
+        match effect.id.as_str() {
+            "poison" => {
+                poison_total += effect.magnitude.max(0);
+                effect.magnitude = (effect.magnitude - 1).max(1);
+            }
+            "slow" => slowed = true,
+            _ => {}
+        }
+        effect.remaining_turns = effect.remaining_turns.saturating_sub(1);
+    }
+    state.monsters[idx].status_effects.retain(|effect| effect.remaining_turns > 0);
+
+    if poison_total > 0 {
+        let monster_id = state.monsters[idx].id;
+        let name = state.monsters[idx].name.clone();
+        let applied = state.monsters[idx].stats.apply_damage(poison_total);
+        let remaining_hp = state.monsters[idx].stats.hp;
+        events.push(Event::Attacked { monster_id, damage: applied, remaining_hp });
+        state.log.push(format!("The {name} suffers {applied} poison damage."));
+        if !state.monsters[idx].stats.is_alive() {
+            let _ = remove_monster_with_drops(state, idx, events);
+            state.monsters_defeated = state.monsters_defeated.saturating_add(1);
+            state.log.push(format!("{name} succumbs to poison."));
+            events.push(Event::MonsterDefeated { monster_id, name });
+            return false;
+        }
+    }
+    !(slowed && state.clock.turn % 2 == 1)
+}
+
 fn run_monster_turn<R: RandomSource>(state: &mut GameState, rng: &mut R, events: &mut Vec<Event>) {
-    state.scheduler.monster_phase = state.scheduler.monster_phase.saturating_add(1);
     let equipment_profile = equipment_effect_profile(state);
+    propagate_combat_alarm(state);
+    break_off_distant_duel(state);
     let monster_ids: Vec<u64> = state.monsters.iter().map(|m| m.id).collect();
 
     for monster_id in monster_ids {
@@ -14809,20 +19857,61 @@ fn run_monster_turn<R: RandomSource>(state: &mut GameState, rng: &mut R, events:
         let Some(idx) = state.monsters.iter().position(|m| m.id == monster_id) else {
             continue;
         };
+        if !tick_monster_status_effects(state, idx, events) {
+            continue;
+        }
+        if state.active_duel.is_some_and(|duel| duel.monster_id == monster_id) {
+            // A dueling opponent only acts through the duel exchanges.
+            continue;
+        }
+        if state.monsters[idx].awareness == Awareness::Asleep {
+            let distance = state.monsters[idx].position.manhattan_distance(state.player.position);
+            if distance <= MONSTER_WAKE_RADIUS {
+                state.monsters[idx].awareness = Awareness::Aware;
+                state.log.push(format!("The {} wakes up!", state.monsters[idx].name));
+            }
+            continue;
+        }
+        if state.monsters[idx].awareness == Awareness::Searching {
+            let monster_pos = state.monsters[idx].position;
+            let distance = monster_pos.manhattan_distance(state.player.position);
+            if distance <= monster_search_radius(state)
+                && line_of_sight(state, monster_pos, state.player.position)
+            {
+                state.monsters[idx].awareness = Awareness::Aware;
+                state.log.push(format!("The {} spots you!", state.monsters[idx].name));
+            } else {
+                // Still hunting for whatever it heard: it wanders instead of closing in.
+                let (dx, dy) = match rng.range_inclusive_i32(0, 3) {
+                    0 => (1, 0),
+                    1 => (-1, 0),
+                    2 => (0, 1),
+                    _ => (0, -1),
+                };
+                let candidate = Position { x: monster_pos.x + dx, y: monster_pos.y + dy };
+                if state.bounds.contains(candidate)
+                    && state.tile_is_walkable(candidate)
+                    && candidate != state.player.position
+                    && !is_monster_occupied_except(state, candidate, monster_id)
+                {
+                    state.monsters[idx].position = candidate;
+                    events.push(Event::MonsterMoved {
+                        monster_id,
+                        from: monster_pos,
+                        to: candidate,
+                    });
+                }
+                continue;
+            }
+        }
 
         let monster_pos = state.monsters[idx].position;
         let player_pos = state.player.position;
         let behavior = state.monsters[idx].behavior;
         let faction = state.monsters[idx].faction;
-        let faction_hostile = match (faction, state.progression.alignment, behavior) {
-            (Faction::Law, Alignment::Lawful, _) => false,
-            (Faction::Chaos, Alignment::Chaotic, _) => false,
-            (Faction::Neutral, _, MonsterBehavior::Social) => false,
-            (Faction::Wild, _, MonsterBehavior::Social) => false,
-            (Faction::Neutral, _, _) => true,
-            (Faction::Wild, _, _) => true,
-            _ => true,
-        };
+        let faction_hostile = state.monsters[idx].is_hostile_to_player(state);
+        let fleeing_with_loot =
+            state.monsters[idx].status_effects.iter().any(|effect| effect.id == "fleeing");
 
         if behavior == MonsterBehavior::Social && !faction_hostile {
             state
@@ -14835,6 +19924,15 @@ fn run_monster_turn<R: RandomSource>(state: &mut GameState, rng: &mut R, events:
             continue;
         }
 
+        if faction_hostile && resolve_monster_special_attack(state, idx, rng, events) {
+            if !state.player.stats.is_alive() {
+                let monster_name = state.monsters[idx].name.clone();
+                mark_player_defeated(state, monster_name, events);
+                break;
+            }
+            continue;
+        }
+
         if behavior == MonsterBehavior::Caster
             && faction_hostile
             && resolve_monster_projectile_strike(state, idx, &equipment_profile, rng, events)
@@ -14842,14 +19940,30 @@ fn run_monster_turn<R: RandomSource>(state: &mut GameState, rng: &mut R, events:
             continue;
         }
 
-        if monster_pos.manhattan_distance(player_pos) == 1 && faction_hostile {
-            let rolled = rng.range_inclusive_i32(
+        if monster_pos.manhattan_distance(player_pos) == 1
+            && faction_hostile
+            && faction == Faction::Wild
+            && state.questionnaire_traits.animals_react_oddly
+            && rng.range_inclusive_i32(1, 6) == 1
+        {
+            state.log.push(format!(
+                "The {} eyes you strangely and hesitates.",
+                state.monsters[idx].name
+            ));
+            continue;
+        }
+
+        if monster_pos.manhattan_distance(player_pos) == 1 && faction_hostile && !fleeing_with_loot
+        {
+            let rolled = roll_combat_damage(
+                rng,
                 state.monsters[idx].stats.attack_min,
                 state.monsters[idx].stats.attack_max,
+                state.options.combat_variance,
             );
             let block_bonus =
                 status_magnitude(state, "block_bonus").max(0) + equipment_profile.block_bonus;
-            let defense_total = state.player.stats.defense + equipment_profile.defense_bonus;
+            let defense_total = player_defense_total(state, &equipment_profile);
             let mitigated = (rolled - defense_total - block_bonus).max(1);
             let damage = state.player.stats.apply_damage(mitigated);
             let remaining_hp = state.player.stats.hp;
@@ -14860,6 +19974,12 @@ fn run_monster_turn<R: RandomSource>(state: &mut GameState, rng: &mut R, events:
             if block_bonus > 0 {
                 consume_status(state, "block_bonus");
             }
+            if state.monsters[idx].special_attacks.contains(&SpecialAttack::Drain) {
+                apply_monster_drain(state, &monster_name);
+            }
+            if state.monsters[idx].special_attacks.contains(&SpecialAttack::Steal) {
+                apply_monster_theft(state, idx, rng, events);
+            }
 
             let riposte_bonus = status_magnitude(state, "riposte_ready").max(0);
             if riposte_bonus > 0
@@ -14879,9 +19999,10 @@ fn run_monster_turn<R: RandomSource>(state: &mut GameState, rng: &mut R, events:
                     remaining_hp: riposte_remaining,
                 });
                 if !state.monsters[riposte_idx].stats.is_alive() {
+                    let name = state.monsters[riposte_idx].name.clone();
                     let _ = remove_monster_with_drops(state, riposte_idx, events);
                     state.monsters_defeated += 1;
-                    events.push(Event::MonsterDefeated { monster_id });
+                    events.push(Event::MonsterDefeated { monster_id, name });
                 }
                 consume_status(state, "riposte_ready");
             }
@@ -14893,8 +20014,9 @@ fn run_monster_turn<R: RandomSource>(state: &mut GameState, rng: &mut R, events:
             continue;
         }
 
-        let candidate = if behavior == MonsterBehavior::Skirmisher
-            && monster_pos.manhattan_distance(player_pos) <= 2
+        let candidate = if fleeing_with_loot
+            || (behavior == MonsterBehavior::Skirmisher
+                && monster_pos.manhattan_distance(player_pos) <= 2)
         {
             Position {
                 x: monster_pos.x - (player_pos.x - monster_pos.x).signum(),
@@ -14903,6 +20025,7 @@ fn run_monster_turn<R: RandomSource>(state: &mut GameState, rng: &mut R, events:
         } else {
             next_monster_step(monster_pos, player_pos)
         };
+        let candidate = pack_cohesive_step(state, idx, candidate);
         let candidate_blocked = !state.bounds.contains(candidate)
             || !state.tile_is_walkable(candidate)
             || candidate == state.player.position
@@ -14914,71 +20037,395 @@ fn run_monster_turn<R: RandomSource>(state: &mut GameState, rng: &mut R, events:
         state.monsters[idx].position = candidate;
         events.push(Event::MonsterMoved { monster_id, from: monster_pos, to: candidate });
     }
-}
 
-fn advance_time(state: &mut GameState, turn_minutes: u64, events: &mut Vec<Event>) {
-    state.clock.turn += 1;
-    state.clock.minutes += turn_minutes;
-    events.push(Event::TurnAdvanced { turn: state.clock.turn, minutes: state.clock.minutes });
+    if state.status == SessionStatus::InProgress {
+        run_companion_turn(state, rng, events);
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use proptest::prelude::*;
-    use std::collections::BTreeSet;
+const MONSTER_SPECIAL_RANGE: i32 = 4;
+const PACK_SPAWN_RADIUS: i32 = 2;
+const PACK_COHESION_RADIUS: i32 = 2;
 
-    struct FixedRng {
-        rolls: Vec<i32>,
-        index: usize,
+fn pack_cohesive_step(state: &GameState, idx: usize, candidate: Position) -> Position {
+    let monster = &state.monsters[idx];
+    let Some(pack_id) = monster.pack_id else {
+        return candidate;
+    };
+    let chebyshev = |a: Position, b: Position| (a.x - b.x).abs().max((a.y - b.y).abs());
+    let Some(nearest) = state
+        .monsters
+        .iter()
+        .filter(|other| other.id != monster.id && other.pack_id == Some(pack_id))
+        .map(|other| other.position)
+        .min_by_key(|pos| (chebyshev(*pos, monster.position), pos.y, pos.x))
+    else {
+        return candidate;
+    };
+    // Straying past the cohesion radius pulls the member back toward its closest packmate.
+    if chebyshev(candidate, nearest) <= PACK_COHESION_RADIUS {
+        candidate
+    } else {
+        next_monster_step(monster.position, nearest)
     }
+}
 
-    impl FixedRng {
-        fn new(rolls: Vec<i32>) -> Self {
-            Self { rolls, index: 0 }
-        }
+fn resolve_monster_special_attack<R: RandomSource>(
+    state: &mut GameState,
+    monster_idx: usize,
+    rng: &mut R,
+    events: &mut Vec<Event>,
+) -> bool {
+    let monster = &state.monsters[monster_idx];
+    let distance = monster.position.manhattan_distance(state.player.position);
+    if distance > MONSTER_SPECIAL_RANGE {
+        return false;
     }
-
-    impl RandomSource for FixedRng {
-        fn range_inclusive_i32(&mut self, min: i32, max: i32) -> i32 {
-            let value = self.rolls.get(self.index).copied().unwrap_or(min);
-            self.index += 1;
-            value.clamp(min, max)
-        }
+    let Some(special) = monster
+        .special_attacks
+        .iter()
+        .copied()
+        .find(|special| !matches!(special, SpecialAttack::Drain | SpecialAttack::Steal))
+    else {
+        return false;
+    };
+    if rng.range_inclusive_i32(1, 3) != 1 {
+        return false;
     }
+    let monster_id = monster.id;
+    let monster_name = monster.name.clone();
 
-    fn arena_test_site_definition() -> SiteMapDefinition {
-        let width = 64usize;
-        let height = 16usize;
-        let mut rows = vec!["#".repeat(width); height];
-        for row in rows.iter_mut().take(13).skip(3) {
-            let mut chars: Vec<char> = row.chars().collect();
-            for cell in chars.iter_mut().take(62).skip(2) {
-                *cell = '.';
+    match special {
+        SpecialAttack::Breath { element, damage } => {
+            let resistances = state.effective_resistances();
+            let (label, resist) = match element {
+                BreathElement::Fire => ("fire", resistances.fire),
+                BreathElement::Cold => ("frost", resistances.cold),
+            };
+            let reduced = (damage - i32::from(resist.max(0))).max(0);
+            let applied = state.player.stats.apply_damage(reduced);
+            state
+                .log
+                .push(format!("The {monster_name} breathes {label} at you for {applied} damage."));
+            events.push(Event::MonsterAttacked {
+                monster_id,
+                damage: applied,
+                remaining_hp: state.player.stats.hp,
+            });
+            let player_pos = state.player.position;
+            for companion in state.companions.iter_mut() {
+                if companion.position.manhattan_distance(player_pos) <= 1 {
+                    let _ = companion.stats.apply_damage(damage);
+                }
+            }
+            let before = state.companions.len();
+            state.companions.retain(|companion| companion.stats.is_alive());
+            if state.companions.len() < before {
+                state.log.push(format!("The {label} engulfs your companions."));
             }
-            *row = chars.into_iter().collect();
         }
-        for y in [7usize, 8usize] {
-            let mut chars: Vec<char> = rows[y].chars().collect();
-            chars[0] = 'X';
-            chars[1] = 'P';
-            chars[2] = 'P';
-            rows[y] = chars.into_iter().collect();
+        SpecialAttack::ParalyzingGaze { turns } => {
+            if state.immunities.sleep {
+                state.log.push(format!("You shrug off the {monster_name}'s gaze."));
+            } else {
+                push_or_refresh_status(&mut state.status_effects, "immobile", turns, 1);
+                state.log.push(format!("The {monster_name}'s gaze paralyzes you!"));
+            }
         }
+        SpecialAttack::Drain | SpecialAttack::Steal => return false,
+    }
+    true
+}
 
-        let mut site_grid = Vec::with_capacity(width * height);
-        for row in &rows {
-            for glyph in row.chars() {
-                let mut cell = TileSiteCell { glyph, site_id: 0, aux: 0, flags: 0 };
-                match glyph {
-                    'X' => {
-                        cell.aux = SITE_AUX_EXIT_ARENA;
-                    }
-                    'P' => {
-                        cell.flags |= TILE_FLAG_PORTCULLIS | TILE_FLAG_BLOCK_MOVE;
-                    }
-                    '#' => {
-                        cell.flags |= TILE_FLAG_BLOCK_MOVE;
+const THIEF_FLIGHT_TURNS: u32 = 8;
+
+// Thieves grab gold first, then a loose pack item, and run off with it for a
+// while before going back to their usual ways. The loot stays on them until slain.
+fn apply_monster_theft<R: RandomSource>(
+    state: &mut GameState,
+    monster_idx: usize,
+    rng: &mut R,
+    events: &mut Vec<Event>,
+) {
+    let monster_name = state.monsters[monster_idx].name.clone();
+    let loot = if state.gold > 0 {
+        let amount = (state.gold / 4).max(1);
+        if !state.spend_gold(amount, "theft", events) {
+            return;
+        }
+        let id = state.next_item_id;
+        state.next_item_id += 1;
+        let mut coins = Item::basic(id, format!("{amount} gold pieces"));
+        coins.family = ItemFamily::Cash;
+        coins.number = amount;
+        coins
+    } else {
+        let equipped = equipped_item_ids(&state.player.equipment);
+        let candidates: Vec<u32> = state
+            .player
+            .inventory
+            .iter()
+            .map(|item| item.id)
+            .filter(|id| !equipped.contains(id))
+            .collect();
+        if candidates.is_empty() {
+            return;
+        }
+        let pick = rng.range_inclusive_i32(0, candidates.len() as i32 - 1) as usize;
+        let Some(item) = remove_inventory_item_by_id(state, candidates[pick]) else {
+            return;
+        };
+        item
+    };
+    state.log.push(format!("The {monster_name} steals {} and flees!", loot.name));
+    let monster = &mut state.monsters[monster_idx];
+    monster.on_death_drops.push(loot);
+    push_or_refresh_status(&mut monster.status_effects, "fleeing", THIEF_FLIGHT_TURNS, 1);
+}
+
+fn apply_monster_drain(state: &mut GameState, monster_name: &str) {
+    if state.attributes.strength <= 3 {
+        return;
+    }
+    state.attributes.strength -= 1;
+    recompute_derived_combat_and_mana_from_attributes(state);
+    state.log.push(format!("The {monster_name}'s touch drains your strength."));
+}
+
+fn run_companion_turn<R: RandomSource>(
+    state: &mut GameState,
+    rng: &mut R,
+    events: &mut Vec<Event>,
+) {
+    for companion_idx in 0..state.companions.len() {
+        let companion_pos = state.companions[companion_idx].position;
+        let foe_idx = state.monsters.iter().position(|monster| {
+            monster.position.manhattan_distance(companion_pos) == 1
+                && monster.is_hostile_to_player(state)
+        });
+        if let Some(foe_idx) = foe_idx {
+            let companion = &state.companions[companion_idx];
+            let companion_name = companion.name.clone();
+            let rolled = roll_combat_damage(
+                rng,
+                companion.stats.attack_min,
+                companion.stats.attack_max,
+                state.options.combat_variance,
+            );
+            let foe = &mut state.monsters[foe_idx];
+            let monster_id = foe.id;
+            let damage = foe.stats.apply_damage((rolled - foe.stats.defense).max(1));
+            let remaining_hp = foe.stats.hp;
+            let foe_name = foe.name.clone();
+            state
+                .log
+                .push(format!("Your {companion_name} hits the {foe_name} for {damage} damage."));
+            events.push(Event::Attacked { monster_id, damage, remaining_hp });
+            if remaining_hp <= 0 {
+                let _ = remove_monster_with_drops(state, foe_idx, events);
+                state.monsters_defeated += 1;
+                events.push(Event::MonsterDefeated { monster_id, name: foe_name });
+            }
+            continue;
+        }
+
+        let player_pos = state.player.position;
+        if projectile_distance(companion_pos, player_pos) <= 1 {
+            continue;
+        }
+        let companion_id = state.companions[companion_idx].id;
+        let candidate = next_monster_step(companion_pos, player_pos);
+        let stranded = !state.bounds.contains(companion_pos)
+            || projectile_distance(companion_pos, player_pos) > COMPANION_REGROUP_DISTANCE;
+        let destination = if stranded {
+            free_tile_adjacent_to_player(state)
+        } else if state.bounds.contains(candidate)
+            && state.tile_is_walkable(candidate)
+            && candidate != player_pos
+            && !is_monster_occupied_except(state, candidate, companion_id)
+        {
+            Some(candidate)
+        } else {
+            None
+        };
+        if let Some(destination) = destination {
+            state.companions[companion_idx].position = destination;
+            events.push(Event::MonsterMoved {
+                monster_id: companion_id,
+                from: companion_pos,
+                to: destination,
+            });
+        }
+    }
+}
+
+fn advance_time(state: &mut GameState, turn_minutes: u64, events: &mut Vec<Event>) {
+    let week_before = state.clock.minutes / MINUTES_PER_WEEK;
+    state.scheduler.player_phase = state.scheduler.player_phase.saturating_add(1);
+    state.scheduler.monster_phase = state.scheduler.monster_phase.saturating_add(1);
+    state.scheduler.environment_phase = state.scheduler.environment_phase.saturating_add(1);
+    state.scheduler.timed_effect_phase = state.scheduler.timed_effect_phase.saturating_add(1);
+    state.clock.turn += 1;
+    state.clock.minutes += turn_minutes;
+    state.clock.minutes_per_turn = environment_move_minutes(state);
+    state.restock_shops();
+    events.push(Event::TurnAdvanced { turn: state.clock.turn, minutes: state.clock.minutes });
+    for _ in week_before..state.clock.minutes / MINUTES_PER_WEEK {
+        accrue_weekly_guild_ledger(state);
+    }
+}
+
+pub const MINUTES_PER_DAY: u64 = 24 * 60;
+const MINUTES_PER_WEEK: u64 = 7 * MINUTES_PER_DAY;
+const GUILD_WEEKLY_DUES: i64 = 15;
+const GUILD_DUES_ARREARS_WEEKS: i64 = 3;
+const GUILD_FLAG_DUES_ARREARS: u64 = 1 << 32;
+const GUILD_WEEKLY_SALARY_PER_RANK: i64 = 20;
+
+fn dues_guild_tracks(
+    progression: &mut QuestProgression,
+) -> [(&'static str, &mut GuildTrackState); 4] {
+    [
+        ("thieves guild", &mut progression.thieves),
+        ("collegium", &mut progression.college),
+        ("sorcerors' circle", &mut progression.sorcerors),
+        ("monastery", &mut progression.monastery),
+    ]
+}
+
+/// Charges a week of dues against every guild the player belongs to and
+/// credits the legion salary. Nothing leaves or enters the purse until the
+/// player next visits the guild hall.
+fn accrue_weekly_guild_ledger(state: &mut GameState) {
+    let mut notices = Vec::new();
+    for (name, track) in dues_guild_tracks(&mut state.progression.quests) {
+        if track.rank <= 0 {
+            continue;
+        }
+        track.salary_due = track.salary_due.saturating_sub(GUILD_WEEKLY_DUES);
+        if track.salary_due <= -GUILD_WEEKLY_DUES * GUILD_DUES_ARREARS_WEEKS
+            && track.promotion_flags & GUILD_FLAG_DUES_ARREARS == 0
+        {
+            track.promotion_flags |= GUILD_FLAG_DUES_ARREARS;
+            notices.push(format!("The {name} warns that your dues are in arrears."));
+        }
+    }
+    state.log.extend(notices);
+
+    let merc_rank = i64::from(state.progression.guild_rank);
+    if merc_rank > 0 {
+        let merc = &mut state.progression.quests.merc;
+        merc.salary_due = merc.salary_due.saturating_add(merc_rank * GUILD_WEEKLY_SALARY_PER_RANK);
+    }
+}
+
+fn settle_guild_dues(state: &mut GameState, kind: &SiteInteractionKind, events: &mut Vec<Event>) {
+    if *kind == SiteInteractionKind::MercGuild {
+        let salary = state.progression.quests.merc.salary_due;
+        if let Ok(salary) = i32::try_from(salary)
+            && salary > 0
+        {
+            state.progression.quests.merc.salary_due = 0;
+            state.gain_gold(salary, "guild_salary", events);
+            state.log.push(format!("The legion pays out {salary}g in back salary."));
+        }
+        return;
+    }
+    let quests = &state.progression.quests;
+    let owed = match kind {
+        SiteInteractionKind::ThievesGuild => quests.thieves.salary_due,
+        SiteInteractionKind::College => quests.college.salary_due,
+        SiteInteractionKind::Sorcerors => quests.sorcerors.salary_due,
+        SiteInteractionKind::Monastery => quests.monastery.salary_due,
+        _ => return,
+    }
+    .saturating_neg();
+    let paid = owed.min(i64::from(state.gold)) as i32;
+    if paid <= 0 || !state.spend_gold(paid, "guild_dues", events) {
+        return;
+    }
+    let quests = &mut state.progression.quests;
+    let track = match kind {
+        SiteInteractionKind::ThievesGuild => &mut quests.thieves,
+        SiteInteractionKind::College => &mut quests.college,
+        SiteInteractionKind::Sorcerors => &mut quests.sorcerors,
+        _ => &mut quests.monastery,
+    };
+    track.salary_due += i64::from(paid);
+    track.dues_paid = track.dues_paid.saturating_add(i64::from(paid));
+    if track.salary_due >= 0 {
+        track.promotion_flags &= !GUILD_FLAG_DUES_ARREARS;
+    }
+    state.log.push(format!("You settle {paid}g in guild dues."));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::collections::BTreeSet;
+
+    struct FixedRng {
+        rolls: Vec<i32>,
+        index: usize,
+    }
+
+    impl FixedRng {
+        fn new(rolls: Vec<i32>) -> Self {
+            Self { rolls, index: 0 }
+        }
+    }
+
+    impl RandomSource for FixedRng {
+        fn range_inclusive_i32(&mut self, min: i32, max: i32) -> i32 {
+            let value = self.rolls.get(self.index).copied().unwrap_or(min);
+            self.index += 1;
+            value.clamp(min, max)
+        }
+    }
+
+    fn state_with_rows(rows: &[&str], player: Position) -> GameState {
+        let mut state = GameState::new(MapBounds {
+            width: rows.first().map_or(0, |row| row.len() as i32),
+            height: rows.len() as i32,
+        });
+        state.player.position = player;
+        state.map_rows = rows.iter().map(|row| row.to_string()).collect();
+        state
+    }
+
+    fn arena_test_site_definition() -> SiteMapDefinition {
+        let width = 64usize;
+        let height = 16usize;
+        let mut rows = vec!["#".repeat(width); height];
+        for row in rows.iter_mut().take(13).skip(3) {
+            let mut chars: Vec<char> = row.chars().collect();
+            for cell in chars.iter_mut().take(62).skip(2) {
+                *cell = '.';
+            }
+            *row = chars.into_iter().collect();
+        }
+        for y in [7usize, 8usize] {
+            let mut chars: Vec<char> = rows[y].chars().collect();
+            chars[0] = 'X';
+            chars[1] = 'P';
+            chars[2] = 'P';
+            rows[y] = chars.into_iter().collect();
+        }
+
+        let mut site_grid = Vec::with_capacity(width * height);
+        for row in &rows {
+            for glyph in row.chars() {
+                let mut cell = TileSiteCell { glyph, site_id: 0, aux: 0, flags: 0 };
+                match glyph {
+                    'X' => {
+                        cell.aux = SITE_AUX_EXIT_ARENA;
+                    }
+                    'P' => {
+                        cell.flags |= TILE_FLAG_PORTCULLIS | TILE_FLAG_BLOCK_MOVE;
+                    }
+                    '#' => {
+                        cell.flags |= TILE_FLAG_BLOCK_MOVE;
                     }
                     _ => {}
                 }
@@ -15055,6 +20502,58 @@ mod tests {
         assert!(out.events.iter().any(|event| matches!(event, Event::MoveBlocked { .. })));
     }
 
+    #[test]
+    fn try_set_map_rows_accepts_uniform_rows() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+
+        let result = state.try_set_map_rows(vec!["....".to_string(); 2]);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(state.bounds, MapBounds { width: 4, height: 2 });
+        assert_eq!(state.map_rows.len(), 2);
+    }
+
+    #[test]
+    fn try_set_map_rows_rejects_empty_and_ragged_rows() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        let rows_before = state.map_rows.clone();
+
+        assert_eq!(state.try_set_map_rows(Vec::new()), Err(MapError::Empty));
+        assert_eq!(
+            state.try_set_map_rows(vec!["...".to_string(), "..".to_string(), "...".to_string()]),
+            Err(MapError::RaggedRow { row: 1, expected: 3, found: 2 })
+        );
+        assert!(matches!(
+            state.try_set_map_rows(vec!["..".to_string()]),
+            Err(MapError::SpawnOutOfBounds { .. })
+        ));
+        assert_eq!(state.map_rows, rows_before);
+        assert_eq!(state.bounds, MapBounds { width: 3, height: 3 });
+    }
+
+    #[test]
+    fn activating_a_ragged_site_map_leaves_the_level_alone() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.site_maps = vec![SiteMapDefinition {
+            map_id: 7,
+            level_index: 0,
+            source: "test/ragged.map".to_string(),
+            environment: LegacyEnvironment::City,
+            semantic: MapSemanticKind::Site,
+            spawn: Position { x: 0, y: 0 },
+            rows: vec!["....".to_string(), "..".to_string()],
+            site_grid: Vec::new(),
+        }];
+        let rows_before = state.map_rows.clone();
+
+        assert!(!state.activate_site_map_by_id(7, None));
+        assert_eq!(state.map_rows, rows_before);
+        assert_eq!(state.bounds, MapBounds { width: 3, height: 3 });
+    }
+
     #[test]
     fn guard_marker_spawns_interactive_guard_monster() {
         let mut state = GameState::new(MapBounds { width: 3, height: 3 });
@@ -15069,6 +20568,69 @@ mod tests {
         assert!(state.monsters.iter().any(|monster| monster.position == Position { x: 1, y: 0 }));
     }
 
+    #[test]
+    fn guards_turn_hostile_only_once_legal_heat_is_high() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.spawn_monster(
+            "city guard",
+            Position { x: 1, y: 0 },
+            Stats { hp: 20, max_hp: 20, attack_min: 3, attack_max: 3, defense: 0, weight: 60 },
+        );
+        state.legal_heat = GUARD_HOSTILE_HEAT - 1;
+        assert!(!state.monsters[0].is_hostile_to_player(&state));
+
+        let hp_before = state.player.stats.hp;
+        let _ = step(&mut state, Command::Wait, &mut FixedRng::new(vec![]));
+        assert_eq!(state.player.stats.hp, hp_before);
+
+        state.legal_heat = GUARD_HOSTILE_HEAT;
+        assert!(state.monsters[0].is_hostile_to_player(&state));
+        let _ = step(&mut state, Command::Wait, &mut FixedRng::new(vec![]));
+        assert!(state.player.stats.hp < hp_before);
+    }
+
+    #[test]
+    fn belligerent_players_provoke_otherwise_peaceful_monsters() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.spawn_monster(
+            "city guard",
+            Position { x: 1, y: 0 },
+            Stats { hp: 20, max_hp: 20, attack_min: 3, attack_max: 3, defense: 0, weight: 60 },
+        );
+        state.legal_heat = 0;
+        assert!(!state.monsters[0].is_hostile_to_player(&state));
+
+        state.options.belligerent = true;
+        assert!(state.monsters[0].is_hostile_to_player(&state));
+        let hp_before = state.player.stats.hp;
+        let _ = step(&mut state, Command::Wait, &mut FixedRng::new(vec![]));
+        assert!(state.player.stats.hp < hp_before);
+    }
+
+    #[test]
+    fn allied_monsters_are_never_hostile() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.spawn_monster(
+            "wolf",
+            Position { x: 1, y: 0 },
+            Stats { hp: 10, max_hp: 10, attack_min: 4, attack_max: 4, defense: 0, weight: 60 },
+        );
+        state.monsters[0].allied = true;
+        state.legal_heat = 50;
+        state.options.belligerent = true;
+        assert!(!state.monsters[0].is_hostile_to_player(&state));
+        state.options.belligerent = false;
+
+        let hp_before = state.player.stats.hp;
+        let out = step(&mut state, Command::Move(Direction::North), &mut FixedRng::new(vec![]));
+        assert_eq!(state.player.stats.hp, hp_before);
+        assert_eq!(state.monsters[0].stats.hp, 10);
+        assert!(!out.events.iter().any(|event| matches!(event, Event::Attacked { .. })));
+    }
+
     #[test]
     fn moving_into_guard_monster_triggers_bump_attack() {
         let mut state = GameState::new(MapBounds { width: 3, height: 3 });
@@ -15095,7 +20657,7 @@ mod tests {
             Position { x: 3, y: 2 },
             Stats { hp: 6, max_hp: 6, attack_min: 1, attack_max: 2, defense: 1, weight: 60 },
         );
-        let mut rng = FixedRng::new(vec![4, 1, 4]);
+        let mut rng = FixedRng::new(vec![4, 1, 1, 4]);
 
         let _ = step(&mut state, Command::Attack(Direction::East), &mut rng);
         assert_eq!(state.monsters[0].stats.hp, 3);
@@ -15107,6 +20669,63 @@ mod tests {
         assert_eq!(state.status, SessionStatus::InProgress);
     }
 
+    #[test]
+    fn arriving_on_item_tile_reports_what_is_here() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.player.position = Position { x: 1, y: 2 };
+        state.place_item("short sword", Position { x: 2, y: 2 });
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Move(Direction::East), &mut rng);
+
+        let description = state.describe_current_tile();
+        assert_eq!(description.position, Position { x: 2, y: 2 });
+        assert_eq!(description.items, vec!["short sword".to_string()]);
+        assert!(description.traps.is_empty());
+        assert!(state.log.iter().any(|line| line == "You see here: short sword."));
+    }
+
+    #[test]
+    fn gaining_an_item_with_a_full_pack_drops_it_underfoot() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.player.position = Position { x: 2, y: 2 };
+        state.player.inventory_capacity = 1;
+        let mut events = Vec::new();
+        let _ = add_item_to_inventory_or_ground(&mut state, "food ration", &mut events);
+        assert_eq!(state.player.inventory.len(), 1);
+
+        let note = add_item_to_inventory_or_ground(&mut state, "healing potion", &mut events);
+
+        assert!(note.contains("left on ground"));
+        assert_eq!(state.player.inventory.len(), 1);
+        let dropped = state
+            .ground_items
+            .iter()
+            .find(|ground| ground.position == Position { x: 2, y: 2 })
+            .expect("overflow lands at the player's feet");
+        assert!(events.contains(&Event::InventoryFull { capacity: 1 }));
+        assert!(events.contains(&Event::InventoryOverflow {
+            item_id: dropped.item.id,
+            name: dropped.item.name.clone(),
+        }));
+    }
+
+    #[test]
+    fn apportation_respects_pack_capacity() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.player.position = Position { x: 2, y: 2 };
+        state.player.inventory_capacity = 1;
+        state.player.inventory.push(Item::new(9, "practice blade"));
+        state.place_item("scroll", Position { x: 4, y: 2 });
+        let mut events = Vec::new();
+
+        assert!(apport_nearby_item(&mut state, 6, &mut events).is_some());
+
+        assert_eq!(state.player.inventory.len(), 1);
+        assert!(state.ground_items.iter().any(|ground| ground.position == state.player.position));
+        assert!(events.iter().any(|event| matches!(event, Event::InventoryOverflow { .. })));
+    }
+
     #[test]
     fn pickup_drop_and_inventory_capacity_are_enforced() {
         let mut state = GameState::new(MapBounds { width: 5, height: 5 });
@@ -15135,21 +20754,188 @@ mod tests {
         );
     }
 
-    #[test]
-    fn two_handed_weapon_prevents_shield_auto_equip() {
+    fn state_with_equippables() -> GameState {
         let mut state = GameState::new(MapBounds { width: 5, height: 5 });
-        state.player.position = Position { x: 2, y: 2 };
-        state.place_item("Victrix", state.player.position);
-        state.place_item("heater shield", state.player.position);
-        let mut rng = FixedRng::new(vec![]);
+        state.player.inventory = vec![
+            Item {
+                id: 1,
+                name: "greatsword".to_string(),
+                family: ItemFamily::Weapon,
+                weight: 120,
+                ..Item::default()
+            },
+            Item {
+                id: 2,
+                name: "buckler".to_string(),
+                family: ItemFamily::Shield,
+                ..Item::default()
+            },
+            Item {
+                id: 3,
+                name: "cursed ring".to_string(),
+                family: ItemFamily::Ring,
+                blessing: -1,
+                used: true,
+                ..Item::default()
+            },
+            Item {
+                id: 4,
+                name: "plain ring".to_string(),
+                family: ItemFamily::Ring,
+                ..Item::default()
+            },
+        ];
+        state
+    }
 
-        let _ = step(&mut state, Command::Pickup, &mut rng);
-        let _ = step(&mut state, Command::Pickup, &mut rng);
+    #[test]
+    fn can_equip_reports_typed_reasons() {
+        let mut state = state_with_equippables();
+        assert_eq!(state.can_equip(99, SLOT_SHIELD), Err(EquipError::ItemNotFound));
+        assert_eq!(state.can_equip(1, SLOT_ARMOR), Err(EquipError::WrongFamily));
+        assert_eq!(state.can_equip(2, SLOT_SHIELD), Ok(()));
 
-        assert!(state.player.equipment.weapon_hand.is_some());
-        assert!(state.player.equipment.ready_hand.is_some());
-        assert!(
-            state.player.equipment.shield.is_none(),
+        state.player.equipment.weapon_hand = Some(1);
+        state.player.equipment.ready_hand = Some(1);
+        assert_eq!(state.can_equip(2, SLOT_SHIELD), Err(EquipError::TwoHandedConflict));
+
+        state.player.equipment.ring_1 = Some(3);
+        assert_eq!(state.can_equip(3, SLOT_RING_2), Err(EquipError::Cursed));
+        assert_eq!(state.can_equip(4, SLOT_RING_1), Err(EquipError::SlotOccupiedByCursed));
+        assert_eq!(state.can_equip(4, SLOT_RING_2), Ok(()));
+    }
+
+    #[test]
+    fn equipping_through_inventory_respects_can_equip() {
+        let mut state = state_with_equippables();
+        state.player.equipment.weapon_hand = Some(1);
+        state.player.equipment.ready_hand = Some(1);
+
+        let note = inventory_equip_pack_item_to_slot(&mut state, SLOT_SHIELD, 2);
+
+        assert_eq!(note, "Cannot equip a shield while wielding a two-handed weapon.");
+        assert_eq!(state.player.equipment.shield, None);
+    }
+
+    #[test]
+    fn equipping_backpack_grows_inventory_capacity() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        let base = effective_inventory_capacity(&state);
+        let pack = instantiate_item_from_name(state.next_item_id, "backpack");
+        state.next_item_id += 1;
+        assert_eq!(pack.usef, BACKPACK_USEF);
+        let pack_id = pack.id;
+        state.player.inventory.push(pack);
+
+        let note = inventory_equip_pack_item_to_slot(&mut state, SLOT_LEFT_SHOULDER, pack_id);
+
+        assert!(note.contains("equipped"), "{note}");
+        assert_eq!(effective_inventory_capacity(&state), base + BACKPACK_CAPACITY_BONUS as usize);
+    }
+
+    #[test]
+    fn removing_backpack_while_overfull_is_refused_but_dropping_is_not() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.player.position = Position { x: 2, y: 2 };
+        state.player.inventory_capacity = 2;
+        let mut pack = Item::new(state.next_item_id, "backpack");
+        state.next_item_id += 1;
+        pack.usef = BACKPACK_USEF.to_string();
+        let pack_id = pack.id;
+        let mut rng = FixedRng::new(vec![]);
+        let _ = add_existing_item_to_inventory_or_ground(&mut state, pack, &mut Vec::new());
+        assert_eq!(state.player.equipment.left_shoulder, Some(pack_id));
+        for name in ["potion", "scroll", "ration", "torch"] {
+            state.place_item(name, state.player.position);
+            let _ = step(&mut state, Command::Pickup, &mut rng);
+        }
+        assert_eq!(state.player.inventory.len(), 5);
+
+        let note = inventory_put_slot_item_to_pack(&mut state, SLOT_LEFT_SHOULDER);
+        assert!(note.contains("would overflow"), "{note}");
+        assert_eq!(state.player.equipment.left_shoulder, Some(pack_id));
+
+        let _ = step(&mut state, Command::Drop { slot: 1 }, &mut rng);
+        assert_eq!(state.player.inventory.len(), 4);
+        let _ = step(&mut state, Command::Drop { slot: 0 }, &mut rng);
+        assert!(state.player.inventory.iter().all(|item| item.id != pack_id));
+        assert!(state.ground_items.iter().any(|ground| ground.item.id == pack_id));
+    }
+
+    #[test]
+    fn deeper_loot_rolls_carry_higher_average_plus() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        let average_plus = |state: &mut GameState, depth: i16| {
+            let mut rng = DeterministicRng::seeded(0x10_07);
+            let total: i32 =
+                (0..400).map(|_| state.roll_item_for_depth(depth, &mut rng).plus).sum();
+            f64::from(total) / 400.0
+        };
+
+        let shallow = average_plus(&mut state, 1);
+        let deep = average_plus(&mut state, 20);
+
+        assert!(deep > shallow, "deep {deep} should exceed shallow {shallow}");
+    }
+
+    #[test]
+    fn drop_quantity_splits_stack_between_ground_and_pack() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.player.position = Position { x: 2, y: 2 };
+        let mut arrows = instantiate_item_from_name(state.next_item_id, "arrow");
+        state.next_item_id += 1;
+        arrows.number = 5;
+        arrows.weight = 100;
+        let arrow_id = arrows.id;
+        state.carry_burden = item_burden(&arrows);
+        state.player.inventory.push(arrows);
+        let mut rng = FixedRng::new(vec![]);
+
+        let out = step(&mut state, Command::DropQuantity { item_id: arrow_id, count: 2 }, &mut rng);
+
+        assert_eq!(state.player.inventory.len(), 1);
+        assert_eq!(state.player.inventory[0].id, arrow_id);
+        assert_eq!(state.player.inventory[0].number, 3);
+        assert_eq!(state.ground_items.len(), 1);
+        assert_eq!(state.ground_items[0].item.number, 2);
+        assert_ne!(state.ground_items[0].item.id, arrow_id);
+        assert_eq!(state.carry_burden, 6);
+        assert!(out.events.iter().any(|event| matches!(event, Event::Dropped { .. })));
+    }
+
+    #[test]
+    fn drop_quantity_beyond_stack_drops_everything() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.player.position = Position { x: 2, y: 2 };
+        let mut arrows = instantiate_item_from_name(state.next_item_id, "arrow");
+        state.next_item_id += 1;
+        arrows.number = 5;
+        let arrow_id = arrows.id;
+        state.player.inventory.push(arrows);
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::DropQuantity { item_id: arrow_id, count: 9 }, &mut rng);
+
+        assert!(state.player.inventory.is_empty());
+        assert_eq!(state.ground_items.len(), 1);
+        assert_eq!(state.ground_items[0].item.number, 5);
+    }
+
+    #[test]
+    fn two_handed_weapon_prevents_shield_auto_equip() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.player.position = Position { x: 2, y: 2 };
+        state.place_item("Victrix", state.player.position);
+        state.place_item("heater shield", state.player.position);
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Pickup, &mut rng);
+        let _ = step(&mut state, Command::Pickup, &mut rng);
+
+        assert!(state.player.equipment.weapon_hand.is_some());
+        assert!(state.player.equipment.ready_hand.is_some());
+        assert!(
+            state.player.equipment.shield.is_none(),
             "two-handed weapon should block shield slot"
         );
     }
@@ -15235,6 +21021,95 @@ mod tests {
         );
     }
 
+    #[test]
+    fn known_items_list_their_enchantments_and_unknown_items_do_not() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        let mut known = Item::new(1, "sword");
+        known.known = true;
+        known.plus = 2;
+        known.blessing = 1;
+        known.usef = "I_PERM_FIRE_RESIST".to_string();
+        let mut unknown = known.clone();
+        unknown.id = 2;
+        unknown.known = false;
+        state.player.inventory = vec![known.clone(), unknown];
+
+        assert_eq!(item_condition_label(&known), "+2 blessed sword of fire resistance");
+        let listing = pack_listing(&state);
+        assert!(listing.contains("a) +2 blessed sword of fire resistance"));
+        assert!(listing.contains("b) sword"));
+        assert!(!listing.contains("b) +2"));
+    }
+
+    #[test]
+    fn look_mode_moves_cursor_onto_monster_without_passing_time() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.spawn_monster(
+            "goblin",
+            Position { x: 3, y: 1 },
+            Stats { hp: 4, max_hp: 10, attack_min: 1, attack_max: 2, defense: 0, weight: 60 },
+        );
+        let turn = state.clock.turn;
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Look, &mut rng);
+        assert_eq!(modal_input_profile(&state), ModalInputProfile::DirectionEntry);
+        let _ = step(&mut state, Command::Move(Direction::East), &mut rng);
+        let out = step(&mut state, Command::Legacy { token: "l".to_string() }, &mut rng);
+
+        assert_eq!(state.player.position, Position { x: 1, y: 1 });
+        assert_eq!(state.monsters[0].position, Position { x: 3, y: 1 });
+        assert_eq!(state.clock.turn, turn);
+        assert_eq!(
+            state.pending_look_interaction,
+            Some(LookInteraction { cursor: Position { x: 3, y: 1 } })
+        );
+        assert!(out.events.iter().any(|event| matches!(
+            event,
+            Event::LegacyHandled { token, note, .. }
+                if token == "look" && note.contains("goblin") && note.contains("badly wounded")
+        )));
+
+        let _ = step(&mut state, Command::Legacy { token: "<esc>".to_string() }, &mut rng);
+        assert!(state.pending_look_interaction.is_none());
+        assert_eq!(state.clock.turn, turn);
+    }
+
+    #[test]
+    fn look_mode_hides_monsters_out_of_sight() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 3 });
+        state.player.position = Position { x: 0, y: 1 };
+        state.map_rows = vec![".....".to_string(), "..#..".to_string(), ".....".to_string()];
+        state.spawn_monster(
+            "goblin",
+            Position { x: 4, y: 1 },
+            Stats { hp: 4, max_hp: 4, attack_min: 1, attack_max: 2, defense: 0, weight: 60 },
+        );
+
+        assert!(state.examine_monster(Position { x: 4, y: 1 }).is_none());
+        assert_eq!(
+            look_description_at(&state, Position { x: 4, y: 1 }),
+            "(4, 1): nothing of interest."
+        );
+
+        state.map_rows[1] = ".....".to_string();
+        assert!(look_description_at(&state, Position { x: 4, y: 1 }).contains("goblin"));
+    }
+
+    #[test]
+    fn legacy_look_token_opens_look_mode_without_passing_time() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        let mut rng = FixedRng::new(vec![]);
+        let out = step(&mut state, Command::Legacy { token: ";".to_string() }, &mut rng);
+        assert_eq!(
+            state.pending_look_interaction,
+            Some(LookInteraction { cursor: Position { x: 1, y: 1 } })
+        );
+        assert_eq!(out.minutes, 0);
+    }
+
     #[test]
     fn inventory_show_pack_is_visible_and_non_advancing() {
         let mut state = GameState::new(MapBounds { width: 5, height: 5 });
@@ -15256,6 +21131,25 @@ mod tests {
         assert!(state.log.iter().any(|line| line.starts_with("Pack:")));
     }
 
+    #[test]
+    fn each_advanced_turn_ticks_every_scheduler_phase() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        let mut rng = FixedRng::new(vec![]);
+        let before = state.scheduler_snapshot();
+
+        for _ in 0..4 {
+            let _ = step(&mut state, Command::Wait, &mut rng);
+        }
+        let _ = step(&mut state, Command::Legacy { token: "i".to_string() }, &mut rng);
+
+        let after = state.scheduler_snapshot();
+        assert_eq!(after.player_phase - before.player_phase, 4);
+        assert_eq!(after.monster_phase - before.monster_phase, 4);
+        assert_eq!(after.environment_phase - before.environment_phase, 4);
+        assert_eq!(after.timed_effect_phase - before.timed_effect_phase, 4);
+        assert_eq!(state.clock.turn, 4);
+    }
+
     #[test]
     fn monsters_attack_player_and_can_defeat() {
         let mut state = GameState::new(MapBounds { width: 7, height: 7 });
@@ -15284,6 +21178,361 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn life_save_item_averts_death_only_when_carried() {
+        let doomed = || {
+            let mut state = GameState::new(MapBounds { width: 7, height: 7 });
+            state.player.position = Position { x: 3, y: 3 };
+            state.player.stats.hp = 3;
+            state.player.stats.max_hp = 10;
+            state.spawn_monster(
+                "fang",
+                Position { x: 4, y: 3 },
+                Stats { hp: 5, max_hp: 5, attack_min: 4, attack_max: 4, defense: 0, weight: 60 },
+            );
+            state
+        };
+
+        let mut unprotected = doomed();
+        let _ = step(&mut unprotected, Command::Wait, &mut FixedRng::new(vec![4]));
+        assert_eq!(unprotected.status, SessionStatus::Lost);
+
+        let mut protected = doomed();
+        protected.player.inventory.push(instantiate_item_from_name(9, "amulet of life saving"));
+        let out = step(&mut protected, Command::Wait, &mut FixedRng::new(vec![4]));
+        assert_eq!(protected.status, SessionStatus::InProgress);
+        assert_eq!(protected.player.stats.hp, 5);
+        assert!(protected.player.inventory.is_empty());
+        assert!(protected.death_source.is_none());
+        assert!(out.events.contains(&Event::DeathAverted {
+            item_id: 9,
+            name: "amulet of life saving".to_string()
+        }));
+        assert!(!out.events.iter().any(|event| matches!(event, Event::EndingResolved { .. })));
+        assert!(!out.events.contains(&Event::PlayerDefeated));
+        assert!(!protected.log.iter().any(|line| line.contains("You are defeated.")));
+
+        let mut modern = doomed();
+        modern.mode = GameMode::Modern;
+        modern.player.inventory.push(instantiate_item_from_name(9, "amulet of life saving"));
+        let _ = step(&mut modern, Command::Wait, &mut FixedRng::new(vec![4]));
+        assert_eq!(modern.status, SessionStatus::InProgress);
+        assert_eq!(modern.player.stats.hp, 10);
+    }
+
+    #[test]
+    fn life_save_item_averts_a_deathwish() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.player.stats.max_hp = 12;
+        state.player.inventory.push(Item {
+            id: 4,
+            name: "amulet of life saving".to_string(),
+            usef: LIFE_SAVE_USEF.to_string(),
+            ..Item::default()
+        });
+        let mut events = Vec::new();
+        let _ = apply_wish_intent(&mut state, WishIntent::Death, &mut events, 0);
+        assert_eq!(state.status, SessionStatus::InProgress);
+        assert_eq!(state.player.stats.hp, 6);
+        assert!(state.death_source.is_none());
+        assert!(!events.contains(&Event::PlayerDefeated));
+    }
+
+    #[test]
+    fn site_grid_index_round_trips_every_position() {
+        let state = GameState::new(MapBounds { width: 4, height: 3 });
+        for y in 0..3 {
+            for x in 0..4 {
+                let pos = Position { x, y };
+                let idx = state.index_for(pos).expect("in bounds");
+                assert_eq!(idx, (y * 4 + x) as usize);
+                assert_eq!(state.pos_for_index(idx), Some(pos));
+            }
+        }
+        assert_eq!(state.index_for(Position { x: 4, y: 0 }), None);
+        assert_eq!(state.index_for(Position { x: 0, y: 3 }), None);
+        assert_eq!(state.index_for(Position { x: -1, y: 1 }), None);
+        assert_eq!(state.pos_for_index(12), None);
+    }
+
+    #[test]
+    fn sleeping_monster_ignores_distant_player_until_approached() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 3 });
+        state.player.position = Position { x: 0, y: 1 };
+        state.spawn_monster(
+            "ogre",
+            Position { x: 6, y: 1 },
+            Stats { hp: 20, max_hp: 20, attack_min: 1, attack_max: 1, defense: 0, weight: 60 },
+        );
+        state.monsters[0].awareness = Awareness::Asleep;
+        let mut rng = FixedRng::new(vec![]);
+
+        for _ in 0..3 {
+            let _ = step(&mut state, Command::Wait, &mut rng);
+        }
+        assert_eq!(state.monsters[0].position, Position { x: 6, y: 1 });
+        assert_eq!(state.monsters[0].awareness, Awareness::Asleep);
+
+        state.player.position = Position { x: 4, y: 1 };
+        let _ = step(&mut state, Command::Wait, &mut rng);
+        assert_eq!(state.monsters[0].awareness, Awareness::Aware);
+        assert!(state.log.iter().any(|line| line.contains("ogre wakes up")));
+    }
+
+    #[test]
+    fn attacking_a_sleeping_monster_grants_a_sneak_bonus() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.spawn_monster(
+            "ogre",
+            Position { x: 2, y: 1 },
+            Stats { hp: 40, max_hp: 40, attack_min: 0, attack_max: 0, defense: 0, weight: 60 },
+        );
+        let mut awake = state.clone();
+        state.monsters[0].awareness = Awareness::Asleep;
+
+        let _ = step(&mut state, Command::Attack(Direction::East), &mut FixedRng::new(vec![]));
+        let _ = step(&mut awake, Command::Attack(Direction::East), &mut FixedRng::new(vec![]));
+
+        assert_eq!(state.monsters[0].stats.hp + SNEAK_ATTACK_BONUS, awake.monsters[0].stats.hp);
+        assert_eq!(state.monsters[0].awareness, Awareness::Aware);
+        assert!(state.log.iter().any(|line| line.contains("strike the sleeping ogre")));
+    }
+
+    #[test]
+    fn attacking_one_monster_alerts_its_sleeping_allies() {
+        let build = || {
+            let mut state = GameState::new(MapBounds { width: 9, height: 5 });
+            state.map_rows = vec![".........".to_string(); 5];
+            state.map_rows[3] = ".#####...".to_string();
+            state.player.position = Position { x: 1, y: 2 };
+            let stats =
+                Stats { hp: 40, max_hp: 40, attack_min: 0, attack_max: 0, defense: 0, weight: 60 };
+            state.spawn_monster("goblin", Position { x: 2, y: 2 }, stats);
+            state.spawn_monster("goblin", Position { x: 4, y: 2 }, stats);
+            state.spawn_monster("goblin", Position { x: 2, y: 4 }, stats);
+            state.spawn_monster("wolf", Position { x: 4, y: 4 }, stats);
+            for monster in &mut state.monsters {
+                monster.awareness = Awareness::Asleep;
+                monster.faction = Faction::Chaos;
+            }
+            state.monsters[3].faction = Faction::Wild;
+            state
+        };
+
+        let mut state = build();
+        let _ = step(&mut state, Command::Attack(Direction::East), &mut FixedRng::new(vec![]));
+        assert_eq!(state.monsters[1].awareness, Awareness::Aware);
+        assert_eq!(state.monsters[2].awareness, Awareness::Aware);
+        assert_eq!(state.monsters[3].awareness, Awareness::Searching, "other factions only hear");
+        assert!(state.log.iter().any(|line| line.contains("alerted by the fighting")));
+        assert!(state.pending_combat_alarm.is_none());
+
+        let mut stealthy = build();
+        stealthy.status_effects.push(StatusEffect {
+            id: "invisible".to_string(),
+            remaining_turns: 10,
+            magnitude: 1,
+        });
+        let _ = step(&mut stealthy, Command::Attack(Direction::East), &mut FixedRng::new(vec![]));
+        assert_eq!(stealthy.monsters[1].awareness, Awareness::Asleep);
+        assert_eq!(stealthy.monsters[2].awareness, Awareness::Asleep);
+    }
+
+    #[test]
+    fn searching_monster_wanders_until_it_sees_the_player() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 3 });
+        state.map_rows = vec!["....#....".to_string(); 3];
+        state.player.position = Position { x: 1, y: 1 };
+        state.spawn_monster(
+            "ogre",
+            Position { x: 6, y: 1 },
+            Stats { hp: 20, max_hp: 20, attack_min: 1, attack_max: 1, defense: 0, weight: 60 },
+        );
+        state.monsters[0].faction = Faction::Chaos;
+        state.monsters[0].awareness = Awareness::Searching;
+
+        let mut events = Vec::new();
+        run_monster_turn(&mut state, &mut FixedRng::new(vec![0]), &mut events);
+        assert_eq!(state.monsters[0].position, Position { x: 7, y: 1 }, "wall hides the player");
+        assert_eq!(state.monsters[0].awareness, Awareness::Searching);
+
+        state.map_rows = vec![".........".to_string(); 3];
+        state.monsters[0].position = Position { x: 5, y: 1 };
+        run_monster_turn(&mut state, &mut FixedRng::new(vec![]), &mut events);
+        assert_eq!(state.monsters[0].awareness, Awareness::Aware);
+        assert_eq!(state.monsters[0].position, Position { x: 4, y: 1 });
+        assert!(state.log.iter().any(|line| line.contains("The ogre spots you!")));
+    }
+
+    #[test]
+    fn wandering_encounters_can_start_asleep_per_legacy_sleep_chance() {
+        assert_eq!(legacy_monster_sleep_chance("hornet"), 50);
+        assert_eq!(roll_spawn_awareness("hornet", &mut FixedRng::new(vec![50])), Awareness::Asleep);
+        assert_eq!(roll_spawn_awareness("hornet", &mut FixedRng::new(vec![51])), Awareness::Aware);
+        assert_eq!(
+            roll_spawn_awareness("guardsman", &mut FixedRng::new(vec![1])),
+            Awareness::Aware,
+            "social monsters never start asleep"
+        );
+        assert_eq!(
+            roll_spawn_awareness("no such beast", &mut FixedRng::new(vec![1])),
+            Awareness::Aware
+        );
+    }
+
+    #[test]
+    fn stats_hp_helpers_keep_hp_within_bounds() {
+        let mut stats =
+            Stats { hp: 8, max_hp: 10, attack_min: 1, attack_max: 2, defense: 0, weight: 60 };
+
+        stats.heal(50);
+        assert_eq!(stats.hp, 10, "over-heal stops at max_hp");
+        stats.heal(-4);
+        assert_eq!(stats.hp, 10, "negative heal is ignored");
+
+        assert_eq!(stats.apply_damage(-3), 0, "negative damage does not heal");
+        assert_eq!(stats.hp, 10);
+
+        assert_eq!(stats.apply_damage(10), 10);
+        assert_eq!(stats.hp, 0, "exact-lethal damage lands on zero");
+        assert!(!stats.is_alive());
+        assert_eq!(stats.apply_damage(5), 0);
+        assert_eq!(stats.hp, 0);
+
+        stats.set_hp(i32::MAX);
+        assert_eq!(stats.hp, 10);
+        stats.set_hp(-7);
+        assert_eq!(stats.hp, 0);
+        stats.hp = 9;
+        stats.heal(i32::MAX);
+        assert_eq!(stats.hp, 10, "heal saturates instead of overflowing");
+    }
+
+    #[test]
+    fn spellbook_mana_helpers_keep_mana_within_bounds() {
+        let mut spellbook = SpellbookState { mana: 30, max_mana: 40, ..SpellbookState::default() };
+
+        spellbook.restore_mana(25);
+        assert_eq!(spellbook.mana, 40, "over-restore stops at max_mana");
+        spellbook.restore_mana(-10);
+        assert_eq!(spellbook.mana, 40, "negative restore is ignored");
+        spellbook.restore_mana(i32::MAX);
+        assert_eq!(spellbook.mana, 40, "restore saturates instead of overflowing");
+
+        spellbook.set_mana(-3);
+        assert_eq!(spellbook.mana, 0);
+        spellbook.max_mana = 20;
+        spellbook.set_mana(35);
+        assert_eq!(spellbook.mana, 20);
+    }
+
+    #[test]
+    fn format_event_matches_log_phrasing() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 3 });
+        state.player.position = Position { x: 0, y: 1 };
+        let ogre = state.spawn_monster(
+            "ogre",
+            Position { x: 2, y: 1 },
+            Stats { hp: 20, max_hp: 20, attack_min: 1, attack_max: 1, defense: 0, weight: 60 },
+        );
+        state.place_item("food ration", Position { x: 0, y: 1 });
+        let mut rng = FixedRng::new(vec![]);
+        let out = step(&mut state, Command::Pickup, &mut rng);
+        let picked_up = out
+            .events
+            .iter()
+            .find(|event| matches!(event, Event::PickedUp { .. }))
+            .expect("pickup event");
+        assert_eq!(state.format_event(picked_up), "Picked up food ration.");
+        assert!(state.log.contains(&state.format_event(picked_up)));
+
+        let moved = Event::Moved { from: Position { x: 0, y: 1 }, to: Position { x: 1, y: 1 } };
+        assert_eq!(state.format_event(&moved), "You move.");
+        let attacked = Event::Attacked { monster_id: ogre, damage: 4, remaining_hp: 16 };
+        assert_eq!(state.format_event(&attacked), "You hit ogre for 4 damage.");
+        assert_eq!(
+            state.format_event(&Event::MonsterDefeated { monster_id: ogre, name: "ogre".into() }),
+            "ogre is defeated."
+        );
+        let left = Event::EnvironmentChanged {
+            from: LegacyEnvironment::City,
+            to: LegacyEnvironment::Countryside,
+        };
+        assert_eq!(state.format_event(&left), "You leave Rampart for the countryside.");
+        let quest = Event::QuestAdvanced { state: LegacyQuestState::Active, steps_completed: 1 };
+        assert_eq!(state.format_event(&quest), "Quest active: 1 steps completed.");
+        let ending = Event::EndingResolved {
+            ending: EndingKind::Victory,
+            score: 2500,
+            high_score_eligible: true,
+        };
+        assert_eq!(state.format_event(&ending), "You have won! Final score: 2500.");
+        let wizard_defeat = Event::EndingResolved {
+            ending: EndingKind::Defeat,
+            score: 10,
+            high_score_eligible: false,
+        };
+        assert_eq!(
+            state.format_event(&wizard_defeat),
+            "Your adventure ends in defeat. Final score: 10 (not eligible)."
+        );
+    }
+
+    #[test]
+    fn defeat_event_keeps_the_slain_monster_name() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.spawn_monster(
+            "goblin",
+            Position { x: 2, y: 1 },
+            Stats { hp: 1, max_hp: 1, attack_min: 1, attack_max: 1, defense: 0, weight: 60 },
+        );
+        let out = step(&mut state, Command::Attack(Direction::East), &mut FixedRng::new(vec![]));
+
+        let defeated = out
+            .events
+            .iter()
+            .find(|event| matches!(event, Event::MonsterDefeated { .. }))
+            .expect("defeat event");
+        assert!(state.monsters.is_empty());
+        assert_eq!(state.format_event(defeated), "goblin is defeated.");
+        assert!(state.log.contains(&state.format_event(defeated)));
+    }
+
+    #[test]
+    fn resting_recovers_only_when_fed() {
+        let mut fed = GameState::new(MapBounds { width: 5, height: 5 });
+        fed.player.stats.hp = 5;
+        fed.player.stats.max_hp = 10;
+        fed.food = 3;
+        let mut starving = fed.clone();
+        starving.food = 0;
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut fed, Command::Legacy { token: ",".to_string() }, &mut rng);
+        let _ = step(&mut starving, Command::Legacy { token: ",".to_string() }, &mut rng);
+
+        assert_eq!(fed.player.stats.hp, 6);
+        assert_eq!(starving.player.stats.hp, 5);
+        assert!(starving.log.iter().any(|line| line == TOO_HUNGRY_TO_RECOVER));
+        assert!(!fed.log.iter().any(|line| line == TOO_HUNGRY_TO_RECOVER));
+
+        let warnings = |state: &GameState| {
+            state.log.iter().filter(|line| *line == TOO_HUNGRY_TO_RECOVER).count()
+        };
+        for _ in 0..3 {
+            let _ = step(&mut starving, Command::Legacy { token: ",".to_string() }, &mut rng);
+        }
+        assert_eq!(warnings(&starving), 1);
+
+        starving.food = 3;
+        let _ = step(&mut starving, Command::Wait, &mut rng);
+        starving.food = 0;
+        let _ = step(&mut starving, Command::Legacy { token: ",".to_string() }, &mut rng);
+        assert_eq!(warnings(&starving), 2);
+    }
+
     #[test]
     fn status_effects_tick_and_expire() {
         let mut state = GameState::default();
@@ -15313,6 +21562,41 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn strong_poison_outdamages_weak_poison_and_fades_out() {
+        let poison_damage = |magnitude: i32| {
+            let mut state = GameState::new(MapBounds { width: 9, height: 9 });
+            state.player.stats.hp = 50;
+            state.player.stats.max_hp = 50;
+            assert!(apply_poison(&mut state, 4, magnitude));
+            let mut rng = FixedRng::new(vec![]);
+            for _ in 0..6 {
+                let _ = step(&mut state, Command::Wait, &mut rng);
+            }
+            assert!(state.status_effects.iter().all(|effect| effect.id != "poison"));
+            50 - state.player.stats.hp
+        };
+
+        assert_eq!(poison_damage(1), 4);
+        assert_eq!(poison_damage(5), 5 + 4 + 3 + 2);
+    }
+
+    #[test]
+    fn reapplied_poison_stacks_up_to_cap_unless_immune() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 9 });
+        assert!(apply_poison(&mut state, 3, 2));
+        assert!(apply_poison(&mut state, 5, 3));
+        assert_eq!(status_magnitude(&state, "poison"), 5);
+        assert_eq!(state.status_effects[0].remaining_turns, 5);
+        assert!(apply_poison(&mut state, 2, 9));
+        assert_eq!(status_magnitude(&state, "poison"), POISON_MAX_MAGNITUDE);
+
+        let mut immune = GameState::new(MapBounds { width: 9, height: 9 });
+        immune.immunities.poison = true;
+        assert!(!apply_poison(&mut immune, 5, 4));
+        assert!(immune.status_effects.is_empty());
+    }
+
     #[test]
     fn legacy_world_mode_and_hunt_commands_apply_modeled_effects() {
         let mut state = GameState::new(MapBounds { width: 9, height: 9 });
@@ -15332,12 +21616,59 @@ mod tests {
     }
 
     #[test]
-    fn countryside_movement_applies_terrain_time_bonus() {
-        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
-        state.world_mode = WorldMode::Countryside;
-        state.environment = LegacyEnvironment::Countryside;
-        state.map_binding.semantic = MapSemanticKind::Country;
-        state.map_rows = vec!["...".to_string(); 3];
+    fn environment_change_emits_single_transition_and_clears_modals() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 9 });
+        let mut rng = FixedRng::new(vec![]);
+        state.tunnel_progress =
+            Some(TunnelProgress { target: Position { x: 1, y: 1 }, effort: 30 });
+        state.target_input_buffer = "3".to_string();
+
+        let out = step(&mut state, Command::Legacy { token: "<".to_string() }, &mut rng);
+        let transitions: Vec<_> = out
+            .events
+            .iter()
+            .filter(|event| matches!(event, Event::EnvironmentChanged { .. }))
+            .collect();
+        assert_eq!(
+            transitions,
+            vec![&Event::EnvironmentChanged {
+                from: LegacyEnvironment::City,
+                to: LegacyEnvironment::Countryside,
+            }]
+        );
+        assert!(state.tunnel_progress.is_none());
+        assert!(state.target_input_buffer.is_empty());
+
+        let out = step(&mut state, Command::Wait, &mut rng);
+        assert!(!out.events.iter().any(|event| matches!(event, Event::EnvironmentChanged { .. })));
+    }
+
+    #[test]
+    fn environment_turn_minutes_table_sets_move_cost() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.environment = LegacyEnvironment::Sewers;
+        state.player.position = Position { x: 1, y: 1 };
+        state.environment_turn_minutes.push(EnvironmentTurnMinutes {
+            environment: LegacyEnvironment::Sewers,
+            minutes_per_turn: 12,
+        });
+        let mut rng = FixedRng::new(vec![]);
+
+        let out = step(&mut state, Command::Move(Direction::East), &mut rng);
+
+        assert_eq!(state.player.position, Position { x: 2, y: 1 });
+        assert_eq!(out.minutes, 12);
+        assert_eq!(state.clock.minutes, 12);
+        assert_eq!(state.clock.minutes_per_turn, 12);
+    }
+
+    #[test]
+    fn countryside_movement_applies_terrain_time_bonus() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.world_mode = WorldMode::Countryside;
+        state.environment = LegacyEnvironment::Countryside;
+        state.map_binding.semantic = MapSemanticKind::Country;
+        state.map_rows = vec!["...".to_string(); 3];
         state.country_map_rows = state.map_rows.clone();
         state.country_site_grid = vec![TileSiteCell::default(); 9];
         state.country_grid = CountryGrid {
@@ -15367,6 +21698,67 @@ mod tests {
         assert_eq!(state.clock.minutes, 120);
     }
 
+    #[test]
+    fn country_regions_restore_their_own_grid_and_position() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.country_grid = CountryGrid {
+            width: 3,
+            height: 3,
+            cells: vec![
+                CountryCell {
+                    glyph: '.',
+                    base_terrain: CountryTerrainKind::Plains,
+                    current_terrain: CountryTerrainKind::Plains,
+                    aux: 0,
+                    status: 0,
+                };
+                9
+            ],
+        };
+        state.world_mode = WorldMode::Countryside;
+        state.map_rows = vec!["...".to_string(); 3];
+        state.country_map_rows = state.map_rows.clone();
+        state.topology.last_country_position = Some(Position { x: 0, y: 0 });
+
+        state.travel_to_country_region(1);
+        assert_eq!(state.country_grid.cells[4].glyph, '.');
+        state.country_grid.cells[4].glyph = '^';
+        state.country_grid.cells[4].current_terrain = CountryTerrainKind::Mountains;
+        let mountain_rows = vec!["...".to_string(), ".^.".to_string(), "...".to_string()];
+        state.map_rows = mountain_rows.clone();
+        state.country_map_rows = mountain_rows.clone();
+        state.topology.last_country_position = Some(Position { x: 2, y: 2 });
+
+        state.travel_to_country_region(0);
+        assert_eq!(state.topology.country_region_id, 0);
+        assert_eq!(state.country_grid.cells[4].glyph, '.');
+        assert_eq!(state.map_glyph_at(Position { x: 1, y: 1 }), '.');
+        assert_eq!(state.country_map_rows, vec!["...".to_string(); 3]);
+        assert_eq!(state.topology.last_country_position, Some(Position { x: 0, y: 0 }));
+
+        state.travel_to_country_region(1);
+        assert_eq!(state.country_grid.cells[4].glyph, '^');
+        assert_eq!(state.country_grid.cells[4].current_terrain, CountryTerrainKind::Mountains);
+        assert_eq!(state.map_glyph_at(Position { x: 1, y: 1 }), '^');
+        assert_eq!(state.country_map_rows, mountain_rows);
+        assert_eq!(state.topology.last_country_position, Some(Position { x: 2, y: 2 }));
+    }
+
+    #[test]
+    fn leaving_the_same_map_reuses_its_country_region() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+
+        let city = state.country_region_for_exit(3);
+        let village = state.country_region_for_exit(7);
+        for _ in 0..300 {
+            assert_eq!(state.country_region_for_exit(3), city);
+        }
+
+        assert_ne!(city, village);
+        assert_eq!(state.country_region_for_exit(7), village);
+        assert_eq!(state.topology.country_region_exits.len(), 2);
+    }
+
     #[test]
     fn countryside_movement_can_spawn_encounter() {
         let mut state = GameState::new(MapBounds { width: 3, height: 3 });
@@ -15547,6 +21939,21 @@ mod tests {
         assert!(state.log.iter().any(|line| line.contains("dark laughter")));
     }
 
+    #[test]
+    fn encounter_shuffle_is_reproducible_for_a_seed() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        let original = state.encounter_monsters.clone();
+        let mut rng = DeterministicRng::seeded(0x5EED);
+
+        state.shuffle_encounters(&mut rng);
+
+        assert_eq!(state.encounter_monsters, vec!["wolf", "goblin", "stalker", "bandit"]);
+        assert_ne!(state.encounter_monsters, original);
+        let mut replay = GameState::new(MapBounds { width: 5, height: 5 });
+        replay.shuffle_encounters(&mut DeterministicRng::seeded(0x5EED));
+        assert_eq!(replay.encounter_monsters, state.encounter_monsters);
+    }
+
     #[test]
     fn countryside_encounter_does_not_spawn_on_city_or_village_cells() {
         let mut state = GameState::new(MapBounds { width: 3, height: 3 });
@@ -15969,6 +22376,50 @@ mod tests {
         assert_eq!(state.map_binding.semantic, MapSemanticKind::Site);
     }
 
+    #[test]
+    fn country_entry_generates_caves_interior_when_map_is_missing() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.player.position = Position { x: 2, y: 2 };
+        state.country_grid = CountryGrid {
+            width: 5,
+            height: 5,
+            cells: vec![
+                CountryCell {
+                    glyph: '.',
+                    base_terrain: CountryTerrainKind::Road,
+                    current_terrain: CountryTerrainKind::Road,
+                    aux: 0,
+                    status: 0,
+                };
+                25
+            ],
+        };
+        state.country_grid.cells[12] = CountryCell {
+            glyph: '*',
+            base_terrain: CountryTerrainKind::Caves,
+            current_terrain: CountryTerrainKind::Caves,
+            aux: 0,
+            status: 0,
+        };
+        assert!(state.site_maps.is_empty());
+
+        let (note, handled) = resolve_enter_country_site(&mut state);
+
+        assert!(handled);
+        assert_eq!(note, "entered the caves");
+        assert_eq!(state.environment, LegacyEnvironment::Caves);
+        assert_eq!(state.world_mode, WorldMode::DungeonCity);
+        assert_eq!(state.map_binding.map_id, 2);
+        assert_eq!(state.map_binding.source, "generated");
+        assert_eq!(state.player.position, Position { x: 2, y: 2 });
+        assert!(state.tile_is_walkable(state.player.position));
+        assert!(!state.tile_is_walkable(Position { x: 0, y: 0 }));
+        assert_eq!(
+            state.tile_site_at(state.player.position).map(|cell| cell.aux),
+            Some(SITE_AUX_EXIT_COUNTRYSIDE)
+        );
+    }
+
     #[test]
     fn country_entry_opens_volcano_site_binding() {
         let mut state = GameState::new(MapBounds { width: 5, height: 5 });
@@ -16012,6 +22463,31 @@ mod tests {
         assert_eq!(state.map_binding.semantic, MapSemanticKind::Site);
     }
 
+    #[test]
+    fn call_item_applies_typed_label_to_chosen_item() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.player.inventory.push(Item {
+            id: 1,
+            name: "bubbly potion".to_string(),
+            family: ItemFamily::Potion,
+            ..Item::default()
+        });
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Legacy { token: "C".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "a".to_string() }, &mut rng);
+        assert!(state.pending_item_prompt.is_none());
+        assert!(state.pending_call_label.is_some());
+        assert_eq!(modal_input_profile(&state), ModalInputProfile::TextEntry);
+        let _ = step(&mut state, Command::Legacy { token: "healing".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "<enter>".to_string() }, &mut rng);
+
+        assert!(state.pending_call_label.is_none());
+        let potion = &state.player.inventory[0];
+        assert_eq!(potion.objstr, "healing");
+        assert_eq!(potion.name, "bubbly potion called healing");
+    }
+
     #[test]
     fn give_command_uses_item_prompt_when_inventory_present() {
         let mut state = GameState::new(MapBounds { width: 7, height: 7 });
@@ -16030,7 +22506,58 @@ mod tests {
         let _ = step(&mut state, Command::Legacy { token: "a".to_string() }, &mut rng);
         assert!(state.pending_item_prompt.is_none());
         assert!(state.player.inventory.is_empty());
-        assert!(state.progression.deity_favor > 0);
+        assert_eq!(state.progression.deity_favor, 0);
+        assert!(state.ground_items.iter().any(|ground| ground.item.name == "offering dagger"));
+    }
+
+    #[test]
+    fn reading_magic_map_reveals_the_whole_level() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 4 });
+        state.player.inventory.push(instantiate_item_from_name(1, "scroll of magic mapping"));
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Legacy { token: "r".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "a".to_string() }, &mut rng);
+
+        assert!(state.player.inventory.is_empty());
+        assert_eq!(state.known_sites.len(), 20);
+        assert!(state.log.iter().any(|line| line.contains("etches itself into your mind")));
+    }
+
+    #[test]
+    fn map_reveal_reports_only_newly_explored_tiles() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 4 });
+        state.known_sites = vec![Position { x: 0, y: 0 }, Position { x: 1, y: 0 }];
+        state.player.inventory.push(instantiate_item_from_name(1, "scroll of magic mapping"));
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Legacy { token: "r".to_string() }, &mut rng);
+        let out = step(&mut state, Command::Legacy { token: "a".to_string() }, &mut rng);
+        assert!(out.events.contains(&Event::MapRevealed { tiles: 18 }));
+
+        state.known_sites.truncate(5);
+        state.wizard.enabled = true;
+        let out = step(&mut state, Command::Legacy { token: "^w".to_string() }, &mut rng);
+        assert!(out.events.contains(&Event::MapRevealed { tiles: 15 }));
+        assert_eq!(state.known_sites.len(), 20);
+    }
+
+    #[test]
+    fn reading_magic_map_in_countryside_charts_nearby_sites() {
+        let mut state = GameState::new(MapBounds { width: 12, height: 3 });
+        state.world_mode = WorldMode::Countryside;
+        state.player.position = Position { x: 0, y: 1 };
+        state.site_grid = vec![TileSiteCell::default(); 36];
+        state.site_grid[12 + 5].site_id = 3;
+        state.site_grid[12 + 11].site_id = 4;
+        state.player.inventory.push(instantiate_item_from_name(1, "scroll of magic mapping"));
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Legacy { token: "r".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "a".to_string() }, &mut rng);
+
+        assert!(state.known_sites.contains(&Position { x: 5, y: 1 }));
+        assert!(!state.known_sites.contains(&Position { x: 11, y: 1 }));
     }
 
     #[test]
@@ -16061,6 +22588,51 @@ mod tests {
         assert!(out.events.iter().all(|event| !matches!(event, Event::VictoryAchieved)));
     }
 
+    #[test]
+    fn completing_main_quest_final_rites_wins_the_game() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.progression.quest_state = LegacyQuestState::ReturnToPatron;
+        state.progression.main_quest.stage = LegacyQuestState::ReturnToPatron;
+        state.progression.guild_rank = 2;
+        state.progression.priest_rank = 1;
+        state.pending_site_interaction = Some(SiteInteractionKind::Castle);
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Legacy { token: "3".to_string() }, &mut rng);
+        assert_eq!(state.progression.main_quest.stage, LegacyQuestState::Completed);
+        let _ = step(&mut state, Command::Legacy { token: "q".to_string() }, &mut rng);
+        let out = step(&mut state, Command::Wait, &mut rng);
+
+        assert_eq!(state.status, SessionStatus::Won);
+        assert_eq!(
+            state.progression.victory_trigger,
+            Some(VictoryTrigger::ExplicitQuestCompletion)
+        );
+        assert_eq!(state.progression.ending, EndingKind::TotalWinner);
+        assert!(out.events.iter().any(|event| matches!(event, Event::EndingResolved { .. })));
+    }
+
+    #[test]
+    fn retiring_at_condo_ends_game_with_wealth_based_score() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.gold = 3_000;
+        state.bank_gold = 1_000;
+        state.pending_site_interaction = Some(SiteInteractionKind::Condo);
+        let mut rng = FixedRng::new(vec![]);
+
+        let out = step(&mut state, Command::Legacy { token: "t".to_string() }, &mut rng);
+
+        assert_eq!(state.status, SessionStatus::Won);
+        assert_eq!(state.progression.victory_trigger, Some(VictoryTrigger::RetireCondo));
+        assert_eq!(state.progression.ending, EndingKind::Victory);
+        let hoard_score = 2 * 4_000 + i64::from(state.food * 3);
+        assert_eq!(state.progression.score, hoard_score);
+        assert!(out.events.iter().any(|event| matches!(
+            event,
+            Event::EndingResolved { ending: EndingKind::Victory, score, .. } if *score == hoard_score
+        )));
+    }
+
     #[test]
     fn legacy_q_cancel_keeps_session_in_progress() {
         let mut state = GameState::new(MapBounds { width: 5, height: 5 });
@@ -16144,6 +22716,56 @@ mod tests {
         assert!(state.player.stats.attack_max > state.player.stats.attack_min);
     }
 
+    #[test]
+    fn wizard_teleport_moves_player_and_refuses_out_of_bounds() {
+        let mut state = GameState::new(MapBounds { width: 10, height: 10 });
+        state.wizard.enabled = true;
+        state.player.position = Position { x: 1, y: 1 };
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Legacy { token: "^t".to_string() }, &mut rng);
+        assert_eq!(state.pending_wizard_interaction, Some(WizardInteraction::TeleportEntry));
+        let _ = step(&mut state, Command::Legacy { token: "6,7".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "<enter>".to_string() }, &mut rng);
+        assert_eq!(state.player.position, Position { x: 6, y: 7 });
+        assert!(state.pending_wizard_interaction.is_none());
+
+        let _ = step(&mut state, Command::Legacy { token: "^t".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "40,2".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "<enter>".to_string() }, &mut rng);
+        assert_eq!(state.player.position, Position { x: 6, y: 7 });
+        assert!(state.log.iter().any(|line| line.contains("Teleport refused")));
+    }
+
+    #[test]
+    fn wizard_spawn_places_named_monster_adjacent_with_inferred_profile() {
+        let mut state = GameState::new(MapBounds { width: 10, height: 10 });
+        state.wizard.enabled = true;
+        state.player.position = Position { x: 4, y: 4 };
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Legacy { token: "^n".to_string() }, &mut rng);
+        assert_eq!(state.pending_wizard_interaction, Some(WizardInteraction::SpawnMonsterEntry));
+        let _ = step(&mut state, Command::Legacy { token: "grey wolf".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "<enter>".to_string() }, &mut rng);
+
+        let wolf = state.monsters.iter().find(|m| m.name == "grey wolf").expect("wolf spawned");
+        let dx = (wolf.position.x - 4).abs();
+        let dy = (wolf.position.y - 4).abs();
+        assert_eq!(dx.max(dy), 1);
+        assert_eq!(wolf.behavior, MonsterBehavior::Skirmisher);
+        assert_eq!(wolf.faction, Faction::Wild);
+        let wolf_id = wolf.id;
+        assert!(state.log.iter().any(|line| line.contains(&format!("id {wolf_id}"))));
+
+        let _ = step(&mut state, Command::Legacy { token: "^n".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "glorp".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "<enter>".to_string() }, &mut rng);
+        let glorp = state.monsters.iter().find(|m| m.name == "glorp").expect("glorp spawned");
+        assert_eq!(glorp.stats.max_hp, 12);
+        assert_eq!(glorp.behavior, MonsterBehavior::Brute);
+    }
+
     #[test]
     fn options_command_cycles_runtime_toggles() {
         let mut state = GameState::default();
@@ -16173,6 +22795,26 @@ mod tests {
         assert!(state.gold >= 200);
     }
 
+    #[test]
+    fn random_player_names_are_seeded_and_varied() {
+        let first = random_player_name(&mut DeterministicRng::seeded(7));
+        assert_eq!(first, random_player_name(&mut DeterministicRng::seeded(7)));
+        assert!(first.chars().next().is_some_and(|ch| ch.is_ascii_uppercase()));
+
+        let names: std::collections::BTreeSet<String> =
+            (0..20).map(|seed| random_player_name(&mut DeterministicRng::seeded(seed))).collect();
+        assert!(names.len() > 10, "expected varied names, got {names:?}");
+
+        let mut state = GameState::default();
+        let creation = CharacterCreation {
+            name: "  ".to_string(),
+            archetype_id: "mage".to_string(),
+            alignment: Alignment::Neutral,
+        };
+        apply_character_creation(&mut state, &creation);
+        assert_eq!(state.player_name, "Adventurer", "a blank name keeps the existing one");
+    }
+
     #[test]
     fn legacy_questionnaire_profile_uses_reference_scoring() {
         let answers = LegacyQuestionnaireAnswers {
@@ -16197,6 +22839,81 @@ mod tests {
         assert_eq!(creation.creation.alignment, Alignment::Neutral);
     }
 
+    #[test]
+    fn questionnaire_archetype_scores_are_sorted_and_match_formula() {
+        let answers = LegacyQuestionnaireAnswers {
+            bench_press_lbs: 120,
+            took_iq_test: true,
+            iq_score: 150,
+            plays_video_games: true,
+            gets_high_scores: true,
+            cast_spell: true,
+            spell_worked: true,
+            ..LegacyQuestionnaireAnswers::default()
+        };
+        let profile = derive_legacy_questionnaire_profile(&answers);
+        let scores = questionnaire_archetype_scores(&profile);
+
+        assert_eq!(scores.len(), 4);
+        assert!(scores.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+
+        let score_for = |id: &str| {
+            scores.iter().find(|(candidate, _)| candidate == id).map(|(_, score)| *score)
+        };
+        assert_eq!(
+            score_for("fighter"),
+            Some(profile.strength * 3 + profile.constitution * 2 + profile.agility)
+        );
+        assert_eq!(
+            score_for("rogue"),
+            Some(profile.dexterity * 3 + profile.agility * 2 + profile.iq)
+        );
+        assert_eq!(score_for("mage"), Some(profile.iq * 3 + profile.power * 3 + profile.dexterity));
+        assert_eq!(
+            score_for("priest"),
+            Some(profile.power * 3 + profile.iq * 2 + profile.constitution)
+        );
+
+        let creation = derive_legacy_questionnaire_creation("Runner".to_string(), &answers);
+        assert_eq!(creation.creation.archetype_id, scores[0].0);
+    }
+
+    #[test]
+    fn questionnaire_esp_trait_senses_nearby_monster() {
+        let answers = LegacyQuestionnaireAnswers { has_esp: true, ..Default::default() };
+        let profile = derive_legacy_questionnaire_profile(&answers);
+        let mut state = GameState::new(MapBounds { width: 9, height: 9 });
+        apply_legacy_questionnaire_profile(&mut state, profile);
+        assert!(state.questionnaire_traits.has_esp);
+
+        state.player.position = Position { x: 1, y: 1 };
+        state.spawn_monster(
+            "goblin",
+            Position { x: 5, y: 5 },
+            Stats { hp: 5, max_hp: 5, attack_min: 1, attack_max: 2, defense: 0, weight: 40 },
+        );
+        state.ai_paused = true;
+        let mut rng = FixedRng::new(vec![1]);
+
+        let out = step(&mut state, Command::Wait, &mut rng);
+        assert!(out.events.iter().any(|event| matches!(
+            event,
+            Event::LegacyHandled { token, .. } if token == "esp"
+        )));
+        assert!(state.log.iter().any(|line| line.contains("sense the presence of a goblin")));
+
+        let mut plain = GameState::new(MapBounds { width: 9, height: 9 });
+        plain.spawn_monster(
+            "goblin",
+            Position { x: 7, y: 7 },
+            Stats { hp: 5, max_hp: 5, attack_min: 1, attack_max: 2, defense: 0, weight: 40 },
+        );
+        plain.ai_paused = true;
+        let mut rng = FixedRng::new(vec![1]);
+        let _ = step(&mut plain, Command::Wait, &mut rng);
+        assert!(!plain.log.iter().any(|line| line.contains("sense the presence")));
+    }
+
     #[test]
     fn applying_legacy_questionnaire_profile_updates_runtime_stats() {
         let mut state = GameState::default();
@@ -16360,28 +23077,287 @@ mod tests {
     }
 
     #[test]
-    fn merc_contract_sets_specific_legion_objective() {
+    fn time_of_day_and_day_number_follow_clock_minutes() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        let cases = [
+            (0, TimeOfDay::Day, 1),
+            (6 * 60, TimeOfDay::Dusk, 1),
+            (11 * 60, TimeOfDay::Night, 1),
+            (12 * 60, TimeOfDay::Night, 2),
+            (17 * 60 + 30, TimeOfDay::Dawn, 2),
+            (MINUTES_PER_DAY + 60, TimeOfDay::Day, 2),
+            (3 * MINUTES_PER_DAY, TimeOfDay::Day, 4),
+        ];
+        for (minutes, expected_time, expected_day) in cases {
+            state.clock.minutes = minutes;
+            assert_eq!(state.time_of_day(), expected_time, "minutes={minutes}");
+            assert_eq!(state.day_number(), expected_day, "minutes={minutes}");
+        }
+    }
+
+    #[test]
+    fn shop_is_locked_at_night_and_opens_by_day() {
         let mut state = GameState::new(MapBounds { width: 3, height: 3 });
         state.options.interactive_sites = true;
         state.player.position = Position { x: 1, y: 1 };
         state.site_grid = vec![TileSiteCell::default(); 9];
+        state.site_grid[4].aux = SITE_AUX_SERVICE_SHOP;
         state.city_site_grid = state.site_grid.clone();
-        state.site_grid[4].aux = SITE_AUX_SERVICE_MERC_GUILD;
-        state.city_site_grid[4].aux = SITE_AUX_SERVICE_MERC_GUILD;
         let mut rng = FixedRng::new(vec![]);
 
+        state.clock.minutes = 11 * 60;
+        assert_eq!(state.hour_of_day(), 23);
         let _ = step(&mut state, Command::Legacy { token: ">".to_string() }, &mut rng);
-        let _ = step(&mut state, Command::Legacy { token: "2".to_string() }, &mut rng);
-        let objective = state.progression.main_quest.objective.to_ascii_lowercase();
-        let line = state.log.last().cloned().unwrap_or_default().to_ascii_lowercase();
+        assert!(state.pending_site_interaction.is_none());
+        assert!(state.log.iter().any(|line| line.contains("The door is locked")));
+        assert!(site_is_open(&SiteInteractionKind::Temple, TimeOfDay::Night));
+        assert!(site_is_open(&SiteInteractionKind::Healer, TimeOfDay::Night));
 
-        assert!(objective.contains("legion"));
-        assert!(objective.contains("centurion") || objective.contains("regalia"));
-        assert!(line.contains("accepted legion contract"));
+        state.clock.minutes = 22 * 60;
+        assert_eq!(state.hour_of_day(), 10);
+        let _ = step(&mut state, Command::Legacy { token: ">".to_string() }, &mut rng);
+        assert_eq!(state.pending_site_interaction, Some(SiteInteractionKind::Shop));
     }
 
     #[test]
-    fn tavern_rumor_purchase_sets_actionable_quest_objective() {
+    fn clever_haggler_lowers_shop_prices() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.options.interactive_sites = true;
+        state.player.position = Position { x: 1, y: 1 };
+        state.site_grid = vec![TileSiteCell::default(); 9];
+        state.site_grid[4].aux = SITE_AUX_SERVICE_SHOP;
+        state.city_site_grid = state.site_grid.clone();
+        state.clock.minutes = 22 * 60;
+        state.attributes.iq = 16;
+        state.gold = 100;
+        assert_eq!(parse_site_interaction_choice(&state, &SiteInteractionKind::Shop, "h"), Some(5));
+
+        let _ = step(
+            &mut state,
+            Command::Legacy { token: ">".to_string() },
+            &mut FixedRng::new(vec![]),
+        );
+        let _ = step(
+            &mut state,
+            Command::Legacy { token: "h".to_string() },
+            &mut FixedRng::new(vec![9]),
+        );
+        assert!(state.log.iter().any(|line| line.contains("prices drop")));
+        assert!(
+            site_interaction_prompt(&state, &SiteInteractionKind::Shop).contains("potion (24g)")
+        );
+        let _ = step(
+            &mut state,
+            Command::Legacy { token: "2".to_string() },
+            &mut FixedRng::new(vec![]),
+        );
+        assert_eq!(state.gold, 76);
+    }
+
+    #[test]
+    fn shops_on_very_wide_maps_keep_distinct_stock_keys() {
+        let mut state = GameState::new(MapBounds { width: 65_537, height: 1 });
+        state.player.position = Position { x: 0, y: 0 };
+        let first = shop_site_key(&state);
+        state.player.position = Position { x: 65_536, y: 0 };
+        assert_ne!(shop_site_key(&state), first);
+    }
+
+    #[test]
+    fn shops_keep_separate_stock_and_restock_as_days_pass() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.gold = 500;
+        let mut events = Vec::new();
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = begin_site_interaction(&mut state, SiteInteractionKind::Shop, &mut events, "test");
+        let key = shop_site_key(&state);
+        let opening = state.shop_stock[&key].clone();
+        let _ = apply_site_interaction_choice(
+            &mut state,
+            SiteInteractionKind::Shop,
+            2,
+            &mut rng,
+            &mut events,
+            false,
+        );
+        let _ = begin_site_interaction(&mut state, SiteInteractionKind::Shop, &mut events, "test");
+        assert_eq!(state.shop_stock[&key].wares[2], opening.wares[2]);
+        assert_eq!(state.shop_stock[&key].wares[1], opening.wares[1] - 1);
+
+        // A second shop on another map has its own shelves.
+        state.pending_site_interaction = None;
+        state.map_binding.map_id = 9;
+        let _ = begin_site_interaction(&mut state, SiteInteractionKind::Shop, &mut events, "test");
+        let other = shop_site_key(&state);
+        assert_ne!(other, key);
+        let other_opening = state.shop_stock[&other].clone();
+        state.map_binding.map_id = 0;
+
+        // Shopping again the same day does not restock; waiting into the next day does.
+        let _ = apply_site_interaction_choice(
+            &mut state,
+            SiteInteractionKind::Shop,
+            2,
+            &mut rng,
+            &mut events,
+            false,
+        );
+        assert_eq!(state.shop_stock[&key].wares[1], opening.wares[1] - 2);
+        assert_eq!(state.shop_stock[&other], other_opening);
+        state.pending_site_interaction = None;
+        state.clock.minutes += MINUTES_PER_DAY;
+        let mut replay = state.clone();
+        let _ = step(&mut state, Command::Wait, &mut rng);
+        let _ = step(&mut replay, Command::Wait, &mut rng);
+        assert_eq!(state.shop_stock[&key].day, opening.day + 1);
+        assert_eq!(state.shop_stock[&other].day, opening.day + 1);
+        assert_eq!(state.shop_stock[&key], replay.shop_stock[&key]);
+        assert!(
+            state.shop_stock[&key].wares.iter().all(|count| {
+                (SHOP_RESTOCK_MIN..=SHOP_RESTOCK_MAX).contains(&i32::from(*count))
+            })
+        );
+
+        state.shop_stock.get_mut(&key).expect("stock").wares[0] = 0;
+        let note = apply_site_interaction_choice(
+            &mut state,
+            SiteInteractionKind::Shop,
+            1,
+            &mut rng,
+            &mut events,
+            false,
+        );
+        assert!(note.contains("sold out"));
+
+        // Entering a shop restocks it even if no turn has passed since the day turned.
+        state.clock.minutes += MINUTES_PER_DAY;
+        state.maybe_restock_shop(shop_site_id(&state));
+        assert_eq!(state.shop_stock[&key].day, opening.day + 2);
+        assert_eq!(state.shop_stock[&key], shop_stock_for_day(key, opening.day + 2));
+    }
+
+    #[test]
+    fn spending_gold_refuses_overdraft_and_both_helpers_report_the_change() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.gold = 30;
+        let mut events = Vec::new();
+
+        assert!(!state.spend_gold(31, "casino", &mut events));
+        assert_eq!(state.gold, 30);
+        assert!(events.is_empty());
+
+        assert!(state.spend_gold(30, "casino", &mut events));
+        assert_eq!(state.gold, 0);
+        state.gain_gold(-5, "altar", &mut events);
+        assert_eq!(state.gold, 0);
+        state.gain_gold(12, "altar", &mut events);
+        assert_eq!(state.gold, 12);
+        let sources: Vec<(&str, i32)> = events
+            .iter()
+            .filter_map(|event| match event {
+                Event::EconomyUpdated { source, gold, .. } => Some((source.as_str(), *gold)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(sources, vec![("casino", 0), ("altar", 0), ("altar", 12)]);
+    }
+
+    #[test]
+    fn casino_chips_move_gold_to_the_bank_only_when_affordable() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.gold = 20;
+        let mut events = Vec::new();
+        let mut rng = FixedRng::new(vec![]);
+
+        let note = apply_site_interaction_choice(
+            &mut state,
+            SiteInteractionKind::Casino,
+            1,
+            &mut rng,
+            &mut events,
+            false,
+        );
+        assert!(note.contains("Not enough gold"));
+        assert_eq!((state.gold, state.bank_gold), (20, 0));
+        assert!(events.is_empty());
+
+        state.gold = 30;
+        let _ = apply_site_interaction_choice(
+            &mut state,
+            SiteInteractionKind::Casino,
+            1,
+            &mut rng,
+            &mut events,
+            false,
+        );
+        assert_eq!((state.gold, state.bank_gold), (5, 5));
+        assert!(events.iter().any(|event| matches!(
+            event,
+            Event::EconomyUpdated { source, gold: 5, bank_gold: 5 } if source == "casino"
+        )));
+    }
+
+    #[test]
+    fn failed_haggle_offends_merchant_until_the_shop_restocks() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.options.interactive_sites = true;
+        state.player.position = Position { x: 1, y: 1 };
+        state.site_grid = vec![TileSiteCell::default(); 9];
+        state.site_grid[4].aux = SITE_AUX_SERVICE_SHOP;
+        state.city_site_grid = state.site_grid.clone();
+        state.clock.minutes = 22 * 60;
+        state.attributes.iq = 8;
+        state.gold = 100;
+        let legacy = |token: &str| Command::Legacy { token: token.to_string() };
+
+        let _ = step(&mut state, legacy(">"), &mut FixedRng::new(vec![]));
+        let _ = step(&mut state, legacy("h"), &mut FixedRng::new(vec![5]));
+        assert!(state.log.iter().any(|line| line.contains("takes offense")));
+
+        // Walking out and back in does not wipe the merchant's memory.
+        let _ = step(&mut state, legacy("x"), &mut FixedRng::new(vec![]));
+        assert!(state.pending_site_interaction.is_none());
+        let _ = step(&mut state, legacy(">"), &mut FixedRng::new(vec![]));
+        let mut rng = FixedRng::new(vec![20]);
+        let _ = step(&mut state, legacy("h"), &mut rng);
+        assert!(state.log.iter().any(|line| line.contains("final price")));
+        assert_eq!(rng.index, 0, "a settled price should not roll again");
+        let _ = step(&mut state, legacy("2"), &mut FixedRng::new(vec![]));
+        assert_eq!(state.gold, 100 - 37);
+
+        state.clock.minutes += MINUTES_PER_DAY;
+        let _ = step(&mut state, legacy("x"), &mut FixedRng::new(vec![]));
+        let _ = step(&mut state, legacy(">"), &mut FixedRng::new(vec![]));
+        assert!(
+            site_interaction_prompt(&state, &SiteInteractionKind::Shop).contains("potion (30g)")
+        );
+    }
+
+    #[test]
+    fn merc_contract_sets_specific_legion_objective() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.options.interactive_sites = true;
+        state.player.position = Position { x: 1, y: 1 };
+        state.site_grid = vec![TileSiteCell::default(); 9];
+        state.city_site_grid = state.site_grid.clone();
+        state.site_grid[4].aux = SITE_AUX_SERVICE_MERC_GUILD;
+        state.city_site_grid[4].aux = SITE_AUX_SERVICE_MERC_GUILD;
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Legacy { token: ">".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "2".to_string() }, &mut rng);
+        let objective = state.progression.main_quest.objective.to_ascii_lowercase();
+        let line = state.log.last().cloned().unwrap_or_default().to_ascii_lowercase();
+
+        assert!(objective.contains("legion"));
+        assert!(objective.contains("centurion") || objective.contains("regalia"));
+        assert!(line.contains("accepted legion contract"));
+    }
+
+    #[test]
+    fn tavern_rumor_purchase_sets_actionable_quest_objective() {
         let mut state = GameState::new(MapBounds { width: 3, height: 3 });
         state.options.interactive_sites = true;
         state.player.position = Position { x: 1, y: 1 };
@@ -16439,6 +23415,36 @@ mod tests {
         assert!(hints.contains(&Position { x: 0, y: 1 }));
     }
 
+    #[test]
+    fn active_objective_hints_point_returning_hero_at_the_guild() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.progression.quest_state = LegacyQuestState::ReturnToPatron;
+        state.progression.main_quest.stage = LegacyQuestState::ReturnToPatron;
+        state.site_grid = vec![TileSiteCell::default(); 9];
+        state.site_grid[5].aux = SITE_AUX_SERVICE_MERC_GUILD;
+
+        let hints = state.active_objective_hints();
+        assert_eq!(hints.len(), 1);
+        assert_eq!(hints[0].target, Some(Position { x: 2, y: 1 }));
+        assert!(hints[0].label.contains("Mercenary Guild"));
+
+        state.world_mode = WorldMode::Countryside;
+        state.topology.country_rampart_position = Some(Position { x: 0, y: 2 });
+        let hints = state.active_objective_hints();
+        assert_eq!(hints[0].target, Some(Position { x: 0, y: 2 }));
+    }
+
+    #[test]
+    fn active_objective_hints_point_at_the_stairs_regardless_of_known_sites() {
+        let mut state = state_with_rows(&["...", "..>", "..."], Position { x: 0, y: 0 });
+        state.progression.main_quest.stage = LegacyQuestState::Active;
+        state.known_sites.push(Position { x: 1, y: 1 });
+
+        let hints = state.active_objective_hints();
+        assert_eq!(hints.len(), 1);
+        assert_eq!(hints[0].target, Some(Position { x: 2, y: 1 }));
+    }
+
     #[test]
     fn objective_map_hints_bias_to_walkable_approach_near_door() {
         let mut state = GameState::new(MapBounds { width: 3, height: 3 });
@@ -16512,10 +23518,22 @@ mod tests {
         let mut rng = FixedRng::new(vec![]);
 
         let _ = step(&mut state, Command::Legacy { token: ">".to_string() }, &mut rng);
+        let (offer, price) = pawn_shop_offer(&state);
+        assert_eq!(offer.name, "boots of heroism");
+        assert_eq!(price, 1_000, "oddities resell at twice their appraisal");
+        let _ = step(&mut state, Command::Legacy { token: "1".to_string() }, &mut rng);
+        assert!(state.player.inventory.is_empty());
+        assert_eq!(state.gold, 100);
+
+        state.gold = price;
+        let next_id = state.next_item_id;
         let _ = step(&mut state, Command::Legacy { token: "1".to_string() }, &mut rng);
 
         let line = state.log.last().cloned().unwrap_or_default().to_ascii_lowercase();
         assert!(state.player.inventory.len() == 1, "pawn buy should add one item");
+        assert_eq!(state.gold, 0);
+        assert_eq!(state.player.inventory[0].id, next_id);
+        assert_eq!(state.next_item_id, next_id + 1);
         assert!(
             !line.contains("pawned oddity"),
             "pawn buy should report actual catalog item name, got: {line}"
@@ -16527,6 +23545,71 @@ mod tests {
         assert!(state.player.inventory[0].known, "pawn purchases should be identified stock");
     }
 
+    #[test]
+    fn pawn_shop_sale_identifies_and_prices_unknown_items() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.options.interactive_sites = true;
+        state.player.position = Position { x: 1, y: 1 };
+        state.site_grid = vec![TileSiteCell::default(); 9];
+        state.city_site_grid = state.site_grid.clone();
+        state.site_grid[4].aux = SITE_AUX_SERVICE_PAWN_SHOP;
+        state.city_site_grid[4].aux = SITE_AUX_SERVICE_PAWN_SHOP;
+        state.gold = 0;
+        let ring = Item {
+            id: 3,
+            name: "tarnished ring".to_string(),
+            truename: "ring of strength".to_string(),
+            family: ItemFamily::Ring,
+            basevalue: 300,
+            plus: 2,
+            ..Item::default()
+        };
+        state.player.inventory.push(ring.clone());
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Legacy { token: ">".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "2".to_string() }, &mut rng);
+
+        assert!(state.player.inventory.is_empty());
+        assert_eq!(state.gold, 170);
+        let line = state.log.last().cloned().unwrap_or_default();
+        assert!(line.contains("+2 ring of strength for 170 gold"), "got: {line}");
+
+        assert!(!ring.known);
+
+        state.gold = 400;
+        let _ = step(&mut state, Command::Legacy { token: "1".to_string() }, &mut rng);
+        assert_eq!(state.gold, 60, "the pledge is resold at twice its appraisal");
+        let returned = &state.player.inventory[0];
+        assert_eq!(returned.id, 3);
+        assert!(returned.known, "the returned ring keeps its appraisal");
+        assert_eq!(returned.name, "ring of strength");
+        assert!(state.pawn_stock.is_empty());
+    }
+
+    #[test]
+    fn pawn_shop_buy_then_sell_back_never_gains_gold() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.options.interactive_sites = true;
+        state.player.position = Position { x: 1, y: 1 };
+        state.site_grid = vec![TileSiteCell::default(); 9];
+        state.city_site_grid = state.site_grid.clone();
+        state.site_grid[4].aux = SITE_AUX_SERVICE_PAWN_SHOP;
+        state.city_site_grid[4].aux = SITE_AUX_SERVICE_PAWN_SHOP;
+        state.gold = 5_000;
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Legacy { token: ">".to_string() }, &mut rng);
+        for _ in 0..3 {
+            let before = state.gold;
+            let _ = step(&mut state, Command::Legacy { token: "1".to_string() }, &mut rng);
+            assert_eq!(state.player.inventory.len(), 1);
+            let _ = step(&mut state, Command::Legacy { token: "2".to_string() }, &mut rng);
+            assert!(state.player.inventory.is_empty());
+            assert!(state.gold < before, "round trip went {before} -> {}", state.gold);
+        }
+    }
+
     #[test]
     fn castle_talk_assigns_goblin_king_quest_first() {
         let mut state = GameState::new(MapBounds { width: 3, height: 3 });
@@ -16565,6 +23648,78 @@ mod tests {
         assert!(line.contains("star gem") || line.contains("justiciar"));
     }
 
+    #[test]
+    fn club_absolution_clears_worn_curses_and_reduces_heat() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.gold = 100;
+        state.legal_heat = 5;
+        state.player.inventory.push(Item {
+            id: 1,
+            name: "cursed helm".to_string(),
+            family: ItemFamily::Armor,
+            blessing: -3,
+            used: true,
+            ..Item::default()
+        });
+        state.player.inventory.push(Item {
+            id: 2,
+            name: "cursed ring".to_string(),
+            family: ItemFamily::Ring,
+            blessing: -1,
+            ..Item::default()
+        });
+        state.player.inventory.push(Item {
+            id: 3,
+            name: "blessed cloak".to_string(),
+            family: ItemFamily::Cloak,
+            blessing: 1,
+            used: true,
+            ..Item::default()
+        });
+        state.pending_site_interaction = Some(SiteInteractionKind::Club);
+        // The step rng decides the botch: an exhausted FixedRng rolls its minimum.
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Legacy { token: "a".to_string() }, &mut rng);
+
+        assert_eq!(state.gold, 100 - CLUB_ABSOLUTION_COST);
+        assert_eq!(state.legal_heat, 5 - ABSOLUTION_HEAT_RELIEF);
+        assert_eq!(state.player.inventory[0].blessing, 0);
+        assert_eq!(state.player.inventory[1].blessing, -1, "only worn items are absolved");
+        assert_eq!(state.player.inventory[2].blessing, 0, "the botch uses the step rng");
+        assert!(state.log.iter().any(|line| line.contains("A curse lifts from your gear.")));
+
+        let _ = step(&mut state, Command::Legacy { token: "3".to_string() }, &mut rng);
+        assert!(state.pending_site_interaction.is_none(), "leave stays on 3");
+    }
+
+    #[test]
+    fn botched_absolution_strips_a_worn_blessing() {
+        let blessed_shield = Item {
+            id: 1,
+            name: "blessed shield".to_string(),
+            family: ItemFamily::Shield,
+            blessing: 2,
+            used: true,
+            ..Item::default()
+        };
+
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.inventory.push(blessed_shield.clone());
+        let note = perform_absolution_rite(&mut state, &mut FixedRng::new(vec![50]));
+        assert_eq!(state.player.inventory[0].blessing, 2);
+        assert!(!note.contains("loses its blessing"));
+
+        let mut botched = GameState::new(MapBounds { width: 3, height: 3 });
+        botched.player.inventory.push(blessed_shield);
+        let note = perform_absolution_rite(
+            &mut botched,
+            &mut FixedRng::new(vec![ABSOLUTION_BOTCH_PERCENT]),
+        );
+        assert_eq!(botched.player.inventory[0].blessing, 0);
+        assert!(note.contains("blessed shield loses its blessing"));
+    }
+
     #[test]
     fn arena_service_does_not_apply_immediate_monster_hit() {
         let mut state = GameState::new(MapBounds { width: 3, height: 3 });
@@ -16740,7 +23895,7 @@ mod tests {
     }
 
     #[test]
-    fn arena_menu_accepts_legacy_letter_choices() {
+    fn losing_arena_match_can_be_surrendered_for_a_penalty() {
         let mut state = GameState::new(MapBounds { width: 3, height: 3 });
         state.options.interactive_sites = true;
         state.player.position = Position { x: 1, y: 1 };
@@ -16749,461 +23904,2947 @@ mod tests {
         state.site_maps = vec![arena_test_site_definition()];
         state.site_grid[4].aux = SITE_AUX_SERVICE_ARENA;
         state.city_site_grid[4].aux = SITE_AUX_SERVICE_ARENA;
+        state.gold = 200;
+        state.progression.quests.arena.xp = 80;
         let mut rng = FixedRng::new(vec![2]);
 
         let _ = step(&mut state, Command::Legacy { token: ">".to_string() }, &mut rng);
-        let register = step(&mut state, Command::Legacy { token: "r".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "1".to_string() }, &mut rng);
+        assert!(state.progression.arena_match_active);
+        let gold_before = state.gold;
 
-        assert_eq!(state.progression.arena_rank, 1);
-        assert_eq!(state.pending_site_interaction, Some(SiteInteractionKind::Arena));
-        assert!(register.events.iter().any(|event| matches!(
-            event,
-            Event::LegacyHandled { token, note, .. }
-                if token == "interaction" && note.contains("Selected option 2")
-        )));
+        let _ = step(&mut state, Command::OfferSurrender, &mut rng);
+        assert!(state.progression.arena_match_active, "healthy fighters can't yield");
 
-        let start = step(&mut state, Command::Legacy { token: "y".to_string() }, &mut rng);
-        assert_eq!(state.pending_site_interaction, None);
-        assert_eq!(state.environment, LegacyEnvironment::Arena);
-        assert!(state.progression.arena_match_active);
-        assert!(start.events.iter().any(|event| matches!(
+        state.player.stats.hp = 3;
+        let _ = step(&mut state, Command::OfferSurrender, &mut rng);
+        assert_eq!(state.status, SessionStatus::InProgress);
+        assert!(state.player.stats.is_alive());
+        assert!(!state.progression.arena_match_active);
+        assert_eq!(state.environment, LegacyEnvironment::City);
+        assert_eq!(state.gold, gold_before - ARENA_SURRENDER_FINE);
+        assert_eq!(state.progression.quests.arena.xp, 80 - ARENA_SURRENDER_XP_PENALTY);
+        assert!(state.monsters.iter().all(|monster| !monster.name.contains("champion")));
+    }
+
+    #[test]
+    fn legacy_yield_token_offers_arena_surrender() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        let mut rng = FixedRng::new(vec![]);
+        let out = step(&mut state, Command::Legacy { token: "Y".to_string() }, &mut rng);
+        assert!(out.events.iter().any(|event| matches!(
             event,
-            Event::LegacyHandled { token, note, .. }
-                if token == "interaction" && note.contains("arranging a match")
+            Event::LegacyHandled { token, note, fully_modeled: true }
+                if token == "Y" && note == "There is no match to yield."
         )));
+        assert_eq!(out.minutes, 5);
+    }
+
+    fn state_at_arena_gate() -> GameState {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.options.interactive_sites = true;
+        state.player.position = Position { x: 1, y: 1 };
+        state.site_grid = vec![TileSiteCell::default(); 9];
+        state.city_site_grid = state.site_grid.clone();
+        state.site_maps = vec![arena_test_site_definition()];
+        state.site_grid[4].aux = SITE_AUX_SERVICE_ARENA;
+        state.city_site_grid[4].aux = SITE_AUX_SERVICE_ARENA;
+        state
     }
 
     #[test]
-    fn arena_menu_rejects_restart_while_match_active() {
-        let mut state = GameState::new(MapBounds { width: 9, height: 9 });
-        state.progression.arena_rank = 1;
-        state.progression.arena_opponent = 3;
-        state.progression.arena_match_active = true;
-        state.spawn_monster(
-            "arena goblin",
-            Position { x: 5, y: 4 },
-            Stats { hp: 8, max_hp: 8, attack_min: 2, attack_max: 3, defense: 1, weight: 60 },
-        );
-        let monster_count_before = state.monsters.len();
-        let mut events = Vec::new();
-
-        let note = apply_site_interaction_choice(
-            &mut state,
-            SiteInteractionKind::Arena,
-            1,
-            &mut events,
-            true,
-        );
-
-        assert!(note.contains("already in the games"));
-        assert_eq!(state.monsters.len(), monster_count_before);
-        assert!(state.progression.arena_match_active);
-    }
-
-    #[test]
-    fn arena_exit_tile_returns_player_to_city_context() {
-        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
-        state.player.position = Position { x: 1, y: 1 };
-        state.site_grid = vec![TileSiteCell::default(); 9];
-        state.city_site_grid = state.site_grid.clone();
-        state.site_maps = vec![arena_test_site_definition()];
-        state.site_grid[4].aux = SITE_AUX_SERVICE_ARENA;
-        state.city_site_grid[4].aux = SITE_AUX_SERVICE_ARENA;
-        let mut rng = FixedRng::new(vec![]);
+    fn arena_wager_is_placed_before_the_match_at_stat_derived_odds() {
+        let mut state = state_at_arena_gate();
+        state.gold = 120;
+        let mut rng = FixedRng::new(vec![2]);
 
         let _ = step(&mut state, Command::Legacy { token: ">".to_string() }, &mut rng);
-        assert_eq!(state.environment, LegacyEnvironment::Arena);
-        state.player.position = Position { x: 1, y: 7 };
+        let out = step(&mut state, Command::Legacy { token: "b".to_string() }, &mut rng);
 
-        let _ = step(&mut state, Command::Move(Direction::West), &mut rng);
+        assert_eq!(state.gold, 120 - ARENA_WAGER_STAKE);
+        assert_eq!(state.progression.arena_wager, ARENA_WAGER_STAKE);
+        assert_eq!(state.progression.arena_wager_odds, arena_wager_odds(&state));
+        assert!(out.events.iter().any(|event| matches!(
+            event,
+            Event::EconomyUpdated { source, .. } if source == "arena_wager"
+        )));
 
-        assert_eq!(state.environment, LegacyEnvironment::City);
-        assert_eq!(state.map_binding.semantic, MapSemanticKind::City);
-        assert_eq!(state.player.position, Position { x: 1, y: 1 });
-        assert!(state.monsters.is_empty(), "arena rival should not persist into city context");
+        let _ = step(&mut state, Command::Legacy { token: "b".to_string() }, &mut rng);
+        assert_eq!(state.gold, 120 - ARENA_WAGER_STAKE, "only one wager per bout");
+
+        let _ = step(&mut state, Command::Legacy { token: "1".to_string() }, &mut rng);
+        assert!(state.progression.arena_match_active);
+        assert_eq!(state.progression.arena_wager, ARENA_WAGER_STAKE);
     }
 
     #[test]
-    fn activating_city_view_clears_transient_hostiles() {
+    fn arena_wager_pays_out_on_victory() {
         let mut state = GameState::new(MapBounds { width: 3, height: 3 });
-        state.city_map_rows = vec!["...".to_string(), "...".to_string(), "...".to_string()];
-        state.city_site_grid = vec![TileSiteCell::default(); 9];
-        state.country_map_rows = state.city_map_rows.clone();
-        state.country_site_grid = state.city_site_grid.clone();
-        state.activate_country_view();
-        state.spawn_monster(
-            "sheep",
-            Position { x: 2, y: 1 },
-            Stats { hp: 4, max_hp: 4, attack_min: 1, attack_max: 1, defense: 0, weight: 60 },
-        );
-        assert_eq!(state.monsters.len(), 1);
+        state.environment = LegacyEnvironment::Arena;
+        state.progression.arena_match_active = true;
+        state.progression.arena_wager = ARENA_WAGER_STAKE;
+        state.progression.arena_wager_odds = 150;
+        state.gold = 0;
+        let mut events = Vec::new();
 
-        state.activate_city_view();
+        resolve_arena_round(&mut state, &mut events);
 
-        assert_eq!(state.environment, LegacyEnvironment::City);
-        assert!(state.monsters.is_empty());
+        let prize = 25;
+        let payout = ARENA_WAGER_STAKE + ARENA_WAGER_STAKE * 150 / 100;
+        assert_eq!(state.gold, prize + payout);
+        assert_eq!(state.progression.arena_wager, 0);
+        assert!(events.iter().any(|event| matches!(
+            event,
+            Event::EconomyUpdated { source, gold, .. }
+                if source == "arena_wager" && *gold == prize + payout
+        )));
     }
 
     #[test]
-    fn altar_prayer_accepts_matching_alignment_and_sets_patron() {
-        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
-        state.options.interactive_sites = true;
-        state.progression.alignment = Alignment::Lawful;
-        state.progression.law_chaos_score = 6;
-        state.player.position = Position { x: 1, y: 1 };
-        state.site_grid = vec![TileSiteCell::default(); 9];
-        state.city_site_grid = state.site_grid.clone();
-        state.site_grid[4].aux = SITE_AUX_ALTAR_ODIN;
-        state.city_site_grid[4].aux = SITE_AUX_ALTAR_ODIN;
-        let mut rng = FixedRng::new(vec![]);
+    fn arena_wager_is_lost_when_yielding() {
+        let mut state = state_at_arena_gate();
+        state.gold = 200;
+        let mut rng = FixedRng::new(vec![2]);
 
         let _ = step(&mut state, Command::Legacy { token: ">".to_string() }, &mut rng);
-        let _ = step(&mut state, Command::Legacy { token: "1".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "b".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "e".to_string() }, &mut rng);
+        assert!(state.progression.arena_match_active);
 
-        assert_eq!(state.progression.patron_deity, DEITY_ID_ODIN);
-        assert!(state.progression.priest_rank >= 1);
-        assert!(state.progression.deity_favor >= 3);
+        state.player.stats.hp = 3;
+        let _ = step(&mut state, Command::OfferSurrender, &mut rng);
+
+        assert_eq!(state.gold, 200 - ARENA_WAGER_STAKE - ARENA_SURRENDER_FINE);
+        assert_eq!(state.progression.arena_wager, 0);
+        assert!(state.log.iter().any(|line| line.contains("wager is forfeit")));
     }
 
     #[test]
-    fn altar_prayer_to_hostile_deity_triggers_sacrilege() {
+    fn arena_menu_accepts_legacy_letter_choices() {
         let mut state = GameState::new(MapBounds { width: 3, height: 3 });
         state.options.interactive_sites = true;
-        state.progression.alignment = Alignment::Lawful;
-        state.progression.patron_deity = DEITY_ID_ODIN;
-        state.progression.priest_rank = 2;
-        state.progression.deity_favor = 16;
         state.player.position = Position { x: 1, y: 1 };
         state.site_grid = vec![TileSiteCell::default(); 9];
         state.city_site_grid = state.site_grid.clone();
-        state.site_grid[4].aux = SITE_AUX_ALTAR_SET;
-        state.city_site_grid[4].aux = SITE_AUX_ALTAR_SET;
-        let mut rng = FixedRng::new(vec![]);
+        state.site_maps = vec![arena_test_site_definition()];
+        state.site_grid[4].aux = SITE_AUX_SERVICE_ARENA;
+        state.city_site_grid[4].aux = SITE_AUX_SERVICE_ARENA;
+        let mut rng = FixedRng::new(vec![2]);
 
         let _ = step(&mut state, Command::Legacy { token: ">".to_string() }, &mut rng);
-        let _ = step(&mut state, Command::Legacy { token: "1".to_string() }, &mut rng);
+        let register = step(&mut state, Command::Legacy { token: "r".to_string() }, &mut rng);
 
-        assert_eq!(state.progression.patron_deity, 0);
-        assert_eq!(state.progression.priest_rank, 0);
-        assert_eq!(state.progression.deity_favor, 0);
+        assert_eq!(state.progression.arena_rank, 1);
+        assert_eq!(state.pending_site_interaction, Some(SiteInteractionKind::Arena));
+        assert!(register.events.iter().any(|event| matches!(
+            event,
+            Event::LegacyHandled { token, note, .. }
+                if token == "interaction" && note.contains("Selected option 2")
+        )));
+
+        let start = step(&mut state, Command::Legacy { token: "y".to_string() }, &mut rng);
+        assert_eq!(state.pending_site_interaction, None);
+        assert_eq!(state.environment, LegacyEnvironment::Arena);
+        assert!(state.progression.arena_match_active);
+        assert!(start.events.iter().any(|event| matches!(
+            event,
+            Event::LegacyHandled { token, note, .. }
+                if token == "interaction" && note.contains("arranging a match")
+        )));
     }
 
     #[test]
-    fn door_open_and_close_commands_toggle_walkability() {
-        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
-        state.player.position = Position { x: 1, y: 1 };
-        state.map_rows = vec!["...".to_string(), "..-".to_string(), "...".to_string()];
-        state.city_map_rows = state.map_rows.clone();
-        state.map_binding.semantic = MapSemanticKind::City;
-        state.site_grid = vec![TileSiteCell::default(); 9];
-        state.city_site_grid = state.site_grid.clone();
-        state.site_grid[5].flags = TILE_FLAG_BLOCK_MOVE;
-        state.city_site_grid[5].flags = TILE_FLAG_BLOCK_MOVE;
+    fn arena_menu_rejects_restart_while_match_active() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 9 });
+        state.progression.arena_rank = 1;
+        state.progression.arena_opponent = 3;
+        state.progression.arena_match_active = true;
+        state.spawn_monster(
+            "arena goblin",
+            Position { x: 5, y: 4 },
+            Stats { hp: 8, max_hp: 8, attack_min: 2, attack_max: 3, defense: 1, weight: 60 },
+        );
+        let monster_count_before = state.monsters.len();
+        let mut events = Vec::new();
 
-        assert!(!state.tile_is_walkable(Position { x: 2, y: 1 }));
-        let mut rng = FixedRng::new(vec![]);
-        let _ = step(&mut state, Command::Legacy { token: "o".to_string() }, &mut rng);
-        assert_eq!(state.map_glyph_at(Position { x: 2, y: 1 }), '/');
-        assert!(state.tile_is_walkable(Position { x: 2, y: 1 }));
+        let note = apply_site_interaction_choice(
+            &mut state,
+            SiteInteractionKind::Arena,
+            1,
+            &mut FixedRng::new(vec![]),
+            &mut events,
+            true,
+        );
 
-        let _ = step(&mut state, Command::Legacy { token: "c".to_string() }, &mut rng);
-        assert_eq!(state.map_glyph_at(Position { x: 2, y: 1 }), '-');
-        assert!(!state.tile_is_walkable(Position { x: 2, y: 1 }));
+        assert!(note.contains("already in the games"));
+        assert_eq!(state.monsters.len(), monster_count_before);
+        assert!(state.progression.arena_match_active);
     }
 
     #[test]
-    fn bumping_closed_door_opens_and_steps_forward() {
-        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
-        state.player.position = Position { x: 1, y: 1 };
-        state.map_rows = vec!["...".to_string(), "..-".to_string(), "...".to_string()];
-        state.city_map_rows = state.map_rows.clone();
-        state.map_binding.semantic = MapSemanticKind::City;
-        state.site_grid = vec![TileSiteCell::default(); 9];
-        state.city_site_grid = state.site_grid.clone();
-        state.site_grid[5].flags = TILE_FLAG_BLOCK_MOVE;
-        state.city_site_grid[5].flags = TILE_FLAG_BLOCK_MOVE;
+    fn skilled_rogue_pickpocket_scales_gold_with_target() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 9 });
+        state.player.position = Position { x: 4, y: 4 };
+        state.attributes.dexterity = 18;
+        state.options.confirm = false;
+        state.progression.quests.thieves.rank = 3;
+        state.spawn_monster(
+            "merchant",
+            Position { x: 5, y: 4 },
+            Stats { hp: 30, max_hp: 30, attack_min: 1, attack_max: 2, defense: 0, weight: 60 },
+        );
+        state.monsters[0].behavior = MonsterBehavior::Social;
+        state.monsters[0].faction = Faction::Neutral;
+        let gold_before = state.gold;
+        let mut rng = FixedRng::new(vec![100, 1]);
 
-        let mut rng = FixedRng::new(vec![]);
-        let out = step(&mut state, Command::Move(Direction::East), &mut rng);
+        let out = step(&mut state, Command::Legacy { token: "p".to_string() }, &mut rng);
 
-        assert_eq!(state.player.position, Position { x: 2, y: 1 });
-        assert_eq!(state.map_glyph_at(Position { x: 2, y: 1 }), '/');
-        assert!(out.events.iter().any(|event| matches!(event, Event::Moved { .. })));
+        assert_eq!(state.gold, gold_before + 55);
+        assert_eq!(state.monsters[0].behavior, MonsterBehavior::Social);
         assert!(out.events.iter().any(|event| matches!(
             event,
-            Event::LegacyHandled { token, .. } if token == "step"
+            Event::EconomyUpdated { source, .. } if source == "pickpocket"
         )));
     }
 
     #[test]
-    fn stepping_on_service_tile_triggers_interaction() {
-        let mut state = GameState::new(MapBounds { width: 4, height: 3 });
-        state.player.position = Position { x: 1, y: 1 };
-        state.map_rows = vec!["....".to_string(), "....".to_string(), "....".to_string()];
-        state.city_map_rows = state.map_rows.clone();
-        state.map_binding.semantic = MapSemanticKind::City;
-        state.site_grid = vec![TileSiteCell::default(); 12];
-        state.city_site_grid = state.site_grid.clone();
-        state.site_grid[4 + 2].aux = SITE_AUX_SERVICE_SHOP;
-        state.city_site_grid[4 + 2].aux = SITE_AUX_SERVICE_SHOP;
-        let start_gold = state.gold;
+    fn unskilled_pickpocket_fails_and_angers_target() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 9 });
+        state.player.position = Position { x: 4, y: 4 };
+        state.attributes.dexterity = 8;
+        state.options.confirm = false;
+        state.spawn_monster(
+            "merchant",
+            Position { x: 5, y: 4 },
+            Stats { hp: 20, max_hp: 20, attack_min: 1, attack_max: 2, defense: 0, weight: 60 },
+        );
+        state.monsters[0].behavior = MonsterBehavior::Social;
+        state.monsters[0].faction = Faction::Neutral;
+        let gold_before = state.gold;
+        let heat_before = state.legal_heat;
+        let mut rng = FixedRng::new(vec![1, 100, 1]);
 
-        let mut rng = FixedRng::new(vec![]);
-        let out = step(&mut state, Command::Move(Direction::East), &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "p".to_string() }, &mut rng);
 
-        assert_eq!(state.player.position, Position { x: 2, y: 1 });
-        assert!(state.gold < start_gold);
-        assert!(out.events.iter().any(|event| matches!(event, Event::EconomyUpdated { .. })));
-        assert!(out.events.iter().any(|event| matches!(
-            event,
-            Event::LegacyHandled { token, .. } if token == "step"
-        )));
+        assert_eq!(state.gold, gold_before);
+        assert!(state.legal_heat > heat_before);
+        assert_ne!(state.monsters[0].behavior, MonsterBehavior::Social);
+        assert!(state.log.iter().any(|line| line.contains("turns on you")));
     }
 
-    #[test]
-    fn stepping_on_service_tile_opens_interactive_menu_when_enabled() {
-        let mut state = GameState::new(MapBounds { width: 4, height: 3 });
-        state.options.interactive_sites = true;
-        state.player.position = Position { x: 1, y: 1 };
-        state.map_rows = vec!["....".to_string(), "....".to_string(), "....".to_string()];
-        state.city_map_rows = state.map_rows.clone();
-        state.map_binding.semantic = MapSemanticKind::City;
-        state.site_grid = vec![TileSiteCell::default(); 12];
-        state.city_site_grid = state.site_grid.clone();
-        state.site_grid[4 + 2].aux = SITE_AUX_SERVICE_SHOP;
-        state.city_site_grid[4 + 2].aux = SITE_AUX_SERVICE_SHOP;
-        let start_gold = state.gold;
+    fn state_on_bank_tile() -> GameState {
+        let mut state = GameState::new(MapBounds { width: 9, height: 9 });
+        state.player.position = Position { x: 4, y: 4 };
+        state.site_grid = vec![TileSiteCell::default(); 81];
+        state.site_grid[4 * 9 + 4].aux = SITE_AUX_SERVICE_BANK;
+        state
+    }
 
+    #[test]
+    fn bank_command_deposits_arbitrary_amount() {
+        let mut state = state_on_bank_tile();
+        state.gold = 300;
         let mut rng = FixedRng::new(vec![]);
-        let out = step(&mut state, Command::Move(Direction::East), &mut rng);
 
-        assert_eq!(state.player.position, Position { x: 2, y: 1 });
-        assert_eq!(state.gold, start_gold, "stepping should open menu before applying purchase");
-        assert_eq!(state.pending_site_interaction, Some(SiteInteractionKind::Shop));
+        let out = step(&mut state, Command::Bank { op: BankOp::Deposit, amount: 137 }, &mut rng);
+
+        assert_eq!(state.gold, 163);
+        assert_eq!(state.bank_gold, 137);
         assert!(out.events.iter().any(|event| matches!(
             event,
-            Event::LegacyHandled { token, .. } if token == "interaction"
+            Event::EconomyUpdated { source, gold: 163, bank_gold: 137 } if source == "bank"
         )));
     }
 
     #[test]
-    fn interactive_site_menu_accepts_numeric_choice_via_legacy_token() {
-        let mut state = GameState::new(MapBounds { width: 4, height: 3 });
-        state.options.interactive_sites = true;
-        state.player.position = Position { x: 1, y: 1 };
-        state.map_rows = vec!["....".to_string(), "....".to_string(), "....".to_string()];
-        state.city_map_rows = state.map_rows.clone();
-        state.map_binding.semantic = MapSemanticKind::City;
-        state.site_grid = vec![TileSiteCell::default(); 12];
-        state.city_site_grid = state.site_grid.clone();
-        state.site_grid[4 + 2].aux = SITE_AUX_SERVICE_SHOP;
-        state.city_site_grid[4 + 2].aux = SITE_AUX_SERVICE_SHOP;
+    fn bank_command_refuses_over_withdrawal() {
+        let mut state = state_on_bank_tile();
+        state.gold = 10;
+        state.bank_gold = 40;
         let mut rng = FixedRng::new(vec![]);
 
-        let _ = step(&mut state, Command::Move(Direction::East), &mut rng);
-        let gold_before = state.gold;
-        let out = step(&mut state, Command::Legacy { token: "1".to_string() }, &mut rng);
+        let out = step(&mut state, Command::Bank { op: BankOp::Withdraw, amount: 41 }, &mut rng);
 
-        assert!(state.gold < gold_before);
-        assert!(state.player.inventory.iter().any(|item| item.name == "food ration"));
-        assert_eq!(state.pending_site_interaction, Some(SiteInteractionKind::Shop));
-        assert!(out.events.iter().any(|event| matches!(
-            event,
-            Event::EconomyUpdated { source, .. } if source == "shop"
-        )));
+        assert_eq!(state.gold, 10);
+        assert_eq!(state.bank_gold, 40);
+        assert!(!out.events.iter().any(|event| matches!(event, Event::EconomyUpdated { .. })));
+        assert!(state.log.iter().any(|line| line.contains("balance is only 40")));
     }
 
     #[test]
-    fn jail_doors_are_openable_with_open_command() {
-        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
-        state.player.position = Position { x: 1, y: 1 };
-        state.map_rows = vec!["...".to_string(), "..J".to_string(), "...".to_string()];
-        state.city_map_rows = state.map_rows.clone();
-        state.map_binding.semantic = MapSemanticKind::City;
-        state.site_grid = vec![TileSiteCell::default(); 9];
-        state.city_site_grid = state.site_grid.clone();
-        state.site_grid[5].flags = TILE_FLAG_BLOCK_MOVE;
-        state.city_site_grid[5].flags = TILE_FLAG_BLOCK_MOVE;
+    fn bank_command_posting_surety_reduces_legal_heat() {
+        let mut state = state_on_bank_tile();
+        state.gold = 100;
+        state.legal_heat = 5;
         let mut rng = FixedRng::new(vec![]);
 
-        assert!(!state.tile_is_walkable(Position { x: 2, y: 1 }));
-        let _ = step(&mut state, Command::Legacy { token: "o".to_string() }, &mut rng);
-        assert_eq!(state.map_glyph_at(Position { x: 2, y: 1 }), '/');
-        assert!(state.tile_is_walkable(Position { x: 2, y: 1 }));
+        let _ = step(&mut state, Command::Bank { op: BankOp::PostSurety, amount: 75 }, &mut rng);
+
+        assert_eq!(state.gold, 25);
+        assert_eq!(state.legal_heat, 2);
+    }
+
+    fn state_at_sorcerors_with_wand(charge: i32) -> GameState {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.gold = 100;
+        state.pending_site_interaction = Some(SiteInteractionKind::Sorcerors);
+        state.player.inventory.push(Item {
+            id: 700,
+            name: "wand of frost".to_string(),
+            family: ItemFamily::Stick,
+            charge,
+            level: 5,
+            ..Item::default()
+        });
+        state
     }
 
     #[test]
-    fn pending_interaction_blocks_non_choice_commands_until_closed() {
-        let mut state = GameState::new(MapBounds { width: 4, height: 3 });
-        state.options.interactive_sites = true;
-        state.player.position = Position { x: 1, y: 1 };
-        state.map_rows = vec!["....".to_string(), "....".to_string(), "....".to_string()];
-        state.city_map_rows = state.map_rows.clone();
-        state.map_binding.semantic = MapSemanticKind::City;
-        state.site_grid = vec![TileSiteCell::default(); 12];
-        state.city_site_grid = state.site_grid.clone();
-        state.site_grid[4 + 2].aux = SITE_AUX_SERVICE_SHOP;
-        state.city_site_grid[4 + 2].aux = SITE_AUX_SERVICE_SHOP;
-        let mut rng = FixedRng::new(vec![]);
+    fn sorcerors_recharge_adds_charges_to_selected_stick() {
+        let mut state = state_at_sorcerors_with_wand(2);
+        let mut rng = FixedRng::new(vec![4]);
+
+        let _ = step(&mut state, Command::Legacy { token: "1".to_string() }, &mut rng);
+        assert!(state.pending_item_prompt.is_some());
+        let _ = step(&mut state, Command::Legacy { token: "1".to_string() }, &mut rng);
+
+        let wand = state.player.inventory.iter().find(|item| item.id == 700).expect("wand kept");
+        assert_eq!(wand.charge, 6);
+        assert_eq!(state.gold, 70);
+    }
+
+    #[test]
+    fn sorcerors_recharge_can_destroy_an_overcharged_stick() {
+        let mut state = state_at_sorcerors_with_wand(10);
+        let mut rng = FixedRng::new(vec![1]);
+
+        let _ = step(&mut state, Command::Legacy { token: "1".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "1".to_string() }, &mut rng);
+
+        assert!(state.player.inventory.iter().all(|item| item.id != 700));
+        assert!(state.log.iter().any(|line| line.contains("overcharges and explodes")));
+    }
+
+    fn state_at_sorcerors_with_sword() -> GameState {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.gold = 100;
+        state.progression.quests.sorcerors.rank = 1;
+        state.pending_site_interaction = Some(SiteInteractionKind::Sorcerors);
+        state.player.inventory.push(Item {
+            id: 710,
+            name: "long sword".to_string(),
+            family: ItemFamily::Weapon,
+            ..Item::default()
+        });
+        state
+    }
+
+    #[test]
+    fn sorcerors_enchant_raises_selected_item_plus() {
+        let mut state = state_at_sorcerors_with_sword();
+        let mut rng = FixedRng::new(vec![50]);
+
+        let _ = step(&mut state, Command::Legacy { token: "3".to_string() }, &mut rng);
+        assert!(state.pending_item_prompt.is_some());
+        let _ = step(&mut state, Command::Legacy { token: "1".to_string() }, &mut rng);
+
+        let sword = state.player.inventory.iter().find(|item| item.id == 710).expect("sword kept");
+        assert_eq!(sword.plus, 1);
+        assert_eq!(sword.blessing, 0);
+        assert_eq!(state.gold, 100 - SORCERORS_ENCHANT_FEE);
+    }
+
+    #[test]
+    fn sorcerors_enchant_failure_curses_the_item() {
+        let mut state = state_at_sorcerors_with_sword();
+        let mut rng = FixedRng::new(vec![5]);
+
+        let _ = step(&mut state, Command::Legacy { token: "3".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "1".to_string() }, &mut rng);
+
+        let sword = state.player.inventory.iter().find(|item| item.id == 710).expect("sword kept");
+        assert_eq!(sword.plus, 0);
+        assert!(sword.blessing < 0);
+        assert_eq!(state.gold, 100 - SORCERORS_ENCHANT_FEE);
+        assert!(state.log.iter().any(|line| line.contains("darkens with a curse")));
+    }
+
+    #[test]
+    fn thieves_heist_contract_completes_on_target_with_dexterity_check() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 9 });
+        state.player.position = Position { x: 2, y: 4 };
+        state.site_grid = vec![TileSiteCell::default(); 81];
+        state.site_grid[4 * 9 + 6].aux = SITE_AUX_SERVICE_BANK;
+        state.progression.quests.thieves.rank = 1;
+        state.gold = 100;
+        let mut events = Vec::new();
 
+        let note = apply_site_interaction_choice(
+            &mut state,
+            SiteInteractionKind::ThievesGuild,
+            2,
+            &mut FixedRng::new(vec![]),
+            &mut events,
+            false,
+        );
+        assert!(note.contains("Heist contract taken"));
+        assert_eq!(state.gold, 75);
+        let contract =
+            state.progression.quests.thieves.active_contract.expect("heist should be active");
+        assert_eq!(contract.target, Position { x: 6, y: 4 });
+        assert_eq!(contract.map_id, state.map_binding.map_id);
+
+        let mut rng = FixedRng::new(vec![3]);
+        let city_map = state.map_binding.map_id;
+        state.map_binding.map_id = city_map + 1;
+        state.player.position = Position { x: 5, y: 4 };
         let _ = step(&mut state, Command::Move(Direction::East), &mut rng);
-        assert_eq!(state.pending_site_interaction, Some(SiteInteractionKind::Shop));
+        assert!(state.progression.quests.thieves.active_contract.is_some());
 
-        let out_pending = step(&mut state, Command::Move(Direction::West), &mut rng);
-        assert_eq!(state.pending_site_interaction, Some(SiteInteractionKind::Shop));
-        assert_eq!(state.player.position, Position { x: 2, y: 1 });
-        assert!(out_pending.events.iter().any(|event| matches!(
+        state.map_binding.map_id = city_map;
+        state.player.position = Position { x: 5, y: 4 };
+        let heat_before = state.legal_heat;
+        let out = step(&mut state, Command::Move(Direction::East), &mut rng);
+
+        assert!(state.progression.quests.thieves.active_contract.is_none());
+        assert_eq!(state.gold, 75 + contract.reward_gold);
+        assert_eq!(state.progression.quests.thieves.rank, 2);
+        assert!(state.legal_heat > heat_before);
+        assert!(state.progression.quests.thieves.quest_flags & 0x0002 != 0);
+        assert!(out.events.iter().any(|event| matches!(event, Event::QuestAdvanced { .. })));
+    }
+
+    #[test]
+    fn weekly_guild_dues_accrue_and_settle_on_guild_visit() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 9 });
+        state.progression.quests.thieves.rank = 1;
+        state.progression.guild_rank = 1;
+        state.gold = 0;
+        let mut events = Vec::new();
+
+        for _ in 0..3 {
+            advance_time(&mut state, MINUTES_PER_WEEK, &mut events);
+        }
+
+        let thieves = &state.progression.quests.thieves;
+        assert_eq!(thieves.salary_due, -GUILD_WEEKLY_DUES * 3);
+        assert!(thieves.promotion_flags & GUILD_FLAG_DUES_ARREARS != 0);
+        assert_eq!(state.progression.quests.college.salary_due, 0);
+        assert_eq!(state.progression.quests.merc.salary_due, GUILD_WEEKLY_SALARY_PER_RANK * 3);
+        assert_eq!(state.gold, 0, "nothing is paid until a guild visit");
+        assert!(!events.iter().any(|event| matches!(event, Event::EconomyUpdated { .. })));
+
+        let _ = begin_site_interaction(
+            &mut state,
+            SiteInteractionKind::ThievesGuild,
+            &mut events,
+            "step",
+        );
+        assert!(
+            !events.iter().any(|event| matches!(event, Event::EconomyUpdated { .. })),
+            "an empty purse settles nothing"
+        );
+        state.pending_site_interaction = None;
+
+        let _ =
+            begin_site_interaction(&mut state, SiteInteractionKind::MercGuild, &mut events, "step");
+        assert_eq!(state.gold, 60);
+        assert_eq!(state.progression.quests.merc.salary_due, 0);
+        assert!(events.iter().any(|event| matches!(
             event,
-            Event::LegacyHandled { token, note, .. }
-                if token == "interaction" && note.contains("prompt active")
+            Event::EconomyUpdated { source, gold: 60, .. } if source == "guild_salary"
         )));
+        state.pending_site_interaction = None;
 
-        let out_close = step(&mut state, Command::Legacy { token: "q".to_string() }, &mut rng);
-        assert_eq!(state.pending_site_interaction, None);
-        assert!(out_close.events.iter().any(|event| matches!(
+        events.clear();
+        let _ = begin_site_interaction(
+            &mut state,
+            SiteInteractionKind::ThievesGuild,
+            &mut events,
+            "step",
+        );
+
+        let thieves = &state.progression.quests.thieves;
+        assert_eq!(i64::from(state.gold), 60 - GUILD_WEEKLY_DUES * 3);
+        assert_eq!(thieves.salary_due, 0);
+        assert_eq!(thieves.dues_paid, GUILD_WEEKLY_DUES * 3);
+        assert_eq!(thieves.promotion_flags & GUILD_FLAG_DUES_ARREARS, 0);
+        assert!(events.iter().any(|event| matches!(
             event,
-            Event::LegacyHandled { token, note, .. }
-                if token == "interaction" && note.contains("closed")
+            Event::EconomyUpdated { source, .. } if source == "guild_dues"
         )));
+    }
 
-        let out_move = step(&mut state, Command::Move(Direction::West), &mut rng);
-        assert_eq!(state.player.position, Position { x: 1, y: 1 });
-        assert!(out_move.events.iter().any(|event| matches!(
+    fn state_in_merc_guild() -> GameState {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.site_grid = vec![TileSiteCell::default(); 9];
+        state.site_grid[4].aux = SITE_AUX_SERVICE_MERC_GUILD;
+        state
+    }
+
+    #[test]
+    fn guild_action_joins_merc_guild_only_on_the_guild_tile() {
+        let mut state = state_in_merc_guild();
+        state.gold = 100;
+        let mut rng = FixedRng::new(vec![]);
+        let join = Command::GuildAction {
+            guild: SiteInteractionKind::MercGuild,
+            action: GuildAction::Join,
+        };
+
+        state.player.position = Position { x: 0, y: 0 };
+        let _ = step(&mut state, join.clone(), &mut rng);
+        assert_eq!(state.progression.guild_rank, 0);
+        assert_eq!(state.gold, 100);
+        assert!(state.log.iter().any(|line| line.contains("must be inside the merc guild")));
+
+        state.player.position = Position { x: 1, y: 1 };
+        let out = step(&mut state, join.clone(), &mut rng);
+        assert_eq!(state.progression.guild_rank, 1);
+        assert_eq!(state.gold, 60);
+        assert!(out.events.iter().any(|event| matches!(
             event,
-            Event::Moved { from, to }
-                if *from == Position { x: 2, y: 1 } && *to == Position { x: 1, y: 1 }
+            Event::EconomyUpdated { source, .. } if source == "merc_guild"
+        )));
+        assert!(out.events.iter().any(|event| matches!(
+            event,
+            Event::LegacyHandled { token, .. } if token == "guild_action"
         )));
+
+        let _ = step(&mut state, join, &mut rng);
+        assert_eq!(state.gold, 60);
+        assert!(state.log.iter().any(|line| line.contains("already a member")));
     }
 
     #[test]
-    fn pending_interaction_hint_is_not_spammed_in_log() {
-        let mut state = GameState::new(MapBounds { width: 4, height: 3 });
-        state.options.interactive_sites = true;
-        state.player.position = Position { x: 1, y: 1 };
-        state.map_rows = vec!["....".to_string(), "....".to_string(), "....".to_string()];
-        state.city_map_rows = state.map_rows.clone();
-        state.map_binding.semantic = MapSemanticKind::City;
-        state.site_grid = vec![TileSiteCell::default(); 12];
-        state.city_site_grid = state.site_grid.clone();
-        state.site_grid[4 + 2].aux = SITE_AUX_SERVICE_SHOP;
-        state.city_site_grid[4 + 2].aux = SITE_AUX_SERVICE_SHOP;
+    fn guild_action_trains_merc_members_separately_from_joining() {
+        let mut state = state_in_merc_guild();
+        state.gold = 200;
+        let attack_max = state.player.stats.attack_max;
         let mut rng = FixedRng::new(vec![]);
+        let action =
+            |action| Command::GuildAction { guild: SiteInteractionKind::MercGuild, action };
 
-        let _ = step(&mut state, Command::Move(Direction::East), &mut rng);
-        assert_eq!(state.pending_site_interaction, Some(SiteInteractionKind::Shop));
+        let _ = step(&mut state, action(GuildAction::Train), &mut rng);
+        assert_eq!(state.gold, 200);
+        assert!(state.log.iter().any(|line| line.contains("must join the merc guild first")));
 
-        let _ = step(&mut state, Command::Move(Direction::West), &mut rng);
-        let _ = step(&mut state, Command::Move(Direction::West), &mut rng);
-        let _ = step(&mut state, Command::Move(Direction::West), &mut rng);
+        let _ = step(&mut state, action(GuildAction::Join), &mut rng);
+        assert_eq!(state.progression.guild_rank, 1);
+        assert_eq!(state.player.stats.attack_max, attack_max);
+        assert_eq!(state.gold, 160);
 
-        let hint_count = state
-            .log
-            .iter()
-            .filter(|line| line.contains("Site prompt active: choose a bracketed option"))
-            .count();
-        assert_eq!(hint_count, 0);
+        let _ = step(&mut state, action(GuildAction::Train), &mut rng);
+        assert_eq!(state.player.stats.attack_max, attack_max + 1);
+        assert_eq!(state.gold, 120);
+        assert!(state.log.iter().any(|line| line.contains("Merc training completed")));
     }
 
     #[test]
-    fn entering_interactive_site_does_not_log_menu_prompt_lines() {
-        let mut state = GameState::new(MapBounds { width: 4, height: 3 });
-        state.options.interactive_sites = true;
-        state.player.position = Position { x: 1, y: 1 };
-        state.map_rows = vec!["....".to_string(), "....".to_string(), "....".to_string()];
-        state.city_map_rows = state.map_rows.clone();
-        state.map_binding.semantic = MapSemanticKind::City;
-        state.site_grid = vec![TileSiteCell::default(); 12];
-        state.city_site_grid = state.site_grid.clone();
-        state.site_grid[4 + 1].aux = SITE_AUX_SERVICE_TEMPLE;
-        state.city_site_grid[4 + 1].aux = SITE_AUX_SERVICE_TEMPLE;
+    fn legacy_guild_token_prompts_for_the_action() {
+        let mut state = state_in_merc_guild();
+        state.gold = 100;
         let mut rng = FixedRng::new(vec![]);
 
-        let _ = step(&mut state, Command::Legacy { token: ">".to_string() }, &mut rng);
+        state.player.position = Position { x: 0, y: 0 };
+        let out = step(&mut state, Command::Legacy { token: "U".to_string() }, &mut rng);
+        assert!(state.pending_guild_action.is_none());
+        assert!(out.events.iter().any(|event| matches!(
+            event,
+            Event::LegacyHandled { token, note, .. }
+                if token == "U" && note == "There is no guild here."
+        )));
 
-        assert_eq!(state.pending_site_interaction, Some(SiteInteractionKind::Temple));
-        assert!(state.log.iter().all(|line| {
-            !line.contains("Temple: [")
-                && !line.contains("Site prompt active:")
-                && !line.contains("Temple prompt active:")
-        }));
+        state.player.position = Position { x: 1, y: 1 };
+        let minutes_before = state.clock.minutes;
+        let _ = step(&mut state, Command::Legacy { token: "U".to_string() }, &mut rng);
+        assert_eq!(state.pending_guild_action, Some(SiteInteractionKind::MercGuild));
+        assert_eq!(modal_input_profile(&state), ModalInputProfile::ChoiceEntry);
+        let _ = step(&mut state, Command::Legacy { token: "x".to_string() }, &mut rng);
+        assert!(state.pending_guild_action.is_some());
+        assert_eq!(state.clock.minutes, minutes_before);
+
+        let out = step(&mut state, Command::Legacy { token: "j".to_string() }, &mut rng);
+        assert!(state.pending_guild_action.is_none());
+        assert_eq!(state.progression.guild_rank, 1);
+        assert_eq!(state.gold, 60);
+        assert_eq!(out.minutes - minutes_before, 10);
+
+        let _ = step(&mut state, Command::Legacy { token: "U".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "<esc>".to_string() }, &mut rng);
+        assert!(state.pending_guild_action.is_none());
+        assert_eq!(state.progression.guild_rank, 1);
     }
 
     #[test]
-    fn invalid_modal_input_does_not_append_prompt_hint_noise_to_timeline() {
-        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
-        state.pending_site_interaction = Some(SiteInteractionKind::Temple);
-        let before_len = state.log.len();
+    fn guild_action_refuses_promotion_without_requirements() {
+        let mut state = state_in_merc_guild();
+        state.gold = 200;
         let mut rng = FixedRng::new(vec![]);
+        let promote = Command::GuildAction {
+            guild: SiteInteractionKind::MercGuild,
+            action: GuildAction::Promote,
+        };
+
+        let _ = step(&mut state, promote.clone(), &mut rng);
+        assert_eq!(state.progression.guild_rank, 0);
+        assert!(state.log.iter().any(|line| line.contains("must join the merc guild first")));
+
+        state.progression.guild_rank = 1;
+        let _ = step(&mut state, promote, &mut rng);
+        assert_eq!(state.progression.guild_rank, 1);
+        assert_eq!(state.gold, 200);
+        assert!(state.log.iter().any(|line| line.starts_with("Promotion denied")));
+    }
+
+    #[test]
+    fn hired_merc_companion_follows_player_movement() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 9 });
+        state.player.position = Position { x: 4, y: 4 };
+        state.progression.guild_rank = 1;
+        state.gold = 200;
+        let mut events = Vec::new();
 
+        let note = apply_site_interaction_choice(
+            &mut state,
+            SiteInteractionKind::MercGuild,
+            5,
+            &mut FixedRng::new(vec![]),
+            &mut events,
+            false,
+        );
+
+        assert!(note.contains("mercenary signs on"));
+        assert_eq!(state.gold, 200 - companion_hire_cost(1));
+        assert_eq!(state.companions.len(), 1);
+        assert_eq!(state.companions[0].position, Position { x: 5, y: 4 });
+
+        let mut rng = FixedRng::new(vec![]);
+        let _ = step(&mut state, Command::Move(Direction::West), &mut rng);
         let _ = step(&mut state, Command::Move(Direction::West), &mut rng);
 
-        assert_eq!(state.log.len(), before_len);
-        assert!(state.log.iter().all(|line| !line.contains("prompt active")));
+        assert_eq!(state.player.position, Position { x: 2, y: 4 });
+        assert_eq!(state.companions[0].position, Position { x: 3, y: 4 });
     }
 
     #[test]
-    fn sanitize_legacy_prompt_noise_preserves_real_outcomes() {
-        let mut log = vec![
-            "You move.".to_string(),
-            "Site prompt active: choose a bracketed option, or press q/x to close.".to_string(),
-            "Wish text: Victrix_".to_string(),
-            "Dropped ration.".to_string(),
-        ];
+    fn recalling_companions_brings_wanderers_adjacent() {
+        let mut state = GameState::new(MapBounds { width: 12, height: 9 });
+        state.player.position = Position { x: 2, y: 4 };
+        let mut rng = FixedRng::new(vec![]);
 
-        sanitize_legacy_prompt_noise(&mut log);
+        let _ = step(&mut state, Command::RecallCompanions, &mut rng);
+        assert!(state.log.iter().any(|line| line.contains("no companions answer")));
 
-        assert_eq!(log, vec!["You move.".to_string(), "Dropped ration.".to_string()]);
+        state.progression.guild_rank = 1;
+        state.gold = 200;
+        let mut events = Vec::new();
+        let _ = hire_merc_companion(&mut state, &mut events);
+        state.companions[0].position = Position { x: 10, y: 7 };
+        let companion_id = state.companions[0].id;
+        let turn_before = state.clock.turn;
+
+        let out = step(&mut state, Command::RecallCompanions, &mut rng);
+
+        assert_eq!(projectile_distance(state.companions[0].position, state.player.position), 1);
+        assert!(out.events.iter().any(|event| matches!(
+            event,
+            Event::MonsterMoved { monster_id, from, .. }
+                if *monster_id == companion_id && *from == Position { x: 10, y: 7 }
+        )));
+        assert!(state.clock.turn > turn_before);
+    }
+
+    #[test]
+    fn legacy_recall_token_whistles_for_companions() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        let mut rng = FixedRng::new(vec![]);
+        let out = step(&mut state, Command::Legacy { token: "K".to_string() }, &mut rng);
+        assert!(out.events.iter().any(|event| matches!(
+            event,
+            Event::LegacyHandled { token, note, fully_modeled: true }
+                if token == "K" && note == "You whistle, but no companions answer."
+        )));
+        assert_eq!(out.minutes, 10);
+    }
+
+    #[test]
+    fn butchering_a_corpse_yields_rations_and_removes_it() {
+        let mut state = GameState::new(MapBounds { width: 7, height: 7 });
+        state.player.position = Position { x: 3, y: 3 };
+        let mut rng = FixedRng::new(vec![]);
+
+        let turn_before = state.clock.turn;
+        let out = step(&mut state, Command::Butcher, &mut rng);
+        assert!(state.log.iter().any(|line| line.contains("no corpse here")));
+        assert!(!out.events.iter().any(|event| matches!(event, Event::PickedUp { .. })));
+        assert_eq!(out.minutes, 0);
+        assert_eq!(state.clock.turn, turn_before);
+
+        state.spawn_monster(
+            "sheep",
+            Position { x: 4, y: 3 },
+            Stats { hp: 1, max_hp: 1, attack_min: 0, attack_max: 0, defense: 0, weight: 60 },
+        );
+        let _ = step(&mut state, Command::Attack(Direction::East), &mut rng);
+        let corpse = state
+            .ground_items
+            .iter()
+            .find(|ground| ground.item.family == ItemFamily::Corpse)
+            .expect("the sheep leaves a carcass");
+        assert_eq!(corpse.position, Position { x: 4, y: 3 });
+        assert_eq!(corpse.item.name, "side of mutton");
+        let turn_before = state.clock.turn;
+
+        let _ = step(&mut state, Command::Butcher, &mut rng);
+
+        assert!(state.ground_items.iter().all(|ground| ground.item.family != ItemFamily::Corpse));
+        let rations =
+            state.player.inventory.iter().filter(|item| item.name == "food ration").count();
+        assert_eq!(rations, 2);
+        assert!(state.clock.turn > turn_before);
+    }
+
+    #[test]
+    fn butcher_yield_scales_with_the_slain_monster() {
+        let corpse_of = |name: &str| {
+            let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+            state.spawn_monster(
+                name,
+                Position { x: 1, y: 1 },
+                Stats { hp: 1, max_hp: 1, attack_min: 0, attack_max: 0, defense: 0, weight: 60 },
+            );
+            let mut events = Vec::new();
+            let _ = remove_monster_with_drops(&mut state, 0, &mut events);
+            state.ground_items.pop().expect("corpse").item
+        };
+        assert_eq!(corpse_of("rabbit").aux, 0);
+        assert_eq!(corpse_of("sheep").aux, 2);
+        assert_eq!(corpse_of("goblin").aux, 4);
+        assert_eq!(corpse_of("goblin").name, "dead goblin");
+
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state
+            .ground_items
+            .push(GroundItem { position: Position { x: 1, y: 1 }, item: corpse_of("rabbit") });
+        let out = step(&mut state, Command::Butcher, &mut FixedRng::new(vec![]));
+        assert_eq!(out.minutes, 0);
+        assert_eq!(state.ground_items.len(), 1);
+        assert!(state.log.iter().any(|line| line.contains("isn't enough of the bunnyburgers")));
+    }
+
+    #[test]
+    fn unpaid_merc_companion_deserts() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 9 });
+        state.player.position = Position { x: 4, y: 4 };
+        state.progression.guild_rank = 1;
+        state.gold = companion_hire_cost(1);
+        let mut events = Vec::new();
+        let _ = apply_site_interaction_choice(
+            &mut state,
+            SiteInteractionKind::MercGuild,
+            5,
+            &mut FixedRng::new(vec![]),
+            &mut events,
+            false,
+        );
+        assert_eq!(state.companions.len(), 1);
+        assert_eq!(state.gold, 0);
+        let mut rng = FixedRng::new(vec![]);
+
+        state.clock.turn = COMPANION_PAY_INTERVAL_TURNS;
+        let _ = step(&mut state, Command::Wait, &mut rng);
+        assert_eq!(state.companions.len(), 1);
+        assert_eq!(state.companion_wages_due, COMPANION_WAGE);
+        assert_eq!(state.progression.quests.merc.salary_due, 0);
+
+        state.clock.turn = COMPANION_PAY_INTERVAL_TURNS * 2;
+        let _ = step(&mut state, Command::Wait, &mut rng);
+        assert!(state.companions.is_empty());
+        assert!(state.log.iter().any(|line| line.contains("deserts you")));
+    }
+
+    #[test]
+    fn arena_exit_tile_returns_player_to_city_context() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.site_grid = vec![TileSiteCell::default(); 9];
+        state.city_site_grid = state.site_grid.clone();
+        state.site_maps = vec![arena_test_site_definition()];
+        state.site_grid[4].aux = SITE_AUX_SERVICE_ARENA;
+        state.city_site_grid[4].aux = SITE_AUX_SERVICE_ARENA;
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Legacy { token: ">".to_string() }, &mut rng);
+        assert_eq!(state.environment, LegacyEnvironment::Arena);
+        state.player.position = Position { x: 1, y: 7 };
+
+        let _ = step(&mut state, Command::Move(Direction::West), &mut rng);
+
+        assert_eq!(state.environment, LegacyEnvironment::City);
+        assert_eq!(state.map_binding.semantic, MapSemanticKind::City);
+        assert_eq!(state.player.position, Position { x: 1, y: 1 });
+        assert!(state.monsters.is_empty(), "arena rival should not persist into city context");
+    }
+
+    #[test]
+    fn activating_city_view_clears_transient_hostiles() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.city_map_rows = vec!["...".to_string(), "...".to_string(), "...".to_string()];
+        state.city_site_grid = vec![TileSiteCell::default(); 9];
+        state.country_map_rows = state.city_map_rows.clone();
+        state.country_site_grid = state.city_site_grid.clone();
+        state.activate_country_view();
+        state.spawn_monster(
+            "sheep",
+            Position { x: 2, y: 1 },
+            Stats { hp: 4, max_hp: 4, attack_min: 1, attack_max: 1, defense: 0, weight: 60 },
+        );
+        assert_eq!(state.monsters.len(), 1);
+
+        state.activate_city_view();
+
+        assert_eq!(state.environment, LegacyEnvironment::City);
+        assert!(state.monsters.is_empty());
+    }
+
+    #[test]
+    fn altar_prayer_accepts_matching_alignment_and_sets_patron() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.options.interactive_sites = true;
+        state.progression.alignment = Alignment::Lawful;
+        state.progression.law_chaos_score = 6;
+        state.player.position = Position { x: 1, y: 1 };
+        state.site_grid = vec![TileSiteCell::default(); 9];
+        state.city_site_grid = state.site_grid.clone();
+        state.site_grid[4].aux = SITE_AUX_ALTAR_ODIN;
+        state.city_site_grid[4].aux = SITE_AUX_ALTAR_ODIN;
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Legacy { token: ">".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "1".to_string() }, &mut rng);
+
+        assert_eq!(state.progression.patron_deity, DEITY_ID_ODIN);
+        assert!(state.progression.priest_rank >= 1);
+        assert!(state.progression.deity_favor >= 3);
+    }
+
+    #[test]
+    fn giving_item_at_altar_sacrifices_it_for_favor() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.site_grid = vec![TileSiteCell::default(); 9];
+        state.site_grid[4].aux = SITE_AUX_ALTAR_ODIN;
+        let mut gem = Item::new(state.next_item_id, "ruby");
+        state.next_item_id += 1;
+        gem.basevalue = 300;
+        state.player.inventory.push(gem);
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Legacy { token: "G".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "a".to_string() }, &mut rng);
+
+        assert!(state.player.inventory.is_empty());
+        assert!(state.ground_items.is_empty());
+        assert_eq!(state.progression.deity_favor, 6);
+        assert!(state.log.iter().any(|line| line.contains("Odin accepts your sacrifice")));
+    }
+
+    #[test]
+    fn giving_item_to_adjacent_guard_reduces_legal_heat() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.player.position = Position { x: 2, y: 2 };
+        state.legal_heat = 10;
+        let guard_id =
+            state.spawn_monster("city guard", Position { x: 3, y: 2 }, guard_marker_stats());
+        if let Some(guard) = state.monsters.iter_mut().find(|monster| monster.id == guard_id) {
+            guard.behavior = MonsterBehavior::Social;
+            guard.faction = Faction::Neutral;
+        }
+        let mut coin = Item::new(state.next_item_id, "silver chalice");
+        state.next_item_id += 1;
+        coin.basevalue = 100;
+        state.player.inventory.push(coin);
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Legacy { token: "G".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "a".to_string() }, &mut rng);
+
+        assert!(state.player.inventory.is_empty());
+        assert_eq!(state.legal_heat, 5);
+        assert!(state.log.iter().any(|line| line.contains("looks the other way")));
+    }
+
+    #[test]
+    fn altar_prayer_to_hostile_deity_triggers_sacrilege() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.options.interactive_sites = true;
+        state.progression.alignment = Alignment::Lawful;
+        state.progression.patron_deity = DEITY_ID_ODIN;
+        state.progression.priest_rank = 2;
+        state.progression.deity_favor = 16;
+        state.player.position = Position { x: 1, y: 1 };
+        state.site_grid = vec![TileSiteCell::default(); 9];
+        state.city_site_grid = state.site_grid.clone();
+        state.site_grid[4].aux = SITE_AUX_ALTAR_SET;
+        state.city_site_grid[4].aux = SITE_AUX_ALTAR_SET;
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Legacy { token: ">".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "1".to_string() }, &mut rng);
+
+        assert_eq!(state.progression.patron_deity, 0);
+        assert_eq!(state.progression.priest_rank, 0);
+        assert_eq!(state.progression.deity_favor, 0);
+    }
+
+    #[test]
+    fn door_open_and_close_commands_toggle_walkability() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.map_rows = vec!["...".to_string(), "..-".to_string(), "...".to_string()];
+        state.city_map_rows = state.map_rows.clone();
+        state.map_binding.semantic = MapSemanticKind::City;
+        state.site_grid = vec![TileSiteCell::default(); 9];
+        state.city_site_grid = state.site_grid.clone();
+        state.site_grid[5].flags = TILE_FLAG_BLOCK_MOVE;
+        state.city_site_grid[5].flags = TILE_FLAG_BLOCK_MOVE;
+
+        assert!(!state.tile_is_walkable(Position { x: 2, y: 1 }));
+        let mut rng = FixedRng::new(vec![]);
+        let _ = step(&mut state, Command::Legacy { token: "o".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "l".to_string() }, &mut rng);
+        assert_eq!(state.map_glyph_at(Position { x: 2, y: 1 }), '/');
+        assert!(state.tile_is_walkable(Position { x: 2, y: 1 }));
+
+        let _ = step(&mut state, Command::Legacy { token: "c".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "l".to_string() }, &mut rng);
+        assert_eq!(state.map_glyph_at(Position { x: 2, y: 1 }), '-');
+        assert!(!state.tile_is_walkable(Position { x: 2, y: 1 }));
+    }
+
+    #[test]
+    fn talking_to_guard_walks_dialogue_branch_to_pay_fine() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 9 });
+        state.player.position = Position { x: 4, y: 4 };
+        state.legal_heat = 3;
+        state.gold = 100;
+        let guard = state.spawn_monster(
+            "city guard",
+            Position { x: 5, y: 4 },
+            Stats { hp: 20, max_hp: 20, attack_min: 2, attack_max: 4, defense: 2, weight: 60 },
+        );
+        if let Some(monster) = state.monsters.iter_mut().find(|monster| monster.id == guard) {
+            monster.behavior = MonsterBehavior::Social;
+            monster.faction = Faction::Neutral;
+        }
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Legacy { token: "t".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "l".to_string() }, &mut rng);
+        let dialogue = state.pending_dialogue.clone().expect("dialogue opened");
+        assert_eq!(dialogue.speaker, "city guard");
+        assert!(
+            dialogue.node.options.iter().any(|(_, action)| *action == DialogueAction::AskAboutLaw)
+        );
+
+        let out = step(&mut state, Command::Legacy { token: "2".to_string() }, &mut rng);
+        assert!(out.events.iter().any(|event| matches!(
+            event,
+            Event::DialogueAdvanced { speaker, .. } if speaker == "city guard"
+        )));
+        let node = state.pending_dialogue.as_ref().map(|it| it.node.clone()).expect("law branch");
+        assert_eq!(node.options[0].1, DialogueAction::PayFine);
+
+        let _ = step(&mut state, Command::Legacy { token: "1".to_string() }, &mut rng);
+        assert_eq!(state.legal_heat, 0);
+        assert_eq!(state.gold, 70);
+
+        let _ = step(&mut state, Command::Legacy { token: "<esc>".to_string() }, &mut rng);
+        assert!(state.pending_dialogue.is_none());
+    }
+
+    #[test]
+    fn hostile_monster_refuses_dialogue() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 9 });
+        state.player.position = Position { x: 4, y: 4 };
+        state.spawn_monster(
+            "goblin",
+            Position { x: 5, y: 4 },
+            Stats { hp: 6, max_hp: 6, attack_min: 1, attack_max: 1, defense: 0, weight: 60 },
+        );
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Legacy { token: "t".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "l".to_string() }, &mut rng);
+
+        assert!(state.pending_dialogue.is_none());
+        assert!(state.log.iter().any(|line| line.contains("refuses to parley")));
+    }
+
+    fn state_beside_chest(locked: bool, trapped: bool) -> GameState {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.containers.push(Container {
+            position: Position { x: 2, y: 1 },
+            locked,
+            trapped,
+            contents: vec![instantiate_item_from_name(900, "food ration")],
+        });
+        state
+    }
+
+    #[test]
+    fn opening_unlocked_chest_spills_contents_for_pickup() {
+        let mut state = state_beside_chest(false, false);
+        let chest = Position { x: 2, y: 1 };
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Legacy { token: "o".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "l".to_string() }, &mut rng);
+
+        assert!(state.containers[0].contents.is_empty());
+        assert!(
+            state
+                .ground_items
+                .iter()
+                .any(|ground| ground.position == chest && ground.item.id == 900)
+        );
+    }
+
+    #[test]
+    fn locked_chest_refuses_without_key() {
+        let mut state = state_beside_chest(true, false);
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Legacy { token: "o".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "l".to_string() }, &mut rng);
+
+        assert!(state.containers[0].locked);
+        assert_eq!(state.containers[0].contents.len(), 1);
+        assert!(state.ground_items.is_empty());
+        assert!(state.log.iter().any(|line| line.contains("The chest is locked")));
+    }
+
+    #[test]
+    fn only_a_real_key_unlocks_a_chest() {
+        let mut state = state_beside_chest(true, false);
+        state.player.inventory.push(Item::basic(40, "flask of whiskey"));
+        assert!(!player_has_key(&state));
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Legacy { token: "o".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "l".to_string() }, &mut rng);
+        assert!(state.containers[0].locked);
+
+        state.player.inventory.push(instantiate_item_from_name(41, "magic key"));
+        let _ = step(&mut state, Command::Legacy { token: "o".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "l".to_string() }, &mut rng);
+        assert!(!state.containers[0].locked);
+
+        state.player.inventory.retain(|item| item.id != 41);
+        state.player.inventory.push(Item::basic(42, "Brass Key"));
+        assert!(player_has_key(&state));
+    }
+
+    #[test]
+    fn trapped_chest_triggers_trap_when_opened() {
+        let mut state = state_beside_chest(false, true);
+        let hp_before = state.player.stats.hp;
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Legacy { token: "o".to_string() }, &mut rng);
+        let out = step(&mut state, Command::Legacy { token: "l".to_string() }, &mut rng);
+
+        assert!(!state.containers[0].trapped);
+        assert_eq!(state.player.stats.hp, hp_before - CONTAINER_TRAP_DAMAGE);
+        assert!(out.events.iter().any(|event| matches!(
+            event,
+            Event::LegacyHandled { token, .. } if token == "trap"
+        )));
+        assert_eq!(state.ground_items.len(), 1);
+    }
+
+    #[test]
+    fn nimble_lockpick_opens_locked_chest() {
+        let mut state = state_beside_chest(true, false);
+        state.attributes.dexterity = 24;
+        state.player.inventory.push(instantiate_item_from_name(901, "lock pick"));
+        let mut rng = FixedRng::new(vec![10]);
+
+        let _ = step(&mut state, Command::ApplyLockpick(Direction::East), &mut rng);
+
+        assert!(!state.containers[0].locked);
+        assert!(state.player.inventory.iter().any(|item| item.id == 901));
+        assert!(state.log.iter().any(|line| line.contains("You pick the lock")));
+    }
+
+    #[test]
+    fn clumsy_lockpick_fails_and_can_snap_the_pick() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.site_grid = vec![TileSiteCell::default(); 9];
+        state.site_grid[5].flags = TILE_FLAG_BLOCK_MOVE | TILE_FLAG_LOCKED_DOOR;
+        state.attributes.dexterity = 4;
+        state.player.inventory.push(instantiate_item_from_name(901, "lock pick"));
+        let mut rng = FixedRng::new(vec![10, 5]);
+
+        let _ = step(&mut state, Command::ApplyLockpick(Direction::East), &mut rng);
+
+        let flags = state.tile_site_at(Position { x: 2, y: 1 }).map(|cell| cell.flags);
+        assert!(flags.is_some_and(|flags| flags & TILE_FLAG_LOCKED_DOOR != 0));
+        assert!(state.player.inventory.iter().all(|item| item.id != 901));
+        assert!(state.log.iter().any(|line| line.contains("lockpick snaps")));
+    }
+
+    #[test]
+    fn applying_torch_lights_surrounding_tiles() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 9 });
+        state.player.position = Position { x: 4, y: 4 };
+        state.site_grid = vec![TileSiteCell::default(); 81];
+        state.player.inventory.push(instantiate_item_from_name(902, "torch"));
+        let mut rng = FixedRng::new(vec![]);
+        assert!(!state.tile_is_lit(Position { x: 5, y: 4 }));
+
+        let _ = step(&mut state, Command::ApplyTool { item_id: 902, target: None }, &mut rng);
+
+        assert!(state.tile_is_lit(Position { x: 4, y: 4 }));
+        assert!(state.tile_is_lit(Position { x: 7, y: 7 }));
+        assert!(!state.tile_is_lit(Position { x: 8, y: 4 }));
+        assert!(state.log.iter().any(|line| line.contains("flares")));
+        assert!(state.known_sites.contains(&Position { x: 7, y: 7 }));
+        assert!(!state.known_sites.contains(&Position { x: 8, y: 4 }));
+    }
+
+    #[test]
+    fn lit_tiles_come_into_view_from_a_distance() {
+        let mut state = GameState::new(MapBounds { width: 12, height: 3 });
+        state.player.position = Position { x: 0, y: 1 };
+        state.site_grid = vec![TileSiteCell::default(); 36];
+        state.site_grid[12 + 9].flags |= TILE_FLAG_LIT;
+        state.site_grid[12 + 11].flags |= TILE_FLAG_LIT;
+
+        let _ = step(&mut state, Command::Move(Direction::East), &mut FixedRng::new(vec![]));
+
+        assert!(state.known_sites.contains(&Position { x: 9, y: 1 }));
+        assert!(!state.known_sites.contains(&Position { x: 11, y: 1 }));
+        assert!(!state.known_sites.contains(&Position { x: 5, y: 1 }));
+    }
+
+    #[test]
+    fn applying_thieves_pick_opens_adjacent_locked_door() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.site_grid = vec![TileSiteCell::default(); 9];
+        state.site_grid[5].flags = TILE_FLAG_BLOCK_MOVE | TILE_FLAG_LOCKED_DOOR;
+        state.attributes.dexterity = 24;
+        let pick = instantiate_item_from_name(903, "thieve's pick");
+        assert_eq!(pick.usef, "I_PICK");
+        state.player.inventory.push(pick);
+        let mut rng = FixedRng::new(vec![10]);
+
+        let _ = step(
+            &mut state,
+            Command::ApplyTool { item_id: 903, target: Some(Direction::East) },
+            &mut rng,
+        );
+
+        let flags = state.tile_site_at(Position { x: 2, y: 1 }).map(|cell| cell.flags);
+        assert!(flags.is_some_and(|flags| flags & TILE_FLAG_LOCKED_DOOR == 0));
+        assert!(state.log.iter().any(|line| line.contains("You pick the lock on the door")));
+    }
+
+    fn state_beside_chasm() -> GameState {
+        state_with_rows(&["....", "..:.", "...."], Position { x: 1, y: 1 })
+    }
+
+    #[test]
+    fn descending_a_level_requests_autosave_but_plain_moves_do_not() {
+        let mut state = state_beside_chasm();
+        let mut rng = FixedRng::new(vec![3, 4]);
+
+        let walked = step(&mut state, Command::Move(Direction::North), &mut rng);
+        assert!(!walked.should_autosave);
+
+        state.player.position = Position { x: 1, y: 1 };
+        let fell = step(&mut state, Command::Move(Direction::East), &mut rng);
+        assert_eq!(state.topology.dungeon_level, 1);
+        assert!(fell.should_autosave);
+    }
+
+    #[test]
+    fn large_wealth_swings_request_autosave_past_the_configured_threshold() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.options.autosave_gold_threshold = 100;
+        let before = AutosaveSnapshot::capture(&state);
+
+        state.gold += 99;
+        assert!(!before.warrants_autosave(&state));
+        state.gold += 1;
+        assert!(before.warrants_autosave(&state));
+    }
+
+    #[test]
+    fn stepping_into_chasm_drops_a_level_with_falling_damage() {
+        let mut state = state_beside_chasm();
+        let mut rng = FixedRng::new(vec![3, 4]);
+
+        let _ = step(&mut state, Command::Move(Direction::East), &mut rng);
+
+        assert_eq!(state.topology.dungeon_level, 1);
+        assert_eq!(state.player.stats.hp, 13);
+        assert!(state.log.iter().any(|line| line.contains("plunge into the chasm")));
+        assert_eq!(state.map_binding.map_id, DUNGEON_LEVEL_MAP_BASE + 1);
+        assert_eq!(state.map_binding.semantic, MapSemanticKind::Dungeon);
+        assert_ne!(state.map_glyph_at(state.player.position), CHASM_GLYPH);
+    }
+
+    #[test]
+    fn chasm_level_keeps_an_up_staircase_back_to_the_countryside() {
+        let mut state = state_beside_chasm();
+        let mut rng = FixedRng::new(vec![3, 4]);
+
+        let _ = step(&mut state, Command::Move(Direction::East), &mut rng);
+
+        assert_eq!(state.world_mode, WorldMode::DungeonCity);
+        let stairs = Position { x: 1, y: 1 };
+        assert_eq!(state.map_glyph_at(stairs), '<');
+        assert_eq!(
+            state.tile_site_at(stairs).map(|cell| cell.aux),
+            Some(SITE_AUX_EXIT_COUNTRYSIDE)
+        );
+        state.player.position = Position { x: 2, y: 1 };
+        let _ = step(&mut state, Command::Move(Direction::West), &mut rng);
+        assert_eq!(state.world_mode, WorldMode::Countryside);
+    }
+
+    #[test]
+    fn carried_rope_negates_a_chasm_fall() {
+        let mut state = state_beside_chasm();
+        state.player.inventory.push(instantiate_item_from_name(950, "rope"));
+        let mut rng = FixedRng::new(vec![3, 4]);
+
+        let _ = step(&mut state, Command::Move(Direction::East), &mut rng);
+
+        assert_eq!(state.player.position, Position { x: 2, y: 1 });
+        assert_eq!(state.topology.dungeon_level, 0);
+        assert_eq!(state.player.stats.hp, 20);
+        assert!(state.log.iter().any(|line| line.contains("rope your way safely")));
+    }
+
+    #[test]
+    fn plain_descent_keeps_the_loaded_map() {
+        let mut state = state_beside_chasm();
+        state.environment = LegacyEnvironment::Caves;
+        let map_id = state.map_binding.map_id;
+        let rows = state.map_rows.clone();
+        let mut rng = FixedRng::new(vec![3, 4]);
+
+        let _ = step(&mut state, Command::Legacy { token: ">".to_string() }, &mut rng);
+
+        assert_eq!(state.topology.dungeon_level, 1);
+        assert_eq!(state.map_binding.map_id, map_id);
+        assert_eq!(state.map_rows, rows);
+        assert!(state.ground_items.is_empty());
+    }
+
+    #[test]
+    fn a_newly_generated_chasm_level_is_scattered_with_loot_once() {
+        let mut state = state_beside_chasm();
+        state.player.stats.hp = 200;
+        state.player.stats.max_hp = 200;
+        let mut rng = DeterministicRng::seeded(0x10_07);
+
+        let _ = step(&mut state, Command::Move(Direction::East), &mut rng);
+
+        assert_eq!(state.map_binding.map_id, DUNGEON_LEVEL_MAP_BASE + 1);
+        let landing = state.player.position;
+        let loot: Vec<Position> = state.ground_items.iter().map(|ground| ground.position).collect();
+        assert_eq!(loot.len(), LEVEL_LOOT_BASE as usize);
+        assert!(loot.iter().all(|pos| *pos != landing && state.tile_is_walkable(*pos)));
+
+        state.topology.dungeon_level = 0;
+        assert_eq!(descend_one_level(&mut state, &mut rng), 1);
+        assert_eq!(state.ground_items.len(), LEVEL_LOOT_BASE as usize);
+    }
+
+    #[test]
+    fn levitation_crosses_chasm_safely() {
+        let mut state = state_beside_chasm();
+        push_or_refresh_status(&mut state.status_effects, "levitate", 5, 1);
+        let mut rng = FixedRng::new(vec![3, 4]);
+
+        let _ = step(&mut state, Command::Move(Direction::East), &mut rng);
+
+        assert_eq!(state.player.position, Position { x: 2, y: 1 });
+        assert_eq!(state.topology.dungeon_level, 0);
+        assert_eq!(state.player.stats.hp, 20);
+        assert!(state.log.iter().any(|line| line.contains("float serenely")));
+    }
+
+    #[test]
+    fn perceptive_player_spots_hidden_trap_and_refuses_the_step() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.player.position = Position { x: 1, y: 2 };
+        state.attributes.iq = 32;
+        let hp_before = state.player.stats.hp;
+        state.place_trap(Position { x: 2, y: 2 }, 6, "pit");
+        let mut rng = FixedRng::new(vec![10]);
+
+        let out = step(&mut state, Command::Move(Direction::East), &mut rng);
+
+        assert_eq!(state.player.position, Position { x: 1, y: 2 });
+        assert!(state.traps[0].armed);
+        assert!(state.traps[0].discovered);
+        assert_eq!(state.player.stats.hp, hp_before);
+        assert!(out.events.contains(&Event::MoveBlocked { target: Position { x: 2, y: 2 } }));
+        assert!(state.log.iter().any(|line| line.contains("notice a pit trap")));
+    }
+
+    #[test]
+    fn lit_tiles_make_hidden_traps_easier_to_notice() {
+        let setup = || {
+            let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+            state.player.position = Position { x: 1, y: 2 };
+            state.site_grid = vec![TileSiteCell::default(); 25];
+            state.attributes.iq = 18;
+            state.place_trap(Position { x: 2, y: 2 }, 6, "pit");
+            state
+        };
+
+        let mut dark = setup();
+        let _ = step(&mut dark, Command::Move(Direction::East), &mut FixedRng::new(vec![10]));
+        assert_eq!(dark.player.position, Position { x: 2, y: 2 });
+
+        let mut lit = setup();
+        lit.site_grid[12].flags |= TILE_FLAG_LIT;
+        let _ = step(&mut lit, Command::Move(Direction::East), &mut FixedRng::new(vec![10]));
+        assert_eq!(lit.player.position, Position { x: 1, y: 2 });
+        assert!(lit.traps[0].discovered);
+    }
+
+    #[test]
+    fn unperceptive_player_walks_into_hidden_trap() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.player.position = Position { x: 1, y: 2 };
+        state.attributes.iq = 8;
+        state.place_trap(Position { x: 2, y: 2 }, 6, "pit");
+        let mut rng = FixedRng::new(vec![10]);
+
+        let _ = step(&mut state, Command::Move(Direction::East), &mut rng);
+
+        assert_eq!(state.player.position, Position { x: 2, y: 2 });
+        assert!(!state.traps[0].armed);
+        assert!(state.log.iter().any(|line| line.contains("triggers")));
+    }
+
+    #[test]
+    fn levitating_over_pit_trap_does_not_trigger_it() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.player.position = Position { x: 1, y: 2 };
+        let hp_before = state.player.stats.hp;
+        state.place_trap(Position { x: 2, y: 2 }, 6, "pit");
+        push_or_refresh_status(&mut state.status_effects, "levitate", 5, 1);
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Move(Direction::East), &mut rng);
+
+        assert_eq!(state.player.position, Position { x: 2, y: 2 });
+        assert!(state.traps[0].armed);
+        assert_eq!(state.player.stats.hp, hp_before);
+    }
+
+    #[test]
+    fn levitation_expiring_over_chasm_causes_a_fall() {
+        let mut state = state_beside_chasm();
+        state.player.position = Position { x: 2, y: 1 };
+        push_or_refresh_status(&mut state.status_effects, "levitate", 1, 1);
+        let mut rng = FixedRng::new(vec![]);
+
+        let out = step(&mut state, Command::Wait, &mut rng);
+
+        assert!(out.events.iter().any(|event| matches!(
+            event,
+            Event::StatusExpired { effect_id } if effect_id == "levitate"
+        )));
+        assert_eq!(state.topology.dungeon_level, 1);
+        assert!(state.player.stats.hp < 20);
+        assert!(state.log.iter().any(|line| line.contains("plunge into the chasm")));
+    }
+
+    #[test]
+    fn glyph_legend_override_makes_rubble_walkable() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 1 });
+        state.player.position = Position { x: 0, y: 0 };
+        state.map_rows = vec![".=.".to_string()];
+        let mut rng = FixedRng::new(vec![]);
+        let _ = step(&mut state, Command::Move(Direction::East), &mut rng);
+        assert_eq!(state.player.position, Position { x: 0, y: 0 });
+
+        state.glyph_legend.set_rule(GlyphRule {
+            glyph: '=',
+            world_mode: Some(WorldMode::DungeonCity),
+            walkable: true,
+            flags: 0,
+        });
+        let _ = step(&mut state, Command::Move(Direction::East), &mut rng);
+        assert_eq!(state.player.position, Position { x: 1, y: 0 });
+    }
+
+    #[test]
+    fn custom_glyph_can_block_movement_in_one_world_mode() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 1 });
+        state.player.position = Position { x: 0, y: 0 };
+        state.map_rows = vec![".%.".to_string()];
+        state.glyph_legend.set_rule(GlyphRule {
+            glyph: '%',
+            world_mode: Some(WorldMode::DungeonCity),
+            walkable: false,
+            flags: TILE_FLAG_LIT,
+        });
+        let mut rng = FixedRng::new(vec![]);
+
+        let out = step(&mut state, Command::Move(Direction::East), &mut rng);
+        assert_eq!(state.player.position, Position { x: 0, y: 0 });
+        assert!(out.events.iter().any(|event| matches!(event, Event::MoveBlocked { .. })));
+        assert!(state.tile_is_lit(Position { x: 1, y: 0 }));
+        assert!(state.glyph_legend.is_walkable('%', WorldMode::Countryside));
+    }
+
+    fn small_room_state() -> GameState {
+        state_with_rows(
+            &["#######", "#.....#", "#.....#", "#.....#", "#######"],
+            Position { x: 1, y: 1 },
+        )
+    }
+
+    #[test]
+    fn auto_explore_walks_a_room_until_everything_is_known() {
+        let mut state = small_room_state();
+        let mut rng = FixedRng::new(vec![]);
+        let mut steps = 0;
+        loop {
+            let out = step(&mut state, Command::AutoExplore, &mut rng);
+            if out.events.contains(&Event::AutoExploreDone) {
+                break;
+            }
+            assert!(out.events.contains(&Event::AutoExploreStep { to: state.player.position }));
+            steps += 1;
+            assert!(steps < 20, "auto-explore should finish a small room");
+        }
+
+        for y in 1..4 {
+            for x in 1..6 {
+                assert!(state.explored_tiles.contains(&Position { x, y }));
+            }
+        }
+        assert!(state.known_sites.is_empty(), "exploring leaves the site list alone");
+        assert!(state.log.iter().any(|line| line.contains("nothing left to explore")));
+    }
+
+    #[test]
+    fn auto_explore_halts_when_a_monster_comes_into_view() {
+        let mut state = small_room_state();
+        let mut rng = FixedRng::new(vec![]);
+        let first = step(&mut state, Command::AutoExplore, &mut rng);
+        assert!(first.events.iter().any(|event| matches!(event, Event::AutoExploreStep { .. })));
+
+        state.spawn_monster(
+            "rat",
+            Position { x: 5, y: 3 },
+            Stats { hp: 3, max_hp: 3, attack_min: 1, attack_max: 1, defense: 0, weight: 60 },
+        );
+        let before = state.player.position;
+        let out = step(&mut state, Command::AutoExplore, &mut rng);
+
+        assert!(out.events.contains(&Event::AutoExploreDone));
+        assert_eq!(state.player.position, before);
+        assert!(state.log.iter().any(|line| line.contains("You spot a rat")));
+    }
+
+    #[test]
+    fn auto_explore_ignores_peaceful_and_walled_off_monsters() {
+        let mut state = small_room_state();
+        state.map_rows[2] = "#..#..#".to_string();
+        state.map_rows[3] = "#..#..#".to_string();
+        let mut rng = FixedRng::new(vec![]);
+        state.spawn_monster(
+            "shopkeeper",
+            Position { x: 2, y: 3 },
+            Stats { hp: 3, max_hp: 3, attack_min: 1, attack_max: 1, defense: 0, weight: 60 },
+        );
+        state.monsters[0].allied = true;
+        state.spawn_monster(
+            "rat",
+            Position { x: 4, y: 3 },
+            Stats { hp: 3, max_hp: 3, attack_min: 1, attack_max: 1, defense: 0, weight: 60 },
+        );
+
+        let out = step(&mut state, Command::AutoExplore, &mut rng);
+        assert!(out.events.iter().any(|event| matches!(event, Event::AutoExploreStep { .. })));
+        assert!(!state.log.iter().any(|line| line.contains("You spot")));
+    }
+
+    fn state_beside_water() -> GameState {
+        state_with_rows(&["....", "..~.", "...."], Position { x: 1, y: 1 })
+    }
+
+    #[test]
+    fn heavily_laden_player_cannot_swim() {
+        let mut state = state_beside_water();
+        let mut rng = FixedRng::new(vec![]);
+        for name in ["full plate mail", "tower shield"] {
+            state.place_item(name, state.player.position);
+            let _ = step(&mut state, Command::Pickup, &mut rng);
+        }
+
+        let out = step(&mut state, Command::Move(Direction::East), &mut rng);
+
+        assert_eq!(state.player.position, Position { x: 1, y: 1 });
+        assert!(out.events.iter().any(|event| matches!(event, Event::MoveBlocked { .. })));
+        assert!(state.log.iter().any(|line| line.contains("too heavily laden to swim")));
+        assert!(!state.tile_is_walkable(Position { x: 2, y: 1 }));
+        push_or_refresh_status(&mut state.status_effects, "levitate", 5, 1);
+        assert!(state.tile_is_walkable(Position { x: 2, y: 1 }));
+    }
+
+    #[test]
+    fn current_sweeps_away_the_last_loose_item_from_a_full_pack() {
+        let mut state = state_beside_water();
+        state.player.inventory_capacity = 2;
+        state.player.inventory.push(Item::basic(1, "short sword"));
+        state.player.inventory.push(Item::basic(2, "silver locket"));
+        state.player.equipment.weapon_hand = Some(1);
+        state.player.pack_order = vec![1, 2];
+        let mut rng = FixedRng::new(vec![WATER_ITEM_LOSS_CHANCE]);
+
+        let out = step(&mut state, Command::Move(Direction::East), &mut rng);
+
+        assert_eq!(state.player.position, Position { x: 2, y: 1 });
+        assert_eq!(state.player.inventory.len(), 1);
+        assert_eq!(state.player.inventory[0].id, 1);
+        assert!(state.ground_items.is_empty());
+        assert!(
+            out.events
+                .contains(&Event::ItemSweptAway { item_id: 2, name: "silver locket".to_string() })
+        );
+        assert!(!out.events.iter().any(|event| matches!(event, Event::Dropped { .. })));
+        assert!(state.log.iter().any(|line| line == "The current sweeps away your silver locket!"));
+    }
+
+    #[test]
+    fn swimming_costs_extra_time_unless_levitating() {
+        let mut swimmer = state_beside_water();
+        let mut rng = FixedRng::new(vec![]);
+        let swim = step(&mut swimmer, Command::Move(Direction::East), &mut rng);
+        assert_eq!(swimmer.player.position, Position { x: 2, y: 1 });
+        assert_eq!(swim.minutes, 5 + WATER_EXTRA_MINUTES);
+
+        let mut flyer = state_beside_water();
+        push_or_refresh_status(&mut flyer.status_effects, "levitate", 5, 1);
+        let float = step(&mut flyer, Command::Move(Direction::East), &mut rng);
+        assert_eq!(flyer.player.position, Position { x: 2, y: 1 });
+        assert_eq!(float.minutes, 5);
+    }
+
+    #[test]
+    fn aura_sight_senses_monsters_behind_walls_until_it_expires() {
+        let mut state = GameState::new(MapBounds { width: 7, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.map_rows = vec!["...#...".to_string(), "...#...".to_string(), "...#...".to_string()];
+        let id = state.spawn_monster(
+            "lurker",
+            Position { x: 5, y: 1 },
+            Stats { hp: 5, max_hp: 5, attack_min: 1, attack_max: 1, defense: 0, weight: 60 },
+        );
+        assert!(state.sensed_monsters().is_empty());
+
+        push_or_refresh_status(&mut state.status_effects, AURA_SIGHT_STATUS, 1, AURA_SIGHT_RADIUS);
+        let monster_pos = state.monsters[0].position;
+        assert_eq!(state.sensed_monsters(), vec![(id, monster_pos)]);
+
+        let mut rng = FixedRng::new(vec![]);
+        let _ = step(&mut state, Command::Wait, &mut rng);
+        assert!(!state.status_effects.iter().any(|effect| effect.id == AURA_SIGHT_STATUS));
+        assert!(state.sensed_monsters().is_empty());
+    }
+
+    #[test]
+    fn potion_of_monster_detection_grants_aura_sight() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.spawn_monster(
+            "lurker",
+            Position { x: 6, y: 1 },
+            Stats { hp: 5, max_hp: 5, attack_min: 1, attack_max: 1, defense: 0, weight: 60 },
+        );
+        let potion = instantiate_item_from_name(1, "potion of monster detection");
+        assert_eq!(potion.usef, "I_MONDET");
+
+        let mut events = Vec::new();
+        let note =
+            apply_item_usef_effect(&mut state, &potion, &mut FixedRng::new(vec![]), &mut events);
+        assert!(note.contains("1 aura(s)"), "{note}");
+        assert_eq!(status_magnitude(&state, AURA_SIGHT_STATUS), AURA_SIGHT_RADIUS);
+        assert_eq!(state.sensed_monsters().len(), 1);
+    }
+
+    #[test]
+    fn replay_verification_reports_first_tampered_outcome() {
+        let mut initial = GameState::new(MapBounds { width: 7, height: 7 });
+        initial.player.position = Position { x: 3, y: 3 };
+        initial.spawn_monster(
+            "rat",
+            Position { x: 4, y: 3 },
+            Stats { hp: 8, max_hp: 8, attack_min: 1, attack_max: 2, defense: 0, weight: 60 },
+        );
+        let commands = vec![
+            Command::Attack(Direction::East),
+            Command::Wait,
+            Command::Attack(Direction::East),
+            Command::Move(Direction::North),
+        ];
+        let mut state = initial.clone();
+        let mut rng = DeterministicRng::seeded(0xAB);
+        let mut outcomes: Vec<Outcome> =
+            commands.iter().map(|command| step(&mut state, command.clone(), &mut rng)).collect();
+        assert_eq!(verify_replay(&initial, 0xAB, &commands, &outcomes), Ok(()));
+
+        outcomes[2].minutes += 1;
+        let report = verify_replay(&initial, 0xAB, &commands, &outcomes)
+            .expect_err("tampered outcome should desync");
+        assert_eq!(report.index, 2);
+        assert_eq!(report.expected.as_ref(), Some(&outcomes[2]));
+        assert_ne!(report.actual.as_ref(), Some(&outcomes[2]));
+    }
+
+    #[test]
+    fn open_door_prompt_targets_chosen_direction() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.map_rows = vec!["...".to_string(), "-.-".to_string(), "...".to_string()];
+        state.city_map_rows = state.map_rows.clone();
+        state.map_binding.semantic = MapSemanticKind::City;
+        state.site_grid = vec![TileSiteCell::default(); 9];
+        state.site_grid[3].flags = TILE_FLAG_BLOCK_MOVE;
+        state.site_grid[5].flags = TILE_FLAG_BLOCK_MOVE;
+        state.city_site_grid = state.site_grid.clone();
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Legacy { token: "o".to_string() }, &mut rng);
+        assert_eq!(state.pending_talk_direction, Some(TalkDirectionInteraction::OpenDoor));
+        let _ = step(&mut state, Command::Legacy { token: "l".to_string() }, &mut rng);
+
+        assert!(state.pending_talk_direction.is_none());
+        assert_eq!(state.map_glyph_at(Position { x: 2, y: 1 }), '/');
+        assert!(state.tile_is_walkable(Position { x: 2, y: 1 }));
+        let east_flags = state.tile_site_at(Position { x: 2, y: 1 }).map(|cell| cell.flags);
+        assert!(east_flags.is_some_and(|flags| flags & TILE_FLAG_OPENED_DOOR != 0));
+        assert_eq!(state.map_glyph_at(Position { x: 0, y: 1 }), '-');
+        assert!(!state.tile_is_walkable(Position { x: 0, y: 1 }));
+    }
+
+    #[test]
+    fn close_door_is_refused_when_monster_stands_in_doorway() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.map_rows = vec!["...".to_string(), "../".to_string(), "...".to_string()];
+        state.city_map_rows = state.map_rows.clone();
+        state.map_binding.semantic = MapSemanticKind::City;
+        state.site_grid = vec![TileSiteCell::default(); 9];
+        state.site_grid[5].flags = TILE_FLAG_OPENED_DOOR;
+        state.city_site_grid = state.site_grid.clone();
+        state.spawn_monster(
+            "rat",
+            Position { x: 2, y: 1 },
+            Stats { hp: 3, max_hp: 3, attack_min: 0, attack_max: 0, defense: 0, weight: 10 },
+        );
+        state.monsters[0].behavior = MonsterBehavior::Social;
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Legacy { token: "c".to_string() }, &mut rng);
+        let out = step(&mut state, Command::Legacy { token: "l".to_string() }, &mut rng);
+
+        assert_eq!(state.map_glyph_at(Position { x: 2, y: 1 }), '/');
+        assert!(out.events.iter().any(|event| matches!(
+            event,
+            Event::LegacyHandled { token, note, .. }
+                if token == "c" && note.contains("standing in the doorway")
+        )));
+    }
+
+    #[test]
+    fn tunneling_opens_diggable_wall_after_accumulated_effort() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.map_rows = vec!["###".to_string(), "..#".to_string(), "###".to_string()];
+        state.city_map_rows = state.map_rows.clone();
+        state.map_binding.semantic = MapSemanticKind::City;
+        state.site_grid = vec![TileSiteCell::default(); 9];
+        state.site_grid[5].flags = TILE_FLAG_BLOCK_MOVE;
+        state.city_site_grid = state.site_grid.clone();
+        state.attributes.strength = 12;
+        let wall = Position { x: 2, y: 1 };
+        let mut rng = FixedRng::new(vec![]);
+
+        let mut attempts = 0;
+        while state.map_glyph_at(wall) == '#' && attempts < 20 {
+            let _ = step(&mut state, Command::Legacy { token: "T".to_string() }, &mut rng);
+            let _ = step(&mut state, Command::Legacy { token: "l".to_string() }, &mut rng);
+            attempts += 1;
+            if attempts == 1 {
+                assert_eq!(state.map_glyph_at(wall), '#');
+                assert!(state.tunnel_progress.is_some_and(|progress| progress.target == wall));
+            }
+        }
+
+        assert_eq!(attempts, 8);
+        assert_eq!(state.map_glyph_at(wall), '.');
+        assert!(state.tile_is_walkable(wall));
+        assert!(state.tunnel_progress.is_none());
+    }
+
+    #[test]
+    fn tunneling_is_refused_on_undiggable_rock() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.environment = LegacyEnvironment::Arena;
+        state.map_rows = vec!["###".to_string(), "..#".to_string(), "###".to_string()];
+        state.site_grid = vec![TileSiteCell::default(); 9];
+        state.site_grid[5].flags = TILE_FLAG_BLOCK_MOVE;
+        let mut rng = FixedRng::new(vec![]);
+
+        for _ in 0..10 {
+            let _ = step(&mut state, Command::Legacy { token: "T".to_string() }, &mut rng);
+            let _ = step(&mut state, Command::Legacy { token: "l".to_string() }, &mut rng);
+        }
+
+        assert_eq!(state.map_glyph_at(Position { x: 2, y: 1 }), '#');
+        assert!(state.tunnel_progress.is_none());
+        assert!(state.log.iter().any(|line| line.contains("too hard to dig")));
+    }
+
+    fn state_facing_closed_door(strength: i32) -> GameState {
+        let mut state = state_with_rows(&["...", "..-", "..."], Position { x: 1, y: 1 });
+        state.city_map_rows = state.map_rows.clone();
+        state.map_binding.semantic = MapSemanticKind::City;
+        state.site_grid = vec![TileSiteCell::default(); 9];
+        state.site_grid[5].flags = TILE_FLAG_BLOCK_MOVE;
+        state.city_site_grid = state.site_grid.clone();
+        state.attributes.strength = strength;
+        state
+    }
+
+    #[test]
+    fn bashing_wakes_nearby_sleeping_monsters() {
+        let mut state = state_facing_closed_door(30);
+        state.spawn_monster(
+            "ogre",
+            Position { x: 0, y: 0 },
+            Stats { hp: 20, max_hp: 20, attack_min: 0, attack_max: 0, defense: 0, weight: 60 },
+        );
+        state.monsters[0].awareness = Awareness::Asleep;
+
+        let _ = step(&mut state, Command::Bash(Direction::East), &mut FixedRng::new(vec![10]));
+
+        // Roused by the noise, the ogre goes searching and at once spots the player.
+        assert_eq!(state.monsters[0].awareness, Awareness::Aware);
+        assert!(state.log.iter().any(|line| line.contains("The ogre spots you!")));
+        assert!(!state.log.iter().any(|line| line.contains("The ogre wakes up!")));
+    }
+
+    #[test]
+    fn strong_bash_breaks_down_closed_door() {
+        let mut state = state_facing_closed_door(30);
+        let door = Position { x: 2, y: 1 };
+        let hp_before = state.player.stats.hp;
+        let mut rng = FixedRng::new(vec![10]);
+
+        let out = step(&mut state, Command::Bash(Direction::East), &mut rng);
+
+        assert!(out.events.contains(&Event::BashResult { target: door, success: true }));
+        assert_eq!(state.map_glyph_at(door), '.');
+        assert!(state.tile_is_walkable(door));
+        assert_eq!(state.player.stats.hp, hp_before);
+    }
+
+    #[test]
+    fn weak_bash_fails_and_hurts_foot() {
+        let mut state = state_facing_closed_door(3);
+        let door = Position { x: 2, y: 1 };
+        let hp_before = state.player.stats.hp;
+        let mut rng = FixedRng::new(vec![10, 2]);
+
+        let out = step(&mut state, Command::Bash(Direction::East), &mut rng);
+
+        assert!(out.events.contains(&Event::BashResult { target: door, success: false }));
+        assert_eq!(state.map_glyph_at(door), '-');
+        assert!(!state.tile_is_walkable(door));
+        assert_eq!(state.player.stats.hp, hp_before - 2);
+        assert!(state.log.iter().any(|line| line.contains("hurt your foot")));
+    }
+
+    #[test]
+    fn bumping_closed_door_opens_and_steps_forward() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.map_rows = vec!["...".to_string(), "..-".to_string(), "...".to_string()];
+        state.city_map_rows = state.map_rows.clone();
+        state.map_binding.semantic = MapSemanticKind::City;
+        state.site_grid = vec![TileSiteCell::default(); 9];
+        state.city_site_grid = state.site_grid.clone();
+        state.site_grid[5].flags = TILE_FLAG_BLOCK_MOVE;
+        state.city_site_grid[5].flags = TILE_FLAG_BLOCK_MOVE;
+
+        let mut rng = FixedRng::new(vec![]);
+        let out = step(&mut state, Command::Move(Direction::East), &mut rng);
+
+        assert_eq!(state.player.position, Position { x: 2, y: 1 });
+        assert_eq!(state.map_glyph_at(Position { x: 2, y: 1 }), '/');
+        assert!(out.events.iter().any(|event| matches!(event, Event::Moved { .. })));
+        assert!(out.events.iter().any(|event| matches!(
+            event,
+            Event::LegacyHandled { token, .. } if token == "step"
+        )));
+    }
+
+    #[test]
+    fn stepping_on_service_tile_triggers_interaction() {
+        let mut state = GameState::new(MapBounds { width: 4, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.map_rows = vec!["....".to_string(), "....".to_string(), "....".to_string()];
+        state.city_map_rows = state.map_rows.clone();
+        state.map_binding.semantic = MapSemanticKind::City;
+        state.site_grid = vec![TileSiteCell::default(); 12];
+        state.city_site_grid = state.site_grid.clone();
+        state.site_grid[4 + 2].aux = SITE_AUX_SERVICE_SHOP;
+        state.city_site_grid[4 + 2].aux = SITE_AUX_SERVICE_SHOP;
+        let start_gold = state.gold;
+
+        let mut rng = FixedRng::new(vec![]);
+        let out = step(&mut state, Command::Move(Direction::East), &mut rng);
+
+        assert_eq!(state.player.position, Position { x: 2, y: 1 });
+        assert!(state.gold < start_gold);
+        assert!(out.events.iter().any(|event| matches!(event, Event::EconomyUpdated { .. })));
+        assert!(out.events.iter().any(|event| matches!(
+            event,
+            Event::LegacyHandled { token, .. } if token == "step"
+        )));
+    }
+
+    #[test]
+    fn stepping_on_service_tile_opens_interactive_menu_when_enabled() {
+        let mut state = GameState::new(MapBounds { width: 4, height: 3 });
+        state.options.interactive_sites = true;
+        state.player.position = Position { x: 1, y: 1 };
+        state.map_rows = vec!["....".to_string(), "....".to_string(), "....".to_string()];
+        state.city_map_rows = state.map_rows.clone();
+        state.map_binding.semantic = MapSemanticKind::City;
+        state.site_grid = vec![TileSiteCell::default(); 12];
+        state.city_site_grid = state.site_grid.clone();
+        state.site_grid[4 + 2].aux = SITE_AUX_SERVICE_SHOP;
+        state.city_site_grid[4 + 2].aux = SITE_AUX_SERVICE_SHOP;
+        let start_gold = state.gold;
+
+        let mut rng = FixedRng::new(vec![]);
+        let out = step(&mut state, Command::Move(Direction::East), &mut rng);
+
+        assert_eq!(state.player.position, Position { x: 2, y: 1 });
+        assert_eq!(state.gold, start_gold, "stepping should open menu before applying purchase");
+        assert_eq!(state.pending_site_interaction, Some(SiteInteractionKind::Shop));
+        assert!(out.events.iter().any(|event| matches!(
+            event,
+            Event::LegacyHandled { token, .. } if token == "interaction"
+        )));
+    }
+
+    #[test]
+    fn interactive_site_menu_accepts_numeric_choice_via_legacy_token() {
+        let mut state = GameState::new(MapBounds { width: 4, height: 3 });
+        state.options.interactive_sites = true;
+        state.player.position = Position { x: 1, y: 1 };
+        state.map_rows = vec!["....".to_string(), "....".to_string(), "....".to_string()];
+        state.city_map_rows = state.map_rows.clone();
+        state.map_binding.semantic = MapSemanticKind::City;
+        state.site_grid = vec![TileSiteCell::default(); 12];
+        state.city_site_grid = state.site_grid.clone();
+        state.site_grid[4 + 2].aux = SITE_AUX_SERVICE_SHOP;
+        state.city_site_grid[4 + 2].aux = SITE_AUX_SERVICE_SHOP;
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Move(Direction::East), &mut rng);
+        let gold_before = state.gold;
+        let out = step(&mut state, Command::Legacy { token: "1".to_string() }, &mut rng);
+
+        assert!(state.gold < gold_before);
+        assert!(state.player.inventory.iter().any(|item| item.name == "food ration"));
+        assert_eq!(state.pending_site_interaction, Some(SiteInteractionKind::Shop));
+        assert!(out.events.iter().any(|event| matches!(
+            event,
+            Event::EconomyUpdated { source, .. } if source == "shop"
+        )));
+    }
+
+    #[test]
+    fn jail_doors_are_openable_with_open_command() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.map_rows = vec!["...".to_string(), "..J".to_string(), "...".to_string()];
+        state.city_map_rows = state.map_rows.clone();
+        state.map_binding.semantic = MapSemanticKind::City;
+        state.site_grid = vec![TileSiteCell::default(); 9];
+        state.city_site_grid = state.site_grid.clone();
+        state.site_grid[5].flags = TILE_FLAG_BLOCK_MOVE;
+        state.city_site_grid[5].flags = TILE_FLAG_BLOCK_MOVE;
+        let mut rng = FixedRng::new(vec![]);
+
+        assert!(!state.tile_is_walkable(Position { x: 2, y: 1 }));
+        let _ = step(&mut state, Command::Legacy { token: "o".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "l".to_string() }, &mut rng);
+        assert_eq!(state.map_glyph_at(Position { x: 2, y: 1 }), '/');
+        assert!(state.tile_is_walkable(Position { x: 2, y: 1 }));
+    }
+
+    #[test]
+    fn pending_interaction_blocks_non_choice_commands_until_closed() {
+        let mut state = GameState::new(MapBounds { width: 4, height: 3 });
+        state.options.interactive_sites = true;
+        state.player.position = Position { x: 1, y: 1 };
+        state.map_rows = vec!["....".to_string(), "....".to_string(), "....".to_string()];
+        state.city_map_rows = state.map_rows.clone();
+        state.map_binding.semantic = MapSemanticKind::City;
+        state.site_grid = vec![TileSiteCell::default(); 12];
+        state.city_site_grid = state.site_grid.clone();
+        state.site_grid[4 + 2].aux = SITE_AUX_SERVICE_SHOP;
+        state.city_site_grid[4 + 2].aux = SITE_AUX_SERVICE_SHOP;
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Move(Direction::East), &mut rng);
+        assert_eq!(state.pending_site_interaction, Some(SiteInteractionKind::Shop));
+
+        let out_pending = step(&mut state, Command::Move(Direction::West), &mut rng);
+        assert_eq!(state.pending_site_interaction, Some(SiteInteractionKind::Shop));
+        assert_eq!(state.player.position, Position { x: 2, y: 1 });
+        assert!(out_pending.events.iter().any(|event| matches!(
+            event,
+            Event::LegacyHandled { token, note, .. }
+                if token == "interaction" && note.contains("prompt active")
+        )));
+
+        let out_close = step(&mut state, Command::Legacy { token: "q".to_string() }, &mut rng);
+        assert_eq!(state.pending_site_interaction, None);
+        assert!(out_close.events.iter().any(|event| matches!(
+            event,
+            Event::LegacyHandled { token, note, .. }
+                if token == "interaction" && note.contains("closed")
+        )));
+
+        let out_move = step(&mut state, Command::Move(Direction::West), &mut rng);
+        assert_eq!(state.player.position, Position { x: 1, y: 1 });
+        assert!(out_move.events.iter().any(|event| matches!(
+            event,
+            Event::Moved { from, to }
+                if *from == Position { x: 2, y: 1 } && *to == Position { x: 1, y: 1 }
+        )));
+    }
+
+    #[test]
+    fn pending_interaction_hint_is_not_spammed_in_log() {
+        let mut state = GameState::new(MapBounds { width: 4, height: 3 });
+        state.options.interactive_sites = true;
+        state.player.position = Position { x: 1, y: 1 };
+        state.map_rows = vec!["....".to_string(), "....".to_string(), "....".to_string()];
+        state.city_map_rows = state.map_rows.clone();
+        state.map_binding.semantic = MapSemanticKind::City;
+        state.site_grid = vec![TileSiteCell::default(); 12];
+        state.city_site_grid = state.site_grid.clone();
+        state.site_grid[4 + 2].aux = SITE_AUX_SERVICE_SHOP;
+        state.city_site_grid[4 + 2].aux = SITE_AUX_SERVICE_SHOP;
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Move(Direction::East), &mut rng);
+        assert_eq!(state.pending_site_interaction, Some(SiteInteractionKind::Shop));
+
+        let _ = step(&mut state, Command::Move(Direction::West), &mut rng);
+        let _ = step(&mut state, Command::Move(Direction::West), &mut rng);
+        let _ = step(&mut state, Command::Move(Direction::West), &mut rng);
+
+        let hint_count = state
+            .log
+            .iter()
+            .filter(|line| line.contains("Site prompt active: choose a bracketed option"))
+            .count();
+        assert_eq!(hint_count, 0);
+    }
+
+    #[test]
+    fn entering_interactive_site_does_not_log_menu_prompt_lines() {
+        let mut state = GameState::new(MapBounds { width: 4, height: 3 });
+        state.options.interactive_sites = true;
+        state.player.position = Position { x: 1, y: 1 };
+        state.map_rows = vec!["....".to_string(), "....".to_string(), "....".to_string()];
+        state.city_map_rows = state.map_rows.clone();
+        state.map_binding.semantic = MapSemanticKind::City;
+        state.site_grid = vec![TileSiteCell::default(); 12];
+        state.city_site_grid = state.site_grid.clone();
+        state.site_grid[4 + 1].aux = SITE_AUX_SERVICE_TEMPLE;
+        state.city_site_grid[4 + 1].aux = SITE_AUX_SERVICE_TEMPLE;
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Legacy { token: ">".to_string() }, &mut rng);
+
+        assert_eq!(state.pending_site_interaction, Some(SiteInteractionKind::Temple));
+        assert!(state.log.iter().all(|line| {
+            !line.contains("Temple: [")
+                && !line.contains("Site prompt active:")
+                && !line.contains("Temple prompt active:")
+        }));
+    }
+
+    #[test]
+    fn invalid_modal_input_does_not_append_prompt_hint_noise_to_timeline() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.pending_site_interaction = Some(SiteInteractionKind::Temple);
+        let before_len = state.log.len();
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Move(Direction::West), &mut rng);
+
+        assert_eq!(state.log.len(), before_len);
+        assert!(state.log.iter().all(|line| !line.contains("prompt active")));
+    }
+
+    #[test]
+    fn sanitize_legacy_prompt_noise_preserves_real_outcomes() {
+        let mut log = vec![
+            "You move.".to_string(),
+            "Site prompt active: choose a bracketed option, or press q/x to close.".to_string(),
+            "Wish text: Victrix_".to_string(),
+            "Dropped ration.".to_string(),
+        ];
+
+        sanitize_legacy_prompt_noise(&mut log);
+
+        assert_eq!(log, vec!["You move.".to_string(), "Dropped ration.".to_string()]);
+    }
+
+    #[test]
+    fn interactive_site_menu_accepts_letter_alias_choice() {
+        let mut state = GameState::new(MapBounds { width: 4, height: 3 });
+        state.options.interactive_sites = true;
+        state.player.position = Position { x: 1, y: 1 };
+        state.map_rows = vec!["....".to_string(), "....".to_string(), "....".to_string()];
+        state.city_map_rows = state.map_rows.clone();
+        state.map_binding.semantic = MapSemanticKind::City;
+        state.site_grid = vec![TileSiteCell::default(); 12];
+        state.city_site_grid = state.site_grid.clone();
+        state.site_grid[4 + 2].aux = SITE_AUX_SERVICE_SHOP;
+        state.city_site_grid[4 + 2].aux = SITE_AUX_SERVICE_SHOP;
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Move(Direction::East), &mut rng);
+        let gold_before = state.gold;
+        let out = step(&mut state, Command::Legacy { token: "r".to_string() }, &mut rng);
+
+        assert!(state.gold < gold_before);
+        assert!(state.player.inventory.iter().any(|item| item.name == "food ration"));
+        assert_eq!(state.pending_site_interaction, Some(SiteInteractionKind::Shop));
+        assert!(out.events.iter().any(|event| matches!(
+            event,
+            Event::LegacyHandled { token, note, .. }
+                if token == "interaction" && note.contains("Selected option 1")
+        )));
+    }
+
+    #[test]
+    fn trap_triggers_and_can_be_disarmed() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 9 });
+        let mut rng = FixedRng::new(vec![]);
+        let trap_pos = Position { x: state.player.position.x + 1, y: state.player.position.y };
+        state.traps = vec![Trap {
+            id: 99,
+            position: trap_pos,
+            damage: 2,
+            effect_id: "poison".to_string(),
+            armed: true,
+            discovered: false,
+        }];
+
+        let _ = step(&mut state, Command::Move(Direction::East), &mut rng);
+        assert!(state.player.stats.hp < state.player.stats.max_hp);
+        assert!(state.status_effects.iter().any(|effect| effect.id == "poison"));
+
+        state.player.position = Position { x: trap_pos.x - 1, y: trap_pos.y };
+        state.traps[0].armed = true;
+        let _ = step(&mut state, Command::Legacy { token: "D".to_string() }, &mut rng);
+        assert!(!state.traps[0].armed);
+    }
+
+    #[test]
+    fn wolf_pack_spawns_clustered_and_keeps_together() {
+        let mut state = GameState::new(MapBounds { width: 20, height: 20 });
+        state.player.position = Position { x: 0, y: 0 };
+        let center = Position { x: 10, y: 10 };
+        let mut rng = FixedRng::new(vec![3, 0, 5, 1]);
+
+        let ids = state.spawn_pack("wolf", center, 4, &mut rng);
+        assert_eq!(ids.len(), 4);
+        let pack: Vec<&Monster> =
+            state.monsters.iter().filter(|monster| ids.contains(&monster.id)).collect();
+        assert!(pack.iter().all(|wolf| wolf.pack_id == Some(ids[0])));
+        assert!(pack.iter().all(|wolf| {
+            (wolf.position.x - center.x).abs() <= PACK_SPAWN_RADIUS
+                && (wolf.position.y - center.y).abs() <= PACK_SPAWN_RADIUS
+        }));
+        let mut positions: Vec<Position> = pack.iter().map(|wolf| wolf.position).collect();
+        positions.sort_by_key(|pos| (pos.y, pos.x));
+        positions.dedup();
+        assert_eq!(positions.len(), 4);
+
+        let straggler = state.monsters.iter().position(|m| m.id == ids[1]).expect("straggler");
+        state.monsters[straggler].position = Position { x: 10, y: 13 };
+        for other in state.monsters.iter_mut().filter(|m| m.id != ids[1]) {
+            other.position.y -= 4;
+        }
+        let from = state.monsters[straggler].position;
+        let next = pack_cohesive_step(&state, straggler, Position { x: 10, y: 14 });
+        assert_eq!(next.y, from.y - 1);
+    }
+
+    fn state_beside_thief(gold: i32) -> GameState {
+        let mut state = GameState::new(MapBounds { width: 9, height: 3 });
+        state.player.position = Position { x: 4, y: 1 };
+        state.gold = gold;
+        state.spawn_monster(
+            "sneak thief",
+            Position { x: 5, y: 1 },
+            Stats { hp: 6, max_hp: 6, attack_min: 1, attack_max: 1, defense: 0, weight: 60 },
+        );
+        state
+    }
+
+    #[test]
+    fn thief_steals_a_quarter_of_the_purse_and_flees() {
+        let mut state = state_beside_thief(100);
+        assert_eq!(state.monsters[0].special_attacks, vec![SpecialAttack::Steal]);
+        let behavior = state.monsters[0].behavior;
+        let mut rng = FixedRng::new(vec![]);
+
+        let out = step(&mut state, Command::Wait, &mut rng);
+        assert_eq!(state.gold, 75);
+        assert!(out.events.iter().any(|event| matches!(
+            event,
+            Event::EconomyUpdated { source, gold: 75, .. } if source == "theft"
+        )));
+        assert!(state.log.iter().any(|line| line.contains("steals 25 gold pieces and flees")));
+
+        let _ = step(&mut state, Command::Wait, &mut rng);
+        assert!(state.monsters[0].position.x > 5);
+
+        for _ in 0..THIEF_FLIGHT_TURNS {
+            let _ = step(&mut state, Command::Wait, &mut rng);
+        }
+        assert!(state.monsters[0].status_effects.iter().all(|effect| effect.id != "fleeing"));
+        assert_eq!(state.monsters[0].behavior, behavior);
+        assert!(!state.monsters[0].on_death_drops.is_empty());
+    }
+
+    #[test]
+    fn thief_takes_an_unequipped_item_from_an_empty_purse() {
+        let mut state = state_beside_thief(0);
+        state.player.inventory.push(Item::basic(40, "silver locket"));
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Wait, &mut rng);
+        assert!(state.player.inventory.iter().all(|item| item.id != 40));
+        assert!(state.monsters[0].on_death_drops.iter().any(|item| item.id == 40));
+    }
+
+    #[test]
+    fn slain_thief_drops_stolen_gold_back_into_the_purse() {
+        let mut state = state_beside_thief(100);
+        let mut rng = FixedRng::new(vec![]);
+        let _ = step(&mut state, Command::Wait, &mut rng);
+        assert_eq!(state.gold, 75);
+
+        state.monsters[0].position = Position { x: 5, y: 1 };
+        state.monsters[0].stats.hp = 1;
+        let _ = step(&mut state, Command::Attack(Direction::East), &mut rng);
+        assert!(state.monsters.is_empty());
+        let _ = step(&mut state, Command::Move(Direction::East), &mut rng);
+        let inventory_before = state.player.inventory.len();
+        let out = step(&mut state, Command::Pickup, &mut rng);
+        assert_eq!(state.gold, 100);
+        assert_eq!(state.player.inventory.len(), inventory_before);
+        assert!(out.events.iter().any(|event| matches!(
+            event,
+            Event::EconomyUpdated { source, gold: 100, .. } if source == "pickup"
+        )));
+    }
+
+    #[test]
+    fn gold_theft_trap_lifts_a_quarter_of_the_purse() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 3 });
+        state.player.position = Position { x: 5, y: 1 };
+        state.gold = 100;
+        state.traps = vec![Trap {
+            id: 8,
+            position: Position { x: 6, y: 1 },
+            damage: 0,
+            effect_id: "gold_theft".to_string(),
+            armed: true,
+            discovered: false,
+        }];
+        let mut rng = FixedRng::new(vec![]);
+        let _ = step(&mut state, Command::Move(Direction::East), &mut rng);
+        assert_eq!(state.gold, 75);
+    }
+
+    #[test]
+    fn quaffing_an_unknown_potion_identifies_its_copies() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        let murky = Item {
+            name: "murky potion".to_string(),
+            family: ItemFamily::Potion,
+            legacy_id: 7,
+            usef: "I_HEAL".to_string(),
+            ..Item::default()
+        };
+        state.player.inventory.push(Item { id: 1, ..murky.clone() });
+        state.player.inventory.push(Item { id: 2, ..murky });
+        state.player.inventory.push(Item {
+            id: 3,
+            name: "fizzy potion".to_string(),
+            family: ItemFamily::Potion,
+            legacy_id: 8,
+            ..Item::default()
+        });
+        let interaction = ItemPromptInteraction {
+            context: ItemPromptContext::Quaff,
+            filter: ItemPromptFilter::Families(vec![ItemFamily::Potion]),
+            prompt: "Quaff which potion?".to_string(),
+        };
+        let mut events = Vec::new();
+
+        let _ = apply_item_prompt_selection(
+            &mut state,
+            &interaction,
+            1,
+            &mut events,
+            &mut FixedRng::new(vec![]),
+        );
+
+        assert_eq!(state.player.inventory.len(), 2);
+        assert!(state.player.inventory.iter().find(|item| item.id == 2).is_some_and(|i| i.known));
+        assert!(state.player.inventory.iter().find(|item| item.id == 3).is_some_and(|i| !i.known));
+    }
+
+    #[test]
+    fn zapping_self_heal_wand_heals_and_spends_a_charge() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.player.stats.hp = 5;
+        state.player.inventory.push(Item {
+            id: 1,
+            name: "wand of healing".to_string(),
+            family: ItemFamily::Stick,
+            usef: "I_HEAL".to_string(),
+            charge: 3,
+            ..Item::default()
+        });
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Zap { item_id: 1 }, &mut rng);
+
+        assert!(state.player.stats.hp > 5);
+        assert_eq!(state.player.inventory[0].charge, 2);
+        assert!(state.pending_targeting_interaction.is_none());
+    }
+
+    #[test]
+    fn zapping_offensive_wand_opens_targeting() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.player.inventory.push(Item {
+            id: 1,
+            name: "wand of fire".to_string(),
+            family: ItemFamily::Stick,
+            usef: "I_FIREBOLT".to_string(),
+            charge: 3,
+            ..Item::default()
+        });
+        let hp = state.player.stats.hp;
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Zap { item_id: 1 }, &mut rng);
+
+        assert!(state.pending_targeting_interaction.is_some());
+        assert_eq!(state.player.stats.hp, hp);
+        assert_eq!(state.clock.turn, 0);
+        let wand = state.player.inventory.iter().find(|item| item.id == 1).expect("wand kept");
+        assert_eq!(wand.charge, 2);
+    }
+
+    #[test]
+    fn combat_variance_reshapes_damage_spread() {
+        let sample = |variance: CombatVariance| {
+            let mut rng = DeterministicRng::seeded(0xC0FFEE);
+            let rolls: Vec<i32> =
+                (0..4000).map(|_| roll_combat_damage(&mut rng, 1, 11, variance)).collect();
+            let mean = rolls.iter().sum::<i32>() as f64 / rolls.len() as f64;
+            let spread = rolls.iter().map(|roll| (*roll as f64 - 6.0).abs()).sum::<f64>()
+                / rolls.len() as f64;
+            assert!(rolls.iter().all(|roll| (1..=11).contains(roll)));
+            (mean, spread)
+        };
+
+        let (uniform_mean, uniform_spread) = sample(CombatVariance::Uniform);
+        let (average_mean, average_spread) = sample(CombatVariance::Average);
+        let (extreme_mean, extreme_spread) = sample(CombatVariance::Extreme);
+
+        assert!(average_spread + 0.5 < uniform_spread, "{average_spread} vs {uniform_spread}");
+        assert!(extreme_spread > uniform_spread + 0.5, "{extreme_spread} vs {uniform_spread}");
+        assert!((average_mean - 6.0).abs() < 0.2, "averaging stays unbiased: {average_mean}");
+        let average = CombatVariance::Average;
+        assert_eq!(roll_combat_damage(&mut FixedRng::new(vec![1, 2]), 1, 11, average), 1);
+        assert_eq!(roll_combat_damage(&mut FixedRng::new(vec![2, 1]), 1, 11, average), 2);
+        for mean in [uniform_mean, average_mean, extreme_mean] {
+            assert!((mean - 6.0).abs() < 0.75, "mean {mean} drifted from the midpoint");
+        }
+        assert_eq!(RuntimeOptions::default().combat_variance, CombatVariance::Uniform);
+    }
+
+    #[test]
+    fn empty_consumables_report_nothing_happens_uniformly() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.player.inventory.push(Item {
+            id: 1,
+            name: "empty vial".to_string(),
+            family: ItemFamily::Potion,
+            ..Item::default()
+        });
+        state.player.inventory.push(Item {
+            id: 2,
+            name: "blank scroll".to_string(),
+            family: ItemFamily::Scroll,
+            usef: "I_NOTHING".to_string(),
+            ..Item::default()
+        });
+        state.player.inventory.push(Item {
+            id: 3,
+            name: "wand of healing".to_string(),
+            family: ItemFamily::Stick,
+            usef: "I_HEAL".to_string(),
+            charge: 0,
+            ..Item::default()
+        });
+        state.player.inventory.push(Item {
+            id: 4,
+            name: "walking stick".to_string(),
+            family: ItemFamily::Stick,
+            usef: "I_NOTHING".to_string(),
+            charge: 2,
+            ..Item::default()
+        });
+        state.player.inventory.push(Item {
+            id: 5,
+            name: "wand of fire bolts".to_string(),
+            family: ItemFamily::Stick,
+            usef: "I_FIREBOLT".to_string(),
+            charge: 0,
+            ..Item::default()
+        });
+        state.player.inventory.push(Item {
+            id: 6,
+            name: "stale crust".to_string(),
+            family: ItemFamily::Food,
+            ..Item::default()
+        });
+        state.player.inventory.push(Item {
+            id: 7,
+            name: "dull orb".to_string(),
+            family: ItemFamily::Artifact,
+            ..Item::default()
+        });
+        let mut events = Vec::new();
+        let mut rng = FixedRng::new(vec![]);
+        let quaff = ItemPromptInteraction {
+            context: ItemPromptContext::Quaff,
+            filter: ItemPromptFilter::Families(vec![ItemFamily::Potion]),
+            prompt: "Quaff which potion?".to_string(),
+        };
+        let read = ItemPromptInteraction {
+            context: ItemPromptContext::Read,
+            filter: ItemPromptFilter::Families(vec![ItemFamily::Scroll]),
+            prompt: "Read which scroll?".to_string(),
+        };
+        let eat = ItemPromptInteraction {
+            context: ItemPromptContext::Eat,
+            filter: ItemPromptFilter::Families(vec![ItemFamily::Food]),
+            prompt: "Eat which item?".to_string(),
+        };
+        let activate = ItemPromptInteraction {
+            context: ItemPromptContext::ActivateArtifact,
+            filter: ItemPromptFilter::Families(vec![ItemFamily::Artifact]),
+            prompt: "Activate which artifact?".to_string(),
+        };
+
+        let quaffed = apply_item_prompt_selection(&mut state, &quaff, 1, &mut events, &mut rng);
+        let read_note = apply_item_prompt_selection(&mut state, &read, 2, &mut events, &mut rng);
+        let depleted = zap_stick(&mut state, 3, &mut rng, &mut events);
+        let plain = zap_stick(&mut state, 4, &mut rng, &mut events);
+        let spent_bolts = zap_stick(&mut state, 5, &mut rng, &mut events);
+        let eaten = apply_item_prompt_selection(&mut state, &eat, 6, &mut events, &mut rng);
+        let activated =
+            apply_item_prompt_selection(&mut state, &activate, 7, &mut events, &mut rng);
+
+        for note in [&quaffed, &read_note, &depleted, &plain, &spent_bolts, &eaten, &activated] {
+            assert_eq!(note, "Nothing happens.");
+        }
+        let remaining: Vec<(u32, i32)> =
+            state.player.inventory.iter().map(|item| (item.id, item.charge)).collect();
+        assert_eq!(remaining, vec![(3, 0), (4, 2), (5, 0)], "consumables used up, sticks kept");
+        assert!(state.pending_targeting_interaction.is_none(), "a spent wand never fires");
+        assert_eq!(state.progression.quest_state, LegacyQuestState::NotStarted);
+    }
+
+    #[test]
+    fn thrown_poison_potion_shatters_into_a_cloud_over_a_monster_cluster() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 5 });
+        state.player.position = Position { x: 1, y: 2 };
+        let weak =
+            Stats { hp: 30, max_hp: 30, attack_min: 0, attack_max: 0, defense: 0, weight: 60 };
+        state.spawn_monster("rat", Position { x: 5, y: 2 }, weak);
+        state.spawn_monster("rat", Position { x: 6, y: 2 }, weak);
+        state.spawn_monster("rat", Position { x: 5, y: 3 }, weak);
+        state.spawn_monster("rat", Position { x: 8, y: 4 }, weak);
+        for monster in &mut state.monsters {
+            monster.behavior = MonsterBehavior::Social;
+            monster.faction = Faction::Neutral;
+        }
+        let mut potion = instantiate_item_from_name(5, "potion of neutralize poison");
+        potion.blessing = -1;
+        potion.name = "potion of poison".to_string();
+        state.player.inventory.push(potion);
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Throw { item_id: 5 }, &mut rng);
+        assert!(state.pending_targeting_interaction.is_some());
+        for _ in 0..4 {
+            let _ = step(&mut state, Command::Move(Direction::East), &mut rng);
+        }
+        let _ = step(&mut state, Command::Legacy { token: ".".to_string() }, &mut rng);
+
+        let poisoned = |idx: usize| {
+            state.monsters[idx].status_effects.iter().any(|effect| effect.id == "poison")
+        };
+        assert!(poisoned(0) && poisoned(1) && poisoned(2));
+        assert!(!poisoned(3));
+        assert_eq!(state.monsters[0].stats.hp, 30 - MONSTER_POISON_MAGNITUDE);
+        assert_eq!(state.monsters[3].stats.hp, 30);
+        assert!(state.player.inventory.is_empty());
+        assert!(state.ground_items.is_empty());
+        assert!(state.log.iter().any(|line| line.contains("potion of poison shatters")));
+    }
+
+    #[test]
+    fn thrown_liquid_fire_burns_the_cluster_and_sleep_lulls_it() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 5 });
+        state.player.position = Position { x: 1, y: 2 };
+        let stats =
+            Stats { hp: 10, max_hp: 10, attack_min: 0, attack_max: 0, defense: 0, weight: 60 };
+        state.spawn_monster("rat", Position { x: 5, y: 2 }, stats);
+        state.spawn_monster("rat", Position { x: 6, y: 2 }, Stats { hp: 4, ..stats });
+        state.spawn_monster("rat", Position { x: 8, y: 4 }, stats);
+        for monster in &mut state.monsters {
+            monster.behavior = MonsterBehavior::Social;
+            monster.faction = Faction::Neutral;
+        }
+        let far_id = state.monsters[2].id;
+        let mut sleeper = state.clone();
+        state.player.inventory.push(instantiate_item_from_name(5, "potion of liquid fire"));
+        let mut rng = FixedRng::new(vec![]);
+
+        let throw_east = |state: &mut GameState, rng: &mut FixedRng| {
+            let _ = step(state, Command::Throw { item_id: 5 }, rng);
+            for _ in 0..4 {
+                let _ = step(state, Command::Move(Direction::East), rng);
+            }
+            step(state, Command::Legacy { token: ".".to_string() }, rng)
+        };
+        let out = throw_east(&mut state, &mut rng);
+        assert_eq!(state.monsters.len(), 2);
+        assert_eq!(state.monsters[0].stats.hp, 10 - LIQUID_FIRE_DAMAGE);
+        assert_eq!(state.monsters[1].id, far_id);
+        assert_eq!(state.monsters[1].stats.hp, 10);
+        assert!(out.events.iter().any(|event| matches!(event, Event::MonsterDefeated { .. })));
+
+        let mut potion = instantiate_item_from_name(5, "potion of alertness");
+        potion.blessing = -1;
+        sleeper.player.inventory.push(potion);
+        let _ = throw_east(&mut sleeper, &mut FixedRng::new(vec![]));
+        assert_eq!(sleeper.monsters[0].awareness, Awareness::Asleep);
+        assert_eq!(sleeper.monsters[1].awareness, Awareness::Asleep);
+        assert_ne!(sleeper.monsters[2].awareness, Awareness::Asleep);
+        assert!(sleeper.log.iter().any(|line| line.contains("falls asleep")));
+    }
+
+    #[test]
+    fn dipping_sword_in_poison_coats_it_and_consumes_the_potion() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.player.inventory.push(Item {
+            id: 1,
+            name: "short sword".to_string(),
+            family: ItemFamily::Weapon,
+            ..Item::default()
+        });
+        state.player.equipment.weapon_hand = Some(1);
+        let mut potion = instantiate_item_from_name(2, "potion of neutralize poison");
+        potion.blessing = -2;
+        assert_eq!(potion.level, 2);
+        state.player.inventory.push(potion);
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Dip { item_id: 1, potion_id: 2 }, &mut rng);
+        assert!(!state.player.inventory.iter().any(|item| item.id == 2));
+        assert_eq!(state.player.inventory[0].poison_doses, POISON_COATING_DOSES);
+        assert_eq!(state.player.inventory[0].poison_potency, 4);
+        assert!(state.log.iter().any(|line| line.contains("drips with venom")));
+
+        state.spawn_monster(
+            "rat",
+            Position { x: 2, y: 1 },
+            Stats { hp: 30, max_hp: 30, attack_min: 0, attack_max: 0, defense: 0, weight: 60 },
+        );
+        let mut plain = state.clone();
+        plain.player.inventory[0].poison_doses = 0;
+        let _ = step(&mut state, Command::Attack(Direction::East), &mut FixedRng::new(vec![]));
+        let _ = step(&mut plain, Command::Attack(Direction::East), &mut FixedRng::new(vec![]));
+        assert_eq!(
+            state.monsters[0].stats.hp + 4 + MONSTER_POISON_MAGNITUDE,
+            plain.monsters[0].stats.hp
+        );
+        assert!(state.monsters[0].status_effects.iter().any(|effect| effect.id == "poison"));
+        assert!(plain.monsters[0].status_effects.is_empty());
+        assert_eq!(state.player.inventory[0].poison_doses, POISON_COATING_DOSES - 1);
+    }
+
+    #[test]
+    fn dipping_a_coated_blade_in_curing_rinses_off_the_venom() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.inventory.push(Item {
+            id: 1,
+            name: "short sword".to_string(),
+            family: ItemFamily::Weapon,
+            poison_doses: POISON_COATING_DOSES,
+            poison_potency: 4,
+            ..Item::default()
+        });
+        state.player.inventory.push(instantiate_item_from_name(2, "potion of curing"));
+        state.player.inventory.push(instantiate_item_from_name(3, "potion of healing"));
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Dip { item_id: 1, potion_id: 3 }, &mut rng);
+        assert_eq!(state.player.inventory[0].poison_doses, POISON_COATING_DOSES);
+        assert!(state.log.iter().any(|line| line.contains("nothing seems to happen")));
+
+        let _ = step(&mut state, Command::Dip { item_id: 1, potion_id: 2 }, &mut rng);
+        assert_eq!(state.player.inventory.len(), 1);
+        assert_eq!(state.player.inventory[0].poison_doses, 0);
+        assert_eq!(state.player.inventory[0].poison_potency, 0);
+        assert!(state.log.iter().any(|line| line.contains("rinsed clean")));
+    }
+
+    #[test]
+    fn poisoned_monster_loses_hp_each_turn_and_can_die_from_it() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 3 });
+        state.player.position = Position { x: 0, y: 1 };
+        state.spawn_monster(
+            "rat",
+            Position { x: 8, y: 1 },
+            Stats { hp: 5, max_hp: 5, attack_min: 0, attack_max: 0, defense: 0, weight: 60 },
+        );
+        let rat_id = state.monsters[0].id;
+        state.monsters[0].status_effects.push(StatusEffect {
+            id: "poison".to_string(),
+            remaining_turns: MONSTER_POISON_TURNS,
+            magnitude: MONSTER_POISON_MAGNITUDE,
+        });
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Wait, &mut rng);
+        assert_eq!(state.monsters[0].stats.hp, 3);
+        let _ = step(&mut state, Command::Wait, &mut rng);
+        assert_eq!(state.monsters[0].stats.hp, 2);
+        let _ = step(&mut state, Command::Wait, &mut rng);
+        assert_eq!(state.monsters[0].stats.hp, 1);
+
+        let out = step(&mut state, Command::Wait, &mut rng);
+        assert!(state.monsters.is_empty());
+        assert_eq!(state.monsters_defeated, 1);
+        assert!(
+            out.events
+                .contains(&Event::MonsterDefeated { monster_id: rat_id, name: "rat".to_string() })
+        );
+        assert!(state.log.iter().any(|line| line.contains("succumbs to poison")));
+    }
+
+    #[test]
+    fn armed_humanoids_drop_the_gear_they_carried() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 3 });
+        let mut rng = FixedRng::new(vec![1, 0, 100]);
+        let gear = state.roll_equipped_drops("bandit", &mut rng);
+        assert_eq!(gear.len(), 1);
+        assert_eq!(gear[0].family, ItemFamily::Weapon);
+        assert!(state.roll_equipped_drops("giant rat", &mut FixedRng::new(vec![1, 0])).is_empty());
+
+        state.spawn_monster(
+            "bandit",
+            Position { x: 3, y: 1 },
+            Stats { hp: 5, max_hp: 5, attack_min: 1, attack_max: 2, defense: 0, weight: 60 },
+        );
+        state.monsters[0].on_death_drops = gear;
+        let mut events = Vec::new();
+        let _ = remove_monster_with_drops(&mut state, 0, &mut events);
+        assert!(state.ground_items.iter().any(|ground| ground.position == Position { x: 3, y: 1 }
+            && ground.item.family == ItemFamily::Weapon));
+        assert!(state.log.iter().any(|line| line.starts_with("bandit drops")));
+    }
+
+    #[test]
+    fn slain_brigand_drops_the_weapon_from_its_loot_table() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        let next_item_id = state.next_item_id;
+        state.spawn_monster(
+            "brigand",
+            Position { x: 3, y: 1 },
+            Stats { hp: 1, max_hp: 1, attack_min: 0, attack_max: 0, defense: 0, weight: 60 },
+        );
+        assert!(state.monsters[0].on_death_drops.is_empty());
+        assert_eq!(state.next_item_id, next_item_id, "a bare spawn hands out no gear");
+
+        let brigand_id = state.spawn_equipped_monster(
+            "brigand",
+            Position { x: 2, y: 1 },
+            Stats { hp: 1, max_hp: 1, attack_min: 0, attack_max: 0, defense: 0, weight: 60 },
+            &mut FixedRng::new(vec![1, 100]),
+        );
+        let weapon_id = legacy_item_family_base_id(ItemFamily::Weapon) + 27;
+        let gear = &state.monsters[1].on_death_drops;
+        assert_eq!(gear.len(), 1);
+        assert_eq!(gear[0].legacy_id, weapon_id, "brigands carry their legacy start weapon");
+
+        let out = step(&mut state, Command::Attack(Direction::East), &mut FixedRng::new(vec![]));
+        assert!(out.events.contains(&Event::MonsterDefeated {
+            monster_id: brigand_id,
+            name: "brigand".to_string()
+        }));
+        assert!(state.ground_items.iter().any(|ground| ground.position == Position { x: 2, y: 1 }
+            && ground.item.legacy_id == weapon_id));
+    }
+
+    #[test]
+    fn quaffed_poison_stacks_is_weakened_by_resistance_and_cured_by_healer() {
+        let poison_potion = Item {
+            id: 1,
+            name: "murky potion".to_string(),
+            family: ItemFamily::Potion,
+            usef: "I_NEUTRALIZE_POISON".to_string(),
+            blessing: -1,
+            ..Item::default()
+        };
+        let mut events = Vec::new();
+        let mut rng = FixedRng::new(vec![]);
+
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        let _ = apply_item_usef_effect(&mut state, &poison_potion, &mut rng, &mut events);
+        assert_eq!(status_magnitude(&state, "poison"), POISON_POTION_MAGNITUDE);
+        let _ = apply_item_usef_effect(&mut state, &poison_potion, &mut rng, &mut events);
+        assert_eq!(status_magnitude(&state, "poison"), 2 * POISON_POTION_MAGNITUDE);
+
+        state.gold = 100;
+        let note = apply_site_interaction_choice(
+            &mut state,
+            SiteInteractionKind::Healer,
+            2,
+            &mut FixedRng::new(vec![]),
+            &mut events,
+            false,
+        );
+        assert!(note.contains("purges poison"));
+        assert!(state.status_effects.iter().all(|effect| effect.id != "poison"));
+
+        let mut resistant = GameState::new(MapBounds { width: 5, height: 5 });
+        resistant.resistances.poison = 3;
+        let _ = apply_item_usef_effect(&mut resistant, &poison_potion, &mut rng, &mut events);
+        assert_eq!(status_magnitude(&resistant, "poison"), POISON_POTION_MAGNITUDE - 3);
+        resistant.resistances.poison = POISON_POTION_MAGNITUDE as i16;
+        assert!(!apply_poison(&mut resistant, 4, POISON_POTION_MAGNITUDE));
+        assert_eq!(status_magnitude(&resistant, "poison"), POISON_POTION_MAGNITUDE - 3);
+
+        let mut immune = GameState::new(MapBounds { width: 5, height: 5 });
+        immune.immunities.poison = true;
+        let note = apply_item_usef_effect(&mut immune, &poison_potion, &mut rng, &mut events);
+        assert_eq!(note, "you shrug off the poison");
+        assert!(immune.status_effects.is_empty());
+    }
+
+    #[test]
+    fn poison_and_regeneration_net_out_each_turn_regardless_of_order() {
+        let poison = StatusEffect { id: "poison".to_string(), remaining_turns: 5, magnitude: 3 };
+        let regen = StatusEffect { id: "regen".to_string(), remaining_turns: 5, magnitude: 2 };
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.player.stats.max_hp = 20;
+        state.player.stats.hp = 10;
+        state.status_effects = vec![poison.clone(), regen.clone()];
+        let mut events = Vec::new();
+
+        let mut deltas = Vec::new();
+        for _ in 0..3 {
+            let before = state.player.stats.hp;
+            apply_status_effects(&mut state, &mut FixedRng::new(vec![]), &mut events);
+            deltas.push(state.player.stats.hp - before);
+        }
+        // Poison fades 3 -> 2 -> 1 against a steady 2 hp of regeneration.
+        assert_eq!(deltas, vec![-1, 0, 1]);
+
+        for order in [vec![poison.clone(), regen.clone()], vec![regen, poison]] {
+            let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+            state.player.stats.max_hp = 20;
+            state.player.stats.hp = 20;
+            state.status_effects = order;
+            apply_status_effects(&mut state, &mut FixedRng::new(vec![]), &mut events);
+            assert_eq!(state.player.stats.hp, 19);
+        }
+    }
+
+    #[test]
+    fn batched_haste_and_slow_cancel_each_other() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        state.apply_effects(&[
+            StatusEffect { id: "haste".to_string(), remaining_turns: 10, magnitude: 1 },
+            StatusEffect { id: "slow".to_string(), remaining_turns: 8, magnitude: 1 },
+        ]);
+        assert!(state.status_effects.is_empty());
+
+        state.apply_effects(&[StatusEffect {
+            id: "haste".to_string(),
+            remaining_turns: 10,
+            magnitude: 1,
+        }]);
+        state.apply_effects(&[StatusEffect {
+            id: "slow".to_string(),
+            remaining_turns: 8,
+            magnitude: 1,
+        }]);
+        assert!(state.status_effects.is_empty());
+    }
+
+    #[test]
+    fn batched_poisons_stack_into_one_effect_unless_immune() {
+        let mut state = GameState::new(MapBounds { width: 3, height: 3 });
+        let poison = |turns, magnitude| StatusEffect {
+            id: "poison".to_string(),
+            remaining_turns: turns,
+            magnitude,
+        };
+        state.apply_effects(&[poison(3, 2), poison(5, 1)]);
+        assert_eq!(state.status_effects, vec![poison(5, 3)]);
+
+        let mut immune = GameState::new(MapBounds { width: 3, height: 3 });
+        immune.immunities.poison = true;
+        immune.apply_effects(&[poison(3, 2)]);
+        assert!(immune.status_effects.is_empty());
+    }
+
+    #[test]
+    fn engraving_is_reported_when_returning_to_the_tile() {
+        let mut state = walled_room_state();
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ =
+            step(&mut state, Command::Engrave { text: "beware the stairs".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Move(Direction::East), &mut rng);
+        state.log.clear();
+        let _ = step(&mut state, Command::Move(Direction::West), &mut rng);
+
+        assert_eq!(state.player.position, Position { x: 1, y: 1 });
+        assert!(
+            state
+                .log
+                .iter()
+                .any(|line| line == "Something is engraved here: \"beware the stairs\".")
+        );
+    }
+
+    #[test]
+    fn engravings_fade_after_their_lifetime() {
+        let mut state = walled_room_state();
+        let mut rng = FixedRng::new(vec![]);
+        let _ = step(&mut state, Command::Engrave { text: "x marks".to_string() }, &mut rng);
+        assert!(state.engraving_at(Position { x: 1, y: 1 }).is_some());
+
+        state.clock.turn += ENGRAVING_LIFETIME_TURNS;
+        let _ = step(&mut state, Command::Wait, &mut rng);
+        assert!(state.engraving_at(Position { x: 1, y: 1 }).is_none());
+    }
+
+    #[test]
+    fn engravings_stay_on_the_map_they_were_made_on() {
+        let mut state = walled_room_state();
+        let mut rng = FixedRng::new(vec![]);
+        state.map_binding.map_id = 3;
+        let _ = step(&mut state, Command::Engrave { text: "level three".to_string() }, &mut rng);
+
+        state.map_binding.map_id = 4;
+        assert!(state.engraving_at(Position { x: 1, y: 1 }).is_none());
+        state.engrave_at(Position { x: 1, y: 1 }, "level four");
+
+        state.map_binding.map_id = 3;
+        let engraving = state.engraving_at(Position { x: 1, y: 1 }).expect("engraving");
+        assert_eq!(engraving.text, "level three");
+        assert_eq!(state.engravings.len(), 2);
+    }
+
+    fn walled_room_state() -> GameState {
+        state_with_rows(
+            &["######", "#....#", "#....#", "#....#", "######"],
+            Position { x: 1, y: 1 },
+        )
+    }
+
+    #[test]
+    fn critical_hit_and_lightning_knock_monster_into_open_tile() {
+        let mut state = walled_room_state();
+        state.player.stats.attack_min = 4;
+        state.player.stats.attack_max = 6;
+        state.spawn_monster(
+            "goblin",
+            Position { x: 2, y: 1 },
+            Stats { hp: 40, max_hp: 40, attack_min: 1, attack_max: 1, defense: 0, weight: 60 },
+        );
+        let mut events = Vec::new();
+
+        resolve_attack_command(
+            &mut state,
+            Direction::East,
+            &mut FixedRng::new(vec![6, 20]),
+            &mut events,
+        );
+        assert_eq!(state.monsters[0].position, Position { x: 3, y: 1 });
+        assert!(events.iter().any(|event| matches!(event, Event::MonsterMoved { .. })));
+
+        let action = PendingProjectileAction {
+            source_token: "z".to_string(),
+            turn_minutes: 0,
+            mode: ProjectileKind::LightningBolt,
+            item_id: None,
+            item_name: "lightning bolt".to_string(),
+            hit_bonus: 20,
+            damage_bonus: 0,
+            damage_min: 1,
+            damage_max: 1,
+            damage_type: ProjectileDamageType::Electricity,
+            max_range: 12,
+            allows_drop: false,
+        };
+        let target = state.monsters[0].position;
+        let _ = resolve_projectile_action(
+            &mut state,
+            &action,
+            target,
+            &mut events,
+            &mut FixedRng::new(vec![1, 1]),
+        );
+        assert_eq!(state.monsters[0].position, Position { x: 4, y: 1 });
+    }
+
+    #[test]
+    fn knockback_into_wall_deals_crush_damage() {
+        let mut state = walled_room_state();
+        state.player.position = Position { x: 3, y: 1 };
+        state.player.stats.attack_min = 4;
+        state.player.stats.attack_max = 6;
+        state.spawn_monster(
+            "goblin",
+            Position { x: 4, y: 1 },
+            Stats { hp: 40, max_hp: 40, attack_min: 1, attack_max: 1, defense: 0, weight: 60 },
+        );
+        let mut events = Vec::new();
+
+        resolve_attack_command(
+            &mut state,
+            Direction::East,
+            &mut FixedRng::new(vec![6, 20]),
+            &mut events,
+        );
+        let plain_hit = match events.first() {
+            Some(Event::Attacked { damage, .. }) => *damage,
+            other => panic!("expected melee hit, got {other:?}"),
+        };
+        assert_eq!(state.monsters[0].position, Position { x: 4, y: 1 });
+        assert_eq!(state.monsters[0].stats.hp, 40 - plain_hit - KNOCKBACK_CRUSH_DAMAGE);
+        assert!(state.log.iter().any(|line| line.contains("slammed into the wall")));
+
+        let hp_before = state.monsters[0].stats.hp;
+        resolve_attack_command(
+            &mut state,
+            Direction::East,
+            &mut FixedRng::new(vec![6, 1]),
+            &mut events,
+        );
+        // A top damage roll alone no longer triggers knockback; the crit roll does.
+        assert_eq!(state.monsters[0].stats.hp, hp_before - plain_hit);
     }
 
     #[test]
-    fn interactive_site_menu_accepts_letter_alias_choice() {
-        let mut state = GameState::new(MapBounds { width: 4, height: 3 });
-        state.options.interactive_sites = true;
-        state.player.position = Position { x: 1, y: 1 };
-        state.map_rows = vec!["....".to_string(), "....".to_string(), "....".to_string()];
-        state.city_map_rows = state.map_rows.clone();
-        state.map_binding.semantic = MapSemanticKind::City;
-        state.site_grid = vec![TileSiteCell::default(); 12];
-        state.city_site_grid = state.site_grid.clone();
-        state.site_grid[4 + 2].aux = SITE_AUX_SERVICE_SHOP;
-        state.city_site_grid[4 + 2].aux = SITE_AUX_SERVICE_SHOP;
+    fn reading_teleport_scroll_lands_on_walkable_tile() {
+        let mut state = walled_room_state();
+        state.player.inventory.push(Item {
+            id: 1,
+            name: "scroll of teleportation".to_string(),
+            family: ItemFamily::Scroll,
+            usef: "I_TELEPORT".to_string(),
+            ..Item::default()
+        });
         let mut rng = FixedRng::new(vec![]);
 
-        let _ = step(&mut state, Command::Move(Direction::East), &mut rng);
-        let gold_before = state.gold;
-        let out = step(&mut state, Command::Legacy { token: "r".to_string() }, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "r".to_string() }, &mut rng);
+        let out = step(&mut state, Command::Legacy { token: "a".to_string() }, &mut rng);
 
-        assert!(state.gold < gold_before);
-        assert!(state.player.inventory.iter().any(|item| item.name == "food ration"));
-        assert_eq!(state.pending_site_interaction, Some(SiteInteractionKind::Shop));
+        assert!(state.tile_is_walkable(state.player.position));
         assert!(out.events.iter().any(|event| matches!(
             event,
-            Event::LegacyHandled { token, note, .. }
-                if token == "interaction" && note.contains("Selected option 1")
+            Event::Teleported { from, to }
+                if *from == Position { x: 1, y: 1 } && *to == state.player.position
         )));
     }
 
     #[test]
-    fn trap_triggers_and_can_be_disarmed() {
-        let mut state = GameState::new(MapBounds { width: 9, height: 9 });
-        let mut rng = FixedRng::new(vec![]);
-        let trap_pos = Position { x: state.player.position.x + 1, y: state.player.position.y };
+    fn stepping_on_teleport_trap_relocates_player() {
+        let mut state = walled_room_state();
+        let trap_pos = Position { x: 2, y: 1 };
         state.traps = vec![Trap {
-            id: 99,
+            id: 5,
             position: trap_pos,
-            damage: 2,
-            effect_id: "poison".to_string(),
+            damage: 0,
+            effect_id: "teleport".to_string(),
             armed: true,
+            discovered: false,
         }];
+        let mut rng = FixedRng::new(vec![1, 4, 3]);
 
-        let _ = step(&mut state, Command::Move(Direction::East), &mut rng);
-        assert!(state.player.stats.hp < state.player.stats.max_hp);
-        assert!(state.status_effects.iter().any(|effect| effect.id == "poison"));
+        let out = step(&mut state, Command::Move(Direction::East), &mut rng);
 
-        state.player.position = Position { x: trap_pos.x - 1, y: trap_pos.y };
-        state.traps[0].armed = true;
-        let _ = step(&mut state, Command::Legacy { token: "D".to_string() }, &mut rng);
-        assert!(!state.traps[0].armed);
+        assert_eq!(state.player.position, Position { x: 4, y: 3 });
+        assert!(
+            out.events.contains(&Event::Teleported { from: trap_pos, to: Position { x: 4, y: 3 } })
+        );
+        assert!(state.log.iter().any(|line| line.contains("whisks you away")));
     }
 
     #[test]
@@ -17218,6 +26859,7 @@ mod tests {
             damage: 5,
             effect_id: "acid".to_string(),
             armed: true,
+            discovered: false,
         }];
         let mut rng = FixedRng::new(vec![]);
 
@@ -17276,6 +26918,26 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn active_combat_step_tracks_cursor_after_rotation() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 9 });
+        let mut rng = FixedRng::new(vec![]);
+        assert_eq!(state.active_combat_step(), CombatStep::default());
+
+        let _ = step(&mut state, Command::Legacy { token: "F".to_string() }, &mut rng);
+        assert_eq!(state.combat_sequence.len(), 2);
+        assert_eq!(state.active_combat_step(), state.combat_sequence[state.combat_sequence_cursor]);
+        assert_eq!(state.active_combat_step().maneuver, CombatManeuver::Lunge);
+
+        let _ = next_combat_step(&mut state);
+        assert_eq!(state.combat_sequence_cursor, 1);
+        assert_eq!(state.active_combat_step(), state.combat_sequence[1]);
+        assert_eq!(
+            state.combat_sequence_preview(),
+            vec![state.combat_sequence[1].clone(), state.combat_sequence[0].clone()]
+        );
+    }
+
     #[test]
     fn spell_prompt_is_non_advancing_until_enter_commit() {
         let mut state = GameState::new(MapBounds { width: 9, height: 9 });
@@ -17353,6 +27015,193 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn high_iq_unarmored_caster_never_fizzles() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 9 });
+        let mut rng = DeterministicRng::seeded(0x5EED);
+        for spell in &mut state.spellbook.spells {
+            spell.known = true;
+        }
+        state.attributes.iq = 18;
+        let spell_id = LEGACY_SPELL_NAMES
+            .iter()
+            .position(|name| *name == "monster detection")
+            .expect("monster detection spell");
+
+        for _ in 0..30 {
+            state.spellbook.mana = state.spellbook.max_mana.max(100);
+            let mut events = Vec::new();
+            let (note, _) = cast_spell_by_id(&mut state, &mut events, spell_id, &mut rng);
+            assert!(!note.contains("concentration falters"));
+        }
+    }
+
+    #[test]
+    fn healing_spell_targets_injured_companion() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 9 });
+        state.player.position = Position { x: 4, y: 4 };
+        state.progression.guild_rank = 1;
+        state.gold = 200;
+        state.attributes.iq = 18;
+        let mut events = Vec::new();
+        let _ = apply_site_interaction_choice(
+            &mut state,
+            SiteInteractionKind::MercGuild,
+            5,
+            &mut FixedRng::new(vec![]),
+            &mut events,
+            false,
+        );
+        state.companions[0].stats.hp = 1;
+        let player_hp = state.player.stats.hp;
+        for spell in &mut state.spellbook.spells {
+            spell.known = true;
+        }
+        let spell_id =
+            LEGACY_SPELL_NAMES.iter().position(|name| *name == "healing").expect("healing spell");
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = cast_spell_by_id(&mut state, &mut events, spell_id, &mut rng);
+        let cursor = state.pending_targeting_interaction.as_ref().map(|it| it.cursor);
+        assert_eq!(cursor, Some(state.companions[0].position));
+        let _ = step(&mut state, Command::Legacy { token: "<enter>".to_string() }, &mut rng);
+
+        assert!(state.companions[0].stats.hp > 1);
+        assert_eq!(state.player.stats.hp, player_hp);
+    }
+
+    #[test]
+    fn healing_spell_refuses_hostile_target() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 9 });
+        state.player.position = Position { x: 4, y: 4 };
+        state.progression.guild_rank = 1;
+        state.gold = 200;
+        state.attributes.iq = 18;
+        let mut events = Vec::new();
+        let _ = apply_site_interaction_choice(
+            &mut state,
+            SiteInteractionKind::MercGuild,
+            5,
+            &mut FixedRng::new(vec![]),
+            &mut events,
+            false,
+        );
+        let goblin = state.spawn_monster(
+            "goblin",
+            Position { x: 3, y: 4 },
+            Stats { hp: 2, max_hp: 8, attack_min: 1, attack_max: 1, defense: 0, weight: 60 },
+        );
+        for spell in &mut state.spellbook.spells {
+            spell.known = true;
+        }
+        let spell_id =
+            LEGACY_SPELL_NAMES.iter().position(|name| *name == "healing").expect("healing spell");
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = cast_spell_by_id(&mut state, &mut events, spell_id, &mut rng);
+        let _ = step(&mut state, Command::Legacy { token: "h".to_string() }, &mut rng);
+        let out = step(&mut state, Command::Legacy { token: "<enter>".to_string() }, &mut rng);
+
+        assert!(state.log.iter().any(|line| line.contains("refuse to mend the hostile goblin")));
+        let goblin_hp = state.monsters.iter().find(|m| m.id == goblin).map(|m| m.stats.hp);
+        assert_eq!(goblin_hp, Some(2));
+        assert!(!out.events.is_empty());
+    }
+
+    #[test]
+    fn plate_armored_low_iq_caster_frequently_fizzles() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 9 });
+        let mut rng = DeterministicRng::seeded(0x5EED);
+        for spell in &mut state.spellbook.spells {
+            spell.known = true;
+        }
+        state.attributes.iq = 6;
+        let plate = instantiate_item_from_name(1, "full plate mail");
+        assert_eq!(plate.family, ItemFamily::Armor);
+        state.player.inventory.push(plate);
+        state.player.equipment.armor = Some(1);
+        let spell_id = LEGACY_SPELL_NAMES
+            .iter()
+            .position(|name| *name == "monster detection")
+            .expect("monster detection spell");
+
+        let mut fizzles = 0;
+        for _ in 0..40 {
+            state.spellbook.mana = 100;
+            let mut events = Vec::new();
+            let (note, _) = cast_spell_by_id(&mut state, &mut events, spell_id, &mut rng);
+            if note == "Your concentration falters!" {
+                fizzles += 1;
+                assert!(state.spellbook.mana < 100, "fizzled casts still drain mana");
+            }
+        }
+        assert!(fizzles >= 10, "expected frequent fizzles, got {fizzles}");
+    }
+
+    #[test]
+    fn replaying_commands_from_same_rng_state_reproduces_outcomes() {
+        let mut initial = GameState::new(MapBounds { width: 9, height: 9 });
+        initial.player.position = Position { x: 4, y: 4 };
+        initial.spawn_monster(
+            "goblin",
+            Position { x: 6, y: 4 },
+            Stats { hp: 8, max_hp: 8, attack_min: 1, attack_max: 3, defense: 0, weight: 40 },
+        );
+        let commands = vec![
+            Command::Move(Direction::East),
+            Command::Attack(Direction::East),
+            Command::Wait,
+            Command::Legacy { token: "s".to_string() },
+            Command::Move(Direction::West),
+            Command::WaitTurns { count: 3 },
+        ];
+        let seed_state = DeterministicRng::seeded(0xABCD).state();
+
+        let mut recorded_state = initial.clone();
+        let mut rng = DeterministicRng::from_state(seed_state);
+        let recorded = recorded_state.apply_commands(&commands, &mut rng);
+
+        let mut replay_state = initial.clone();
+        let mut replay_rng = DeterministicRng::from_state(seed_state);
+        let replayed = replay_state.apply_commands(&commands, &mut replay_rng);
+
+        assert_eq!(recorded.len(), commands.len());
+        assert_eq!(recorded, replayed);
+        assert_eq!(recorded_state, replay_state);
+        assert_eq!(rng.state(), replay_rng.state());
+    }
+
+    #[test]
+    fn wait_turns_runs_uninterrupted_for_requested_count() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 9 });
+        let mut rng = FixedRng::new(vec![]);
+        let start_turn = state.clock.turn;
+
+        let out = step(&mut state, Command::WaitTurns { count: 10 }, &mut rng);
+
+        assert_eq!(state.clock.turn, start_turn + 10);
+        assert_eq!(out.turn, start_turn + 10);
+        assert!(out.events.contains(&Event::WaitCompleted { requested: 10, waited: 10 }));
+    }
+
+    #[test]
+    fn wait_turns_stops_early_when_hostile_monster_is_adjacent() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 9 });
+        state.player.position = Position { x: 4, y: 4 };
+        state.spawn_monster(
+            "goblin",
+            Position { x: 5, y: 4 },
+            Stats { hp: 5, max_hp: 5, attack_min: 0, attack_max: 0, defense: 0, weight: 40 },
+        );
+        let mut rng = FixedRng::new(vec![]);
+        let start_turn = state.clock.turn;
+
+        let out = step(&mut state, Command::WaitTurns { count: 10 }, &mut rng);
+
+        assert_eq!(state.clock.turn, start_turn + 1);
+        assert!(out.events.contains(&Event::WaitCompleted { requested: 10, waited: 1 }));
+    }
+
     #[test]
     fn carry_burden_blocks_movement_when_over_limit() {
         let mut state = GameState::new(MapBounds { width: 9, height: 9 });
@@ -17480,6 +27329,221 @@ mod tests {
         assert!(state.log.iter().any(|line| line.contains("magic missile")));
     }
 
+    #[test]
+    fn draining_monster_hit_reduces_player_strength() {
+        let mut state = GameState::new(MapBounds { width: 7, height: 7 });
+        state.player.position = Position { x: 3, y: 3 };
+        state.player.stats.hp = 30;
+        state.player.stats.max_hp = 30;
+        let strength_before = state.attributes.strength;
+        let monster_id = state.spawn_monster(
+            "wraith",
+            Position { x: 4, y: 3 },
+            Stats { hp: 10, max_hp: 10, attack_min: 2, attack_max: 2, defense: 0, weight: 60 },
+        );
+        let monster = state.monsters.iter().find(|monster| monster.id == monster_id).unwrap();
+        assert_eq!(monster.special_attacks, vec![SpecialAttack::Drain]);
+
+        let mut rng = FixedRng::new(vec![2]);
+        let out = step(&mut state, Command::Wait, &mut rng);
+
+        assert!(out.events.iter().any(|event| matches!(event, Event::MonsterAttacked { .. })));
+        assert_eq!(state.attributes.strength, strength_before - 1);
+        assert!(state.log.iter().any(|line| line.contains("drains your strength")));
+    }
+
+    #[test]
+    fn dragon_breath_hits_at_range_with_typed_damage() {
+        let mut state = GameState::new(MapBounds { width: 9, height: 9 });
+        state.player.position = Position { x: 2, y: 2 };
+        state.player.stats.hp = 40;
+        state.player.stats.max_hp = 40;
+        state.resistances.fire = 4;
+        state.spawn_monster(
+            "red dragon",
+            Position { x: 4, y: 2 },
+            Stats { hp: 40, max_hp: 40, attack_min: 6, attack_max: 6, defense: 0, weight: 200 },
+        );
+
+        let mut rng = FixedRng::new(vec![1]);
+        let out = step(&mut state, Command::Wait, &mut rng);
+
+        assert_eq!(state.player.stats.hp, 32);
+        assert!(out.events.iter().any(|event| matches!(event, Event::MonsterAttacked { .. })));
+        assert!(state.log.iter().any(|line| line.contains("breathes fire at you for 8 damage")));
+    }
+
+    #[test]
+    fn fire_resist_ring_counts_toward_effective_resistance_only_while_worn() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        state.resistances.fire = 1;
+        state.player.inventory.push(Item {
+            id: 720,
+            name: "ring of fire resistance".to_string(),
+            family: ItemFamily::Ring,
+            usef: "I_PERM_FIRE_RESIST".to_string(),
+            ..Item::default()
+        });
+        assert_eq!(state.effective_resistances().fire, 1);
+
+        state.player.equipment.ring_1 = Some(720);
+        assert_eq!(state.effective_resistances().fire, 3);
+        push_or_refresh_status(&mut state.status_effects, "fire_resist", 5, 2);
+        assert_eq!(state.effective_resistances().fire, 5);
+        assert_eq!(state.resistances.fire, 1, "base profile is never mutated");
+
+        state.player.equipment.ring_1 = None;
+        state.status_effects.clear();
+        assert_eq!(state.effective_resistances().fire, 1);
+    }
+
+    #[test]
+    fn adjacent_caster_outranks_a_weak_distant_brute() {
+        let mut state = GameState::new(MapBounds { width: 20, height: 5 });
+        state.player.position = Position { x: 1, y: 2 };
+        state.spawn_monster(
+            "rat",
+            Position { x: 18, y: 2 },
+            Stats { hp: 4, max_hp: 4, attack_min: 1, attack_max: 2, defense: 0, weight: 20 },
+        );
+        state.spawn_monster(
+            "warlock",
+            Position { x: 2, y: 2 },
+            Stats { hp: 40, max_hp: 40, attack_min: 4, attack_max: 8, defense: 2, weight: 60 },
+        );
+        state.monsters[0].behavior = MonsterBehavior::Brute;
+        state.monsters[1].behavior = MonsterBehavior::Caster;
+
+        let brute = state.monsters[0].threat_level(&state);
+        let caster = state.monsters[1].threat_level(&state);
+
+        assert!(brute > 0);
+        assert!(caster > brute, "caster {caster} should outrank brute {brute}");
+    }
+
+    #[test]
+    fn duel_exchange_table_follows_the_maneuver_cycle() {
+        use CombatManeuver::{Attack, Block, Lunge, Riposte};
+        assert_eq!(duel_exchange(Block, Lunge), DuelExchange::PlayerWins);
+        assert_eq!(duel_exchange(Riposte, Attack), DuelExchange::PlayerWins);
+        assert_eq!(duel_exchange(Attack, Riposte), DuelExchange::OpponentWins);
+        assert_eq!(duel_exchange(Lunge, Block), DuelExchange::OpponentWins);
+        assert_eq!(duel_exchange(Attack, Lunge), DuelExchange::Trade);
+        assert_eq!(duel_exchange(Block, Riposte), DuelExchange::Standoff);
+    }
+
+    #[test]
+    fn engaging_a_duelist_resolves_scripted_rounds() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.player.stats.attack_min = 4;
+        state.player.stats.attack_max = 4;
+        let hp_before = state.player.stats.hp;
+        let boss_id = state.spawn_monster(
+            "goblin chieftain",
+            Position { x: 2, y: 1 },
+            Stats { hp: 20, max_hp: 20, attack_min: 3, attack_max: 3, defense: 0, weight: 90 },
+        );
+        state.combat_sequence = vec![
+            CombatStep { maneuver: CombatManeuver::Riposte, line: CombatLine::Center },
+            CombatStep { maneuver: CombatManeuver::Block, line: CombatLine::Center },
+            CombatStep { maneuver: CombatManeuver::Attack, line: CombatLine::High },
+        ];
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Engage(Direction::East), &mut rng);
+        assert!(state.log.iter().any(|line| line.contains("will not accept a duel")));
+        assert_eq!(state.active_duel, None);
+        assert_eq!(state.combat_sequence_cursor, 0);
+
+        state.monsters[0].duelist = true;
+        state.player.stats.hp = hp_before;
+        // Round 1: Riposte punishes the opening Attack.
+        let _ = step(&mut state, Command::Engage(Direction::East), &mut rng);
+        assert_eq!(state.active_duel, Some(DuelState { monster_id: boss_id, round: 1 }));
+        assert_eq!(state.monsters[0].stats.hp, 16);
+        assert_eq!(state.player.stats.hp, hp_before);
+        // Round 2: Block meets Block and nothing lands.
+        let _ = step(&mut state, Command::Engage(Direction::East), &mut rng);
+        assert_eq!(state.monsters[0].stats.hp, 16);
+        assert_eq!(state.player.stats.hp, hp_before);
+        // Round 3: the boss lunges into an Attack and both take a hit.
+        let out = step(&mut state, Command::Engage(Direction::East), &mut rng);
+        assert_eq!(state.monsters[0].stats.hp, 12);
+        assert!(state.player.stats.hp < hp_before);
+        assert!(out.events.iter().any(|event| matches!(
+            event,
+            Event::LegacyHandled { token, note, .. }
+                if token == "duel"
+                    && note == "Round 3: your attack against the goblin chieftain's lunge; you trade blows."
+        )));
+    }
+
+    #[test]
+    fn legacy_duel_token_engages_in_the_chosen_direction() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        let boss_id = state.spawn_monster(
+            "goblin chieftain",
+            Position { x: 2, y: 1 },
+            Stats { hp: 20, max_hp: 20, attack_min: 3, attack_max: 3, defense: 0, weight: 90 },
+        );
+        state.monsters[0].duelist = true;
+        let mut rng = FixedRng::new(vec![]);
+
+        let out = step(&mut state, Command::Legacy { token: "J".to_string() }, &mut rng);
+        assert_eq!(state.pending_talk_direction, Some(TalkDirectionInteraction::Engage));
+        assert_eq!(out.minutes, 0);
+
+        let _ = step(&mut state, Command::Legacy { token: "u".to_string() }, &mut rng);
+        assert!(state.pending_talk_direction.is_none());
+        assert_eq!(state.active_duel, None);
+        assert!(
+            state
+                .log
+                .iter()
+                .any(|line| line == "You can only duel an opponent squarely before you.")
+        );
+
+        let minutes_before = state.clock.minutes;
+        let _ = step(&mut state, Command::Legacy { token: "J".to_string() }, &mut rng);
+        let out = step(&mut state, Command::Legacy { token: "l".to_string() }, &mut rng);
+        assert_eq!(state.active_duel, Some(DuelState { monster_id: boss_id, round: 1 }));
+        assert_eq!(out.minutes - minutes_before, 10);
+        assert!(out.events.iter().any(|event| matches!(
+            event,
+            Event::LegacyHandled { token, fully_modeled: true, .. } if token == "J"
+        )));
+    }
+
+    #[test]
+    fn stepping_away_from_a_duel_frees_the_boss_to_act() {
+        let mut state = GameState::new(MapBounds { width: 6, height: 3 });
+        state.player.position = Position { x: 2, y: 1 };
+        let boss_id = state.spawn_monster(
+            "goblin king",
+            Position { x: 3, y: 1 },
+            Stats { hp: 20, max_hp: 20, attack_min: 3, attack_max: 3, defense: 0, weight: 90 },
+        );
+        assert!(state.monsters[0].duelist, "legacy uniques accept duels");
+        state.monsters[0].faction = Faction::Chaos;
+        let mut rng = FixedRng::new(vec![]);
+
+        let _ = step(&mut state, Command::Engage(Direction::East), &mut rng);
+        assert_eq!(state.active_duel, Some(DuelState { monster_id: boss_id, round: 1 }));
+
+        let out = step(&mut state, Command::Move(Direction::West), &mut rng);
+        assert_eq!(state.active_duel, None);
+        assert!(
+            state.log.iter().any(|line| line.contains("duel with the goblin king is broken off"))
+        );
+        assert!(out.events.contains(&Event::MonsterMoved {
+            monster_id: boss_id,
+            from: Position { x: 3, y: 1 },
+            to: Position { x: 2, y: 1 },
+        }));
+    }
+
     #[test]
     fn caster_monster_projectile_is_blocked_by_portcullis() {
         let mut state = GameState::new(MapBounds { width: 9, height: 9 });
@@ -17595,6 +27659,59 @@ mod tests {
         assert!(armored_hp > baseline_hp, "armor/shield should mitigate incoming damage");
     }
 
+    #[test]
+    fn status_summaries_name_and_categorize_active_effects() {
+        let mut state = GameState::new(MapBounds { width: 5, height: 5 });
+        push_or_refresh_status(&mut state.status_effects, "poison", 6, 2);
+        push_or_refresh_status(&mut state.status_effects, "haste", 4, 1);
+
+        let summaries = state.status_summaries();
+
+        let poison = summaries.iter().find(|summary| summary.id == "poison").unwrap();
+        assert_eq!(poison.name, "Poisoned");
+        assert_eq!(poison.remaining_turns, 6);
+        assert_eq!(poison.magnitude, 2);
+        assert_eq!(poison.category, StatusCategory::Debuff);
+        let haste = summaries.iter().find(|summary| summary.id == "haste").unwrap();
+        assert_eq!(haste.name, "Hasted");
+        assert_eq!(haste.remaining_turns, 4);
+        assert_eq!(haste.category, StatusCategory::Buff);
+    }
+
+    #[test]
+    fn heavy_armor_raises_encumbrance_tier_and_penalties() {
+        let equip = |names: &[&str]| {
+            let mut state = GameState::new(MapBounds { width: 9, height: 9 });
+            state.player.position = Position { x: 4, y: 4 };
+            let mut rng = FixedRng::new(vec![]);
+            for name in names {
+                state.place_item(*name, state.player.position);
+                let _ = step(&mut state, Command::Pickup, &mut rng);
+            }
+            state
+        };
+
+        let light = equip(&["soft leather armor"]);
+        let heavy = equip(&["full plate mail", "tower shield"]);
+        assert_eq!(light.encumbrance_tier(), EncumbranceTier::Unencumbered);
+        assert_eq!(heavy.encumbrance_tier(), EncumbranceTier::Overloaded);
+        assert!(
+            heavy.encumbrance_tier().agility_penalty() > light.encumbrance_tier().agility_penalty()
+        );
+
+        let light_profile = equipment_effect_profile(&light);
+        let heavy_profile = equipment_effect_profile(&heavy);
+        assert_eq!(
+            player_defense_total(&light, &light_profile),
+            light.player.stats.defense + light_profile.defense_bonus
+        );
+        assert_eq!(
+            player_defense_total(&heavy, &heavy_profile),
+            heavy.player.stats.defense + heavy_profile.defense_bonus
+                - EncumbranceTier::Overloaded.defense_penalty()
+        );
+    }
+
     #[test]
     fn potions_can_heal_and_harm() {
         let mut state = GameState::new(MapBounds { width: 9, height: 9 });
@@ -17642,6 +27759,7 @@ mod tests {
             damage: 6,
             effect_id: "poison".to_string(),
             armed: true,
+            discovered: false,
         });
         let mut rng_base = FixedRng::new(vec![]);
         let _ = step(&mut baseline, Command::Wait, &mut rng_base);
@@ -17660,6 +27778,7 @@ mod tests {
             damage: 6,
             effect_id: "poison".to_string(),
             armed: true,
+            discovered: false,
         });
         let mut rng_ringed = FixedRng::new(vec![]);
         let _ = step(&mut ringed, Command::Wait, &mut rng_ringed);
@@ -17692,6 +27811,7 @@ mod tests {
                 damage: 1,
                 effect_id: "poison".to_string(),
                 armed: true,
+                discovered: false,
             });
 
             let mut events = Vec::new();
@@ -17702,7 +27822,8 @@ mod tests {
                 family: ItemFamily::Thing,
                 ..Item::default()
             };
-            let note = apply_item_usef_effect(&mut state, &item, &mut events);
+            let note =
+                apply_item_usef_effect(&mut state, &item, &mut FixedRng::new(vec![]), &mut events);
             if note.contains("unrecognized item effect") || note.contains("modeled fallback") {
                 missing.push(usef);
             }
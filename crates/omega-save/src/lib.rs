@@ -1,5 +1,5 @@
 use anyhow::{Context, Result, anyhow, bail};
-use omega_core::{GameMode, GameState};
+use omega_core::{GameMode, GameState, SessionStatus};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -137,6 +137,98 @@ pub fn decode_state_json(raw: &str) -> Result<GameState> {
     JsonSaveCodec.decode_state(raw)
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum SaveError {
+    #[error("invalid save JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("unsupported save schema version: {0}")]
+    UnsupportedVersion(u32),
+}
+
+/// Header fields a host needs to list a save slot.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SaveSummary {
+    pub player_name: String,
+    pub dungeon_level: i16,
+    pub gold: i32,
+    pub turn: u64,
+    pub status: SessionStatus,
+}
+
+// Mirrors of the `GameState` serde defaults for fields a summary reads.
+fn default_summary_player_name() -> String {
+    "Adventurer".to_string()
+}
+
+fn default_summary_gold() -> i32 {
+    250
+}
+
+#[derive(Deserialize)]
+struct SummaryClock {
+    turn: u64,
+}
+
+#[derive(Deserialize, Default)]
+struct SummaryTopology {
+    #[serde(default)]
+    dungeon_level: i16,
+}
+
+/// The subset of `GameState` read by `save_summary`; every other field is
+/// skipped rather than decoded.
+#[derive(Deserialize)]
+struct SummaryHeader {
+    #[serde(default = "default_summary_player_name")]
+    player_name: String,
+    #[serde(default = "default_summary_gold")]
+    gold: i32,
+    clock: SummaryClock,
+    #[serde(default)]
+    topology: SummaryTopology,
+    #[serde(default)]
+    status: SessionStatus,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SummaryPayload {
+    V1 { state: SummaryHeader },
+    V0Wrapped { game_state: SummaryHeader },
+    Bare(SummaryHeader),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SummaryDocument {
+    Envelope { version: u32, payload: SummaryPayload },
+    Bare(SummaryHeader),
+}
+
+/// Reads the slot header of a save without decoding the full `GameState`.
+pub fn save_summary(json: &str) -> Result<SaveSummary, SaveError> {
+    let header = match serde_json::from_str::<SummaryDocument>(json)? {
+        SummaryDocument::Envelope { version, payload } => {
+            if version != SAVE_VERSION && version != 0 {
+                return Err(SaveError::UnsupportedVersion(version));
+            }
+            match payload {
+                SummaryPayload::V1 { state } => state,
+                SummaryPayload::V0Wrapped { game_state } => game_state,
+                SummaryPayload::Bare(state) => state,
+            }
+        }
+        SummaryDocument::Bare(state) => state,
+    };
+    Ok(SaveSummary {
+        player_name: header.player_name,
+        dungeon_level: header.topology.dungeon_level,
+        gold: header.gold,
+        turn: header.clock.turn,
+        status: header.status,
+    })
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum LoadModePolicyResult {
     LoadAccepted,
@@ -269,6 +361,18 @@ mod tests {
         assert_eq!(env.metadata.saved_minutes, 252);
     }
 
+    #[test]
+    fn canonical_json_is_byte_stable_across_round_trip() {
+        let mut state = sample_state();
+        state.travel_to_country_region(3);
+        state.travel_to_country_region(1);
+        let first = state.to_canonical_json();
+        let decoded: GameState = serde_json::from_str(&first).expect("canonical json decodes");
+
+        assert_eq!(decoded, state);
+        assert_eq!(decoded.to_canonical_json(), first);
+    }
+
     #[test]
     fn migrates_v0_envelope_to_v1() {
         let state = sample_state();
@@ -379,6 +483,37 @@ mod tests {
         assert!(err.to_string().contains("save mode mismatch"));
     }
 
+    #[test]
+    fn save_summary_reads_header_fields_from_full_save() {
+        let mut state = sample_state();
+        state.player_name = "Rampart Ranger".to_string();
+        state.topology.dungeon_level = 7;
+        state.gold = 1234;
+        state.status = omega_core::SessionStatus::Won;
+        let raw = encode_json(&state).expect("encode should work");
+
+        let summary = save_summary(&raw).expect("summary should parse");
+        assert_eq!(
+            summary,
+            SaveSummary {
+                player_name: "Rampart Ranger".to_string(),
+                dungeon_level: 7,
+                gold: 1234,
+                turn: 42,
+                status: omega_core::SessionStatus::Won,
+            }
+        );
+
+        let legacy = serde_json::to_string(&state).expect("raw state should serialize");
+        assert_eq!(save_summary(&legacy).expect("legacy summary"), summary);
+    }
+
+    #[test]
+    fn save_summary_rejects_unsupported_versions() {
+        let raw = r#"{"version":99,"payload":{"state":{"clock":{"turn":1}}},"metadata":{}}"#;
+        assert!(matches!(save_summary(raw), Err(SaveError::UnsupportedVersion(99))));
+    }
+
     fn arbitrary_state() -> impl Strategy<Value = GameState> {
         (
             10i32..120i32,
@@ -50,6 +50,10 @@ fn direction_label(direction: Direction) -> &'static str {
 fn event_kind(event: &Event) -> &'static str {
     match event {
         Event::Waited => "Waited",
+        Event::WaitCompleted { .. } => "WaitCompleted",
+        Event::AutoExploreStep { .. } => "AutoExploreStep",
+        Event::AutoExploreDone => "AutoExploreDone",
+        Event::Teleported { .. } => "Teleported",
         Event::Moved { .. } => "Moved",
         Event::MoveBlocked { .. } => "MoveBlocked",
         Event::AttackMissed { .. } => "AttackMissed",
@@ -76,6 +80,12 @@ fn event_kind(event: &Event) -> &'static str {
         Event::StatusTick { .. } => "StatusTick",
         Event::StatusExpired { .. } => "StatusExpired",
         Event::TurnAdvanced { .. } => "TurnAdvanced",
+        Event::EnvironmentChanged { .. } => "EnvironmentChanged",
+        Event::BashResult { .. } => "BashResult",
+        Event::DeathAverted { .. } => "DeathAverted",
+        Event::MapRevealed { .. } => "MapRevealed",
+        Event::InventoryOverflow { .. } => "InventoryOverflow",
+        Event::ItemSweptAway { .. } => "ItemSweptAway",
     }
 }
 
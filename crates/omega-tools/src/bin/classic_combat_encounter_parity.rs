@@ -92,6 +92,7 @@ fn main() -> Result<()> {
         damage: 3,
         effect_id: "poison".to_string(),
         armed: true,
+        discovered: false,
     }];
     let hp_before = trap_state.player.stats.hp;
     let _ = step(&mut trap_state, Command::Move(Direction::East), &mut rng);
@@ -1,6 +1,8 @@
 use anyhow::{Context, Result, bail};
 use omega_content::bootstrap_game_state_with_mode;
-use omega_core::{Command, DeterministicRng, Direction, GameMode, GameState, Position, step};
+use omega_core::{
+    BankOp, Command, DeterministicRng, Direction, GameMode, GameState, Position, step,
+};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
@@ -80,6 +82,7 @@ fn run_trace(mode: GameMode, script: &[Command]) -> Result<StateVector> {
 fn command_label(command: &Command) -> String {
     match command {
         Command::Wait => "wait".to_string(),
+        Command::WaitTurns { count } => format!("wait:{count}"),
         Command::Move(direction) => match direction {
             Direction::North => "move:north".to_string(),
             Direction::South => "move:south".to_string(),
@@ -92,8 +95,49 @@ fn command_label(command: &Command) -> String {
             Direction::East => "attack:east".to_string(),
             Direction::West => "attack:west".to_string(),
         },
+        Command::Engage(direction) => match direction {
+            Direction::North => "engage:north".to_string(),
+            Direction::South => "engage:south".to_string(),
+            Direction::East => "engage:east".to_string(),
+            Direction::West => "engage:west".to_string(),
+        },
+        Command::Bash(direction) => match direction {
+            Direction::North => "bash:north".to_string(),
+            Direction::South => "bash:south".to_string(),
+            Direction::East => "bash:east".to_string(),
+            Direction::West => "bash:west".to_string(),
+        },
+        Command::ApplyLockpick(direction) => match direction {
+            Direction::North => "lockpick:north".to_string(),
+            Direction::South => "lockpick:south".to_string(),
+            Direction::East => "lockpick:east".to_string(),
+            Direction::West => "lockpick:west".to_string(),
+        },
+        Command::ApplyTool { item_id, target } => match target {
+            Some(Direction::North) => format!("apply:{item_id}:north"),
+            Some(Direction::South) => format!("apply:{item_id}:south"),
+            Some(Direction::East) => format!("apply:{item_id}:east"),
+            Some(Direction::West) => format!("apply:{item_id}:west"),
+            None => format!("apply:{item_id}"),
+        },
+        Command::AutoExplore => "explore".to_string(),
+        Command::Engrave { text } => format!("engrave:{text}"),
+        Command::Dip { item_id, potion_id } => format!("dip:{item_id}:{potion_id}"),
+        Command::Zap { item_id } => format!("zap:{item_id}"),
+        Command::Throw { item_id } => format!("throw:{item_id}"),
+        Command::GuildAction { guild, action } => format!("guild:{guild:?}:{action:?}"),
+        Command::RecallCompanions => "recall".to_string(),
+        Command::Butcher => "butcher".to_string(),
+        Command::OfferSurrender => "surrender".to_string(),
+        Command::Look => "look".to_string(),
         Command::Pickup => "pickup".to_string(),
         Command::Drop { slot } => format!("drop:{slot}"),
+        Command::DropQuantity { item_id, count } => format!("drop:{item_id}x{count}"),
+        Command::Bank { op, amount } => match op {
+            BankOp::Deposit => format!("bank:deposit:{amount}"),
+            BankOp::Withdraw => format!("bank:withdraw:{amount}"),
+            BankOp::PostSurety => format!("bank:surety:{amount}"),
+        },
         Command::Legacy { token } => format!("legacy:{token}"),
     }
 }
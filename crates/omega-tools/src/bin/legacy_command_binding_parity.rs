@@ -103,6 +103,9 @@ fn talk_direction_name(value: Option<TalkDirectionInteraction>) -> String {
     match value {
         Some(TalkDirectionInteraction::Talk) => "Talk".to_string(),
         Some(TalkDirectionInteraction::Tunnel) => "Tunnel".to_string(),
+        Some(TalkDirectionInteraction::OpenDoor) => "OpenDoor".to_string(),
+        Some(TalkDirectionInteraction::CloseDoor) => "CloseDoor".to_string(),
+        Some(TalkDirectionInteraction::Engage) => "Engage".to_string(),
         None => "None".to_string(),
     }
 }
@@ -28,6 +28,10 @@ fn event_kinds(events: &[Event]) -> Vec<&'static str> {
         .iter()
         .map(|event| match event {
             Event::Waited => "Waited",
+            Event::WaitCompleted { .. } => "WaitCompleted",
+            Event::AutoExploreStep { .. } => "AutoExploreStep",
+            Event::AutoExploreDone => "AutoExploreDone",
+            Event::Teleported { .. } => "Teleported",
             Event::Moved { .. } => "Moved",
             Event::MoveBlocked { .. } => "MoveBlocked",
             Event::AttackMissed { .. } => "AttackMissed",
@@ -54,6 +58,12 @@ fn event_kinds(events: &[Event]) -> Vec<&'static str> {
             Event::StatusTick { .. } => "StatusTick",
             Event::StatusExpired { .. } => "StatusExpired",
             Event::TurnAdvanced { .. } => "TurnAdvanced",
+            Event::EnvironmentChanged { .. } => "EnvironmentChanged",
+            Event::BashResult { .. } => "BashResult",
+            Event::DeathAverted { .. } => "DeathAverted",
+            Event::MapRevealed { .. } => "MapRevealed",
+            Event::InventoryOverflow { .. } => "InventoryOverflow",
+            Event::ItemSweptAway { .. } => "ItemSweptAway",
         })
         .collect()
 }
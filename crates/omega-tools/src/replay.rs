@@ -213,6 +213,10 @@ pub struct FixtureTrace {
 pub fn event_kind(event: &Event) -> &'static str {
     match event {
         Event::Waited => "waited",
+        Event::WaitCompleted { .. } => "wait_completed",
+        Event::AutoExploreStep { .. } => "auto_explore_step",
+        Event::AutoExploreDone => "auto_explore_done",
+        Event::Teleported { .. } => "teleported",
         Event::Moved { .. } => "moved",
         Event::MoveBlocked { .. } => "move_blocked",
         Event::AttackMissed { .. } => "attack_missed",
@@ -239,6 +243,12 @@ pub fn event_kind(event: &Event) -> &'static str {
         Event::StatusTick { .. } => "status_tick",
         Event::StatusExpired { .. } => "status_expired",
         Event::TurnAdvanced { .. } => "turn_advanced",
+        Event::EnvironmentChanged { .. } => "environment_changed",
+        Event::BashResult { .. } => "bash_result",
+        Event::DeathAverted { .. } => "death_averted",
+        Event::MapRevealed { .. } => "map_revealed",
+        Event::InventoryOverflow { .. } => "inventory_overflow",
+        Event::ItemSweptAway { .. } => "item_swept_away",
     }
 }
 
@@ -9,15 +9,19 @@ use omega_core::color::AnimationKind;
 use omega_core::{
     Command, DeterministicRng, Direction, Event, GameMode, GameState, ModalInputProfile, Outcome,
     Position, SessionStatus, SiteInteractionKind, active_activation_interaction_help_hint,
-    active_activation_interaction_prompt, active_inventory_interaction_help_hint,
+    active_activation_interaction_prompt, active_call_label_help_hint, active_call_label_prompt,
+    active_dialogue_help_hint, active_dialogue_prompt, active_guild_action_help_hint,
+    active_guild_action_prompt, active_inventory_interaction_help_hint,
     active_inventory_interaction_prompt, active_item_prompt, active_item_prompt_help_hint,
-    active_objective_snapshot, active_quit_interaction_help_hint, active_quit_interaction_prompt,
+    active_look_interaction_help_hint, active_look_interaction_prompt, active_objective_snapshot,
+    active_quit_interaction_help_hint, active_quit_interaction_prompt,
     active_site_interaction_help_hint, active_site_interaction_prompt,
     active_spell_interaction_help_hint, active_spell_interaction_prompt,
     active_talk_direction_help_hint, active_talk_direction_prompt,
     active_targeting_interaction_help_hint, active_targeting_interaction_prompt,
-    active_wizard_interaction_help_hint, active_wizard_interaction_prompt, modal_input_profile,
-    objective_map_hints, renderable_timeline_lines, sanitize_legacy_prompt_noise, step,
+    active_wizard_interaction_help_hint, active_wizard_interaction_prompt, item_condition_label,
+    modal_input_profile, objective_map_hints, renderable_timeline_lines,
+    sanitize_legacy_prompt_noise, step,
 };
 use omega_save::{decode_state_json_for_mode, encode_json};
 use ratatui::backend::{CrosstermBackend, TestBackend};
@@ -237,9 +241,12 @@ impl App {
             || self.state.pending_activation_interaction.is_some()
             || self.state.pending_quit_interaction.is_some()
             || self.state.pending_talk_direction.is_some()
+            || self.state.pending_dialogue.is_some()
             || self.state.pending_inventory_interaction.is_some()
             || self.state.pending_item_prompt.is_some()
+            || self.state.pending_call_label.is_some()
             || self.state.pending_targeting_interaction.is_some()
+            || self.state.pending_guild_action.is_some()
             || self.state.pending_site_interaction.is_some()
     }
 
@@ -275,13 +282,17 @@ impl App {
                 'A' => UiAction::Dispatch(Command::Attack(Direction::West)),
                 'D' => UiAction::Dispatch(Command::Attack(Direction::East)),
                 'g' => UiAction::Dispatch(Command::Pickup),
+                'Y' => UiAction::Dispatch(Command::OfferSurrender),
+                ';' => UiAction::Dispatch(Command::Look),
+                'B' => UiAction::Dispatch(Command::Butcher),
+                'K' => UiAction::Dispatch(Command::RecallCompanions),
                 ',' | '@' | '<' | '>' | '?' | '/' => {
                     UiAction::Dispatch(Command::Legacy { token: ch.to_string() })
                 }
                 'a' | 'e' | 'f' | 'm' | 'o' | 'p' | 'r' | 't' | 'v' | 'x' | 'z' | 'c' => {
                     UiAction::Dispatch(Command::Legacy { token: ch.to_string() })
                 }
-                'C' | 'E' | 'F' | 'G' | 'H' | 'I' | 'M' | 'O' | 'T' | 'V' | 'Z' => {
+                'C' | 'E' | 'F' | 'G' | 'H' | 'I' | 'J' | 'M' | 'O' | 'T' | 'U' | 'V' | 'Z' => {
                     UiAction::Dispatch(Command::Legacy { token: ch.to_string() })
                 }
                 'u' | 'y' | 'b' | 'n' => {
@@ -1096,6 +1107,11 @@ fn render_map_panel(
                     'm',
                     Some(ColorId::Entity(EntityColorId::Monster(MonsterColorId::HostileHumanoid))),
                 )
+            } else if let Some(companion) = state.companions.iter().find(|c| c.position == pos) {
+                (
+                    companion.display_glyph.unwrap_or('m'),
+                    Some(ColorId::Entity(EntityColorId::Monster(MonsterColorId::Friendly))),
+                )
             } else if state.ground_items.iter().any(|g| g.position == pos) {
                 ('*', Some(ColorId::Entity(EntityColorId::Item(ItemRarityColorId::Common))))
             } else if objective_target == Some(pos) && state.map_glyph_at(pos) == '.' {
@@ -1196,12 +1212,18 @@ fn render_status_panel(
         "quit confirmation active".to_string()
     } else if state.pending_talk_direction.is_some() {
         "directional talk/tunnel prompt active".to_string()
+    } else if state.pending_dialogue.is_some() {
+        "conversation active".to_string()
     } else if state.pending_inventory_interaction.is_some() {
         "inventory interaction active".to_string()
     } else if state.pending_item_prompt.is_some() {
         "item selection prompt active".to_string()
+    } else if state.pending_call_label.is_some() {
+        "item naming prompt active".to_string()
     } else if state.pending_targeting_interaction.is_some() {
         "targeting prompt active".to_string()
+    } else if state.pending_guild_action.is_some() {
+        "guild action prompt active".to_string()
     } else {
         state
             .pending_site_interaction
@@ -1296,7 +1318,7 @@ fn render_status_panel(
             text_default,
         )),
         Line::from(Span::styled(
-            "Keys: S save+quit, L load, R restart, Q retire/quit flow, a activate, z zap, Ctrl+F/G/I/K/L/O/P/R/W/X, F12 wizard",
+            "Keys: S save+quit, L load, R restart, Q retire/quit flow, a activate, z zap, Y yield, ; look, B butcher, K recall, J duel, U guild, Ctrl+F/G/I/K/L/O/P/R/W/X, F12 wizard",
             text_default,
         )),
         Line::from(Span::styled(
@@ -1305,6 +1327,13 @@ fn render_status_panel(
         )),
     ];
 
+    let sensed = state.sensed_monsters();
+    if !sensed.is_empty() {
+        let positions: Vec<String> =
+            sensed.iter().map(|(_, pos)| format!("({}, {})", pos.x, pos.y)).collect();
+        lines.push(Line::from(Span::styled(format!("Auras: {}", positions.join(" ")), highlight)));
+    }
+
     if let Some(last) = renderable_timeline_lines(state, 1).first() {
         lines.push(Line::from(Span::styled(format!("Latest: {last}"), text_default)));
     }
@@ -1334,7 +1363,7 @@ fn describe_pending_interaction(kind: &SiteInteractionKind, state: &GameState) -
     match kind {
         SiteInteractionKind::Shop => "shop menu (1-4, q/x close)".to_string(),
         SiteInteractionKind::Armorer => "armorer menu (1-4, q/x close)".to_string(),
-        SiteInteractionKind::Club => "club menu (1-3, q/x close)".to_string(),
+        SiteInteractionKind::Club => "club menu (1-4, q/x close)".to_string(),
         SiteInteractionKind::Gym => "gym menu (1-3, q/x close)".to_string(),
         SiteInteractionKind::Healer => "healer menu (1-3, q/x close)".to_string(),
         SiteInteractionKind::Casino => "casino menu (1-3, q/x close)".to_string(),
@@ -1403,7 +1432,7 @@ fn render_inventory_panel(state: &GameState, style_cache: &StyleCache) -> Vec<Li
                 format!(
                     "{}: {} [{} | {}]",
                     idx + 1,
-                    item.name,
+                    item_condition_label(item),
                     format!("{:?}", item.family).to_ascii_lowercase(),
                     if item.usef.is_empty() { "no-usef" } else { item.usef.as_str() }
                 ),
@@ -1480,19 +1509,27 @@ fn render_interaction_panel(
         .or_else(|| active_spell_interaction_prompt(state))
         .or_else(|| active_quit_interaction_prompt(state))
         .or_else(|| active_talk_direction_prompt(state))
+        .or_else(|| active_dialogue_prompt(state))
         .or_else(|| active_activation_interaction_prompt(state))
         .or_else(|| active_targeting_interaction_prompt(state))
+        .or_else(|| active_look_interaction_prompt(state))
+        .or_else(|| active_guild_action_prompt(state))
         .or_else(|| active_inventory_interaction_prompt(state))
         .or_else(|| active_item_prompt(state))
+        .or_else(|| active_call_label_prompt(state))
         .or_else(|| active_site_interaction_prompt(state));
     let active_hint = active_wizard_interaction_help_hint(state)
         .or_else(|| active_spell_interaction_help_hint(state))
         .or_else(|| active_quit_interaction_help_hint(state))
         .or_else(|| active_talk_direction_help_hint(state))
+        .or_else(|| active_dialogue_help_hint(state))
         .or_else(|| active_activation_interaction_help_hint(state))
         .or_else(|| active_targeting_interaction_help_hint(state))
+        .or_else(|| active_look_interaction_help_hint(state))
+        .or_else(|| active_guild_action_help_hint(state))
         .or_else(|| active_inventory_interaction_help_hint(state))
         .or_else(|| active_item_prompt_help_hint(state))
+        .or_else(|| active_call_label_help_hint(state))
         .or_else(|| active_site_interaction_help_hint(state));
 
     let text_dim = style_cache.get_fg(&ColorId::Ui(UiColorId::TextDim));
@@ -1598,6 +1635,12 @@ fn render_log_panel(
 fn format_event(event: &Event) -> String {
     match event {
         Event::Waited => "waited".to_string(),
+        Event::WaitCompleted { requested, waited } => format!("waited {waited}/{requested} turns"),
+        Event::AutoExploreStep { to } => format!("explored: ({}, {})", to.x, to.y),
+        Event::AutoExploreDone => "exploration halted".to_string(),
+        Event::Teleported { from, to } => {
+            format!("teleported: ({}, {}) -> ({}, {})", from.x, from.y, to.x, to.y)
+        }
         Event::Moved { from, to } => {
             format!("moved: ({}, {}) -> ({}, {})", from.x, from.y, to.x, to.y)
         }
@@ -1612,7 +1655,7 @@ fn format_event(event: &Event) -> String {
         Event::MonsterAttacked { monster_id, damage, remaining_hp } => {
             format!("monster#{monster_id} hit you for {damage} (hp {remaining_hp})")
         }
-        Event::MonsterDefeated { monster_id } => format!("monster#{monster_id} defeated"),
+        Event::MonsterDefeated { monster_id, .. } => format!("monster#{monster_id} defeated"),
         Event::PlayerDefeated => "you are defeated".to_string(),
         Event::VictoryAchieved => "victory achieved".to_string(),
         Event::CommandIgnoredTerminal { status } => format!("command ignored ({status:?})"),
@@ -1650,6 +1693,16 @@ fn format_event(event: &Event) -> String {
         }
         Event::StatusExpired { effect_id } => format!("status `{effect_id}` expired"),
         Event::TurnAdvanced { turn, minutes } => format!("turn advanced: {turn} ({minutes}m)"),
+        Event::EnvironmentChanged { from, to } => format!("environment {from:?} -> {to:?}"),
+        Event::BashResult { target, success } => {
+            format!("bash ({}, {}) {}", target.x, target.y, if *success { "broke" } else { "held" })
+        }
+        Event::DeathAverted { item_id, name } => format!("death averted by {name}#{item_id}"),
+        Event::MapRevealed { tiles } => format!("map revealed: {tiles} new tile(s)"),
+        Event::InventoryOverflow { item_id, name } => {
+            format!("pack full: {name}#{item_id} dropped at your feet")
+        }
+        Event::ItemSweptAway { item_id, name } => format!("swept away: {name}#{item_id}"),
     }
 }
 
@@ -1678,6 +1731,18 @@ mod tests {
             UiAction::Dispatch(Command::Attack(Direction::East))
         );
         assert_eq!(App::map_input(UiKey::Char('g')), UiAction::Dispatch(Command::Pickup));
+        assert_eq!(App::map_input(UiKey::Char('Y')), UiAction::Dispatch(Command::OfferSurrender));
+        assert_eq!(App::map_input(UiKey::Char(';')), UiAction::Dispatch(Command::Look));
+        assert_eq!(App::map_input(UiKey::Char('B')), UiAction::Dispatch(Command::Butcher));
+        assert_eq!(App::map_input(UiKey::Char('K')), UiAction::Dispatch(Command::RecallCompanions));
+        assert_eq!(
+            App::map_input(UiKey::Char('J')),
+            UiAction::Dispatch(Command::Legacy { token: "J".to_string() })
+        );
+        assert_eq!(
+            App::map_input(UiKey::Char('U')),
+            UiAction::Dispatch(Command::Legacy { token: "U".to_string() })
+        );
         assert_eq!(App::map_input(UiKey::Char('2')), UiAction::Dispatch(Command::Drop { slot: 1 }));
         assert_eq!(
             App::map_input(UiKey::Char('?')),
@@ -1823,7 +1888,7 @@ mod tests {
             Stats { hp: 6, max_hp: 6, attack_min: 1, attack_max: 1, defense: 0, weight: 60 },
         );
         let slot = PathBuf::from("target/test-omega-tui-controls-attack.json");
-        let mut app = App::with_options(19, state.clone(), state, slot);
+        let mut app = App::with_options(20, state.clone(), state, slot);
 
         app.handle_key(UiKey::Char('D'));
 
@@ -1844,7 +1909,7 @@ mod tests {
             Stats { hp: 6, max_hp: 6, attack_min: 1, attack_max: 1, defense: 0, weight: 60 },
         );
         let slot = PathBuf::from("target/test-omega-tui-controls-bump-attack.json");
-        let mut app = App::with_options(1901, state.clone(), state, slot);
+        let mut app = App::with_options(1920, state.clone(), state, slot);
 
         app.handle_key(UiKey::Char('d'));
 
@@ -2043,6 +2108,39 @@ mod tests {
         assert!(rendered.contains("Mana: "));
     }
 
+    #[test]
+    fn status_panel_lists_monsters_sensed_by_aura_sight() {
+        let mut state = GameState::new(omega_core::MapBounds { width: 9, height: 3 });
+        state.player.position = Position { x: 1, y: 1 };
+        state.spawn_monster(
+            "lurker",
+            Position { x: 3, y: 1 },
+            omega_core::Stats {
+                hp: 5,
+                max_hp: 5,
+                attack_min: 1,
+                attack_max: 1,
+                defense: 0,
+                weight: 60,
+            },
+        );
+        let slot = PathBuf::from("target/test-omega-tui-status-auras.json");
+
+        let theme = omega_core::color::ColorTheme::from_toml(include_str!(
+            "../../omega-content/themes/classic.toml"
+        ))
+        .unwrap();
+        let capability = omega_core::color::ColorCapability::TrueColor;
+        let cache = StyleCache::new(&theme, capability);
+
+        let rendered = lines_to_string(render_status_panel(&state, &cache, &slot, &theme, 0.0));
+        assert!(!rendered.contains("Auras:"));
+
+        state.questionnaire_traits.can_see_auras = true;
+        let rendered = lines_to_string(render_status_panel(&state, &cache, &slot, &theme, 0.0));
+        assert!(rendered.contains("Auras: (3, 1)"));
+    }
+
     #[test]
     fn status_panel_includes_objective_summary_only_for_modern_mode() {
         let mut state = GameState::new(omega_core::MapBounds { width: 5, height: 5 });